@@ -0,0 +1,12 @@
+//!Writes the conformance suite of the format into the given directory, see `membuffer::conformance`
+//!`cargo run --example conformance --features conformance -- <directory>`
+use std::path::Path;
+
+fn main() {
+    let directory = std::env::args().nth(1).unwrap_or_else(|| "conformance".to_string());
+    if let Err(err) = membuffer::conformance::write_suite(Path::new(&directory)) {
+        eprintln!("Could not write the conformance suite to {}: {}",directory,err);
+        std::process::exit(1);
+    }
+    println!("Wrote {} buffers and conformance.json to {}",membuffer::conformance::cases().len(),directory);
+}
@@ -0,0 +1,74 @@
+/* C API of membuffer, see the documentation of the ffi module. Build the library with
+   cargo rustc --release --features ffi --crate-type staticlib */
+
+#ifndef MEMBUFFER_H
+#define MEMBUFFER_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Type returned by membuffer_reader_entry_type for entries which do not exist */
+#define MEMBUFFER_NO_ENTRY INT32_MIN
+
+/* Built in type ids, the payloads are in the byte order of the machine */
+#define MEMBUFFER_TYPE_TEXT 0
+#define MEMBUFFER_TYPE_INTEGER32 1
+#define MEMBUFFER_TYPE_VECTOR_U8 2
+#define MEMBUFFER_TYPE_VECTOR_U32 3
+#define MEMBUFFER_TYPE_VECTOR_U64 4
+#define MEMBUFFER_TYPE_MEMBUFFER 5
+#define MEMBUFFER_TYPE_INTEGER64 1021
+#define MEMBUFFER_TYPE_FLOAT32 1022
+#define MEMBUFFER_TYPE_FLOAT64 1023
+#define MEMBUFFER_TYPE_VECTOR_F32 1024
+#define MEMBUFFER_TYPE_VECTOR_F64 1025
+#define MEMBUFFER_TYPE_UNSIGNED8 1030
+#define MEMBUFFER_TYPE_UNSIGNED16 1031
+#define MEMBUFFER_TYPE_UNSIGNED32 1032
+#define MEMBUFFER_TYPE_SIGNED8 1033
+#define MEMBUFFER_TYPE_SIGNED16 1034
+#define MEMBUFFER_TYPE_SIGNED64 1035
+#define MEMBUFFER_TYPE_BOOL 1036
+#define MEMBUFFER_TYPE_CHAR 1037
+#define MEMBUFFER_TYPE_CHUNKED 1038
+
+/* Reader borrowing the buffer it was created from */
+typedef struct MemBufferFfiReader MemBufferFfiReader;
+
+/* Writer owning copies of the added payloads */
+typedef struct MemBufferWriter MemBufferWriter;
+
+MemBufferFfiReader *membuffer_reader_new(const uint8_t *data, size_t len);
+
+void membuffer_reader_free(MemBufferFfiReader *reader);
+
+size_t membuffer_reader_len(const MemBufferFfiReader *reader);
+
+int32_t membuffer_reader_entry_type(const MemBufferFfiReader *reader, size_t index);
+
+const uint8_t *membuffer_reader_entry_ptr(const MemBufferFfiReader *reader, size_t index);
+
+size_t membuffer_reader_entry_len(const MemBufferFfiReader *reader, size_t index);
+
+MemBufferWriter *membuffer_writer_new(void);
+
+void membuffer_writer_free(MemBufferWriter *writer);
+
+size_t membuffer_writer_len(const MemBufferWriter *writer);
+
+bool membuffer_writer_add_entry(MemBufferWriter *writer, int32_t variable_type, const uint8_t *data, size_t len);
+
+uint8_t *membuffer_writer_finalize(const MemBufferWriter *writer, size_t *len);
+
+void membuffer_buffer_free(uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MEMBUFFER_H */
@@ -0,0 +1,80 @@
+//!Derive macro for the membuffer crate, use it through the `derive` feature of membuffer. Every
+//!field of the struct is stored as one entry of a nested buffer in declaration order, fields
+//!borrowing from the buffer like `&str` or `&[u8]` are loaded without copying.
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input,Data,DeriveInput,Fields,GenericParam,Lifetime};
+
+#[proc_macro_derive(MemBuffer)]
+pub fn derive_mem_buffer(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(syn::Error::new_spanned(input,"MemBuffer can only be derived for structs")),
+    };
+    let lifetimes: Vec<&Lifetime> = input.generics.params.iter().filter_map(|x| match x {
+        GenericParam::Lifetime(x) => Some(&x.lifetime),
+        _ => None,
+    }).collect();
+    if input.generics.params.len() != lifetimes.len() || lifetimes.len() > 1 {
+        return Err(syn::Error::new_spanned(&input.generics,"MemBuffer can only be derived for structs with at most one lifetime and no type parameters"));
+    }
+
+    let members: Vec<proc_macro2::TokenStream> = match fields {
+        Fields::Named(fields) => fields.named.iter().map(|x| { let ident = x.ident.as_ref().unwrap(); quote!(#ident) }).collect(),
+        Fields::Unnamed(fields) => (0..fields.unnamed.len()).map(|x| { let index = syn::Index::from(x); quote!(#index) }).collect(),
+        Fields::Unit => Vec::new(),
+    };
+    let indices = 0..members.len();
+    let construct = match fields {
+        Fields::Named(_) => quote!(#name { #(#members: reader.load_entry(#indices)?),* }),
+        Fields::Unnamed(_) => quote!(#name ( #(reader.load_entry(#indices)?),* )),
+        Fields::Unit => quote!(#name),
+    };
+
+    let (impl_generics,ty_generics,_) = input.generics.split_for_impl();
+    //Borrowed fields are loaded with the lifetime of the struct, owned structs get their own one
+    let (de_generics,de_lifetime) = match lifetimes.first() {
+        Some(lifetime) => (quote!(#impl_generics),quote!(#lifetime)),
+        None => (quote!(<'membuffer>),quote!('membuffer)),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::membuffer::MemBufferSerialize for #name #ty_generics {
+            fn to_mem_buffer<'membuffer_ser>(&'membuffer_ser self) -> ::std::borrow::Cow<'membuffer_ser,[u8]> {
+                let mut writer = ::membuffer::MemBufferWriter::new();
+                #(writer.add_entry_ref(&self.#members);)*
+                ::std::borrow::Cow::Owned(writer.finalize())
+            }
+
+            fn get_mem_buffer_type() -> i32 {
+                ::membuffer::MemBufferTypes::MemBuffer as i32
+            }
+        }
+
+        impl #impl_generics ::membuffer::MemBufferSerialize for &#name #ty_generics {
+            fn to_mem_buffer<'membuffer_ser>(&'membuffer_ser self) -> ::std::borrow::Cow<'membuffer_ser,[u8]> {
+                (**self).to_mem_buffer()
+            }
+
+            fn get_mem_buffer_type() -> i32 {
+                ::membuffer::MemBufferTypes::MemBuffer as i32
+            }
+        }
+
+        impl #de_generics ::membuffer::MemBufferDeserialize<#de_lifetime,#name #ty_generics> for #name #ty_generics {
+            fn from_mem_buffer(mem: &#de_lifetime [u8]) -> ::std::result::Result<#name #ty_generics,::membuffer::MemBufferError> {
+                let reader = ::membuffer::MemBufferReader::new(mem)?;
+                ::std::result::Result::Ok(#construct)
+            }
+        }
+    })
+}
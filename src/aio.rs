@@ -0,0 +1,216 @@
+//!Async helpers for tokio, available with the `tokio` feature. `MemBufferWriter::write_to`
+//!writes a buffer into any `AsyncWrite`. `AsyncMemBufferReader::read_header` only reads the
+//!header from an `AsyncRead` like a TCP stream, the payload of every entry is then read when it is
+//!requested with `read_entry`. The stream cannot go back, so entries have to be requested in the
+//!order they are placed in the buffer, the payloads in between are skipped.
+//!```rust
+//!# tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//!use membuffer::MemBufferWriter;
+//!use membuffer::aio::AsyncMemBufferReader;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("header");
+//!writer.add_entry(&vec![0u8;1<<20][..]);
+//!writer.add_entry(42);
+//!let mut wire = Vec::new();
+//!writer.write_to(&mut wire).await.unwrap();
+//!
+//!let mut reader = AsyncMemBufferReader::read_header(&wire[..]).await.unwrap();
+//!assert_eq!(reader.len(),3);
+//!assert_eq!(reader.read_entry(0).await.unwrap().load::<&str>().unwrap(),"header");
+//!//The megabyte in between is skipped
+//!assert_eq!(reader.read_entry(2).await.unwrap().load::<i32>().unwrap(),42);
+//!# });
+//!```
+use tokio::io::{AsyncRead,AsyncReadExt,AsyncWrite,AsyncWriteExt};
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize,SYSTEM_TYPE_COMPRESSION};
+use crate::table;
+use crate::compress::load_detached;
+
+///Converts a format error into an `io::ErrorKind::InvalidData` error
+fn invalid_data(err: MemBufferError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData,err.to_string())
+}
+
+impl MemBufferWriter {
+    ///Finalizes the buffer and writes it into the given writer, returns the number of bytes written
+    pub async fn write_to<W: AsyncWrite+Unpin>(&self, mut w: W) -> std::io::Result<u64> {
+        let data = self.finalize();
+        w.write_all(&data).await?;
+        w.flush().await?;
+        Ok(data.len() as u64)
+    }
+}
+
+///Reader which holds the header of a buffer and reads the payloads from the stream on demand, see
+///the module documentation
+pub struct AsyncMemBufferReader<R> {
+    reader: R,
+    header: Vec<u8>,
+    //Bytes of the payload consumed from the stream so far
+    position: usize,
+}
+
+impl<R: AsyncRead+Unpin> AsyncMemBufferReader<R> {
+    ///Reads and checks the header, format errors are returned as `io::ErrorKind::InvalidData`.
+    ///Interleaved buffers do not start with a header and are rejected.
+    pub async fn read_header(mut reader: R) -> std::io::Result<AsyncMemBufferReader<R>> {
+        let mut header = vec![0u8;8];
+        reader.read_exact(&mut header).await?;
+        let header_len = table::header_len(&header).map_err(invalid_data)?;
+        //The header grows with the data which arrives instead of trusting the announced length
+        if (&mut reader).take(header_len-8).read_to_end(&mut header).await? as u64 != header_len-8 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        MemBufferReader::new(&header).map_err(invalid_data)?;
+        Ok(AsyncMemBufferReader {
+            reader,
+            header,
+            position: 0,
+        })
+    }
+
+    ///Returns a reader over the header, it holds no payload
+    fn header_reader(&self) -> MemBufferReader<'_> {
+        //The header was checked when it was read
+        MemBufferReader::new(&self.header).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.header_reader().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header_reader().is_empty()
+    }
+
+    ///Returns the type of the entry without reading it
+    pub fn variable_type(&self, index: usize) -> Result<i32,MemBufferError> {
+        let reader = self.header_reader();
+        if index >= reader.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,reader.len()));
+        }
+        Ok(reader.offsets.get(index).variable_type)
+    }
+
+    ///Reads the payload of the entry, skipping the payloads in front of it. Fails with
+    ///`io::ErrorKind::InvalidInput` for entries the stream has already passed.
+    pub async fn read_entry(&mut self, index: usize) -> std::io::Result<AsyncEntry> {
+        let (entry,swapped,compressed,encrypted) = {
+            let reader = self.header_reader();
+            if index >= reader.len() {
+                return Err(invalid_data(MemBufferError::IndexOutOfBounds(index,reader.len())));
+            }
+            (reader.offsets.get(index),reader.is_byte_swapped(),reader.system.iter().any(|x| x.variable_type == SYSTEM_TYPE_COMPRESSION),reader.is_encrypted(index))
+        };
+        if entry.start < self.position {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,"Memory buffer error: The stream has already passed the entry"));
+        }
+        if entry.end < entry.start {
+            return Err(invalid_data(MemBufferError::WrongFormat));
+        }
+        let skip = (entry.start-self.position) as u64;
+        if tokio::io::copy(&mut (&mut self.reader).take(skip),&mut tokio::io::sink()).await? != skip {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        let mut data = Vec::new();
+        if (&mut self.reader).take(entry.len() as u64).read_to_end(&mut data).await? != entry.len() {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.position = entry.end;
+        Ok(AsyncEntry {
+            data,
+            variable_type: entry.variable_type,
+            swapped,
+            compressed,
+            encrypted,
+        })
+    }
+
+    ///Returns the underlying stream positioned behind the last entry read
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+///Payload of a single entry read from a stream
+pub struct AsyncEntry {
+    data: Vec<u8>,
+    variable_type: i32,
+    swapped: bool,
+    //The compression table is stored behind the entries and cannot be read in advance
+    compressed: bool,
+    encrypted: bool,
+}
+
+impl AsyncEntry {
+    pub fn variable_type(&self) -> i32 {
+        self.variable_type
+    }
+
+    ///Returns the payload as stored in the buffer
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    ///Loads the entry with the given type like `MemBufferReader::load_entry`. Interned text and
+    ///the entries of buffers holding compressed entries can only be loaded through a reader over
+    ///the whole buffer, the latter fail with `MemBufferError::CompressedEntry`. Entries of encrypted
+    ///buffers fail with `MemBufferError::EncryptedEntry`.
+    pub fn load<'a, X: MemBufferDeserialize<'a,X>+MemBufferSerialize>(&'a self) -> Result<X,MemBufferError> {
+        if self.compressed && self.variable_type == X::get_mem_buffer_type() {
+            return Err(MemBufferError::CompressedEntry);
+        }
+        load_detached(&self.data,self.variable_type,self.swapped,None,self.encrypted)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::MemBufferWriter;
+    use crate::aio::AsyncMemBufferReader;
+
+    #[tokio::test]
+    async fn check_async_reader() {
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry("");
+        writer.add_entry_named("answer",42);
+        let mut wire = Vec::new();
+        let written = writer.write_to(&mut wire).await.unwrap();
+        assert_eq!(written as usize,wire.len());
+        assert_eq!(wire,writer.finalize());
+
+        let mut reader = AsyncMemBufferReader::read_header(&wire[..]).await.unwrap();
+        assert_eq!(reader.len(),4);
+        assert_eq!(reader.variable_type(3).unwrap(),crate::MemBufferTypes::Integer32 as i32);
+        assert_eq!(reader.read_entry(1).await.unwrap().load::<std::borrow::Cow<[u64]>>().unwrap().into_owned(),vec![1,2,3]);
+        assert_eq!(reader.read_entry(2).await.unwrap().load::<&str>().unwrap(),"");
+        let entry = reader.read_entry(3).await.unwrap();
+        assert_eq!(entry.load::<i32>().unwrap(),42);
+        assert!(entry.load::<&str>().is_err());
+        assert_eq!(reader.read_entry(0).await.err().unwrap().kind(),std::io::ErrorKind::InvalidInput);
+        assert!(reader.read_entry(4).await.is_err());
+
+        //Truncated streams and data without a header
+        assert!(AsyncMemBufferReader::read_header(&wire[..20]).await.is_err());
+        assert!(AsyncMemBufferReader::read_header(&b"no buffer"[..]).await.is_err());
+        let header_len = 8+6*12;
+        let mut truncated = AsyncMemBufferReader::read_header(&wire[..header_len+3]).await.unwrap();
+        assert!(truncated.read_entry(0).await.is_err());
+    }
+
+    #[cfg(feature = "encryption")]
+    #[tokio::test]
+    async fn check_async_encrypted_entries() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("secret");
+        writer.add_entry(&b"secret"[..]);
+        let data = writer.finalize_encrypted(&crate::EncryptionKey::new(&[7;32]));
+        let mut reader = AsyncMemBufferReader::read_header(&data[..]).await.unwrap();
+        assert!(matches!(reader.read_entry(0).await.unwrap().load::<&str>(),Err(crate::MemBufferError::EncryptedEntry)));
+        assert!(matches!(reader.read_entry(1).await.unwrap().load::<&[u8]>(),Err(crate::MemBufferError::EncryptedEntry)));
+    }
+}
@@ -0,0 +1,62 @@
+//!Custom allocators for the written buffer, available with the `allocator_api` feature on a
+//!nightly compiler. `finalize_in` places the buffer in the given allocator, for example an arena
+//!or a NUMA aware allocator, instead of the global one.
+//!```rust
+//!#![feature(allocator_api)]
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!use std::alloc::Global;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello");
+//!let data = writer.finalize_in(Global);
+//!assert_eq!(MemBufferReader::new(&data).unwrap().load_entry::<&str>(0).unwrap(),"Hello");
+//!```
+use std::alloc::Allocator;
+use crate::MemBufferWriter;
+
+impl MemBufferWriter {
+    ///Finalizes the buffer like `finalize` into a vector allocated by the given allocator
+    pub fn finalize_in<A: Allocator>(&self, alloc: A) -> Vec<u8,A> {
+        let mut var = Vec::with_capacity_in(self.finalize_capacity(),alloc);
+        //Writing into a vector cannot fail
+        self.finalize_into(&mut var).unwrap();
+        var
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader};
+    use std::alloc::{Allocator,AllocError,Global,Layout};
+    use std::ptr::NonNull;
+    use std::sync::atomic::{AtomicUsize,Ordering};
+
+    ///Forwards to the global allocator and counts the allocated bytes
+    struct CountingAllocator<'a>(&'a AtomicUsize);
+
+    unsafe impl Allocator for CountingAllocator<'_> {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>,AllocError> {
+            self.0.fetch_add(layout.size(),Ordering::Relaxed);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            Global.deallocate(ptr,layout)
+        }
+    }
+
+    #[test]
+    fn check_finalize_in() {
+        let allocated = AtomicUsize::new(0);
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry(&vec![1u8;1000][..]);
+        let data = writer.finalize_in(CountingAllocator(&allocated));
+        assert!(allocated.load(Ordering::Relaxed) >= data.len());
+        assert_eq!(&data[..],&writer.finalize()[..]);
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+    }
+}
@@ -0,0 +1,429 @@
+//!In place appends to existing buffers. `MemBufferWriter::from` copies every entry into the
+//!writer, `MemBufferAppender` only reads the index and the metadata of the buffer instead. On
+//!`commit` the new entries are written over the metadata and the index at the end of the buffer
+//!followed by the updated metadata and a new index, the existing payload stays where it is. Only
+//!buffers written with `finalize_interleaved` can grow in place, as the header of the default
+//!layout lies in front of the payload, other buffers are extended with `MemBufferWriter::from`.
+//!The interleaved layout uses 32 bit positions, so commits growing the buffer beyond 2 GB fail.
+//!The checksums and the manifest cover the whole buffer, the first `commit` reads the existing
+//!payload once to update them and later commits only read the entries they append.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferAppender};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_named("title","Frankenstein");
+//!let mut data = writer.finalize_interleaved();
+//!
+//!let mut appender = MemBufferAppender::open(&mut data).unwrap();
+//!appender.add_entry_named("year",1818);
+//!appender.commit().unwrap();
+//!assert_eq!(appender.write_stats().copied_bytes,0);
+//!
+//!let reader = MemBufferReader::new_verified(&data).unwrap();
+//!assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+//!assert_eq!(reader.load_entry_by_name::<i32>("year").unwrap(),1818);
+//!```
+//!With the `mmap` feature `MemBufferAppender::open_file` appends to a file the same way.
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferSerialize,WriteStats,Change,SYSTEM_TYPE_COMPRESSION,SYSTEM_TYPE_MANIFEST,SYSTEM_TYPE_CHECKSUM};
+use crate::checksum::{Checksums,DigestHasher};
+use crate::manifest::{Manifest,ManifestRecord};
+use crate::stream::{self,STREAM_MAGIC};
+use crate::table::EntryPosition;
+
+///Converts a format error into an `io::ErrorKind::InvalidData` error
+fn invalid_data(err: MemBufferError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData,err.to_string())
+}
+
+///Storage holding the buffer the entries are appended to
+enum Target<'t> {
+    Memory(&'t mut Vec<u8>),
+    //The map is released while the file is written
+    #[cfg(feature = "mmap")]
+    File(std::fs::File,Option<memmap2::MmapMut>),
+}
+
+impl<'t> Target<'t> {
+    fn contents(&self) -> &[u8] {
+        match self {
+            Target::Memory(data) => data,
+            #[cfg(feature = "mmap")]
+            Target::File(_,map) => map.as_ref().map_or(&[],|x| &x[..]),
+        }
+    }
+
+    fn contents_mut(&mut self) -> &mut [u8] {
+        match self {
+            Target::Memory(data) => data,
+            #[cfg(feature = "mmap")]
+            Target::File(_,map) => map.as_mut().map_or(&mut [],|x| &mut x[..]),
+        }
+    }
+
+    ///Replaces everything behind the first `keep` bytes by the tail
+    fn replace_tail(&mut self, keep: usize, tail: &[u8]) -> std::io::Result<()> {
+        match self {
+            Target::Memory(data) => {
+                data.truncate(keep);
+                data.extend_from_slice(tail);
+                Ok(())
+            },
+            #[cfg(feature = "mmap")]
+            Target::File(file,map) => {
+                use std::io::{Seek,SeekFrom,Write};
+                map.take();
+                file.set_len(keep as u64)?;
+                file.seek(SeekFrom::Start(keep as u64))?;
+                file.write_all(tail)?;
+                *map = Some(unsafe{ memmap2::MmapMut::map_mut(&*file)? });
+                Ok(())
+            },
+        }
+    }
+
+    fn flush(&self) -> std::io::Result<()> {
+        match self {
+            Target::Memory(_) => Ok(()),
+            #[cfg(feature = "mmap")]
+            Target::File(_,map) => map.as_ref().map_or(Ok(()),|x| x.flush()),
+        }
+    }
+}
+
+///Appends entries to an interleaved buffer without copying its entries, see the module
+///documentation. Entries which are not committed are discarded when the appender is dropped.
+pub struct MemBufferAppender<'t> {
+    target: Target<'t>,
+    //Holds the entries to append and the metadata of the whole buffer, names refer to the entry
+    //indices of the whole buffer
+    writer: MemBufferWriter,
+    existing: usize,
+    //Digest state over the payloads of the committed entries and the digest of every committed
+    //entry if the buffer has field checksums or a manifest, calculated by the first commit
+    committed: Option<(DigestHasher,Vec<Vec<u8>>)>,
+}
+
+impl<'t> MemBufferAppender<'t> {
+    ///Opens the buffer in the vector for appending, buffers which were not written with the
+    ///interleaved layout in the native byte order fail with `io::ErrorKind::InvalidData` and have
+    ///to be extended with `MemBufferWriter::from`
+    pub fn open(data: &'t mut Vec<u8>) -> std::io::Result<MemBufferAppender<'t>> {
+        MemBufferAppender::from_target(Target::Memory(data))
+    }
+
+    fn from_target(target: Target<'t>) -> std::io::Result<MemBufferAppender<'t>> {
+        if !target.contents().starts_with(&STREAM_MAGIC.to_ne_bytes()) {
+            return Err(invalid_data(MemBufferError::WrongFormat));
+        }
+        let reader = MemBufferReader::new(target.contents()).map_err(invalid_data)?;
+        let mut writer = MemBufferWriter::new();
+        writer.load_settings(&reader).map_err(invalid_data)?;
+        let existing = reader.len();
+        Ok(MemBufferAppender {
+            target,
+            writer,
+            existing,
+            committed: None,
+        })
+    }
+
+    ///Returns the number of entries including the ones which are not committed yet
+    pub fn len(&self) -> usize {
+        self.existing+self.writer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Adds an entry behind the entries of the buffer
+    pub fn add_entry<T: MemBufferSerialize>(&mut self, val: T) {
+        self.writer.add_entry(val);
+        self.shift_logged();
+    }
+
+    ///Moves the index of the operation the writer recorded last from the appended entries to the
+    ///entries of the whole buffer
+    fn shift_logged(&mut self) {
+        if let Some(Some(Change::Add(index,_,_))) = self.writer.changelog.as_mut().map(|x| x.last_mut()) {
+            *index += self.existing;
+        }
+    }
+
+    ///Adds an entry under the given name, an existing entry with the name stays in the buffer but
+    ///the name refers to the new entry from now on
+    pub fn add_entry_named<T: MemBufferSerialize>(&mut self, name: &str, val: T) {
+        self.writer.names.insert(name.to_string(),self.len());
+        self.add_entry(val);
+    }
+
+    ///Adds an entry owned by the given label
+    pub fn add_entry_labeled<T: MemBufferSerialize>(&mut self, val: T, label: &str) {
+        self.writer.labels.insert(self.len(),label.to_string());
+        self.add_entry(val);
+    }
+
+    ///Adds an entry carrying the given flags
+    pub fn add_entry_with_flags<T: MemBufferSerialize>(&mut self, val: T, flags: u32) {
+        self.writer.entry_metadata.entry(self.len()).or_default().flags = flags;
+        self.add_entry(val);
+    }
+
+    ///Adds an interned text entry, texts interned by the buffer keep their ids
+    pub fn add_interned_entry(&mut self, val: &str) {
+        self.writer.add_interned_entry(val);
+        self.shift_logged();
+    }
+
+    ///Writes the added entries, the metadata and the index over the metadata and the index of
+    ///the buffer and updates the checksums and the manifest. Returns the number of bytes written.
+    ///Fails with `io::ErrorKind::InvalidInput` and leaves the buffer unchanged if it would grow
+    ///beyond the 2 GB of the interleaved layout.
+    pub fn commit(&mut self) -> std::io::Result<u64> {
+        let (keep,tail) = {
+            let contents = self.target.contents();
+            let reader = MemBufferReader::new(contents).map_err(invalid_data)?;
+            let index_start = contents.len()-stream::index_len(reader.offsets.len()+reader.system.len());
+            //The records of the metadata entries behind the entries are rewritten
+            let payload_end = reader.offsets.iter().map(|x| x.end).max().unwrap_or(0);
+            let keep = match reader.system.iter().map(|x| x.start).filter(|x| *x >= payload_end+stream::RECORD_HEADER_LEN).min() {
+                Some(start) => start-stream::RECORD_HEADER_LEN,
+                None => index_start,
+            };
+            let mut system = self.writer.system_entries();
+            //New entries are never compressed, the table of the existing entries stays valid and
+            //stays in place if it is written in front of the entries
+            let mut kept = Vec::new();
+            if let Some(table) = reader.system.iter().find(|x| x.variable_type == SYSTEM_TYPE_COMPRESSION) {
+                if table.end <= keep {
+                    kept.push(table);
+                }
+                else {
+                    system.push((SYSTEM_TYPE_COMPRESSION,reader.entry_slice(table).map_err(invalid_data)?.to_vec()));
+                    system.sort_by_key(|x| std::cmp::Reverse(x.0));
+                }
+            }
+            if self.writer.manifest {
+                system.push((SYSTEM_TYPE_MANIFEST,self.writer.manifest_placeholder(self.len()+kept.len()+system.len())));
+            }
+            let fields = if self.writer.field_checksums { self.len() } else { 0 };
+            system.push((SYSTEM_TYPE_CHECKSUM,vec![0;Checksums::serialized_len(self.writer.checksum_algorithm,fields)]));
+
+            let types: Vec<i32> = self.writer.types.iter().copied().chain(system.iter().map(|x| x.0)).collect();
+            let data: Vec<&[u8]> = self.writer.data.iter().map(|x| &x[..]).chain(system.iter().map(|x| &x.1[..])).collect();
+            let mut tail = Vec::new();
+            let mut index: Vec<EntryPosition> = reader.offsets.iter().collect();
+            let written = stream::write_records(&mut tail,keep,&types,&data,false);
+            let appended = self.writer.types.len();
+            index.extend_from_slice(&written[..appended]);
+            index.extend(kept.iter().copied());
+            index.extend_from_slice(&written[appended..]);
+            stream::write_index(&mut tail,&index,false);
+            if keep+tail.len() > i32::MAX as usize {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,"Memory buffer error: Interleaved buffers are limited to 2 GB"));
+            }
+            if self.committed.is_none() {
+                //The payload checksum covers the entries in the order they are placed in
+                let mut committed = (self.writer.checksum_algorithm.hasher(),Vec::new());
+                for table in kept.iter() {
+                    committed.0.update(reader.entry_slice(*table).map_err(invalid_data)?);
+                }
+                digest_entries(&self.writer,&reader,0..self.existing,&mut committed).map_err(invalid_data)?;
+                self.committed = Some(committed);
+            }
+            (keep,tail)
+        };
+        self.target.replace_tail(keep,&tail)?;
+        self.write_digests(keep).map_err(invalid_data)?;
+        self.target.flush()?;
+
+        self.writer.counters.add_reused(keep);
+        self.writer.counters.add_written(tail.len());
+        self.existing += self.writer.len();
+        self.writer.types.clear();
+        self.writer.data.clear();
+        Ok(tail.len() as u64)
+    }
+
+    ///Adds the committed entries to the digest state and writes the manifest and the checksums,
+    ///only the appended entries and the metadata entries are read. The digest state already covers
+    ///the metadata entries in front of `keep`.
+    fn write_digests(&mut self, keep: usize) -> Result<(),MemBufferError> {
+        let algorithm = self.writer.checksum_algorithm;
+        let mut committed = self.committed.take().ok_or(MemBufferError::WrongFormat)?;
+        let buffer = self.target.contents_mut();
+        let (manifest,checksum) = {
+            let reader = MemBufferReader::new(buffer)?;
+            digest_entries(&self.writer,&reader,self.existing..reader.len(),&mut committed)?;
+            let manifest = match reader.system.iter().find(|x| x.variable_type == SYSTEM_TYPE_MANIFEST) {
+                Some(entry) => {
+                    let mut records: Vec<ManifestRecord> = reader.offsets.iter().zip(committed.1.iter()).map(|(entry,digest)| ManifestRecord {
+                        variable_type: entry.variable_type,
+                        len: entry.len(),
+                        digest: digest.clone(),
+                    }).collect();
+                    for entry in reader.system.iter().take_while(|x| x.variable_type != SYSTEM_TYPE_MANIFEST) {
+                        records.push(ManifestRecord {
+                            variable_type: entry.variable_type,
+                            len: entry.len(),
+                            digest: algorithm.digest(core::iter::once(reader.entry_slice(entry)?)),
+                        });
+                    }
+                    Some((Manifest { algorithm, records },entry.start))
+                },
+                None => None,
+            };
+            let checksum = reader.system.iter().find(|x| x.variable_type == SYSTEM_TYPE_CHECKSUM).ok_or(MemBufferError::WrongFormat)?;
+            (manifest,(reader.header_digest(algorithm),checksum.start))
+        };
+        let (header,checksum_start) = checksum;
+        //The positions of interleaved buffers are relative to the start of the buffer
+        if let Some((manifest,start)) = manifest.as_ref() {
+            manifest.serialize(&mut buffer[*start..],false);
+        }
+        //The metadata entries are hashed behind the entries, the manifest only after it was written
+        let mut total = committed.0.clone();
+        {
+            let reader = MemBufferReader::new(buffer)?;
+            for entry in reader.system.iter().filter(|x| x.start >= keep && x.variable_type != SYSTEM_TYPE_CHECKSUM) {
+                total.update(reader.entry_slice(entry)?);
+            }
+        }
+        let checksums = Checksums {
+            algorithm,
+            header,
+            payload: total.finalize(),
+            fields: if self.writer.field_checksums { committed.1.clone() } else { Vec::new() },
+        };
+        checksums.serialize(&mut buffer[checksum_start..],false);
+        self.committed = Some(committed);
+        Ok(())
+    }
+
+    ///Returns the bytes left in place and written by the commits so far
+    pub fn write_stats(&self) -> WriteStats {
+        self.writer.write_stats()
+    }
+}
+
+///Adds the payloads of the entries in the range to the digest state and their digests to the list
+///if the buffer has field checksums or a manifest
+fn digest_entries(writer: &MemBufferWriter, reader: &MemBufferReader, entries: std::ops::Range<usize>, committed: &mut (DigestHasher,Vec<Vec<u8>>)) -> Result<(),MemBufferError> {
+    for index in entries {
+        let slice = reader.entry_slice(reader.offsets.get(index))?;
+        committed.0.update(slice);
+        if writer.field_checksums || writer.manifest {
+            committed.1.push(writer.checksum_algorithm.digest(core::iter::once(slice)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "mmap")]
+impl MemBufferAppender<'static> {
+    ///Opens the buffer file for appending like `MemBufferAppender::open`, the file must not be
+    ///modified by others while the appender is open
+    pub fn open_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<MemBufferAppender<'static>> {
+        let file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+        let map = unsafe{ memmap2::MmapMut::map_mut(&file)? };
+        MemBufferAppender::from_target(Target::File(file,Some(map)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferAppender};
+
+    #[test]
+    fn check_appender() {
+        let mut writer = MemBufferWriter::new().with_field_checksums().with_manifest();
+        writer.add_entry_named("title","Frankenstein");
+        writer.add_interned_entry("novel");
+        writer.add_entry(&vec![7u8;1000][..]);
+        let mut data = writer.finalize_interleaved();
+        let original = data.clone();
+
+        let mut appender = MemBufferAppender::open(&mut data).unwrap();
+        appender.add_entry_named("year",1818);
+        appender.add_interned_entry("novel");
+        assert_eq!(appender.len(),5);
+        let written = appender.commit().unwrap();
+        appender.add_entry_named("title","The Modern Prometheus");
+        appender.commit().unwrap();
+        let stats = appender.write_stats();
+        assert_eq!(stats.copied_bytes,0);
+        assert!(stats.reused_bytes > 2000);
+        assert!(written < 1000);
+
+        //The payload of the existing entries was not moved
+        let pos = original.windows(1000).position(|x| x == &[7u8;1000][..]).unwrap();
+        assert_eq!(data[..pos+1000],original[..pos+1000]);
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        assert!(reader.verify_manifest().is_ok());
+        assert_eq!(reader.len(),6);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Frankenstein");
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"The Modern Prometheus");
+        assert_eq!(reader.load_entry_by_name::<i32>("year").unwrap(),1818);
+        assert_eq!(reader.load_entry::<&str>(4).unwrap(),"novel");
+        assert_eq!(MemBufferWriter::from(&data).unwrap().load_entry::<&[u8]>(2).unwrap().len(),1000);
+
+        //The metadata of earlier commits is overwritten, repeated commits only add their entries
+        let mut appender = MemBufferAppender::open(&mut data).unwrap();
+        for year in 0..6 {
+            appender.add_entry_named("year",1831+year);
+            appender.commit().unwrap();
+        }
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        assert!(reader.verify_manifest().is_ok());
+        assert_eq!(reader.load_entry_by_name::<i32>("year").unwrap(),1836);
+        assert_eq!(data.len(),MemBufferWriter::from(&data).unwrap().finalize_interleaved().len());
+
+        //The header of the default layout lies in front of the payload
+        let mut front = writer.finalize();
+        assert_eq!(MemBufferAppender::open(&mut front).err().unwrap().kind(),std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn check_append_compressed() {
+        let text = "It was on a dreary night of November ".repeat(100);
+        let mut writer = MemBufferWriter::new().with_manifest();
+        writer.add_entry_compressed(&text,crate::Compression::Lz4);
+        let mut data = writer.finalize_interleaved();
+
+        let mut appender = MemBufferAppender::open(&mut data).unwrap();
+        for x in 0..3 {
+            appender.add_entry(x);
+            appender.commit().unwrap();
+        }
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        assert!(reader.verify_manifest().is_ok());
+        assert_eq!(reader.load_entry::<String>(0).unwrap(),text);
+        assert_eq!(reader.load_entry::<i32>(3).unwrap(),2);
+
+        //The compression table stays in front of the entries
+        let entries: Vec<_> = crate::MemBufferStreamReader::new(&data[..]).unwrap().map(|x| x.unwrap()).collect();
+        assert_eq!(entries.len(),4);
+        assert!(entries[0].compressed && !entries[1].compressed);
+        assert_eq!(entries[3].load_entry::<i32>().unwrap(),2);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn check_file_appender() {
+        let path = std::env::temp_dir().join("membuffer_append_test.mem");
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        std::fs::write(&path,writer.finalize_interleaved()).unwrap();
+
+        let mut appender = MemBufferAppender::open_file(&path).unwrap();
+        appender.add_entry("World");
+        appender.commit().unwrap();
+        drop(appender);
+        let data = std::fs::read(&path).unwrap();
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"World");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
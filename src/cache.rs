@@ -0,0 +1,140 @@
+//!Hot reloading of read-mostly buffers like configurations or indices. `CachedBuffer` holds the
+//!current buffer together with a generation number which grows with every replacement.
+//!`CachedBuffer::load` hands out a guard keeping the buffer alive, so readers only hold the lock
+//!for cloning a reference count and keep working on their version while a writer thread swaps in
+//!a new buffer with `CachedBuffer::store`. Readers compare generations to notice replacements.
+//!```rust
+//!use membuffer::{MemBufferWriter,CachedBuffer};
+//!use std::sync::Arc;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_named("limit",10);
+//!let cache = Arc::new(CachedBuffer::new(writer.finalize()).unwrap());
+//!let config = cache.load();
+//!
+//!let shared = cache.clone();
+//!std::thread::spawn(move || {
+//!    let mut writer = MemBufferWriter::new();
+//!    writer.add_entry_named("limit",20);
+//!    shared.store_writer(&writer).unwrap();
+//!}).join().unwrap();
+//!
+//!//The guard still sees the version it was loaded from
+//!assert_eq!(config.load_entry_by_name::<i32>("limit").unwrap(),10);
+//!assert_eq!(cache.load().load_entry_by_name::<i32>("limit").unwrap(),20);
+//!assert_eq!((config.generation(),cache.generation()),(0,1));
+//!```
+use std::sync::{Arc,RwLock};
+use crate::{MemBufferWriter,MemBufferOwnedReader,MemBufferError};
+
+///One version of the cached buffer
+struct Generation {
+    reader: MemBufferOwnedReader<Arc<[u8]>>,
+    generation: u64,
+}
+
+///Shared buffer which can be replaced atomically, see the module documentation
+pub struct CachedBuffer {
+    current: RwLock<Arc<Generation>>,
+}
+
+///Buffer of one generation, dereferences to its reader
+#[derive(Clone)]
+pub struct CachedGuard {
+    current: Arc<Generation>,
+}
+
+impl CachedGuard {
+    ///Returns the generation of the buffer, the first buffer has generation 0
+    pub fn generation(&self) -> u64 {
+        self.current.generation
+    }
+}
+
+impl std::ops::Deref for CachedGuard {
+    type Target = MemBufferOwnedReader<Arc<[u8]>>;
+
+    fn deref(&self) -> &MemBufferOwnedReader<Arc<[u8]>> {
+        &self.current.reader
+    }
+}
+
+impl CachedBuffer {
+    ///Checks the buffer like `MemBufferOwnedReader::new` and caches it as generation 0
+    pub fn new<T: Into<Arc<[u8]>>>(data: T) -> Result<CachedBuffer,MemBufferError> {
+        Ok(CachedBuffer {
+            current: RwLock::new(Arc::new(Generation {
+                reader: MemBufferOwnedReader::new(data.into())?,
+                generation: 0,
+            })),
+        })
+    }
+
+    ///Returns a guard on the current buffer, it stays valid after the buffer was replaced
+    pub fn load(&self) -> CachedGuard {
+        CachedGuard {
+            current: self.current.read().unwrap_or_else(|x| x.into_inner()).clone(),
+        }
+    }
+
+    ///Returns the generation of the current buffer
+    pub fn generation(&self) -> u64 {
+        self.current.read().unwrap_or_else(|x| x.into_inner()).generation
+    }
+
+    ///Replaces the buffer and returns the new generation. The buffer is checked before the lock
+    ///is taken, an invalid buffer fails and keeps the current one.
+    pub fn store<T: Into<Arc<[u8]>>>(&self, data: T) -> Result<u64,MemBufferError> {
+        let reader = MemBufferOwnedReader::new(data.into())?;
+        let mut current = self.current.write().unwrap_or_else(|x| x.into_inner());
+        let generation = current.generation+1;
+        *current = Arc::new(Generation {
+            reader,
+            generation,
+        });
+        Ok(generation)
+    }
+
+    ///Finalizes the writer and replaces the buffer with the result
+    pub fn store_writer(&self, writer: &MemBufferWriter) -> Result<u64,MemBufferError> {
+        self.store(writer.finalize())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,CachedBuffer};
+    use std::sync::Arc;
+
+    #[test]
+    fn check_cached_buffer() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry(0u64);
+        let cache = Arc::new(CachedBuffer::new(writer.finalize()).unwrap());
+
+        let updater = {
+            let cache = cache.clone();
+            std::thread::spawn(move || {
+                for x in 1..=100u64 {
+                    let mut writer = MemBufferWriter::new();
+                    writer.add_entry(x);
+                    assert_eq!(cache.store_writer(&writer).unwrap(),x);
+                }
+            })
+        };
+        //Every guard sees a complete buffer whose content matches its generation
+        let mut last = 0;
+        while last < 100 {
+            let guard = cache.load();
+            assert_eq!(guard.load_entry::<u64>(0).unwrap(),guard.generation());
+            assert!(guard.generation() >= last);
+            last = guard.generation();
+        }
+        updater.join().unwrap();
+
+        assert!(cache.store(vec![1u8,2,3]).is_err());
+        assert_eq!(cache.generation(),100);
+        assert_eq!(cache.load().load_entry::<u64>(0).unwrap(),100);
+    }
+}
@@ -0,0 +1,209 @@
+//!Changelog of the operations of a writer. Writers created with `with_changelog` record every
+//!added, replaced, inserted, removed, swapped and truncated entry, the operations carrying a
+//!payload record the digest of the payload calculated with the checksum algorithm of the writer.
+//!The changelog is stored as metadata entry and writers loaded from the buffer with
+//!`MemBufferWriter::from` continue it, so the changelog of a version starts with the changelog of
+//!the version it was created from. A replica holding the previous version only has to look at the
+//!operations behind the ones it already knows and fetch the entries whose digest it lacks.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,Change};
+//!
+//!let mut writer = MemBufferWriter::new().with_changelog();
+//!writer.add_entry("draft");
+//!let first = writer.finalize();
+//!let known = MemBufferReader::new(&first).unwrap().changelog().unwrap().len();
+//!
+//!let mut update = MemBufferWriter::from(&first).unwrap();
+//!update.set_entry("final",0).unwrap();
+//!update.add_entry(42);
+//!let second = update.finalize();
+//!
+//!let changes = MemBufferReader::new(&second).unwrap().changelog().unwrap();
+//!assert!(matches!(changes[known..],[Change::Set(0,_,_),Change::Add(1,_,_)]));
+//!```
+use crate::{MemBufferReader,MemBufferError,ChecksumAlgorithm,SYSTEM_TYPE_CHANGELOG};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+use crate::endian::read_u32;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///One recorded operation, the payload operations hold the index, the type id and the digest of
+///the payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Add(usize,i32,Vec<u8>),
+    ///The entry at the index was replaced
+    Set(usize,i32,Vec<u8>),
+    ///The entry was inserted at the index, the following entries moved up
+    Insert(usize,i32,Vec<u8>),
+    ///The entry at the index was removed, the following entries moved down
+    Remove(usize),
+    Swap(usize,usize),
+    ///All entries from the index on were removed
+    Truncate(usize),
+}
+
+impl Change {
+    ///Size of one record without its digest, the operation, the index and the argument
+    const RECORD_LEN: usize = 12;
+
+    ///Returns the id of the operation, the index and the type id or second index
+    #[cfg(feature = "std")]
+    fn fields(&self) -> (u32,usize,u32,Option<&[u8]>) {
+        match self {
+            Change::Add(index,variable_type,digest) => (0,*index,*variable_type as u32,Some(digest)),
+            Change::Set(index,variable_type,digest) => (1,*index,*variable_type as u32,Some(digest)),
+            Change::Insert(index,variable_type,digest) => (2,*index,*variable_type as u32,Some(digest)),
+            Change::Remove(index) => (3,*index,0,None),
+            Change::Swap(a,b) => (4,*a,*b as u32,None),
+            Change::Truncate(len) => (5,*len,0,None),
+        }
+    }
+
+    fn from_fields(operation: u32, index: usize, arg: u32, digest: &[u8]) -> Result<Change,MemBufferError> {
+        match operation {
+            0 => Ok(Change::Add(index,arg as i32,digest.to_vec())),
+            1 => Ok(Change::Set(index,arg as i32,digest.to_vec())),
+            2 => Ok(Change::Insert(index,arg as i32,digest.to_vec())),
+            3 => Ok(Change::Remove(index)),
+            4 => Ok(Change::Swap(index,arg as usize)),
+            5 => Ok(Change::Truncate(index)),
+            _ => Err(MemBufferError::WrongFormat),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Records the operations of the writer in a changelog stored with the buffer, see the module
+    ///documentation. Select the checksum algorithm before entries are added.
+    pub fn with_changelog(mut self) -> MemBufferWriter {
+        if self.changelog.is_none() {
+            self.changelog = Some(Vec::new());
+        }
+        self
+    }
+
+    ///Returns the recorded operations if the writer records a changelog
+    pub fn changelog(&self) -> Option<&[Change]> {
+        self.changelog.as_deref()
+    }
+
+    pub(crate) fn log_change(&mut self, change: Change) {
+        if let Some(changelog) = self.changelog.as_mut() {
+            changelog.push(change);
+        }
+    }
+
+    ///Records an operation on the payload of the entry at the index, the digest is only
+    ///calculated if a changelog is recorded
+    pub(crate) fn log_payload(&mut self, index: usize, change: fn(usize,i32,Vec<u8>) -> Change) {
+        if self.changelog.is_some() {
+            let digest = self.checksum_algorithm.digest(core::iter::once(&self.data[index][..]));
+            self.log_change(change(index,self.types[index],digest));
+        }
+    }
+
+    ///Serializes the algorithm id followed by one record per operation, operations without
+    ///payload store an empty digest
+    pub(crate) fn changelog_table(&self) -> Option<Vec<u8>> {
+        let changelog = self.changelog.as_ref()?;
+        let digest_len = self.checksum_algorithm.digest_len();
+        let swapped = self.byte_order.is_swapped();
+        let u32_bytes = |x: u32| if swapped { x.swap_bytes().to_ne_bytes() } else { x.to_ne_bytes() };
+        let mut table = Vec::with_capacity(4+changelog.len()*(Change::RECORD_LEN+digest_len));
+        table.extend_from_slice(&u32_bytes(self.checksum_algorithm.id()));
+        for change in changelog.iter() {
+            let (operation,index,arg,digest) = change.fields();
+            table.extend_from_slice(&u32_bytes(operation));
+            table.extend_from_slice(&u32_bytes(index as u32));
+            table.extend_from_slice(&u32_bytes(arg));
+            match digest {
+                Some(digest) => table.extend_from_slice(digest),
+                None => table.resize(table.len()+digest_len,0),
+            }
+        }
+        Some(table)
+    }
+
+    ///Continues the changelog of the reader
+    pub(crate) fn load_changelog(&mut self, reader: &MemBufferReader) -> Result<(),MemBufferError> {
+        if reader.system_entry(SYSTEM_TYPE_CHANGELOG).is_some() {
+            self.checksum_algorithm = reader.changelog_algorithm()?;
+            self.changelog = Some(reader.changelog()?);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    fn changelog_algorithm(&self) -> Result<ChecksumAlgorithm,MemBufferError> {
+        match self.system_entry(SYSTEM_TYPE_CHANGELOG) {
+            Some(table) if table.len() >= 4 => ChecksumAlgorithm::from_id(read_u32(table,self.is_byte_swapped())),
+            _ => Err(MemBufferError::WrongFormat),
+        }
+    }
+
+    ///Returns the operations recorded by writers created with `with_changelog`, buffers without
+    ///changelog return no operations
+    pub fn changelog(&self) -> Result<Vec<Change>,MemBufferError> {
+        let table = match self.system_entry(SYSTEM_TYPE_CHANGELOG) {
+            Some(table) => table,
+            None => return Ok(Vec::new()),
+        };
+        let swapped = self.is_byte_swapped();
+        let record_len = Change::RECORD_LEN+self.changelog_algorithm()?.digest_len();
+        if (table.len()-4)%record_len != 0 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        table[4..].chunks_exact(record_len).map(|record| {
+            Change::from_fields(read_u32(record,swapped),read_u32(&record[4..],swapped) as usize,read_u32(&record[8..],swapped),&record[Change::RECORD_LEN..])
+        }).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferAppender,Change};
+
+    #[test]
+    fn check_changelog() {
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big).with_changelog();
+        writer.add_entry("first");
+        writer.add_entry_named("second",2);
+        writer.add_entry_named("second",3);
+        writer.add_interned_entry("label");
+        writer.insert_entry(0,"zero");
+        writer.swap_entries(1,2);
+        writer.remove_entry(3);
+        writer.truncate(2);
+        let recorded = writer.changelog().unwrap().to_vec();
+        assert_eq!(recorded.len(),8);
+        assert!(matches!(recorded[2],Change::Set(1,x,_) if x == crate::MemBufferTypes::Integer32 as i32));
+        assert!(matches!((&recorded[1],&recorded[2]),(Change::Add(_,_,a),Change::Set(_,_,b)) if a != b));
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.changelog().unwrap(),recorded);
+        let mut update = MemBufferWriter::from(&data).unwrap();
+        update.add_entry("more");
+        let reader_data = update.finalize();
+        let changes = MemBufferReader::new(&reader_data).unwrap().changelog().unwrap();
+        assert_eq!(changes[..8],recorded[..]);
+        assert!(matches!(changes[8],Change::Add(2,_,_)));
+
+        //Appended entries are recorded with their index in the whole buffer
+        let mut interleaved = MemBufferWriter::from(&data).unwrap().finalize_interleaved();
+        let mut appender = MemBufferAppender::open(&mut interleaved).unwrap();
+        appender.add_entry("appended");
+        appender.commit().unwrap();
+        assert!(matches!(MemBufferReader::new(&interleaved).unwrap().changelog().unwrap().last(),Some(Change::Add(2,_,_))));
+
+        let mut plain = MemBufferWriter::new();
+        plain.add_entry("untracked");
+        assert!(plain.changelog().is_none());
+        assert!(MemBufferReader::new(&plain.finalize()).unwrap().changelog().unwrap().is_empty());
+    }
+}
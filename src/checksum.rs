@@ -0,0 +1,527 @@
+//!CRC32 checksums written by every finalize. The checksum is stored as metadata entry behind the
+//!user entries and covers the entry table as well as the payload of all other entries, optionally
+//!the writer adds one checksum per field. `MemBufferReader::new` does not look at the checksums
+//!to keep loading trusted mmaps fast, `MemBufferReader::new_verified` checks all of them.
+//!
+//!CRC32 is the default algorithm, CRC32C, XXH3 and BLAKE3 are available behind the features
+//!`crc32c`, `xxh3` and `blake3`. The algorithm is recorded in the buffer so readers pick the
+//!right one automatically.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello World");
+//!let mut data = writer.finalize();
+//!assert!(MemBufferReader::new_verified(&data).is_ok());
+//!
+//!//Flip a bit in the payload, the fast path does not notice
+//!let pos = data.windows(5).position(|x| x == b"Hello").unwrap();
+//!data[pos] ^= 1;
+//!assert!(MemBufferReader::new(&data).is_ok());
+//!assert!(MemBufferReader::new_verified(&data).is_err());
+//!```
+use byteorder::{NativeEndian,ByteOrder};
+use crate::{MemBufferReader,MemBufferError,EntryPosition,SYSTEM_TYPE_CHECKSUM};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///The algorithms which can be used for the checksums of a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Crc32,
+    #[cfg(feature = "crc32c")]
+    Crc32c,
+    #[cfg(feature = "xxh3")]
+    Xxh3,
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    ///Returns the id the algorithm is recorded with in the buffer
+    pub fn id(&self) -> u32 {
+        match self {
+            ChecksumAlgorithm::Crc32 => 0,
+            #[cfg(feature = "crc32c")]
+            ChecksumAlgorithm::Crc32c => 1,
+            #[cfg(feature = "xxh3")]
+            ChecksumAlgorithm::Xxh3 => 2,
+            #[cfg(feature = "blake3")]
+            ChecksumAlgorithm::Blake3 => 3,
+        }
+    }
+
+    ///Returns the algorithm with the given id, fails for unknown ids and for algorithms whose
+    ///feature is not enabled
+    pub fn from_id(id: u32) -> Result<ChecksumAlgorithm,MemBufferError> {
+        match id {
+            0 => Ok(ChecksumAlgorithm::Crc32),
+            #[cfg(feature = "crc32c")]
+            1 => Ok(ChecksumAlgorithm::Crc32c),
+            #[cfg(feature = "xxh3")]
+            2 => Ok(ChecksumAlgorithm::Xxh3),
+            #[cfg(feature = "blake3")]
+            3 => Ok(ChecksumAlgorithm::Blake3),
+            _ => Err(MemBufferError::UnsupportedChecksum(id)),
+        }
+    }
+
+    ///Returns the size of one digest in bytes
+    pub fn digest_len(&self) -> usize {
+        match self {
+            ChecksumAlgorithm::Crc32 => 4,
+            #[cfg(feature = "crc32c")]
+            ChecksumAlgorithm::Crc32c => 4,
+            #[cfg(feature = "xxh3")]
+            ChecksumAlgorithm::Xxh3 => 8,
+            #[cfg(feature = "blake3")]
+            ChecksumAlgorithm::Blake3 => 32,
+        }
+    }
+
+    ///Calculates the digest over all given parts as if they were one continuous slice
+    pub(crate) fn digest<'x>(&self, parts: impl Iterator<Item=&'x [u8]>) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        parts.for_each(|x| hasher.update(x));
+        hasher.finalize()
+    }
+
+    ///Returns a hasher to calculate a digest incrementally
+    pub(crate) fn hasher(&self) -> DigestHasher {
+        match self {
+            ChecksumAlgorithm::Crc32 => DigestHasher::Crc32(crc32fast::Hasher::new()),
+            #[cfg(feature = "crc32c")]
+            ChecksumAlgorithm::Crc32c => DigestHasher::Crc32c(0),
+            #[cfg(feature = "xxh3")]
+            ChecksumAlgorithm::Xxh3 => DigestHasher::Xxh3(Box::new(xxhash_rust::xxh3::Xxh3::new())),
+            #[cfg(feature = "blake3")]
+            ChecksumAlgorithm::Blake3 => DigestHasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+///State of an incremental digest calculation
+#[derive(Clone)]
+pub(crate) enum DigestHasher {
+    Crc32(crc32fast::Hasher),
+    #[cfg(feature = "crc32c")]
+    Crc32c(u32),
+    #[cfg(feature = "xxh3")]
+    Xxh3(Box<xxhash_rust::xxh3::Xxh3>),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl DigestHasher {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            DigestHasher::Crc32(hasher) => hasher.update(data),
+            #[cfg(feature = "crc32c")]
+            DigestHasher::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc,data),
+            #[cfg(feature = "xxh3")]
+            DigestHasher::Xxh3(hasher) => hasher.update(data),
+            #[cfg(feature = "blake3")]
+            DigestHasher::Blake3(hasher) => {hasher.update(data);},
+        }
+    }
+
+    pub(crate) fn finalize(self) -> Vec<u8> {
+        match self {
+            DigestHasher::Crc32(hasher) => hasher.finalize().to_le_bytes().to_vec(),
+            #[cfg(feature = "crc32c")]
+            DigestHasher::Crc32c(crc) => crc.to_le_bytes().to_vec(),
+            #[cfg(feature = "xxh3")]
+            DigestHasher::Xxh3(hasher) => hasher.digest().to_le_bytes().to_vec(),
+            #[cfg(feature = "blake3")]
+            DigestHasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+///Checksums of a buffer, the header checksum covers the entry table, the payload checksum the
+///payload of every entry except the checksum entry itself
+pub(crate) struct Checksums {
+    pub(crate) algorithm: ChecksumAlgorithm,
+    pub(crate) header: Vec<u8>,
+    pub(crate) payload: Vec<u8>,
+    pub(crate) fields: Vec<Vec<u8>>,
+}
+
+impl Checksums {
+    pub(crate) fn serialized_len(algorithm: ChecksumAlgorithm, fields: usize) -> usize {
+        4+(2+fields)*algorithm.digest_len()
+    }
+
+    ///Writes the checksums, the algorithm id is swapped for buffers with the other byte order while
+    ///the digests are always stored little endian
+    pub(crate) fn serialize(&self, to: &mut [u8], swapped: bool) {
+        NativeEndian::write_u32(to,if swapped { self.algorithm.id().swap_bytes() } else { self.algorithm.id() });
+        let mut pos = 4;
+        for digest in core::iter::once(&self.header).chain(core::iter::once(&self.payload)).chain(self.fields.iter()) {
+            to[pos..pos+digest.len()].copy_from_slice(digest);
+            pos += digest.len();
+        }
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Calculates the checksums of the buffer, the field checksums are only calculated on request
+    fn calculate_checksums(&self, algorithm: ChecksumAlgorithm, with_fields: bool) -> Result<Checksums,MemBufferError> {
+        //The payload checksum covers the entries in the order they are placed in the payload, the
+        //field checksums are calculated in the same pass
+        let mut placed: Vec<(Option<usize>,EntryPosition)> = self.offsets.iter().enumerate().map(|(x,entry)| (Some(x),entry))
+            .chain(self.system.iter().filter(|x| x.variable_type != SYSTEM_TYPE_CHECKSUM).map(|entry| (None,entry))).collect();
+        if placed.windows(2).any(|x| x[0].1.start > x[1].1.start) {
+            placed.sort_by_key(|x| x.1.start);
+        }
+        let mut payload = algorithm.hasher();
+        let mut fields = vec![Vec::new();if with_fields { self.offsets.len() } else { 0 }];
+        //Entries sharing the payload of an entry placed before them are hashed once
+        let mut hashed = 0;
+        for (index,entry) in placed {
+            let slice = self.entry_slice(entry)?;
+            if entry.start >= hashed {
+                payload.update(slice);
+                hashed = entry.end;
+            }
+            if let (true,Some(index)) = (with_fields,index) {
+                fields[index] = algorithm.digest(core::iter::once(slice));
+            }
+        }
+        Ok(Checksums {
+            algorithm,
+            header: self.header_digest(algorithm),
+            payload: payload.finalize(),
+            fields,
+        })
+    }
+
+    ///Calculates the digest of the entry table
+    pub(crate) fn header_digest(&self, algorithm: ChecksumAlgorithm) -> Vec<u8> {
+        //The visible and the metadata entries are stored in one continuous table
+        algorithm.digest(core::iter::once(self.offsets.stored(self.system.len())))
+    }
+
+    ///Returns the stored checksums and their algorithm, fails if the checksum entry has neither the
+    ///length with nor the one without field checksums
+    fn stored_checksums(&self) -> Result<Option<(ChecksumAlgorithm,&'a [u8])>,MemBufferError> {
+        let stored = match self.system_entry(SYSTEM_TYPE_CHECKSUM) {
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+        let algorithm = self.checksum_algorithm()?;
+        if stored.len() != Checksums::serialized_len(algorithm,0) && stored.len() != Checksums::serialized_len(algorithm,self.len()) {
+            return Err(MemBufferError::ChecksumMismatch);
+        }
+        Ok(Some((algorithm,stored)))
+    }
+
+    ///Verifies the checksum of the entry table, buffers without checksums pass
+    pub(crate) fn verify_header_checksum(&self) -> Result<(),MemBufferError> {
+        if let Some((algorithm,stored)) = self.stored_checksums()? {
+            if self.header_digest(algorithm) != stored[4..4+algorithm.digest_len()] {
+                return Err(MemBufferError::ChecksumMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    ///Verifies the field checksum of the entry, buffers without field checksums pass
+    pub(crate) fn verify_field_checksum(&self, index: usize) -> Result<(),MemBufferError> {
+        if let Some((algorithm,stored)) = self.stored_checksums()? {
+            let len = algorithm.digest_len();
+            let start = 4+(2+index)*len;
+            if stored.len() > Checksums::serialized_len(algorithm,0) && algorithm.digest(core::iter::once(self.entry_slice(self.offsets.get(index))?)) != stored[start..start+len] {
+                return Err(MemBufferError::ChecksumMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    ///Verifies the payload of the entry at the index against its digest stored by a writer created
+    ///with `with_entry_digests` together with the checksum of the entry table, so a single entry can
+    ///be authenticated without reading the rest of the payload. Returns
+    ///`MemBufferError::MissingChecksum` for buffers without entry digests.
+    ///```rust
+    ///# #[cfg(feature = "entry_digests")] {
+    ///use membuffer::{MemBufferWriter,MemBufferReader,MemBufferError};
+    ///
+    ///let mut writer = MemBufferWriter::new().with_entry_digests();
+    ///writer.add_entry("Hello");
+    ///writer.add_entry("World");
+    ///let mut data = writer.finalize();
+    ///let pos = data.windows(5).position(|x| x == b"World").unwrap();
+    ///data[pos] ^= 1;
+    ///
+    ///let reader = MemBufferReader::new(&data).unwrap();
+    ///assert!(reader.verify_entry(0).is_ok());
+    ///assert!(matches!(reader.verify_entry(1),Err(MemBufferError::ChecksumMismatch)));
+    ///# }
+    ///```
+    pub fn verify_entry(&self, index: usize) -> Result<(),MemBufferError> {
+        if index >= self.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,self.len()));
+        }
+        let stored = self.system_entry(SYSTEM_TYPE_CHECKSUM).ok_or(MemBufferError::MissingChecksum)?;
+        self.verify_entry_digest(stored,index,self.entry_slice(self.offsets.get(index))?)
+    }
+
+    ///Verifies every entry against its digest like `verify_entry`, unlike `verify_checksums` the
+    ///metadata entries are not covered
+    pub fn verify_all(&self) -> Result<(),MemBufferError> {
+        (0..self.len()).try_for_each(|index| self.verify_entry(index))
+    }
+
+    ///Verifies the checksum of the entry table and the payload of the entry at the index against
+    ///the stored checksums, the reader only needs to hold the header so the payload can be fetched
+    ///on its own
+    pub(crate) fn verify_entry_digest(&self, stored: &[u8], index: usize, payload: &[u8]) -> Result<(),MemBufferError> {
+        if stored.len() < 4 {
+            return Err(MemBufferError::ChecksumMismatch);
+        }
+        let algorithm = ChecksumAlgorithm::from_id(crate::endian::read_u32(stored,self.is_byte_swapped()))?;
+        let len = algorithm.digest_len();
+        if stored.len() == Checksums::serialized_len(algorithm,0) {
+            return Err(MemBufferError::MissingChecksum);
+        }
+        let start = 4+(2+index)*len;
+        if stored.len() != Checksums::serialized_len(algorithm,self.len()) || self.header_digest(algorithm) != stored[4..4+len]
+            || algorithm.digest(core::iter::once(payload)) != stored[start..start+len] {
+            return Err(MemBufferError::ChecksumMismatch);
+        }
+        Ok(())
+    }
+
+    ///Creates the reader like `MemBufferReader::new_validated` and verifies the checksums of the
+    ///entry table, the payload and of every field if the writer stored field checksums as well as
+    ///the UTF-8 encoding of every text entry. Returns `MemBufferError::MissingChecksum` for buffers
+    ///written without checksums.
+    pub fn new_verified(val: &'a [u8]) -> Result<MemBufferReader<'a>,MemBufferError> {
+        let reader = MemBufferReader::new_validated(val)?;
+        reader.verify_checksums()?;
+        reader.verify_text()?;
+        Ok(reader)
+    }
+
+    ///Returns the algorithm the checksums of the buffer were written with
+    pub fn checksum_algorithm(&self) -> Result<ChecksumAlgorithm,MemBufferError> {
+        let stored = self.system_entry(SYSTEM_TYPE_CHECKSUM).ok_or(MemBufferError::MissingChecksum)?;
+        if stored.len() < 4 {
+            return Err(MemBufferError::ChecksumMismatch);
+        }
+        ChecksumAlgorithm::from_id(crate::endian::read_u32(stored,self.is_byte_swapped()))
+    }
+
+    ///Verifies the checksums stored in the buffer
+    pub fn verify_checksums(&self) -> Result<(),MemBufferError> {
+        let algorithm = self.checksum_algorithm()?;
+        let stored = self.system_entry(SYSTEM_TYPE_CHECKSUM).ok_or(MemBufferError::MissingChecksum)?;
+        let with_fields = stored.len() == Checksums::serialized_len(algorithm,self.len());
+        if !with_fields && stored.len() != Checksums::serialized_len(algorithm,0) {
+            return Err(MemBufferError::ChecksumMismatch);
+        }
+        let mut calculated = vec![0;stored.len()];
+        self.calculate_checksums(algorithm,with_fields)?.serialize(&mut calculated,self.is_byte_swapped());
+        if calculated != stored {
+            return Err(MemBufferError::ChecksumMismatch);
+        }
+        Ok(())
+    }
+
+    ///Recalculates the checksums of a buffer whose payload was modified in place with the algorithm
+    ///and the field checksums it was written with, buffers without checksums are left unchanged
+    pub(crate) fn rewrite_checksums(var: &mut [u8]) -> Result<(),MemBufferError> {
+        let (checksums,offset,swapped) = {
+            let reader = MemBufferReader::new(var)?;
+            let (algorithm,stored) = match reader.stored_checksums()? {
+                Some(x) => x,
+                None => return Ok(()),
+            };
+            let with_fields = stored.len() > Checksums::serialized_len(algorithm,0);
+            (reader.calculate_checksums(algorithm,with_fields)?,stored.as_ptr() as usize-var.as_ptr() as usize,reader.is_byte_swapped())
+        };
+        checksums.serialize(&mut var[offset..],swapped);
+        Ok(())
+    }
+
+    ///Returns the checksum settings of the buffer to carry them over into a writer
+    #[cfg(feature = "std")]
+    pub(crate) fn checksum_settings(&self) -> Option<(ChecksumAlgorithm,bool)> {
+        let algorithm = self.checksum_algorithm().ok()?;
+        let stored = self.system_entry(SYSTEM_TYPE_CHECKSUM)?;
+        Some((algorithm,stored.len() > Checksums::serialized_len(algorithm,0)))
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Additionally stores a checksum of every field
+    pub fn with_field_checksums(mut self) -> MemBufferWriter {
+        self.field_checksums = true;
+        self
+    }
+
+    ///Stores a BLAKE3 digest of every entry which `MemBufferReader::verify_entry` checks on its
+    ///own, for entries fetched separately from untrusted storage. The digests are stored as field
+    ///checksums, so the checksum algorithm of the whole buffer is switched to BLAKE3 as well.
+    #[cfg(feature = "entry_digests")]
+    pub fn with_entry_digests(self) -> MemBufferWriter {
+        self.with_field_checksums().with_checksum_algorithm(ChecksumAlgorithm::Blake3)
+    }
+
+    ///Selects the algorithm used for the checksums, CRC32 is used by default
+    pub fn with_checksum_algorithm(mut self, algorithm: ChecksumAlgorithm) -> MemBufferWriter {
+        self.checksum_algorithm = algorithm;
+        self
+    }
+
+    ///Returns the placeholder of the checksum entry, it is filled in by `write_checksums`
+    pub(crate) fn checksum_placeholder(&self) -> Vec<u8> {
+        vec![0;Checksums::serialized_len(self.checksum_algorithm,if self.field_checksums { self.types.len() } else { 0 })]
+    }
+
+    ///Calculates the checksums of the finalized buffer and writes them into the checksum entry
+    pub(crate) fn write_checksums(&self, var: &mut [u8]) {
+        let (checksums,offset,swapped) = {
+            let reader = MemBufferReader::new(var).unwrap();
+            let entry = reader.system_entry(SYSTEM_TYPE_CHECKSUM).unwrap();
+            (reader.calculate_checksums(self.checksum_algorithm,self.field_checksums).unwrap(),entry.as_ptr() as usize-var.as_ptr() as usize,reader.is_byte_swapped())
+        };
+        checksums.serialize(&mut var[offset..],swapped);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,ChecksumAlgorithm};
+
+    fn corrupt(data: &mut [u8], pattern: &[u8]) {
+        let pos = data.windows(pattern.len()).position(|x| x == pattern).unwrap();
+        data[pos] ^= 0x20;
+    }
+
+    #[test]
+    fn check_verified_reader() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Der moderne Prometheus");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry_named("title","Frankenstein");
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new_verified(&result).unwrap();
+        assert_eq!(reader.len(),3);
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+
+        let mut corrupted = result.clone();
+        corrupt(&mut corrupted,b"Prometheus");
+        assert!(MemBufferReader::new(&corrupted).is_ok());
+        assert!(matches!(MemBufferReader::new_verified(&corrupted),Err(MemBufferError::ChecksumMismatch)));
+
+        //Change the type of the second entry in the header
+        let mut corrupted = result.clone();
+        corrupted[8+12+8] ^= 1;
+        assert!(matches!(MemBufferReader::new_verified(&corrupted),Err(MemBufferError::ChecksumMismatch)));
+
+        //Positions beyond the payload fail before any checksum is calculated
+        let mut corrupted = result.clone();
+        corrupted[8+12+4..8+12+8].copy_from_slice(&u32::MAX.to_ne_bytes());
+        assert!(matches!(MemBufferReader::new_verified(&corrupted),Err(MemBufferError::WrongFormat)));
+        let header = result.len()-MemBufferReader::new(&result).unwrap().data.len();
+        for pos in 8..header {
+            let mut corrupted = result.clone();
+            corrupted[pos] ^= 0xFF;
+            assert!(MemBufferReader::new_verified(&corrupted).is_err());
+        }
+    }
+
+    #[test]
+    fn check_field_checksums() {
+        let mut writer = MemBufferWriter::new().with_field_checksums();
+        writer.add_entry("Hello");
+        writer.add_entry("World");
+        let result = writer.finalize();
+        let mut plain = MemBufferWriter::new();
+        plain.add_entry("Hello");
+        plain.add_entry("World");
+        assert_eq!(result.len(),plain.finalize().len()+8);
+        assert_eq!(MemBufferReader::new(&result).unwrap().checksum_algorithm().unwrap(),ChecksumAlgorithm::Crc32);
+        assert!(MemBufferReader::new_verified(&result).is_ok());
+        assert_eq!(MemBufferWriter::from(&result).unwrap().finalize(),result);
+
+        let interleaved = writer.finalize_interleaved();
+        assert!(MemBufferReader::new_verified(&interleaved).is_ok());
+        let mut corrupted = interleaved.clone();
+        corrupt(&mut corrupted,b"World");
+        assert!(MemBufferReader::new_verified(&corrupted).is_err());
+
+        //Entries are verified on their own against the field checksums
+        let mut corrupted = result.clone();
+        corrupt(&mut corrupted,b"World");
+        let reader = MemBufferReader::new(&corrupted).unwrap();
+        assert!(reader.verify_entry(0).is_ok());
+        assert!(matches!(reader.verify_entry(1),Err(MemBufferError::ChecksumMismatch)));
+        assert!(matches!(reader.verify_entry(2),Err(MemBufferError::IndexOutOfBounds(2,2))));
+        assert!(matches!(reader.verify_all(),Err(MemBufferError::ChecksumMismatch)));
+        assert!(MemBufferReader::new(&result).unwrap().verify_all().is_ok());
+        let plain = plain.finalize();
+        assert!(matches!(MemBufferReader::new(&plain).unwrap().verify_entry(0),Err(MemBufferError::MissingChecksum)));
+    }
+
+    #[cfg(feature = "entry_digests")]
+    #[test]
+    fn check_entry_digests() {
+        let mut writer = MemBufferWriter::new().with_entry_digests();
+        writer.add_entry("Hello");
+        writer.add_entry("World");
+        let mut result = writer.finalize();
+        let reader = MemBufferReader::new_verified(&result).unwrap();
+        assert_eq!(reader.checksum_algorithm().unwrap(),ChecksumAlgorithm::Blake3);
+        assert!(reader.verify_all().is_ok());
+        corrupt(&mut result,b"World");
+        assert!(matches!(MemBufferReader::new(&result).unwrap().verify_entry(1),Err(MemBufferError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn check_missing_checksum() {
+        //Buffer in the layout of earlier versions without any metadata entries
+        let mut legacy = Vec::new();
+        MemBufferWriter::serialize_i32_to(0,&mut legacy);
+        MemBufferWriter::serialize_i32_to((std::num::Wrapping(0i32)-std::num::Wrapping(0x7AFECAFE)).0,&mut legacy);
+        assert!(MemBufferReader::new(&legacy).is_ok());
+        assert!(matches!(MemBufferReader::new_verified(&legacy),Err(MemBufferError::MissingChecksum)));
+    }
+
+    #[test]
+    fn check_unsupported_checksum() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        let mut result = writer.finalize();
+        //The algorithm id is the first word of the checksum entry which is the last entry
+        let reader = MemBufferReader::new(&result).unwrap();
+        let offset = reader.system.get(reader.system.len()-1).start+result.len()-reader.data.len();
+        result[offset] = 200;
+        assert!(matches!(MemBufferReader::new_verified(&result),Err(MemBufferError::UnsupportedChecksum(200))));
+    }
+
+    #[cfg(all(feature = "crc32c", feature = "xxh3", feature = "blake3"))]
+    #[test]
+    fn check_checksum_algorithms() {
+        for algorithm in [ChecksumAlgorithm::Crc32c,ChecksumAlgorithm::Xxh3,ChecksumAlgorithm::Blake3].iter() {
+            let mut writer = MemBufferWriter::new().with_checksum_algorithm(*algorithm).with_field_checksums();
+            writer.add_entry("Hello");
+            writer.add_entry("World");
+            let result = writer.finalize();
+
+            let reader = MemBufferReader::new_verified(&result).unwrap();
+            assert_eq!(reader.checksum_algorithm().unwrap(),*algorithm);
+            assert_eq!(MemBufferWriter::from(&result).unwrap().finalize(),result);
+
+            let mut corrupted = result.clone();
+            corrupt(&mut corrupted,b"World");
+            assert!(matches!(MemBufferReader::new_verified(&corrupted),Err(MemBufferError::ChecksumMismatch)));
+        }
+    }
+}
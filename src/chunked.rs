@@ -0,0 +1,202 @@
+//!Large byte strings split into chunks. `MemBufferWriter::add_entry_chunked` reads the payload
+//!from a slice or any `io::Read` and stores it as `MemBufferTypes::Chunked` entry, a nested buffer
+//!holding one byte entry per chunk whose header is the chunk index. `MemBufferReader::load_chunked`
+//!iterates over the chunks as slices borrowed from the buffer, only the index is read up front so
+//!a mapped file only touches the pages of the chunks which are actually consumed.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let blob: Vec<u8> = (0..10_000u32).map(|x| x as u8).collect();
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_chunked(&blob[..],4096).unwrap();
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let chunks = reader.load_chunked(0).unwrap();
+//!assert_eq!(chunks.len(),3);
+//!assert_eq!(chunks.map(|x| x.len()).collect::<Vec<_>>(),vec![4096,4096,1808]);
+//!let joined: Vec<u8> = reader.load_chunked(0).unwrap().flatten().copied().collect();
+//!assert_eq!(joined,blob);
+//!```
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use std::io::Read;
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,MemBufferSerialize,MemBufferDeserialize};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,Change};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds the payload read from the source as chunked entry, every chunk holds `chunk_size`
+    ///bytes except for the last one. Slices can be passed directly as they implement `io::Read`.
+    ///Panics if `chunk_size` is 0.
+    pub fn add_entry_chunked<R: Read>(&mut self, mut source: R, chunk_size: usize) -> std::io::Result<()> {
+        assert!(chunk_size > 0,"Chunks have to hold at least one byte");
+        let mut chunks = MemBufferWriter::new();
+        loop {
+            let mut chunk = Vec::with_capacity(chunk_size.min(1<<20));
+            source.by_ref().take(chunk_size as u64).read_to_end(&mut chunk)?;
+            if chunk.is_empty() {
+                break;
+            }
+            chunks.add_entry(&chunk[..]);
+        }
+        self.add_chunks(chunks);
+        Ok(())
+    }
+
+    ///Adds the entries of the writer as chunks of a chunked entry, the writer has to hold byte
+    ///entries only
+    pub(crate) fn add_chunks(&mut self, chunks: MemBufferWriter) {
+        let index = self.types.len();
+        self.check_schema_field(index,MemBufferTypes::Chunked as i32);
+        self.types.push(MemBufferTypes::Chunked as i32);
+        self.data.push(Vec::new().into());
+        self.nested.insert(index,chunks);
+        self.log_payload(index,Change::Add);
+    }
+}
+
+///Chunked entry borrowed from a buffer, see the module documentation
+#[derive(Debug, Clone)]
+pub struct ChunkedView<'a> {
+    data: &'a [u8],
+    //Holds one byte entry per chunk
+    chunks: MemBufferReader<'a>,
+}
+
+impl<'a> ChunkedView<'a> {
+    ///Returns the number of chunks
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    ///Returns the number of bytes of all chunks
+    pub fn len(&self) -> usize {
+        self.chunks.offsets.iter().map(|x| x.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Returns the chunk at the index without copying it
+    pub fn chunk(&self, index: usize) -> Option<&'a [u8]> {
+        if index >= self.chunks.len() {
+            return None;
+        }
+        self.chunks.entry_slice(self.chunks.offsets.get(index)).ok()
+    }
+
+    pub fn iter(&self) -> Chunks<'a> {
+        Chunks {
+            view: self.clone(),
+            next: 0,
+        }
+    }
+
+    ///Copies all chunks into one vector
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut var = Vec::with_capacity(self.len());
+        self.iter().for_each(|x| var.extend_from_slice(x));
+        var
+    }
+}
+
+///Iterator over the chunks of a chunked entry
+#[derive(Debug, Clone)]
+pub struct Chunks<'a> {
+    view: ChunkedView<'a>,
+    next: usize,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let chunk = self.view.chunk(self.next)?;
+        self.next += 1;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize,Option<usize>) {
+        let left = self.view.chunk_count()-self.next;
+        (left,Some(left))
+    }
+}
+
+impl ExactSizeIterator for Chunks<'_> {}
+
+impl<'a> MemBufferDeserialize<'a,ChunkedView<'a>> for ChunkedView<'a> {
+    ///Checks the chunk index only, the chunks themselves are not read
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<ChunkedView<'a>,MemBufferError> {
+        let chunks = MemBufferReader::new_validated(mem)?;
+        if chunks.offsets.iter().any(|x| x.variable_type != MemBufferTypes::VectorU8 as i32) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        Ok(ChunkedView {
+            data: mem,
+            chunks,
+        })
+    }
+}
+
+impl MemBufferSerialize for ChunkedView<'_> {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Borrowed(self.data)
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::Chunked as i32
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns an iterator over the chunks of the chunked entry at the index, fails with
+    ///`MemBufferError::FieldTypeError` if the entry is not chunked
+    pub fn load_chunked(&self, index: usize) -> Result<Chunks<'a>,MemBufferError> {
+        Ok(self.load_entry::<ChunkedView<'a>>(index)?.iter())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,ChunkedView,Value};
+    use std::io::Read;
+
+    #[test]
+    fn check_chunked_entries() {
+        let blob: Vec<u8> = (0..100_000u32).map(|x| (x%251) as u8).collect();
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry("before");
+        writer.add_entry_chunked(std::io::Cursor::new(&blob).chain(&b"tail"[..]),1000).unwrap();
+        writer.add_entry_chunked(&b""[..],16).unwrap();
+        writer.add_entry_chunked(&blob[..10],10).unwrap();
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        let view = reader.load_entry::<ChunkedView>(1).unwrap();
+        assert_eq!(view.chunk_count(),101);
+        assert_eq!(view.len(),blob.len()+4);
+        assert_eq!(view.chunk(100),Some(&b"tail"[..]));
+        assert_eq!(view.chunk(101),None);
+        assert_eq!(&view.to_vec()[..blob.len()],&blob[..]);
+        let chunks = reader.load_chunked(1).unwrap();
+        assert_eq!(chunks.len(),101);
+        assert!(chunks.take(100).all(|x| x.len() == 1000));
+        assert_eq!(reader.load_chunked(2).unwrap().count(),0);
+        assert_eq!(reader.load_chunked(3).unwrap().collect::<Vec<_>>(),vec![&blob[..10]]);
+        assert!(matches!(reader.load_chunked(0),Err(MemBufferError::FieldTypeError(_,_))));
+
+        //Chunked entries are values of their own and are copied unchanged
+        let mut copy = MemBufferWriter::new();
+        for value in reader.values() {
+            copy.add_value(&value.unwrap()).unwrap();
+        }
+        let copied = copy.finalize();
+        assert!(matches!(MemBufferReader::new(&copied).unwrap().load_value(3).unwrap(),Value::Chunked(x) if x.to_vec() == blob[..10]));
+    }
+}
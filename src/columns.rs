@@ -0,0 +1,474 @@
+//!Tables of records stored column-wise. `MemBufferTableWriter` takes rows matching the declared
+//!column types and stores every column as one slice entry of a nested buffer, so the header of the
+//!nested buffer is the only offsets table. The columns are named entries in declaration order
+//!followed by the list of column names. `MemBufferTable` loads the nested buffer, `column` borrows
+//!a whole column like `&[u64]` without copying and `row` gives access to the cells of one record.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferTable,MemBufferTableWriter,ColumnType,CellValue};
+//!
+//!let mut table = MemBufferTableWriter::new(&[("ts",ColumnType::U64),("host",ColumnType::Text),("load",ColumnType::F32)]);
+//!table.push_row(&[CellValue::U64(1000),CellValue::Text("alpha"),CellValue::F32(0.5)]).unwrap();
+//!table.push_row(&[CellValue::U64(1010),CellValue::Text("beta"),CellValue::F32(1.5)]).unwrap();
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry(&table);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let table = reader.load_entry::<MemBufferTable>(0).unwrap();
+//!assert_eq!(table.len(),2);
+//!assert_eq!(table.column::<&[u64]>("ts").unwrap(),&[1000,1010]);
+//!let row = table.row(1).unwrap();
+//!assert_eq!(row.get_by_name("host"),Some(CellValue::Text("beta")));
+//!assert_eq!(row.get(2),Some(CellValue::F32(1.5)));
+//!```
+use alloc::borrow::Cow;
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,MemBufferSerialize,MemBufferDeserialize,StrListView};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Type of the cells of a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    U8,
+    U32,
+    U64,
+    F32,
+    F64,
+    Text,
+}
+
+impl ColumnType {
+    ///Returns the column type stored with the entry type, columns are stored as slices
+    fn from_entry_type(variable_type: i32) -> Option<ColumnType> {
+        match variable_type {
+            x if x == MemBufferTypes::VectorU8 as i32 => Some(ColumnType::U8),
+            x if x == MemBufferTypes::VectorU32 as i32 => Some(ColumnType::U32),
+            x if x == MemBufferTypes::VectorU64 as i32 => Some(ColumnType::U64),
+            x if x == MemBufferTypes::VectorF32 as i32 => Some(ColumnType::F32),
+            x if x == MemBufferTypes::VectorF64 as i32 => Some(ColumnType::F64),
+            x if x == MemBufferTypes::StrList as i32 => Some(ColumnType::Text),
+            _ => None
+        }
+    }
+}
+
+///Single cell of a row, the variant has to match the type of the column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellValue<'a> {
+    U8(u8),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Text(&'a str),
+}
+
+impl CellValue<'_> {
+    pub fn column_type(&self) -> ColumnType {
+        match self {
+            CellValue::U8(_) => ColumnType::U8,
+            CellValue::U32(_) => ColumnType::U32,
+            CellValue::U64(_) => ColumnType::U64,
+            CellValue::F32(_) => ColumnType::F32,
+            CellValue::F64(_) => ColumnType::F64,
+            CellValue::Text(_) => ColumnType::Text,
+        }
+    }
+}
+
+///Cells of one column collected by the writer
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+enum ColumnData {
+    U8(Vec<u8>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+    Text(Vec<String>),
+}
+
+#[cfg(feature = "std")]
+impl ColumnData {
+    fn new(column_type: ColumnType) -> ColumnData {
+        match column_type {
+            ColumnType::U8 => ColumnData::U8(Vec::new()),
+            ColumnType::U32 => ColumnData::U32(Vec::new()),
+            ColumnType::U64 => ColumnData::U64(Vec::new()),
+            ColumnType::F32 => ColumnData::F32(Vec::new()),
+            ColumnType::F64 => ColumnData::F64(Vec::new()),
+            ColumnType::Text => ColumnData::Text(Vec::new()),
+        }
+    }
+
+    fn column_type(&self) -> ColumnType {
+        match self {
+            ColumnData::U8(_) => ColumnType::U8,
+            ColumnData::U32(_) => ColumnType::U32,
+            ColumnData::U64(_) => ColumnType::U64,
+            ColumnData::F32(_) => ColumnType::F32,
+            ColumnData::F64(_) => ColumnType::F64,
+            ColumnData::Text(_) => ColumnType::Text,
+        }
+    }
+
+    ///Appends the cell, the type was checked by the caller
+    fn push(&mut self, cell: &CellValue) {
+        match (self,cell) {
+            (ColumnData::U8(x),CellValue::U8(val)) => x.push(*val),
+            (ColumnData::U32(x),CellValue::U32(val)) => x.push(*val),
+            (ColumnData::U64(x),CellValue::U64(val)) => x.push(*val),
+            (ColumnData::F32(x),CellValue::F32(val)) => x.push(*val),
+            (ColumnData::F64(x),CellValue::F64(val)) => x.push(*val),
+            (ColumnData::Text(x),CellValue::Text(val)) => x.push(val.to_string()),
+            _ => unreachable!("The cell type is checked before it is pushed")
+        }
+    }
+
+    fn add_to(&self, writer: &mut MemBufferWriter, name: &str) {
+        match self {
+            ColumnData::U8(x) => writer.add_entry_named(name,&x[..]),
+            ColumnData::U32(x) => writer.add_entry_named(name,&x[..]),
+            ColumnData::U64(x) => writer.add_entry_named(name,&x[..]),
+            ColumnData::F32(x) => writer.add_entry_named(name,&x[..]),
+            ColumnData::F64(x) => writer.add_entry_named(name,&x[..]),
+            ColumnData::Text(x) => writer.add_entry_named(name,&x[..]),
+        }
+    }
+}
+
+///Builder of a table entry collecting the rows column by column, see the module documentation
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct MemBufferTableWriter {
+    names: Vec<String>,
+    columns: Vec<ColumnData>,
+    rows: usize,
+}
+
+#[cfg(feature = "std")]
+impl MemBufferTableWriter {
+    ///Creates a table with the given column names and types
+    ///
+    ///# Panics
+    ///Panics if a column name is used twice
+    pub fn new(columns: &[(&str,ColumnType)]) -> MemBufferTableWriter {
+        for (index,(name,_)) in columns.iter().enumerate() {
+            assert!(columns[..index].iter().all(|x| x.0 != *name),"The column {} is declared twice",name);
+        }
+        MemBufferTableWriter {
+            names: columns.iter().map(|x| x.0.to_string()).collect(),
+            columns: columns.iter().map(|x| ColumnData::new(x.1)).collect(),
+            rows: 0,
+        }
+    }
+
+    ///Appends a row holding one cell per column in declaration order, fails with
+    ///`MemBufferError::WrongFormat` if the number of cells differs from the number of columns and
+    ///with `MemBufferError::FieldTypeError` if a cell does not match the type of its column. The
+    ///table is unchanged if the row is rejected.
+    pub fn push_row(&mut self, row: &[CellValue]) -> Result<(),MemBufferError> {
+        if row.len() != self.columns.len() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        for (column,cell) in self.columns.iter().zip(row) {
+            if column.column_type() != cell.column_type() {
+                return Err(MemBufferError::FieldTypeError(column_entry_type(cell.column_type()),column_entry_type(column.column_type())));
+            }
+        }
+        for (column,cell) in self.columns.iter_mut().zip(row) {
+            column.push(cell);
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    ///Returns the number of rows
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    ///Writes the table as standalone buffer, which is the payload of the table entry
+    pub fn finalize(&self) -> Vec<u8> {
+        let mut writer = MemBufferWriter::new();
+        for (name,column) in self.names.iter().zip(&self.columns) {
+            column.add_to(&mut writer,name);
+        }
+        writer.add_entry(&self.names[..]);
+        writer.finalize()
+    }
+}
+
+///Returns the entry type a column of the type is stored with
+#[cfg(feature = "std")]
+fn column_entry_type(column_type: ColumnType) -> i32 {
+    match column_type {
+        ColumnType::U8 => MemBufferTypes::VectorU8 as i32,
+        ColumnType::U32 => MemBufferTypes::VectorU32 as i32,
+        ColumnType::U64 => MemBufferTypes::VectorU64 as i32,
+        ColumnType::F32 => MemBufferTypes::VectorF32 as i32,
+        ColumnType::F64 => MemBufferTypes::VectorF64 as i32,
+        ColumnType::Text => MemBufferTypes::StrList as i32,
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferSerialize for &MemBufferTableWriter {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Owned(self.finalize())
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::MemBuffer as i32
+    }
+}
+
+///Column loaded for the row access, numbers of buffers written with the other byte order or at
+///unaligned positions are copied
+#[derive(Debug)]
+enum Column<'a> {
+    U8(&'a [u8]),
+    U32(Cow<'a,[u32]>),
+    U64(Cow<'a,[u64]>),
+    F32(Cow<'a,[f32]>),
+    F64(Cow<'a,[f64]>),
+    Text(StrListView<'a>),
+}
+
+impl<'a> Column<'a> {
+    fn load(reader: &MemBufferReader<'a>, index: usize) -> Result<Column<'a>,MemBufferError> {
+        match ColumnType::from_entry_type(reader.entry_type(index)) {
+            Some(ColumnType::U8) => Ok(Column::U8(reader.load_entry(index)?)),
+            Some(ColumnType::U32) => Ok(Column::U32(reader.load_entry(index)?)),
+            Some(ColumnType::U64) => Ok(Column::U64(reader.load_entry(index)?)),
+            Some(ColumnType::F32) => Ok(Column::F32(reader.load_entry(index)?)),
+            Some(ColumnType::F64) => Ok(Column::F64(reader.load_entry(index)?)),
+            Some(ColumnType::Text) => Ok(Column::Text(reader.load_entry(index)?)),
+            None => Err(MemBufferError::WrongFormat)
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Column::U8(x) => x.len(),
+            Column::U32(x) => x.len(),
+            Column::U64(x) => x.len(),
+            Column::F32(x) => x.len(),
+            Column::F64(x) => x.len(),
+            Column::Text(x) => x.len(),
+        }
+    }
+
+    fn column_type(&self) -> ColumnType {
+        match self {
+            Column::U8(_) => ColumnType::U8,
+            Column::U32(_) => ColumnType::U32,
+            Column::U64(_) => ColumnType::U64,
+            Column::F32(_) => ColumnType::F32,
+            Column::F64(_) => ColumnType::F64,
+            Column::Text(_) => ColumnType::Text,
+        }
+    }
+
+    fn get(&self, row: usize) -> Option<CellValue<'_>> {
+        match self {
+            Column::U8(x) => x.get(row).map(|x| CellValue::U8(*x)),
+            Column::U32(x) => x.get(row).map(|x| CellValue::U32(*x)),
+            Column::U64(x) => x.get(row).map(|x| CellValue::U64(*x)),
+            Column::F32(x) => x.get(row).map(|x| CellValue::F32(*x)),
+            Column::F64(x) => x.get(row).map(|x| CellValue::F64(*x)),
+            Column::Text(x) => x.get(row).map(CellValue::Text),
+        }
+    }
+}
+
+///Table entry borrowed from a buffer, see the module documentation
+#[derive(Debug)]
+pub struct MemBufferTable<'a> {
+    data: &'a [u8],
+    reader: MemBufferReader<'a>,
+    names: StrListView<'a>,
+    columns: Vec<Column<'a>>,
+    rows: usize,
+}
+
+impl<'a> MemBufferTable<'a> {
+    ///Returns the number of rows
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    ///Returns the column names in declaration order
+    pub fn column_names(&self) -> impl Iterator<Item=&'a str> + '_ {
+        self.names.iter()
+    }
+
+    pub fn column_type(&self, index: usize) -> Option<ColumnType> {
+        self.columns.get(index).map(Column::column_type)
+    }
+
+    ///Returns the position of the column with the given name
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.reader.entry_index(name)
+    }
+
+    ///Loads the whole column with the given name, slices like `&[u64]` borrow the buffer without
+    ///copying, `StrListView` borrows a text column. Fails with `MemBufferError::KeyNotFound` if the
+    ///table has no such column and with `MemBufferError::FieldTypeError` if the type differs.
+    pub fn column<X: MemBufferDeserialize<'a,X> + MemBufferSerialize>(&self, name: &str) -> Result<X,MemBufferError> {
+        self.reader.load_entry_by_name(name)
+    }
+
+    ///Returns the record at the index
+    pub fn row(&self, index: usize) -> Option<Row<'_,'a>> {
+        if index >= self.rows {
+            return None;
+        }
+        Some(Row {
+            table: self,
+            index,
+        })
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item=Row<'_,'a>> + '_ {
+        (0..self.rows).map(move |index| Row { table: self, index })
+    }
+}
+
+///Record of a table, the cells are read from the columns on access
+#[derive(Debug, Clone, Copy)]
+pub struct Row<'t,'a> {
+    table: &'t MemBufferTable<'a>,
+    index: usize,
+}
+
+impl<'t> Row<'t,'_> {
+    ///Returns the position of the row in the table
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    ///Returns the cell of the column at the given position
+    pub fn get(&self, column: usize) -> Option<CellValue<'t>> {
+        self.table.columns.get(column)?.get(self.index)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<CellValue<'t>> {
+        self.get(self.table.column_index(name)?)
+    }
+
+    ///Returns the cells in column order
+    pub fn cells(&self) -> impl Iterator<Item=CellValue<'t>> + '_ {
+        (0..self.table.column_count()).map(move |x| self.get(x).unwrap())
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,MemBufferTable<'a>> for MemBufferTable<'a> {
+    ///Checks the column names, types and lengths once, so the row access cannot fail
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<MemBufferTable<'a>,MemBufferError> {
+        let reader = MemBufferReader::new_validated(mem)?;
+        if reader.is_empty() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let names: StrListView<'a> = reader.load_entry(reader.len()-1)?;
+        if names.len() != reader.len()-1 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let mut columns = Vec::with_capacity(names.len());
+        for (index,name) in names.iter().enumerate() {
+            if reader.entry_index(name) != Some(index) {
+                return Err(MemBufferError::WrongFormat);
+            }
+            columns.push(Column::load(&reader,index)?);
+        }
+        let rows = columns.first().map(Column::len).unwrap_or(0);
+        if columns.iter().any(|x| x.len() != rows) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        Ok(MemBufferTable {
+            data: mem,
+            reader,
+            names,
+            columns,
+            rows,
+        })
+    }
+}
+
+impl MemBufferSerialize for MemBufferTable<'_> {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Borrowed(self.data)
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::MemBuffer as i32
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferDeserialize,MemBufferTable,MemBufferTableWriter,ColumnType,CellValue,StrListView};
+
+    #[test]
+    fn check_table() {
+        let mut table = MemBufferTableWriter::new(&[("ts",ColumnType::U64),("id",ColumnType::U32),("flag",ColumnType::U8),("value",ColumnType::F64),("name",ColumnType::Text)]);
+        for x in 0..100u32 {
+            let name = format!("row{}",x);
+            table.push_row(&[CellValue::U64(x as u64*10),CellValue::U32(x),CellValue::U8(x as u8%2),CellValue::F64(x as f64/4.0),CellValue::Text(&name)]).unwrap();
+        }
+        assert!(matches!(table.push_row(&[CellValue::U64(0)]),Err(MemBufferError::WrongFormat)));
+        assert!(matches!(table.push_row(&[CellValue::U32(0),CellValue::U32(0),CellValue::U8(0),CellValue::F64(0.0),CellValue::Text("")]),Err(MemBufferError::FieldTypeError(_,_))));
+        assert_eq!(table.len(),100);
+
+        for order in [crate::Endianness::Little,crate::Endianness::Big].iter() {
+            let mut writer = MemBufferWriter::new().with_byte_order(*order);
+            writer.add_entry("metrics");
+            writer.add_entry(&table);
+            let data = writer.finalize();
+
+            let reader = MemBufferReader::new(&data).unwrap();
+            let loaded = reader.load_entry::<MemBufferTable>(1).unwrap();
+            assert_eq!(loaded.len(),100);
+            assert_eq!(loaded.column_names().collect::<Vec<_>>(),vec!["ts","id","flag","value","name"]);
+            assert_eq!(loaded.column_type(4),Some(ColumnType::Text));
+            assert_eq!(loaded.column::<StrListView>("name").unwrap().get(42),Some("row42"));
+            assert!(matches!(loaded.column::<&[u64]>("missing"),Err(MemBufferError::KeyNotFound(_))));
+            assert!(matches!(loaded.column::<&[u64]>("id"),Err(MemBufferError::FieldTypeError(_,_))));
+            let row = loaded.row(7).unwrap();
+            assert_eq!(row.get_by_name("ts"),Some(CellValue::U64(70)));
+            assert_eq!(row.cells().collect::<Vec<_>>(),vec![CellValue::U64(70),CellValue::U32(7),CellValue::U8(1),CellValue::F64(1.75),CellValue::Text("row7")]);
+            assert!(loaded.row(100).is_none());
+            assert_eq!(loaded.rows().filter(|x| x.get(2) == Some(CellValue::U8(0))).count(),50);
+        }
+
+        //The columns of a native buffer are borrowed
+        let data = table.finalize();
+        let loaded = MemBufferTable::from_mem_buffer(&data).unwrap();
+        assert_eq!(loaded.column::<&[u64]>("ts").unwrap()[99],990);
+
+        //Nested buffers whose columns differ in length are rejected
+        let mut nested = MemBufferWriter::new();
+        nested.add_entry_named::<&[u64]>("a",&[1,2]);
+        nested.add_entry_named::<&[u64]>("b",&[1]);
+        nested.add_entry(&["a","b"][..]);
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry(nested);
+        let data = writer.finalize();
+        assert!(matches!(MemBufferReader::new(&data).unwrap().load_entry::<MemBufferTable>(0),Err(MemBufferError::WrongFormat)));
+    }
+}
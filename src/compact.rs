@@ -0,0 +1,225 @@
+//!Compact headers for buffers of many small entries. The default entry table stores 12 bytes per
+//!entry, which outweighs the payload of buffers holding hundreds of integers or short texts, for
+//!example messages of a queue. Writers created `with_compact_header` store the type of every entry
+//!and its length together with the padding in front of it as LEB128 varints, which takes two to
+//!four bytes for small entries. Readers reconstruct the positions once when the buffer is opened,
+//!afterwards entries are loaded like from any other buffer.
+//!
+//!The entries of a compact buffer are placed in the order of their indices, placement priorities
+//!are ignored. Buffers of `LEGACY_FORMAT_VERSION` cannot use compact headers and are written with
+//!the fixed size table.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!for x in 0..100 {
+//!    writer.add_entry(x);
+//!}
+//!let data = writer.finalize();
+//!let compact = writer.with_compact_header().finalize();
+//!assert!(compact.len() < data.len()/2);
+//!
+//!let reader = MemBufferReader::new(&compact).unwrap();
+//!assert_eq!(reader.load_entry::<i32>(42).unwrap(),42);
+//!```
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+use crate::MemBufferError;
+use crate::table::EntryPosition;
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,LEGACY_FORMAT_VERSION,type_alignment};
+#[cfg(feature = "std")]
+use crate::table::header_magic;
+#[cfg(feature = "std")]
+use crate::endian::swap_integers;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Appends the value as LEB128 varint
+#[cfg(feature = "std")]
+fn write_varint(to: &mut Vec<u8>, mut val: u64) {
+    while val >= 0x80 {
+        to.push(val as u8 | 0x80);
+        val >>= 7;
+    }
+    to.push(val as u8);
+}
+
+///Reads the LEB128 varint at the position and moves the position behind it
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64,MemBufferError> {
+    let mut val = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*pos).ok_or(MemBufferError::WrongFormat)?;
+        *pos += 1;
+        if shift == 63 && byte > 1 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        val |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(val);
+        }
+        shift += 7;
+        if shift > 63 {
+            return Err(MemBufferError::WrongFormat);
+        }
+    }
+}
+
+///Entry table of a compact header, decoded when the buffer is opened
+#[derive(Clone)]
+pub(crate) struct CompactTable<'a> {
+    stored: &'a [u8],
+    //Positions of all entries of the table, split tables refer to a range of them
+    entries: Arc<[EntryPosition]>,
+    start: usize,
+    end: usize,
+    swapped: bool,
+}
+
+impl<'a> CompactTable<'a> {
+    ///Decodes the table stored behind the first two words of the header, the table holds the number
+    ///of entries followed by the type of every entry and its length times 8 plus its padding
+    pub(crate) fn decode(stored: &'a [u8], swapped: bool) -> Result<CompactTable<'a>,MemBufferError> {
+        let mut pos = 0;
+        let count = read_varint(stored,&mut pos)?;
+        //Every entry takes at least two bytes, which bounds the allocation for corrupted headers
+        if count > (stored.len()/2) as u64 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let mut entries = Vec::with_capacity(count as usize);
+        let mut offset = 0usize;
+        for _ in 0..count {
+            let variable_type = u32::try_from(read_varint(stored,&mut pos)?).map_err(|_| MemBufferError::WrongFormat)?;
+            let len = read_varint(stored,&mut pos)?;
+            let start = offset.checked_add((len & 7) as usize).ok_or(MemBufferError::WrongFormat)?;
+            let end = usize::try_from(len >> 3).ok().and_then(|x| start.checked_add(x)).ok_or(MemBufferError::WrongFormat)?;
+            entries.push(EntryPosition {
+                start,
+                end,
+                //Types are zigzag encoded to keep the negative ids of metadata entries small
+                variable_type: (variable_type >> 1) as i32 ^ -((variable_type & 1) as i32),
+            });
+            offset = end;
+        }
+        Ok(CompactTable {
+            stored,
+            start: 0,
+            end: entries.len(),
+            entries: entries.into(),
+            swapped,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.end-self.start
+    }
+
+    pub(crate) fn get(&self, index: usize) -> EntryPosition {
+        self.entries[..self.end][self.start+index]
+    }
+
+    pub(crate) fn split_at(&self, mid: usize) -> (CompactTable<'a>,CompactTable<'a>) {
+        assert!(mid <= self.len(),"Entry {} is out of bounds",mid);
+        let mut first = self.clone();
+        let mut second = self.clone();
+        first.end = self.start+mid;
+        second.start = self.start+mid;
+        (first,second)
+    }
+
+    pub(crate) fn is_swapped(&self) -> bool {
+        self.swapped
+    }
+
+    ///Returns the stored table including the number of entries
+    pub(crate) fn stored(&self) -> &'a [u8] {
+        self.stored
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Writes a compact header storing the types and lengths of the entries as varints, see the
+    ///module documentation
+    pub fn with_compact_header(mut self) -> MemBufferWriter {
+        self.compact_header = true;
+        self
+    }
+
+    ///Returns whether the header is written compact, which needs a versioned layout
+    pub(crate) fn uses_compact_header(&self) -> bool {
+        self.compact_header && self.format_version > LEGACY_FORMAT_VERSION
+    }
+
+    ///Serializes the compact header for entries with the given types and lengths, the entries are
+    ///placed in the order of their indices. Returns the header and the start of every entry
+    ///relative to the end of the header.
+    pub(crate) fn serialize_compact_header(&self, types: &[i32], lens: &[usize]) -> (Vec<u8>,Vec<usize>) {
+        let mut table = Vec::with_capacity(10+types.len()*3);
+        write_varint(&mut table,types.len() as u64);
+        let mut starts = Vec::with_capacity(types.len());
+        let mut offset = 0;
+        for (variable_type,len) in types.iter().zip(lens.iter()) {
+            //The header is padded to 8 bytes, so the padding only depends on the payload before
+            let align = type_alignment(*variable_type);
+            let padding = (align - offset%align)%align;
+            write_varint(&mut table,((*variable_type << 1) ^ (*variable_type >> 31)) as u32 as u64);
+            write_varint(&mut table,(*len as u64) << 3 | padding as u64);
+            starts.push(offset+padding);
+            offset += padding+len;
+        }
+        let header_len = (8+table.len()).div_ceil(8)*8;
+        let magic = header_magic(self.format_version,false,true);
+        let mut header: Vec<u8> = Vec::with_capacity(header_len);
+        MemBufferWriter::serialize_i32_to(header_len as i32,&mut header);
+        MemBufferWriter::serialize_i32_to((core::num::Wrapping(header_len as i32)-core::num::Wrapping(magic)).0,&mut header);
+        if self.byte_order.is_swapped() {
+            swap_integers(&mut header,4);
+        }
+        header.extend_from_slice(&table);
+        header.resize(header_len,0);
+        (header,starts)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,Endianness,LEGACY_FORMAT_VERSION};
+
+    #[test]
+    fn check_compact_header() {
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big).with_compact_header().with_field_checksums().with_manifest();
+        writer.add_entry_named("title","Frankenstein");
+        writer.add_entry(1818);
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry(&vec![7u8;1000][..]);
+        writer.add_interned_entry("novel");
+        writer.add_entry::<&[u32]>(&[4,5]);
+        writer.set_placement_priorities(&[0,0,0,0,0,9]);
+        let data = writer.finalize();
+        assert_eq!(data.len(),writer.serialized_len());
+
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        assert!(reader.is_byte_swapped());
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+        assert_eq!(reader.load_entry::<i32>(1).unwrap(),1818);
+        assert_eq!(&reader.load_entry::<std::borrow::Cow<[u64]>>(2).unwrap()[..],&[1,2,3]);
+        assert_eq!(reader.load_entry::<&[u8]>(3).unwrap(),&[7u8;1000][..]);
+        assert_eq!(reader.load_entry::<&str>(4).unwrap(),"novel");
+
+        //Writers loaded from a compact buffer keep the layout, legacy buffers cannot use it
+        let copy = MemBufferWriter::from(&data).unwrap();
+        assert_eq!(copy.finalize().len(),data.len());
+        let legacy = copy.with_format_version(LEGACY_FORMAT_VERSION).finalize();
+        assert!(legacy.len() > data.len());
+        assert_eq!(MemBufferReader::new(&legacy).unwrap().load_entry::<&[u8]>(3).unwrap(),&[7u8;1000][..]);
+
+        //Corrupted tables are rejected instead of decoded
+        let mut corrupted = data.clone();
+        corrupted[8] = 0xFF;
+        assert!(matches!(MemBufferReader::new(&corrupted),Err(MemBufferError::WrongFormat)));
+        assert!(MemBufferReader::new(&data[..12]).is_err());
+    }
+}
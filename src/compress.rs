@@ -0,0 +1,457 @@
+//!Compressed entries. `add_entry_compressed` keeps the value uncompressed in the writer and
+//!compresses it with lz4 (feature `lz4`) or zstd (feature `zstd`) when the buffer is written. The
+//!stored payload of a compressed entry starts with its uncompressed length as 64 bit integer in
+//!the byte order of the buffer followed by the compressed bytes, the codec of every compressed
+//!entry is recorded in a metadata entry. All other entries stay untouched and can still be
+//!borrowed without copying. Compressed entries
+//!are decompressed on load into owned types like `String` or `Cow<[u8]>`, borrowing loads fail
+//!with `MemBufferError::CompressedEntry`. An entry may decompress to at most 4 GB.
+//!```rust
+//!# #[cfg(feature = "zstd")] {
+//!use membuffer::{MemBufferWriter,MemBufferReader,Compression};
+//!use std::borrow::Cow;
+//!
+//!let text = "It was on a dreary night of November ".repeat(1000);
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_compressed(&text,Compression::Zstd(3));
+//!writer.add_entry(&[1u8,2,3][..]);
+//!let data = writer.finalize();
+//!assert!(data.len() < text.len()/10);
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.uncompressed_len(0).unwrap(),text.len());
+//!assert_eq!(reader.load_entry::<String>(0).unwrap(),text);
+//!assert!(matches!(reader.load_entry::<Cow<[u8]>>(1).unwrap(),Cow::Borrowed(_)));
+//!# }
+//!```
+#[cfg(feature = "std")]
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+use crate::{MemBufferReader,MemBufferError,SYSTEM_TYPE_COMPRESSION};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,MemBufferSerialize,MemBufferDeserialize,Payload};
+use crate::endian::{read_u64,swap_integers,swap_width};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Size of the record of one compressed entry: index and codec
+const RECORD_SIZE: usize = 16;
+///Size of the uncompressed length in front of the compressed bytes of an entry
+const SIZE_PREFIX_LEN: usize = 8;
+
+///Largest uncompressed length a compressed entry may have, readers reject larger lengths before
+///allocating anything
+pub(crate) const MAX_UNCOMPRESSED_LEN: u64 = u32::MAX as u64;
+
+///Codec used to compress an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    #[cfg(feature = "lz4")]
+    Lz4,
+    ///Zstd with the given compression level
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+impl Compression {
+    ///Returns the codec id stored in the buffer
+    #[cfg(feature = "std")]
+    fn id(&self) -> u64 {
+        match self {
+            Compression::None => 0,
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => 1,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(_) => 2,
+        }
+    }
+
+    ///Returns the codec for the id stored in the buffer, zstd entries are recompressed with the
+    ///default level
+    #[cfg(feature = "std")]
+    fn from_id(id: u64) -> Result<Compression,MemBufferError> {
+        match id {
+            #[cfg(feature = "lz4")]
+            1 => Ok(Compression::Lz4),
+            #[cfg(feature = "zstd")]
+            2 => Ok(Compression::Zstd(zstd::DEFAULT_COMPRESSION_LEVEL)),
+            _ => Err(MemBufferError::UnsupportedCompression(id as u32)),
+        }
+    }
+
+    ///Compresses the data and puts the uncompressed length in front of the compressed bytes
+    #[cfg(feature = "std")]
+    fn compress(&self, data: &[u8], swapped: bool) -> std::io::Result<Vec<u8>> {
+        if data.len() as u64 > MAX_UNCOMPRESSED_LEN && *self != Compression::None {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,"Memory buffer error: Compressed entries are limited to 4 GB"));
+        }
+        let compressed = match self {
+            Compression::None => data.to_vec(),
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => lz4_flex::block::compress(data),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => zstd::bulk::compress(data,*level)?,
+        };
+        if *self == Compression::None {
+            return Ok(compressed);
+        }
+        let len = data.len() as u64;
+        let mut stored = if swapped { len.swap_bytes() } else { len }.to_ne_bytes().to_vec();
+        stored.extend(compressed);
+        Ok(stored)
+    }
+}
+
+///Splits the stored payload of a compressed entry into the uncompressed length and the compressed
+///bytes, lengths which do not fit into usize exceed `MAX_UNCOMPRESSED_LEN` anyway
+pub(crate) fn split_size_prefix(data: &[u8], swapped: bool) -> Result<(usize,&[u8]),MemBufferError> {
+    if data.len() < SIZE_PREFIX_LEN {
+        return Err(MemBufferError::WrongFormat);
+    }
+    let len = usize::try_from(read_u64(data,swapped)).unwrap_or(usize::MAX);
+    Ok((len,&data[SIZE_PREFIX_LEN..]))
+}
+
+///Checks the uncompressed length recorded for an entry against `MAX_UNCOMPRESSED_LEN` and against
+///the length the codec can produce from the stored bytes, so a corrupted length fails before the
+///output is allocated. Lengths of codecs which are not enabled are not checked, loading those
+///entries fails anyway.
+#[allow(unused_variables)]
+pub(crate) fn check_uncompressed_len(codec: u64, data: &[u8], len: usize) -> Result<(),MemBufferError> {
+    if len as u64 > MAX_UNCOMPRESSED_LEN {
+        return Err(MemBufferError::EntryTooLarge(len,MAX_UNCOMPRESSED_LEN as usize));
+    }
+    let possible = match codec {
+        //Every byte of an lz4 block extends a match by at most 255 bytes
+        #[cfg(feature = "lz4")]
+        1 => len <= data.len().saturating_mul(255).saturating_add(64),
+        //zstd records the content size in the frame header, a block of 4 bytes holds at most 128 KB
+        #[cfg(feature = "zstd")]
+        2 => len <= data.len().saturating_mul(1 << 15) && matches!(zstd::zstd_safe::get_frame_content_size(data),Ok(Some(x)) if x == len as u64),
+        _ => true,
+    };
+    if !possible {
+        return Err(MemBufferError::WrongFormat);
+    }
+    Ok(())
+}
+
+///Decompresses the payload of an entry with the given codec and uncompressed length
+#[allow(unused_variables)]
+fn decompress(codec: u64, data: &[u8], len: usize) -> Result<Vec<u8>,MemBufferError> {
+    check_uncompressed_len(codec,data,len)?;
+    let result: Result<Vec<u8>,MemBufferError> = match codec {
+        #[cfg(feature = "lz4")]
+        1 => lz4_flex::block::decompress(data,len).map_err(|_| MemBufferError::WrongFormat),
+        #[cfg(feature = "zstd")]
+        2 => zstd::bulk::decompress(data,len).map_err(|_| MemBufferError::WrongFormat),
+        _ => Err(MemBufferError::UnsupportedCompression(codec as u32)),
+    };
+    result.and_then(|x| if x.len() == len { Ok(x) } else { Err(MemBufferError::WrongFormat) })
+}
+
+///Decompresses the stored payload of an entry with the given type, starting with its size prefix,
+///and converts it into the native byte order
+pub(crate) fn decompress_native(codec: u64, data: &[u8], max_len: usize, variable_type: i32, swapped: bool) -> Result<Vec<u8>,MemBufferError> {
+    let (len,data) = split_size_prefix(data,swapped)?;
+    if len > max_len {
+        return Err(MemBufferError::EntryTooLarge(len,max_len));
+    }
+    let mut result = decompress(codec,data,len)?;
+    if swapped {
+        swap_integers(&mut result,swap_width(variable_type));
+    }
+    Ok(result)
+}
+
+///Loads the payload of an entry read without the rest of the buffer like `load_entry`, the codec
+///is given for compressed entries. Encrypted entries need the key and fail with
+///`MemBufferError::EncryptedEntry`.
+#[cfg(feature = "std")]
+pub(crate) fn load_detached<'a, X: MemBufferDeserialize<'a,X>+MemBufferSerialize>(data: &'a [u8], variable_type: i32, swapped: bool, compression: Option<u64>, encrypted: bool) -> Result<X,MemBufferError> {
+    if variable_type != X::get_mem_buffer_type() {
+        return Err(MemBufferError::FieldTypeError(variable_type,X::get_mem_buffer_type()));
+    }
+    if encrypted {
+        return Err(MemBufferError::EncryptedEntry);
+    }
+    if let Some(codec) = compression {
+        return X::from_decompressed(decompress_native(codec,data,usize::MAX,variable_type,swapped)?);
+    }
+    if swapped {
+        return X::from_swapped_mem_buffer(data);
+    }
+    X::from_mem_buffer(data)
+}
+
+///Looks up the codec of the entry in the compression table
+pub(crate) fn find_record(table: &[u8], index: usize, swapped: bool) -> Result<Option<u64>,MemBufferError> {
+    if !table.len().is_multiple_of(RECORD_SIZE) {
+        return Err(MemBufferError::WrongFormat);
+    }
+    //The records are sorted by the entry index
+    let record = |x: usize| &table[x*RECORD_SIZE..(x+1)*RECORD_SIZE];
+    let mut low = 0;
+    let mut high = table.len()/RECORD_SIZE;
+    while low < high {
+        let mid = (low+high)/2;
+        match read_u64(record(mid),swapped).cmp(&(index as u64)) {
+            core::cmp::Ordering::Less => low = mid+1,
+            core::cmp::Ordering::Greater => high = mid,
+            core::cmp::Ordering::Equal => return Ok(Some(read_u64(&record(mid)[8..],swapped))),
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds an entry which is compressed with the given codec when the buffer is written
+    pub fn add_entry_compressed<T: MemBufferSerialize>(&mut self, val: T, compression: Compression) {
+        if compression != Compression::None {
+            self.compression.insert(self.types.len(),compression);
+        }
+        self.add_entry(val);
+    }
+
+    ///Serializes the codec of every compressed entry in the byte order of the buffer
+    pub(crate) fn compression_table(&self) -> Option<Vec<u8>> {
+        if self.compression.is_empty() {
+            return None;
+        }
+        let mut table = Vec::with_capacity(self.compression.len()*RECORD_SIZE);
+        for (index,compression) in self.compression.iter() {
+            table.extend_from_slice(&(*index as u64).to_ne_bytes());
+            table.extend_from_slice(&compression.id().to_ne_bytes());
+        }
+        if self.byte_order.is_swapped() {
+            swap_integers(&mut table,8);
+        }
+        Some(table)
+    }
+
+    ///Returns the payload of the entry as it is stored in the buffer, the data has to be in the
+    ///byte order of the buffer already
+    pub(crate) fn stored_payload<'x>(&self, index: usize, data: &'x [u8]) -> Cow<'x,[u8]> {
+        match self.compression.get(&index) {
+            //Compressing into memory only fails for invalid parameters
+            Some(compression) => Cow::Owned(compression.compress(data,self.byte_order.is_swapped()).expect("Compression failed")),
+            None => Cow::Borrowed(data),
+        }
+    }
+
+    ///Replaces the payloads of the compressed entries by their compressed bytes, the entries are
+    ///converted to the byte order of the buffer before they are compressed
+    pub(crate) fn compress_payloads(&self, types: &[i32], payloads: &mut [Payload]) -> std::io::Result<()> {
+        for (index,compression) in self.compression.iter() {
+            let mut data = core::mem::replace(&mut payloads[*index],Payload::Bytes(&[])).into_vec()?;
+            if self.byte_order.is_swapped() {
+                swap_integers(&mut data,swap_width(types[*index]));
+            }
+            payloads[*index] = Payload::Owned(compression.compress(&data,self.byte_order.is_swapped())?);
+        }
+        Ok(())
+    }
+
+    ///Returns whether the entry is compressed when the buffer is written
+    pub(crate) fn is_compressed(&self, index: usize) -> bool {
+        self.compression.contains_key(&index)
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the codec of the entry if it is stored compressed
+    pub(crate) fn compression_of(&self, index: usize) -> Result<Option<u64>,MemBufferError> {
+        match self.system_entry(SYSTEM_TYPE_COMPRESSION) {
+            Some(table) => find_record(table,index,self.is_byte_swapped()),
+            None => Ok(None),
+        }
+    }
+
+    ///Returns whether the entry is stored compressed
+    pub fn is_compressed(&self, index: usize) -> bool {
+        matches!(self.compression_of(index),Ok(Some(_)))
+    }
+
+    ///Returns the length of the entry after decompression, the stored length for entries which
+    ///are not compressed. Use it to allocate the output up front or to skip entries which are too
+    ///large without decompressing them. The length is read from the size prefix of the entry, the
+    ///prefix of a compressed entry of an encrypted buffer is decrypted with the entry.
+    pub fn uncompressed_len(&self, index: usize) -> Result<usize,MemBufferError> {
+        if index >= self.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,self.len()));
+        }
+        let stored = self.entry_slice(self.offsets.get(index))?;
+        match (self.compression_of(index)?.is_some(),self.is_encrypted(index)) {
+            (true,true) => Ok(split_size_prefix(&self.decrypt_entry(index,stored)?,self.is_byte_swapped())?.0),
+            (true,false) => Ok(split_size_prefix(stored,self.is_byte_swapped())?.0),
+            (false,true) => Ok(stored.len().saturating_sub(crate::SEALING_OVERHEAD)),
+            (false,false) => Ok(stored.len()),
+        }
+    }
+
+    ///Decrypts and decompresses the payload of an entry into the native byte order, returns
+    ///`None` for entries which are stored as they are
+    pub(crate) fn decompress_entry(&self, index: usize, data: &[u8]) -> Result<Option<Vec<u8>>,MemBufferError> {
+        let variable_type = self.offsets.get(index).variable_type;
+        let decrypted = if self.is_encrypted(index) { Some(self.decrypt_entry(index,data)?) } else { None };
+        let codec = match self.compression_of(index)? {
+            Some(x) => x,
+            None => return Ok(decrypted.map(|mut data| {
+                if self.is_byte_swapped() {
+                    swap_integers(&mut data,swap_width(variable_type));
+                }
+                data
+            })),
+        };
+        decompress_native(codec,decrypted.as_deref().unwrap_or(data),self.max_entry_size,variable_type,self.is_byte_swapped()).map(Some)
+    }
+
+    ///Checks the record of every compressed entry, see `check_uncompressed_len`. The lengths of
+    ///encrypted entries are checked once they are decrypted.
+    pub(crate) fn check_compression(&self) -> Result<(),MemBufferError> {
+        let table = match self.system_entry(SYSTEM_TYPE_COMPRESSION) {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+        if !table.len().is_multiple_of(RECORD_SIZE) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        for record in table.chunks_exact(RECORD_SIZE) {
+            let index = usize::try_from(read_u64(record,self.is_byte_swapped())).map_err(|_| MemBufferError::WrongFormat)?;
+            if index >= self.len() {
+                return Err(MemBufferError::WrongFormat);
+            }
+            self.check_compressed_entry(index)?;
+        }
+        Ok(())
+    }
+
+    ///Checks the record of the entry if it is compressed, see `check_uncompressed_len`
+    pub(crate) fn check_compressed_entry(&self, index: usize) -> Result<(),MemBufferError> {
+        if self.is_encrypted(index) {
+            return Ok(());
+        }
+        match self.compression_of(index)? {
+            Some(codec) => {
+                let (len,data) = split_size_prefix(self.entry_slice(self.offsets.get(index))?,self.is_byte_swapped())?;
+                check_uncompressed_len(codec,data,len)
+            },
+            None => Ok(()),
+        }
+    }
+
+    ///Returns the codec of a compressed entry for a writer copying it
+    #[cfg(feature = "std")]
+    pub(crate) fn compression_for_copy(&self, index: usize) -> Result<Option<Compression>,MemBufferError> {
+        match self.compression_of(index)? {
+            Some(codec) => Ok(Some(Compression::from_id(codec)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,Compression};
+    use std::borrow::Cow;
+
+    #[test]
+    fn check_uncompressed_loads() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry_compressed("stored",Compression::None);
+        writer.add_entry(&[1u8,2][..]);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert!(!reader.is_compressed(0));
+        assert_eq!(reader.uncompressed_len(0).unwrap(),6);
+        assert_eq!(reader.load_entry::<String>(0).unwrap(),"stored");
+        assert!(matches!(reader.load_entry::<Cow<[u8]>>(1).unwrap(),Cow::Borrowed(&[1,2])));
+        assert!(matches!(reader.uncompressed_len(2),Err(MemBufferError::IndexOutOfBounds(2,2))));
+    }
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    #[test]
+    fn check_compressed_entries() {
+        let text = "Frankenstein ".repeat(1000);
+        let blob = vec![7u8;5000];
+        #[cfg(feature = "lz4")]
+        let compression = Compression::Lz4;
+        #[cfg(not(feature = "lz4"))]
+        let compression = Compression::Zstd(3);
+
+        let mut writer = MemBufferWriter::new().with_field_checksums().with_byte_order(crate::Endianness::Big);
+        writer.add_entry_compressed(&text,compression);
+        writer.add_entry("plain");
+        writer.add_entry_compressed(&blob[..],compression);
+        assert_eq!(writer.load_entry::<&str>(0).unwrap(),text);
+        let result = writer.finalize();
+        assert!(result.len() < 2000);
+
+        let reader = MemBufferReader::new_verified(&result).unwrap();
+        assert!(reader.is_compressed(0));
+        assert!(!reader.is_compressed(1));
+        assert_eq!(reader.uncompressed_len(0).unwrap(),text.len());
+        assert_eq!(reader.load_entry::<String>(0).unwrap(),text);
+        assert!(matches!(reader.load_entry::<&str>(0),Err(MemBufferError::CompressedEntry)));
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"plain");
+        assert_eq!(reader.load_entry::<Cow<[u8]>>(2).unwrap(),&blob[..]);
+        assert!(matches!(MemBufferReader::new(&result).unwrap().with_max_entry_size(100).load_entry::<String>(0),Err(MemBufferError::EntryTooLarge(_,100))));
+
+        //A writer loaded from the buffer keeps the entries compressed
+        let copy = MemBufferWriter::from(&result).unwrap();
+        assert_eq!(copy.load_entry::<&str>(0).unwrap(),text);
+        let copy = copy.finalize();
+        let reader = MemBufferReader::new(&copy).unwrap();
+        assert!(reader.is_compressed(0));
+        assert_eq!(reader.load_entry::<Cow<[u8]>>(2).unwrap(),&blob[..]);
+    }
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    #[test]
+    fn check_corrupted_uncompressed_len() {
+        let text = "Frankenstein ".repeat(1000);
+        let mut compressions = Vec::new();
+        #[cfg(feature = "lz4")]
+        compressions.push((Compression::Lz4,1u64));
+        #[cfg(feature = "zstd")]
+        compressions.push((Compression::Zstd(3),2u64));
+        for (compression,codec) in compressions {
+            let mut writer = MemBufferWriter::new();
+            writer.add_entry("plain");
+            writer.add_entry_compressed(&text,compression);
+            let data = writer.finalize();
+            let record: Vec<u8> = [1u64,codec].iter().flat_map(|x| x.to_ne_bytes()).collect();
+            assert_eq!(MemBufferReader::new(&data).unwrap().system_entry(crate::SYSTEM_TYPE_COMPRESSION).unwrap(),&record[..]);
+            //The uncompressed length is stored in front of the compressed bytes
+            let pos = {
+                let reader = MemBufferReader::new(&data).unwrap();
+                data.len()-reader.data.len()+reader.offsets.get(1).start
+            };
+            assert_eq!(data[pos..pos+8],(text.len() as u64).to_ne_bytes());
+
+            //Lengths beyond the limit and lengths the codec cannot produce from the stored bytes
+            //fail before the output is allocated
+            for len in [1u64 << 56,u32::MAX as u64+1,text.len() as u64*1000] {
+                let mut corrupted = data.clone();
+                corrupted[pos..pos+8].copy_from_slice(&len.to_ne_bytes());
+                let reader = MemBufferReader::new(&corrupted).unwrap();
+                assert!(reader.load_entry::<String>(1).is_err());
+                assert!(reader.load_value(1).is_err());
+                assert_eq!(reader.load_entry::<&str>(0).unwrap(),"plain");
+                assert!(MemBufferReader::new_validated(&corrupted).is_err());
+                let reader = MemBufferReader::new_lazily_validated(&corrupted).unwrap();
+                assert!(reader.validate_entry(1).is_err());
+                assert!(reader.load_entry::<String>(1).is_err());
+            }
+            //Possible lengths which do not match the stored bytes fail when the entry is decompressed
+            let mut corrupted = data.clone();
+            corrupted[pos..pos+8].copy_from_slice(&(text.len() as u64+1).to_ne_bytes());
+            assert!(MemBufferReader::new(&corrupted).unwrap().load_entry::<String>(1).is_err());
+            assert_eq!(MemBufferReader::new_validated(&data).unwrap().load_entry::<String>(1).unwrap(),text);
+        }
+    }
+}
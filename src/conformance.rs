@@ -0,0 +1,248 @@
+//!Conformance suite for other implementations of the format, available with the `conformance`
+//!feature. `cases` generates canonical buffers covering every built in type id, the header
+//!options, the interleaved layout, shared payloads and edge cases like empty entries, large
+//!entries and deep nesting. `write_suite` stores every buffer as `<name>.mem` next to
+//!`conformance.json`, which describes the options of every case and the type, the absolute
+//!position and the CRC32 of the payload of every entry, so bindings can check byte level
+//!compatibility in both directions. The buffers are written in little endian unless a case says
+//!otherwise, so the suite is the same on every machine.
+//!
+//!The example writes the suite into a directory:
+//!`cargo run --example conformance --features conformance -- <directory>`
+//!```rust
+//!use membuffer::MemBufferReader;
+//!use membuffer::conformance;
+//!
+//!for case in conformance::cases() {
+//!    let reader = MemBufferReader::new(&case.data).unwrap();
+//!    assert_eq!(reader.len(),case.entries.len());
+//!}
+//!assert!(conformance::describe(&conformance::cases()).starts_with("{"));
+//!```
+use std::io;
+use std::path::Path;
+use crate::{MemBufferWriter,MemBufferReader,MemBufferMap,Endianness,TypeRegistry};
+
+///Type id of the user defined entries of the suite
+const USER_TYPE: i32 = 5000;
+
+///One buffer of the suite
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceCase {
+    ///File name of the buffer without extension
+    pub name: &'static str,
+    pub description: &'static str,
+    ///Writer options and layout the buffer was written with
+    pub options: Vec<&'static str>,
+    pub data: Vec<u8>,
+    pub entries: Vec<ConformanceEntry>,
+}
+
+///Expected entry of a buffer of the suite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConformanceEntry {
+    pub variable_type: i32,
+    ///Position of the stored payload in the buffer, counted from the start of the buffer
+    pub start: usize,
+    pub end: usize,
+    ///CRC32 of the stored payload
+    pub crc32: u32,
+}
+
+impl ConformanceCase {
+    fn new(name: &'static str, description: &'static str, options: &[&'static str], data: Vec<u8>) -> ConformanceCase {
+        let reader = MemBufferReader::new(&data).unwrap();
+        let base = reader.data.as_ptr() as usize-data.as_ptr() as usize;
+        let entries = reader.offsets.iter().map(|x| ConformanceEntry {
+            variable_type: x.variable_type,
+            start: base+x.start,
+            end: base+x.end,
+            crc32: crc32fast::hash(&reader.data[x.start..x.end]),
+        }).collect();
+        ConformanceCase {
+            name,
+            description,
+            options: options.to_vec(),
+            data,
+            entries,
+        }
+    }
+}
+
+///Adds one entry of every built in type
+fn add_every_type(writer: &mut MemBufferWriter) {
+    let mut nested = MemBufferWriter::new().with_byte_order(Endianness::Little);
+    nested.add_entry("nested");
+    let mut map = MemBufferMap::new();
+    map.insert("key",b"value");
+    writer.add_entry("Grüße");
+    writer.add_entry(-123_456i32);
+    writer.add_entry(&[0u8,1,127,128,255][..]);
+    writer.add_entry(&[0u32,1,u32::MAX][..]);
+    writer.add_entry(&[0u64,1,u64::MAX][..]);
+    writer.add_entry(&nested);
+    writer.add_interned_entry("label");
+    writer.add_entry(u64::MAX-1);
+    writer.add_entry(1.5f32);
+    writer.add_entry(-0.25f64);
+    writer.add_entry(&[1.0f32,-2.5][..]);
+    writer.add_entry(&[f64::MIN_POSITIVE,1e300][..]);
+    writer.add_entry(&map);
+    writer.add_null_entry();
+    writer.add_entry::<&[&str]>(&["first","","läst"]);
+    writer.add_entry::<&[&[u8]]>(&[b"\x00\xFF",b""]);
+    writer.add_entry(255u8);
+    writer.add_entry(0xABCDu16);
+    writer.add_entry(0xABCD_EF01u32);
+    writer.add_entry(-128i8);
+    writer.add_entry(-1234i16);
+    writer.add_entry(i64::MIN);
+    writer.add_entry(true);
+    writer.add_entry('ß');
+    writer.add_entry_chunked(&b"chunked entry"[..],8).unwrap();
+}
+
+fn little_endian() -> MemBufferWriter {
+    MemBufferWriter::new().with_byte_order(Endianness::Little)
+}
+
+///Returns the buffers of the suite, the same ones on every call
+pub fn cases() -> Vec<ConformanceCase> {
+    let mut cases = Vec::new();
+
+    cases.push(ConformanceCase::new("empty","Buffer without entries",&["little_endian"],little_endian().finalize()));
+
+    let mut writer = little_endian();
+    add_every_type(&mut writer);
+    cases.push(ConformanceCase::new("every_type","One entry of every built in type",&["little_endian"],writer.finalize()));
+
+    let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+    add_every_type(&mut writer);
+    cases.push(ConformanceCase::new("big_endian","One entry of every built in type in big endian",&["big_endian"],writer.finalize()));
+
+    let mut writer = little_endian();
+    add_every_type(&mut writer);
+    cases.push(ConformanceCase::new("interleaved","One entry of every built in type with every entry preceded by its type and length",&["little_endian","interleaved"],writer.finalize_interleaved()));
+
+    let mut writer = little_endian().with_large_offsets();
+    add_every_type(&mut writer);
+    cases.push(ConformanceCase::new("large_offsets","One entry of every built in type with 64 bit positions",&["little_endian","large_offsets"],writer.finalize()));
+
+    let mut writer = little_endian().with_compact_header();
+    add_every_type(&mut writer);
+    cases.push(ConformanceCase::new("compact_header","One entry of every built in type with the types and lengths stored as varints",&["little_endian","compact_header"],writer.finalize()));
+
+    let mut writer = little_endian().with_deduplication().with_field_checksums();
+    writer.add_entry(&[1u64,2,3][..]);
+    writer.add_entry("shared");
+    writer.add_entry(&[1u64,2,3][..]);
+    writer.add_entry("shared");
+    cases.push(ConformanceCase::new("deduplicated","Entries sharing the payload of an identical entry",&["little_endian","deduplicated","field_checksums"],writer.finalize()));
+
+    let mut writer = little_endian().with_field_checksums();
+    add_every_type(&mut writer);
+    cases.push(ConformanceCase::new("field_checksums","One entry of every built in type with a CRC32 of every entry",&["little_endian","field_checksums"],writer.finalize()));
+
+    let mut writer = little_endian().with_format_version(1);
+    writer.add_entry("legacy");
+    writer.add_entry(7);
+    cases.push(ConformanceCase::new("format_version_1","Buffer in the first version of the format",&["little_endian","format_version_1"],writer.finalize()));
+
+    let mut writer = little_endian();
+    writer.add_entry("");
+    writer.add_entry(&[0u8;0][..]);
+    writer.add_entry(&[0u32;0][..]);
+    writer.add_entry(&[0u64;0][..]);
+    writer.add_entry(&[0f32;0][..]);
+    writer.add_entry(&[0f64;0][..]);
+    writer.add_entry(little_endian());
+    writer.add_entry(&MemBufferMap::new());
+    writer.add_interned_entry("");
+    writer.add_entry::<&[&str]>(&[]);
+    writer.add_entry::<&[&[u8]]>(&[]);
+    cases.push(ConformanceCase::new("empty_entries","Entries without elements of every type which can be empty",&["little_endian"],writer.finalize()));
+
+    let mut writer = little_endian();
+    writer.add_entry("odd");
+    writer.add_entry(&(0..1u64 << 17).collect::<Vec<u64>>()[..]);
+    writer.add_entry(&(0..1u32 << 18).map(|x| x as u8).collect::<Vec<u8>>()[..]);
+    cases.push(ConformanceCase::new("large_entries","Entries of 1 MiB and 256 KiB behind a text of odd length",&["little_endian"],writer.finalize()));
+
+    let mut writer = little_endian();
+    writer.add_entry("level 8");
+    for level in (0..8).rev() {
+        let mut outer = little_endian();
+        outer.add_entry(format!("level {}",level));
+        outer.add_entry(&writer);
+        writer = outer;
+    }
+    cases.push(ConformanceCase::new("nested","Buffers nested eight levels deep",&["little_endian"],writer.finalize()));
+
+    let mut registry = TypeRegistry::new();
+    registry.register_id("conformance.user",USER_TYPE).unwrap();
+    let mut writer = little_endian().with_type_registry(&registry).with_manifest();
+    writer.add_entry_named("title","named");
+    writer.add_interned_entry("repeated");
+    writer.add_interned_entry("repeated");
+    writer.add_entry_with_content_type(&b"{}"[..],"application/json");
+    writer.add_entry_labeled(1,"owner");
+    writer.add_entry_with_flags(2,0x8000_0001);
+    writer.add_raw_entry(USER_TYPE,&[1,2,3]);
+    cases.push(ConformanceCase::new("metadata","Names, interned text, content types, labels, flags, a user type and a manifest",&["little_endian","manifest"],writer.finalize()));
+
+    cases
+}
+
+///Returns the JSON description of the cases, see the module documentation
+pub fn describe(cases: &[ConformanceCase]) -> String {
+    let quote = |x: &str| format!("\"{}\"",x.replace('\\',"\\\\").replace('"',"\\\""));
+    let cases: Vec<String> = cases.iter().map(|case| {
+        let options: Vec<String> = case.options.iter().map(|x| quote(x)).collect();
+        let entries: Vec<String> = case.entries.iter().map(|x| format!("{{\"type\":{},\"start\":{},\"end\":{},\"crc32\":{}}}",x.variable_type,x.start,x.end,x.crc32)).collect();
+        format!("{{\"name\":{},\"file\":{},\"description\":{},\"options\":[{}],\"len\":{},\"crc32\":{},\"entries\":[{}]}}",
+            quote(case.name),quote(&format!("{}.mem",case.name)),quote(case.description),options.join(","),case.data.len(),crc32fast::hash(&case.data),entries.join(","))
+    }).collect();
+    format!("{{\"format_version\":{},\"cases\":[\n{}\n]}}\n",crate::FORMAT_VERSION,cases.join(",\n"))
+}
+
+///Writes every buffer of the suite and its description into the directory, creating it if needed
+pub fn write_suite(directory: &Path) -> io::Result<()> {
+    let cases = cases();
+    std::fs::create_dir_all(directory)?;
+    for case in cases.iter() {
+        std::fs::write(directory.join(format!("{}.mem",case.name)),&case.data)?;
+    }
+    std::fs::write(directory.join("conformance.json"),describe(&cases))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferReader,MemBufferTypes};
+
+    #[test]
+    fn check_conformance_suite() {
+        let cases = super::cases();
+        assert_eq!(cases,super::cases());
+        for case in cases.iter() {
+            let reader = MemBufferReader::new_verified(&case.data).unwrap();
+            //Slices in the other byte order cannot be borrowed as values
+            if !case.options.contains(&"big_endian") {
+                assert!(reader.values().all(|x| x.is_ok()),"{}",case.name);
+            }
+            for entry in case.entries.iter() {
+                assert_eq!(crc32fast::hash(&case.data[entry.start..entry.end]),entry.crc32);
+            }
+        }
+        let every_type = cases.iter().find(|x| x.name == "every_type").unwrap();
+        assert_eq!(every_type.entries.len(),25);
+        assert_eq!(every_type.entries[13].variable_type,MemBufferTypes::Null as i32);
+        assert_eq!(&every_type.data[every_type.entries[0].start..every_type.entries[0].end],"Grüße".as_bytes());
+
+        let directory = std::env::temp_dir().join(format!("membuffer_check_conformance_suite_{}",std::process::id()));
+        super::write_suite(&directory).unwrap();
+        assert_eq!(std::fs::read(directory.join("nested.mem")).unwrap(),cases.iter().find(|x| x.name == "nested").unwrap().data);
+        assert_eq!(std::fs::read_to_string(directory.join("conformance.json")).unwrap(),super::describe(&cases));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
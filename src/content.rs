@@ -0,0 +1,135 @@
+//!Content types of entries. A writer can tag entries with a content type like `image/png` or
+//!`application/json`, the tags are stored in a metadata entry holding the content types followed
+//!by the sorted entry indices, so gateways serving blob entries know how to hand them out.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_with_content_type(&b"{\"id\":1}"[..],"application/json");
+//!writer.add_entry("untagged");
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.content_type(0),Some("application/json"));
+//!assert_eq!(reader.content_type(1),None);
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,SYSTEM_TYPE_CONTENT_TYPES};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,MemBufferSerialize};
+use crate::named::NameIndices;
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds an entry tagged with the given content type
+    pub fn add_entry_with_content_type<T: MemBufferSerialize>(&mut self, val: T, content_type: &str) {
+        self.content_types.insert(self.types.len(),content_type.to_string());
+        self.add_entry(val);
+    }
+
+    ///Tags the entry with the given content type, replacing an existing one. Replacing the value
+    ///of the entry keeps its content type. Panics if the index is out of bounds.
+    pub fn set_content_type(&mut self, index: usize, content_type: &str) {
+        assert!(index < self.types.len(),"Entry {} is out of bounds",index);
+        self.content_types.insert(index,content_type.to_string());
+    }
+
+    ///Returns the content type of the entry
+    pub fn content_type(&self, index: usize) -> Option<&str> {
+        self.content_types.get(&index).map(|x| &x[..])
+    }
+
+    ///Serializes the content types as nested buffer followed by the entry indices
+    pub(crate) fn content_type_table(&self) -> Option<Vec<u8>> {
+        if self.content_types.is_empty() {
+            return None;
+        }
+        let mut table = MemBufferWriter::new();
+        for content_type in self.content_types.values() {
+            table.add_entry(content_type);
+        }
+        let indices: Vec<u32> = self.content_types.keys().map(|x| *x as u32).collect();
+        table.add_entry(&indices[..]);
+        Some(table.finalize())
+    }
+
+    ///Takes over the content types of the reader
+    pub(crate) fn load_content_types(&mut self, reader: &MemBufferReader) -> Result<(),MemBufferError> {
+        if let Some((table,indices)) = reader.content_type_table()? {
+            for x in 0..indices.len() {
+                let content_type: &str = table.load_entry(x)?;
+                self.content_types.insert(indices.get(x),content_type.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the reader over the content types and the entry indices
+    fn content_type_table(&self) -> Result<Option<(MemBufferReader<'a>,NameIndices<'a>)>,MemBufferError> {
+        let table = match self.system_entry(SYSTEM_TYPE_CONTENT_TYPES) {
+            Some(table) => MemBufferReader::new(table)?,
+            None => return Ok(None),
+        };
+        if table.is_empty() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let indices = table.offsets.get(table.len()-1);
+        if indices.variable_type != MemBufferTypes::VectorU32 as i32 || indices.len() != 4*(table.len()-1) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let indices = NameIndices {
+            data: table.entry_slice(indices)?,
+            swapped: table.is_byte_swapped(),
+        };
+        Ok(Some((table,indices)))
+    }
+
+    ///Returns the content type the entry was tagged with
+    pub fn content_type(&self, index: usize) -> Option<&'a str> {
+        let (table,indices) = self.content_type_table().ok()??;
+        let mut low = 0;
+        let mut high = indices.len();
+        while low < high {
+            let mid = (low+high)/2;
+            match indices.get(mid).cmp(&index) {
+                core::cmp::Ordering::Less => low = mid+1,
+                core::cmp::Ordering::Greater => high = mid,
+                core::cmp::Ordering::Equal => return table.load_entry::<&str>(mid).ok(),
+            }
+        }
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader};
+
+    #[test]
+    fn check_content_types() {
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry("plain");
+        writer.add_entry_with_content_type(&[0x89u8,0x50][..],"image/png");
+        writer.add_entry("text");
+        writer.set_content_type(2,"text/plain");
+        writer.set_content_type(2,"text/markdown");
+        writer.set_entry("# Title",2).unwrap();
+        assert_eq!(writer.content_type(2),Some("text/markdown"));
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.content_type(0),None);
+        assert_eq!(reader.content_type(1),Some("image/png"));
+        assert_eq!(reader.content_type(2),Some("text/markdown"));
+        assert_eq!(reader.content_type(3),None);
+
+        let mut update = MemBufferWriter::from(&data).unwrap();
+        update.remove_entry(0);
+        let data = update.finalize();
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.content_type(0),Some("image/png"));
+        assert_eq!(reader.content_type(1),Some("text/markdown"));
+    }
+}
@@ -0,0 +1,104 @@
+//!Deduplication of identical payloads. Writers created `with_deduplication` hash the payload of
+//!every entry when the buffer is written and store payloads which occur more than once only once,
+//!the header entries of all copies point at the same range. Readers need no support for this, an
+//!entry is loaded from its range like any other entry.
+//!
+//!Only entries of the same type share payloads, so the alignment and the byte order of the shared
+//!range fit every entry. Compressed, encrypted and nested entries are stored separately, compact
+//!headers cannot express shared ranges and store every payload. Readers consuming the buffer as
+//!stream like `AsyncMemBufferReader` cannot go back to a range they already passed.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let attachment = vec![7u8;1<<16];
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry(&attachment[..]);
+//!writer.add_entry("note");
+//!writer.add_entry(&attachment[..]);
+//!let data = writer.finalize();
+//!let deduplicated = writer.with_deduplication().finalize();
+//!assert!(deduplicated.len() < data.len()-(1<<15));
+//!
+//!let reader = MemBufferReader::new(&deduplicated).unwrap();
+//!assert_eq!(reader.load_entry::<&[u8]>(2).unwrap(),&attachment[..]);
+//!```
+use std::collections::HashMap;
+use crate::{MemBufferWriter,MemBufferReader,Payload};
+
+impl MemBufferWriter {
+    ///Stores payloads occurring more than once only once, see the module documentation
+    pub fn with_deduplication(mut self) -> MemBufferWriter {
+        self.dedup = true;
+        self
+    }
+
+    ///Returns for every entry the entry placed before it whose payload it shares, the payloads are
+    ///the ones written into the buffer in the given placement order
+    pub(crate) fn shared_payloads(&self, types: &[i32], payloads: &[Payload], order: &[usize]) -> Vec<Option<usize>> {
+        let mut shared = vec![None;order.len()];
+        if !self.dedup || self.uses_compact_header() {
+            return shared;
+        }
+        let mut placed: HashMap<(i32,&[u8]),usize> = HashMap::new();
+        for x in order.iter().copied().filter(|x| *x < self.types.len()) {
+            match payloads[x] {
+                Payload::Bytes(data) if !data.is_empty() && !self.is_encoded(x) => {
+                    shared[x] = placed.get(&(types[x],data)).copied();
+                    placed.entry((types[x],data)).or_insert(x);
+                },
+                _ => {},
+            }
+        }
+        shared
+    }
+
+    ///Returns whether two entries of the buffer share their payload
+    pub(crate) fn has_shared_payloads(reader: &MemBufferReader) -> bool {
+        let mut ranges = std::collections::HashSet::new();
+        reader.offsets.iter().any(|x| x.len() > 0 && !ranges.insert((x.start,x.end)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,Endianness};
+
+    #[test]
+    fn check_deduplication() {
+        let blob: Vec<u64> = (0..1000).collect();
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big).with_field_checksums().with_manifest().with_deduplication();
+        writer.add_entry("shared");
+        writer.add_entry(&blob[..]);
+        writer.add_entry_named("copy",&blob[..]);
+        writer.add_entry("shared");
+        //Same bytes but another type
+        writer.add_entry("shared".as_bytes());
+        writer.add_entry("");
+        writer.add_entry("");
+        writer.set_placement_priorities(&[0,0,1]);
+        let data = writer.finalize();
+        assert_eq!(data.len(),writer.serialized_len());
+        #[cfg(feature = "rayon")]
+        assert_eq!(writer.finalize_parallel(),data);
+
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        let (first,copy) = (reader.offsets.get(1),reader.offsets.get(2));
+        assert_eq!((first.start,first.end),(copy.start,copy.end));
+        assert_eq!(reader.offsets.get(0).start,reader.offsets.get(3).start);
+        assert_ne!(reader.offsets.get(0).start,reader.offsets.get(4).start);
+        assert_eq!(&reader.load_entry_by_name::<std::borrow::Cow<[u64]>>("copy").unwrap()[..],&blob[..]);
+        assert_eq!(reader.load_entry::<&[u8]>(4).unwrap(),b"shared");
+        assert_eq!(reader.load_entry::<&str>(6).unwrap(),"");
+        assert!(reader.verify_checksums().is_ok());
+
+        //Shared payloads cannot be changed in place
+        let mut inplace = data.clone();
+        assert!(matches!(crate::MemBufferMut::new(&mut inplace).unwrap().store_entry(3,"SHARED"),Err(crate::MemBufferError::ImmutableEntry(3))));
+
+        //Writers loaded from the buffer keep deduplicating
+        let mut copy = MemBufferWriter::from(&data).unwrap();
+        copy.add_entry(&blob[..]);
+        assert!(copy.finalize().len() < data.len()+100);
+    }
+}
@@ -0,0 +1,59 @@
+//!Mapping of structs to nested buffers with `#[derive(MemBuffer)]`, available with the `derive`
+//!feature. Every field is stored as one entry in declaration order, so the field types have to
+//!implement `MemBufferSerialize` and `MemBufferDeserialize`. Structs with borrowed fields load
+//!them straight out of the buffer without copying.
+//!```rust
+//!use membuffer::{MemBuffer,MemBufferWriter,MemBufferReader};
+//!
+//!#[derive(MemBuffer, Debug, PartialEq)]
+//!struct Book<'a> {
+//!    title: &'a str,
+//!    year: i32,
+//!    cover: &'a [u8],
+//!}
+//!
+//!let book = Book { title: "Frankenstein", year: 1818, cover: &[1,2,3] };
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry(&book);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry::<Book>(0).unwrap(),book);
+//!```
+pub use membuffer_derive::MemBuffer;
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBuffer,MemBufferWriter,MemBufferReader,MemBufferError};
+
+    #[derive(MemBuffer, Debug, PartialEq)]
+    struct Author<'a> {
+        name: &'a str,
+        ids: &'a [u64],
+    }
+
+    #[derive(MemBuffer, Debug, PartialEq)]
+    struct Pair(i32,u64);
+
+    #[test]
+    fn check_derive() {
+        let author = Author { name: "Mary Shelley", ids: &[1,2,3] };
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry(&author);
+        writer.add_entry(Pair(-1,7));
+        writer.add_entry("plain");
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        let loaded = reader.load_entry::<Author>(0).unwrap();
+        assert_eq!(loaded,author);
+        //Borrowed fields point into the buffer
+        let range = result.as_ptr_range();
+        assert!(range.contains(&loaded.name.as_ptr()));
+        assert_eq!(reader.load_entry::<Pair>(1).unwrap(),Pair(-1,7));
+        assert!(matches!(reader.load_entry::<Pair>(2),Err(MemBufferError::FieldTypeError(_,_))));
+        //The nested buffer of the author has a string where the pair expects an integer
+        assert!(reader.load_entry::<Pair>(0).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//!Document oriented layer over named entries for storing notes and similar records. Every field of
+//!a `MemDoc` is a named entry, nested documents are stored as nested buffers. The bytes of a
+//!document are an ordinary buffer, so readers which only need single fields can still use
+//!`MemBufferReader::load_entry_by_name` without copying anything.
+//!```rust
+//!use membuffer::MemDoc;
+//!
+//!let mut meta = MemDoc::new();
+//!meta.set_text("author","Mary Shelley");
+//!
+//!let mut note = MemDoc::new();
+//!note.set_text("body","It was on a dreary night of November");
+//!note.set_blob("attachment",&[0xFF,0xD8,0xFF]);
+//!note.set_doc("meta",&meta);
+//!let data = note.to_bytes();
+//!
+//!let loaded = MemDoc::from_bytes(&data).unwrap();
+//!assert_eq!(loaded.text("body").unwrap(),"It was on a dreary night of November");
+//!assert_eq!(loaded.blob("attachment").unwrap(),&[0xFF,0xD8,0xFF]);
+//!assert_eq!(loaded.doc("meta").unwrap().text("author").unwrap(),"Mary Shelley");
+//!```
+use crate::{MemBufferWriter,MemBufferError,MemBufferTypes};
+
+///Document of named fields, see the module documentation
+#[derive(Default)]
+pub struct MemDoc {
+    writer: MemBufferWriter,
+}
+
+impl MemDoc {
+    pub fn new() -> MemDoc {
+        MemDoc {
+            writer: MemBufferWriter::new(),
+        }
+    }
+
+    ///Loads a document from the bytes written by `to_bytes`
+    pub fn from_bytes(data: &[u8]) -> Result<MemDoc,MemBufferError> {
+        Ok(MemDoc {
+            writer: MemBufferWriter::from(data)?,
+        })
+    }
+
+    ///Sets the field to the given text, replacing the previous value of the field
+    pub fn set_text(&mut self, name: &str, val: &str) {
+        self.writer.add_entry_named(name,val);
+    }
+
+    ///Sets the field to the given bytes, replacing the previous value of the field
+    pub fn set_blob(&mut self, name: &str, val: &[u8]) {
+        self.writer.add_entry_named(name,val);
+    }
+
+    ///Sets the field to the given integer, replacing the previous value of the field
+    pub fn set_int(&mut self, name: &str, val: i32) {
+        self.writer.add_entry_named(name,val);
+    }
+
+    ///Stores a copy of the given document in the field, replacing the previous value of the field
+    pub fn set_doc(&mut self, name: &str, val: &MemDoc) {
+        self.writer.add_entry_named(name,&val.writer);
+    }
+
+    pub fn text(&self, name: &str) -> Result<&str,MemBufferError> {
+        self.writer.load_entry_by_name(name)
+    }
+
+    pub fn blob(&self, name: &str) -> Result<&[u8],MemBufferError> {
+        self.writer.load_entry_by_name(name)
+    }
+
+    pub fn int(&self, name: &str) -> Result<i32,MemBufferError> {
+        self.writer.load_entry_by_name(name)
+    }
+
+    ///Loads the nested document stored in the field
+    pub fn doc(&self, name: &str) -> Result<MemDoc,MemBufferError> {
+        let index = self.writer.entry_index(name).ok_or_else(|| MemBufferError::KeyNotFound(name.to_string()))?;
+        if self.writer.types[index] != MemBufferTypes::MemBuffer as i32 {
+            return Err(MemBufferError::FieldTypeError(self.writer.types[index],MemBufferTypes::MemBuffer as i32));
+        }
+        MemDoc::from_bytes(&self.writer.data[index])
+    }
+
+    ///Returns whether the document has a field with the given name
+    pub fn contains(&self, name: &str) -> bool {
+        self.writer.entry_index(name).is_some()
+    }
+
+    ///Returns the names of all fields in sorted order
+    pub fn names(&self) -> impl Iterator<Item=&str> {
+        self.writer.names.keys().map(|x| x.as_str())
+    }
+
+    ///Serializes the document into a buffer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.writer.finalize()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemDoc,MemBufferReader,MemBufferError};
+
+    #[test]
+    fn check_mem_doc() {
+        let mut inner = MemDoc::new();
+        inner.set_int("pages",280);
+        let mut doc = MemDoc::new();
+        doc.set_text("title","draft");
+        doc.set_text("title","Frankenstein");
+        doc.set_blob("cover",&[1,2,3]);
+        doc.set_doc("details",&inner);
+        assert_eq!(doc.names().collect::<Vec<&str>>(),vec!["cover","details","title"]);
+        let data = doc.to_bytes();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.len(),3);
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+
+        let mut loaded = MemDoc::from_bytes(&data).unwrap();
+        assert!(loaded.contains("cover"));
+        assert!(matches!(loaded.text("summary"),Err(MemBufferError::KeyNotFound(_))));
+        assert!(matches!(loaded.text("cover"),Err(MemBufferError::FieldTypeError(_,_))));
+        assert!(matches!(loaded.doc("title"),Err(MemBufferError::FieldTypeError(_,_))));
+        assert_eq!(loaded.doc("details").unwrap().int("pages").unwrap(),280);
+        loaded.set_text("summary","A creature comes to life");
+        let reloaded = MemDoc::from_bytes(&loaded.to_bytes()).unwrap();
+        assert_eq!(reloaded.text("summary").unwrap(),"A creature comes to life");
+        assert_eq!(reloaded.blob("cover").unwrap(),&[1,2,3]);
+    }
+}
@@ -0,0 +1,134 @@
+//!Editing the entry list of a writer. Entries can be removed, inserted at a position, swapped and
+//!truncated, which is mostly useful on writers loaded with `MemBufferWriter::from`. The names,
+//!content types, labels, flags, compression settings, placement priorities and nested writers of
+//!the entries move with them, the payloads are shared and never copied.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("obsolete");
+//!writer.add_entry_named("title","Frankenstein");
+//!let data = writer.finalize();
+//!
+//!let mut update = MemBufferWriter::from(&data).unwrap();
+//!update.remove_entry(0);
+//!update.insert_entry(0,1818);
+//!let data = update.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry::<i32>(0).unwrap(),1818);
+//!assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+//!```
+use crate::{MemBufferWriter,MemBufferSerialize,Change};
+
+impl MemBufferWriter {
+    ///Removes the entry at the given index and shifts the following entries down, a name of the
+    ///entry is removed as well. Panics if the index is out of bounds.
+    pub fn remove_entry(&mut self, index: usize) {
+        self.types.remove(index);
+        self.data.remove(index);
+        if index < self.placement.len() {
+            self.placement.remove(index);
+        }
+        self.remap_indices(|x| if x == index { None } else if x > index { Some(x-1) } else { Some(x) });
+        self.log_change(Change::Remove(index));
+    }
+
+    ///Inserts the entry at the given index and shifts the following entries up. Panics if the
+    ///index is greater than the number of entries.
+    pub fn insert_entry<T: MemBufferSerialize>(&mut self, index: usize, val: T) {
+        self.check_schema_field(index,T::get_mem_buffer_type());
+        self.types.insert(index,T::get_mem_buffer_type());
+        self.data.insert(index,val.to_mem_buffer()[..].into());
+        if index < self.placement.len() {
+            self.placement.insert(index,0);
+        }
+        self.remap_indices(|x| if x >= index { Some(x+1) } else { Some(x) });
+        self.log_payload(index,Change::Insert);
+    }
+
+    ///Swaps the entries at the given indices. Panics if an index is out of bounds.
+    pub fn swap_entries(&mut self, a: usize, b: usize) {
+        self.types.swap(a,b);
+        self.data.swap(a,b);
+        if a.max(b) >= self.placement.len() && a.min(b) < self.placement.len() {
+            self.placement.resize(a.max(b)+1,0);
+        }
+        if a.max(b) < self.placement.len() {
+            self.placement.swap(a,b);
+        }
+        self.remap_indices(|x| if x == a { Some(b) } else if x == b { Some(a) } else { Some(x) });
+        self.log_change(Change::Swap(a,b));
+    }
+
+    ///Removes all entries from the given index on, does nothing if the writer holds fewer entries
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.types.len() {
+            self.log_change(Change::Truncate(len));
+        }
+        self.types.truncate(len);
+        self.data.truncate(len);
+        self.placement.truncate(len);
+        self.remap_indices(|x| if x < len { Some(x) } else { None });
+    }
+
+    ///Moves the names, content types, labels, flags, compression settings, nested writers and
+    ///spooled payloads to the new indices of their entries, entries mapped to None lose them
+    fn remap_indices(&mut self, map: impl Fn(usize) -> Option<usize>) {
+        let names = std::mem::take(&mut self.names);
+        self.names = names.into_iter().filter_map(|(name,x)| map(x).map(|x| (name,x))).collect();
+        let compression = std::mem::take(&mut self.compression);
+        self.compression = compression.into_iter().filter_map(|(x,codec)| map(x).map(|x| (x,codec))).collect();
+        let content_types = std::mem::take(&mut self.content_types);
+        self.content_types = content_types.into_iter().filter_map(|(x,content_type)| map(x).map(|x| (x,content_type))).collect();
+        let labels = std::mem::take(&mut self.labels);
+        self.labels = labels.into_iter().filter_map(|(x,label)| map(x).map(|x| (x,label))).collect();
+        let entry_metadata = std::mem::take(&mut self.entry_metadata);
+        self.entry_metadata = entry_metadata.into_iter().filter_map(|(x,metadata)| map(x).map(|x| (x,metadata))).collect();
+        let nested = std::mem::take(&mut self.nested);
+        self.nested = nested.into_iter().filter_map(|(x,writer)| map(x).map(|x| (x,writer))).collect();
+        let spooled = std::mem::take(&mut self.spooled);
+        self.spooled = spooled.into_iter().filter_map(|(x,payload)| map(x).map(|x| (x,payload))).collect();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,Compression};
+
+    #[test]
+    fn check_edit_entries() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry_named("a","first");
+        writer.add_entry_named("b","second");
+        writer.add_entry_compressed("third",Compression::None);
+        writer.add_entry_named("d","fourth");
+        writer.set_placement_priorities(&[0,0,0,9]);
+
+        writer.swap_entries(0,3);
+        assert_eq!(writer.entry_index("a"),Some(3));
+        assert_eq!(writer.entry_index("d"),Some(0));
+        writer.remove_entry(1);
+        assert_eq!(writer.entry_index("b"),None);
+        assert_eq!(writer.entry_index("a"),Some(2));
+        writer.insert_entry(1,42);
+        assert_eq!(writer.entry_index("a"),Some(3));
+        writer.truncate(10);
+        assert_eq!(writer.len(),4);
+
+        let data = writer.finalize();
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"fourth");
+        assert_eq!(reader.load_entry::<i32>(1).unwrap(),42);
+        assert_eq!(reader.load_entry::<&str>(2).unwrap(),"third");
+        assert_eq!(reader.load_entry_by_name::<&str>("a").unwrap(),"first");
+
+        let mut update = MemBufferWriter::from(&data).unwrap();
+        update.truncate(2);
+        assert_eq!(update.entry_index("a"),None);
+        assert_eq!(update.entry_index("d"),Some(0));
+        let reader_data = update.finalize();
+        assert_eq!(MemBufferReader::new(&reader_data).unwrap().len(),2);
+    }
+}
@@ -0,0 +1,298 @@
+//!Encryption at rest, available with the `encryption` feature. `finalize_encrypted` encrypts the
+//!payload of every entry on its own with AES-256-GCM and a random nonce, the entry table stays in
+//!the clear so a reader only decrypts the entries it loads. The index, the type and the number of
+//!entries are authenticated with every entry, so reordered, retyped or removed entries fail to
+//!decrypt just like modified payloads. The key check authenticates the entry table and the
+//!metadata entries, `new_encrypted` fails with `MemBufferError::DecryptionFailed` if the positions,
+//!the names, the labels, the content types or the compression table were changed. Encrypted entries are loaded into owned types like numbers,
+//!`String` or `Cow<[u8]>`, borrowing loads fail with `MemBufferError::CompressedEntry`. Readers
+//!created without the key fail with `MemBufferError::EncryptedEntry`.
+//!
+//!Only the entries are encrypted, the names, the interning table, the labels and the content
+//!types are metadata and stay in the clear, so interned text should not be used for secrets. The
+//!manifest and the checksums are not authenticated as storing entries in place rewrites them. The
+//!lazy, async and file readers do not decrypt and fail with `MemBufferError::EncryptedEntry`.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferError,EncryptionKey};
+//!
+//!let key = EncryptionKey::new(&[7;32]);
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_named("card","4111 1111 1111 1111");
+//!let data = writer.finalize_encrypted(&key);
+//!
+//!let reader = MemBufferReader::new_encrypted(&data,&key).unwrap();
+//!assert_eq!(reader.load_entry_by_name::<String>("card").unwrap(),"4111 1111 1111 1111");
+//!assert!(matches!(MemBufferReader::new_encrypted(&data,&EncryptionKey::new(&[8;32])),Err(MemBufferError::DecryptionFailed)));
+//!assert!(matches!(MemBufferReader::new(&data).unwrap().load_entry::<String>(0),Err(MemBufferError::EncryptedEntry)));
+//!```
+use aes_gcm::{Aes256Gcm,Nonce,KeyInit,AeadCore};
+use aes_gcm::aead::{Aead,Payload as AeadPayload,OsRng};
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,Payload,SYSTEM_TYPE_ENCRYPTION,SEALING_OVERHEAD,is_sealed_type};
+use crate::endian::read_u32;
+
+///Id of AES-256-GCM in the key check entry
+const ALGORITHM_AES_GCM: u32 = 1;
+const NONCE_LEN: usize = 12;
+///Prefix of the authenticated data of the key check, lets readers with a wrong key fail before any
+///load
+const KEY_CHECK: &[u8] = b"membuffer key check";
+
+///Key used to encrypt and decrypt the entries of buffers
+#[derive(Clone)]
+pub struct EncryptionKey {
+    cipher: Aes256Gcm,
+}
+
+impl EncryptionKey {
+    pub fn new(key: &[u8;32]) -> EncryptionKey {
+        EncryptionKey {
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+
+    fn encrypt(&self, data: &[u8], aad: &[u8]) -> Vec<u8> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut sealed = nonce.to_vec();
+        //Encryption only fails for payloads larger than 64 GB
+        sealed.extend(self.cipher.encrypt(&nonce,AeadPayload { msg: data, aad }).expect("Entry is too large to be encrypted"));
+        sealed
+    }
+
+    fn decrypt(&self, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>,MemBufferError> {
+        if sealed.len() < SEALING_OVERHEAD {
+            return Err(MemBufferError::DecryptionFailed);
+        }
+        let nonce = Nonce::from_slice(&sealed[..NONCE_LEN]);
+        self.cipher.decrypt(nonce,AeadPayload { msg: &sealed[NONCE_LEN..], aad }).map_err(|_| MemBufferError::DecryptionFailed)
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f,"EncryptionKey(..)")
+    }
+}
+
+///Data authenticated with the entry, binding it to its position and type in the buffer
+fn entry_aad(index: usize, variable_type: i32, entries: usize) -> [u8;12] {
+    let mut aad = [0u8;12];
+    aad[..4].copy_from_slice(&(index as u32).to_le_bytes());
+    aad[4..8].copy_from_slice(&variable_type.to_le_bytes());
+    aad[8..].copy_from_slice(&(entries as u32).to_le_bytes());
+    aad
+}
+
+///Data authenticated with the key check, the position and type of every entry followed by the
+///metadata entries in front of the key check
+fn key_check_aad<'x>(table: impl Iterator<Item=(usize,usize,i32)>, metadata: impl Iterator<Item=&'x [u8]>) -> Vec<u8> {
+    let mut aad = KEY_CHECK.to_vec();
+    for (start,end,variable_type) in table {
+        aad.extend_from_slice(&(start as u64).to_le_bytes());
+        aad.extend_from_slice(&(end as u64).to_le_bytes());
+        aad.extend_from_slice(&variable_type.to_le_bytes());
+    }
+    for data in metadata {
+        aad.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        aad.extend_from_slice(data);
+    }
+    aad
+}
+
+///Returns the bytes of a metadata payload, metadata entries are always held in memory
+fn metadata_bytes<'x>(payload: &'x Payload) -> &'x [u8] {
+    match payload {
+        Payload::Bytes(x) => x,
+        Payload::Owned(x) => x,
+        _ => &[],
+    }
+}
+
+impl MemBufferWriter {
+    ///Finalizes the buffer with every entry encrypted, see the module documentation
+    pub fn finalize_encrypted(&self, key: &EncryptionKey) -> Vec<u8> {
+        let mut sealed = self.clone();
+        sealed.encryption = Some(key.clone());
+        let data = sealed.finalize();
+        self.counters.add_written(data.len());
+        data
+    }
+
+    ///Returns whether the entry is encrypted when the buffer is written
+    pub(crate) fn is_sealed(&self, index: usize) -> bool {
+        self.encryption.is_some() && index < self.types.len() && is_sealed_type(self.types[index])
+    }
+
+    ///Returns the length of the stored payload of the entry
+    pub(crate) fn sealed_len(&self, index: usize, len: usize) -> usize {
+        if self.is_sealed(index) { len+SEALING_OVERHEAD } else { len }
+    }
+
+    ///Serializes the algorithm id followed by room for the key check, it is sealed with
+    ///`seal_key_check` once the entry table is known
+    pub(crate) fn key_check_table(&self) -> Option<Vec<u8>> {
+        self.encryption.as_ref()?;
+        let mut table = self.algorithm_id().to_vec();
+        table.extend_from_slice(&[0;SEALING_OVERHEAD]);
+        Some(table)
+    }
+
+    ///Returns the algorithm id in the byte order of the buffer
+    fn algorithm_id(&self) -> [u8;4] {
+        let id = if self.byte_order.is_swapped() { ALGORITHM_AES_GCM.swap_bytes() } else { ALGORITHM_AES_GCM };
+        id.to_ne_bytes()
+    }
+
+    ///Seals the key check over the entry table given by the types, lengths and starts of all
+    ///entries and the payloads of the metadata entries, returns the index of the key check entry
+    ///and its payload
+    pub(crate) fn seal_key_check<'x>(&self, types: &[i32], lens: &[usize], starts: &[usize], payloads: impl Iterator<Item=&'x [u8]>) -> Option<(usize,Vec<u8>)> {
+        let key = self.encryption.as_ref()?;
+        let index = types.iter().position(|x| *x == SYSTEM_TYPE_ENCRYPTION)?;
+        let table = (0..types.len()).map(|x| (starts[x],starts[x]+lens[x],types[x]));
+        let aad = key_check_aad(table,payloads.take(index).skip(self.types.len()));
+        let mut sealed = self.algorithm_id().to_vec();
+        sealed.extend(key.encrypt(&[],&aad));
+        Some((index,sealed))
+    }
+
+    ///Replaces the key check placeholder in the payloads to write
+    pub(crate) fn seal_key_check_payload(&self, types: &[i32], lens: &[usize], starts: &[usize], payloads: &mut [Payload]) {
+        if let Some((index,sealed)) = self.seal_key_check(types,lens,starts,payloads.iter().map(metadata_bytes)) {
+            payloads[index] = Payload::Owned(sealed);
+        }
+    }
+
+    ///Encrypts the payloads of the entries after they were compressed, integers are converted
+    ///into the byte order of the buffer first
+    pub(crate) fn encrypt_payloads(&self, types: &[i32], payloads: &mut [Payload]) -> std::io::Result<()> {
+        let key = match self.encryption.as_ref() {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+        for index in (0..self.types.len()).filter(|x| self.is_sealed(*x)) {
+            let mut data = std::mem::replace(&mut payloads[index],Payload::Bytes(&[])).into_vec()?;
+            if self.byte_order.is_swapped() && !self.is_compressed(index) {
+                crate::endian::swap_integers(&mut data,crate::endian::swap_width(types[index]));
+            }
+            payloads[index] = Payload::Owned(key.encrypt(&data,&entry_aad(index,types[index],self.types.len())));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Creates the reader for a buffer written with `finalize_encrypted`, fails with
+    ///`MemBufferError::DecryptionFailed` if the buffer was encrypted with another key
+    pub fn new_encrypted(val: &'a [u8], key: &'a EncryptionKey) -> Result<MemBufferReader<'a>,MemBufferError> {
+        let mut reader = MemBufferReader::new(val)?;
+        let index = reader.system.iter().position(|x| x.variable_type == SYSTEM_TYPE_ENCRYPTION).ok_or(MemBufferError::DecryptionFailed)?;
+        let table = reader.entry_slice(reader.system.get(index))?;
+        if table.len() < 4 || read_u32(table,reader.is_byte_swapped()) != ALGORITHM_AES_GCM {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let metadata = reader.system.iter().take(index).map(|x| reader.entry_slice(x)).collect::<Result<Vec<&[u8]>,MemBufferError>>()?;
+        let entries = reader.offsets.iter().chain(reader.system.iter()).map(|x| (x.start,x.end,x.variable_type));
+        key.decrypt(&table[4..],&key_check_aad(entries,metadata.into_iter()))?;
+        reader.key = Some(key);
+        Ok(reader)
+    }
+
+    ///Decrypts the payload of an entry of an encrypted buffer, the result is in the byte order of
+    ///the buffer and may still be compressed
+    pub(crate) fn decrypt_entry(&self, index: usize, data: &[u8]) -> Result<Vec<u8>,MemBufferError> {
+        let key = self.key.ok_or(MemBufferError::EncryptedEntry)?;
+        key.decrypt(data,&entry_aad(index,self.offsets.get(index).variable_type,self.offsets.len()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,EncryptionKey,Endianness};
+    use std::borrow::Cow;
+
+    #[test]
+    fn check_encrypted_entries() {
+        let key = EncryptionKey::new(&[1;32]);
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big).with_field_checksums();
+        writer.add_entry("secret");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry(42);
+        let data = writer.finalize_encrypted(&key);
+        assert!(!data.windows(6).any(|x| x == b"secret"));
+        assert_ne!(data,writer.finalize_encrypted(&key));
+
+        let reader = MemBufferReader::new_encrypted(&data,&key).unwrap();
+        assert!(MemBufferReader::new_verified(&data).is_ok());
+        assert_eq!(reader.load_entry::<String>(0).unwrap(),"secret");
+        assert_eq!(reader.load_entry::<Cow<[u64]>>(1).unwrap().into_owned(),vec![1,2,3]);
+        assert!(matches!(reader.load_entry::<&str>(0),Err(MemBufferError::CompressedEntry)));
+        assert_eq!(reader.load_entry::<i32>(2).unwrap(),42);
+        assert_eq!(reader.uncompressed_len(0).unwrap(),6);
+        let copy = MemBufferWriter::from_reader(&reader).unwrap();
+        assert_eq!(copy.load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+
+        //Entries copied to another position fail to decrypt
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("first");
+        writer.add_entry("other");
+        let data = writer.finalize_encrypted(&key);
+        let reader = MemBufferReader::new(&data).unwrap();
+        let (first,second) = (reader.offsets.get(0),reader.offsets.get(1));
+        let offset = data.len()-reader.data.len();
+        let mut moved = data.clone();
+        moved.copy_within(offset+first.start..offset+first.end,offset+second.start);
+        let reader = MemBufferReader::new_encrypted(&moved,&key).unwrap();
+        assert_eq!(reader.load_entry::<String>(0).unwrap(),"first");
+        assert!(matches!(reader.load_entry::<String>(1),Err(MemBufferError::DecryptionFailed)));
+        assert!(MemBufferReader::new_encrypted(&writer.finalize(),&key).is_err());
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn check_encrypted_compressed_entries() {
+        let key = EncryptionKey::new(&[7;32]);
+        let text = "It was on a dreary night of November ".repeat(100);
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry_compressed(&text,crate::Compression::Lz4);
+        let data = writer.finalize_encrypted(&key);
+        let reader = MemBufferReader::new_encrypted(&data,&key).unwrap();
+        assert!(reader.is_compressed(0));
+        assert_eq!(reader.uncompressed_len(0).unwrap(),text.len());
+        assert_eq!(reader.load_entry::<String>(0).unwrap(),text);
+        //The size prefix is encrypted with the compressed bytes
+        assert!(matches!(MemBufferReader::new(&data).unwrap().uncompressed_len(0),Err(MemBufferError::EncryptedEntry)));
+    }
+
+    #[test]
+    fn check_authenticated_metadata() {
+        let key = EncryptionKey::new(&[7;32]);
+        for compact in [false,true] {
+            let mut writer = if compact { MemBufferWriter::new().with_compact_header() } else { MemBufferWriter::new().with_byte_order(Endianness::Big) };
+            writer.add_entry_named("secret","value");
+            writer.add_entry_named("public",42);
+            writer.set_label(0,"hidden");
+            writer.set_content_type(0,"text/plain");
+            let data = writer.finalize_encrypted(&key);
+            let reader = MemBufferReader::new_encrypted(&data,&key).unwrap();
+            assert_eq!(reader.load_entry_by_name::<String>("secret").unwrap(),"value");
+
+            //Renamed entries, changed labels and content types
+            for text in [&b"secret"[..],b"hidden",b"text/plain"] {
+                let pos = data.windows(text.len()).position(|x| x == text).unwrap();
+                let mut changed = data.clone();
+                changed[pos] ^= 1;
+                assert!(matches!(MemBufferReader::new_encrypted(&changed,&key),Err(MemBufferError::DecryptionFailed)));
+            }
+        }
+
+        //Entries moved in the entry table, the end of the first entry is stored big endian at 12
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry("first");
+        writer.add_entry("second");
+        let mut data = writer.finalize_encrypted(&key);
+        data[15] -= 1;
+        assert!(matches!(MemBufferReader::new_encrypted(&data,&key),Err(MemBufferError::DecryptionFailed)));
+    }
+
+}
@@ -0,0 +1,169 @@
+//!Byte order of buffers. Writers use the native byte order unless another one is selected,
+//!`MemBufferWriter::new_portable` always writes little endian so files can be exchanged between
+//!architectures. The magic word of the header doubles as byte order mark: a reader which finds it
+//!byte swapped reads the header, the integers and the metadata of the buffer swapped. Integer
+//!slices cannot be borrowed from a buffer with the other byte order and fail with
+//!`MemBufferError::ByteOrderMismatch`, nested buffers carry their own byte order mark.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new_portable();
+//!writer.add_entry("portable");
+//!writer.add_entry(-7);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry::<&str>(0).unwrap(),"portable");
+//!assert_eq!(reader.load_entry::<i32>(1).unwrap(),-7);
+//!```
+use crate::{MemBufferReader,MemBufferTypes};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+
+///Byte order a writer produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Native,
+    Little,
+    Big,
+}
+
+impl Endianness {
+    ///Returns whether data in this byte order has to be swapped on this machine
+    #[cfg(feature = "std")]
+    pub(crate) fn is_swapped(&self) -> bool {
+        match self {
+            Endianness::Native => false,
+            Endianness::Little => cfg!(target_endian = "big"),
+            Endianness::Big => cfg!(target_endian = "little"),
+        }
+    }
+}
+
+///Returns the width of the integers the payload of the given type consists of, payloads of types
+///with width 1 do not depend on the byte order
+pub(crate) fn swap_width(variable_type: i32) -> usize {
+    if variable_type == MemBufferTypes::Unsigned16 as i32 || variable_type == MemBufferTypes::Signed16 as i32 {
+        2
+    }
+    else if variable_type == MemBufferTypes::Integer32 as i32 || variable_type == MemBufferTypes::VectorU32 as i32 || variable_type == MemBufferTypes::InternedText as i32
+        || variable_type == MemBufferTypes::Float32 as i32 || variable_type == MemBufferTypes::VectorF32 as i32
+        || variable_type == MemBufferTypes::Unsigned32 as i32 || variable_type == MemBufferTypes::Char as i32 {
+        4
+    }
+    else if variable_type == MemBufferTypes::VectorU64 as i32 || variable_type == MemBufferTypes::Integer64 as i32
+        || variable_type == MemBufferTypes::Float64 as i32 || variable_type == MemBufferTypes::VectorF64 as i32
+        || variable_type == MemBufferTypes::Signed64 as i32 {
+        8
+    }
+    else {
+        1
+    }
+}
+
+///Reverses the bytes of every integer of the given width in place
+pub(crate) fn swap_integers(data: &mut [u8], width: usize) {
+    data.chunks_exact_mut(width).for_each(|x| x.reverse());
+}
+
+///Reads a u32 which is byte swapped if requested
+pub(crate) fn read_u32(data: &[u8], swapped: bool) -> u32 {
+    let val = u32::from_ne_bytes([data[0],data[1],data[2],data[3]]);
+    if swapped { val.swap_bytes() } else { val }
+}
+
+///Reads a u64 which is byte swapped if requested
+pub(crate) fn read_u64(data: &[u8], swapped: bool) -> u64 {
+    let mut bytes = [0u8;8];
+    bytes.copy_from_slice(&data[..8]);
+    let val = u64::from_ne_bytes(bytes);
+    if swapped { val.swap_bytes() } else { val }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Creates a writer producing little endian buffers which can be read on every architecture
+    pub fn new_portable() -> MemBufferWriter {
+        MemBufferWriter::new().with_byte_order(Endianness::Little)
+    }
+
+    ///Selects the byte order of the buffer, integers are converted when the buffer is written
+    pub fn with_byte_order(mut self, byte_order: Endianness) -> MemBufferWriter {
+        self.byte_order = byte_order;
+        self
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns whether the buffer was written with the other byte order than the one of this
+    ///machine
+    pub fn is_byte_swapped(&self) -> bool {
+        self.offsets.is_swapped()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,Endianness};
+
+    #[cfg(target_endian = "little")]
+    const FOREIGN: Endianness = Endianness::Big;
+    #[cfg(target_endian = "big")]
+    const FOREIGN: Endianness = Endianness::Little;
+
+    #[test]
+    fn check_foreign_byte_order() {
+        let mut nested = MemBufferWriter::new();
+        nested.add_entry("nested");
+        let mut writer = MemBufferWriter::new().with_byte_order(FOREIGN).with_field_checksums();
+        writer.add_entry("Hello");
+        writer.add_entry(0x01020304);
+        writer.add_entry(0x0102030405060708u64);
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry(nested);
+        writer.add_interned_entry("label");
+        writer.add_entry_named("answer",42);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new_verified(&result).unwrap();
+        assert!(reader.is_byte_swapped());
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+        assert_eq!(reader.load_entry::<i32>(1).unwrap(),0x01020304);
+        assert_eq!(reader.load_entry::<u64>(2).unwrap(),0x0102030405060708);
+        assert!(matches!(reader.load_entry::<&[u64]>(3),Err(MemBufferError::ByteOrderMismatch)));
+        assert_eq!(reader.load_recursive_reader(4).unwrap().load_entry::<&str>(0).unwrap(),"nested");
+        assert_eq!(reader.load_entry::<&str>(5).unwrap(),"label");
+        assert_eq!(reader.load_entry_by_name::<i32>("answer").unwrap(),42);
+
+        let native = MemBufferWriter::from(&result).unwrap().finalize();
+        let reader = MemBufferReader::new_verified(&native).unwrap();
+        assert!(!reader.is_byte_swapped());
+        assert_eq!(reader.load_entry::<&[u64]>(3).unwrap(),&[1,2,3]);
+        assert_eq!(reader.load_entry::<u64>(2).unwrap(),0x0102030405060708);
+    }
+
+    #[test]
+    fn check_scalar_byte_order() {
+        for (byte_order,int,long) in [(Endianness::Little,[4u8,3,2,1],[8u8,7,6,5,4,3,2,1]),(Endianness::Big,[1,2,3,4],[1,2,3,4,5,6,7,8])].iter() {
+            let mut writer = MemBufferWriter::new().with_byte_order(*byte_order);
+            writer.add_entry(0x01020304);
+            writer.add_entry(0x0102030405060708u64);
+            let result = writer.finalize();
+            assert!(result.windows(4).any(|x| x == int));
+            assert!(result.windows(8).any(|x| x == long));
+        }
+    }
+
+    #[test]
+    fn check_portable_byte_order() {
+        let mut writer = MemBufferWriter::new_portable().with_large_offsets();
+        writer.add_entry(7);
+        let result = writer.finalize();
+        //One user entry and the checksum entry
+        assert_eq!(&result[..4],&2i32.to_le_bytes());
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.is_byte_swapped(),cfg!(target_endian = "big"));
+        assert_eq!(reader.load_entry::<i32>(0).unwrap(),7);
+    }
+}
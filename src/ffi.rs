@@ -0,0 +1,238 @@
+//!C API for reading and writing buffers from other languages, available with the `ffi` feature.
+//!The functions are `extern "C"` with unmangled names and are declared in `include/membuffer.h`,
+//!which matches what cbindgen generates for this module. Build the crate as static or dynamic
+//!library with `cargo rustc --release --features ffi --crate-type staticlib` and link it.
+//!
+//!Readers borrow the buffer they are created from, the caller has to keep it alive and unchanged
+//!until `membuffer_reader_free` is called. Entry pointers point into that buffer and hold the
+//!payload as stored, so integers are in the byte order of the buffer. Writers copy the payloads
+//!they are given, `membuffer_writer_finalize` returns a buffer owned by the library which has to
+//!be released with `membuffer_buffer_free`. Every function accepts null handles and reports them
+//!like any other failure.
+//!```c
+//!MemBufferWriter *writer = membuffer_writer_new();
+//!membuffer_writer_add_entry(writer,MEMBUFFER_TYPE_TEXT,(const uint8_t *)"Hello",5);
+//!size_t len = 0;
+//!uint8_t *data = membuffer_writer_finalize(writer,&len);
+//!membuffer_writer_free(writer);
+//!
+//!MemBufferFfiReader *reader = membuffer_reader_new(data,len);
+//!const uint8_t *text = membuffer_reader_entry_ptr(reader,0);
+//!size_t text_len = membuffer_reader_entry_len(reader,0);
+//!membuffer_reader_free(reader);
+//!membuffer_buffer_free(data,len);
+//!```
+use crate::{MemBufferWriter,MemBufferReader,MemBufferTypes,is_system_type};
+use crate::utf8::is_valid_utf8;
+
+///Type returned by `membuffer_reader_entry_type` for entries which do not exist
+pub const MEMBUFFER_NO_ENTRY: i32 = i32::MIN;
+
+///Reader handed out to C, it borrows the buffer it was created from
+pub struct MemBufferFfiReader {
+    reader: MemBufferReader<'static>,
+}
+
+///Returns the slice for the pointer and length, null pointers are only valid for empty slices
+unsafe fn slice<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        return if len == 0 { Some(&[]) } else { None };
+    }
+    Some(core::slice::from_raw_parts(data,len))
+}
+
+///Creates a reader over the buffer and checks that every entry lies within it, returns null if
+///the buffer is invalid
+///
+///# Safety
+///`data` has to point to `len` readable bytes which stay valid and unchanged until the reader is
+///freed
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_reader_new(data: *const u8, len: usize) -> *mut MemBufferFfiReader {
+    let data = match slice(data,len) {
+        Some(data) => data,
+        None => return core::ptr::null_mut(),
+    };
+    match MemBufferReader::new_validated(data) {
+        Ok(reader) => Box::into_raw(Box::new(MemBufferFfiReader { reader })),
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+///Frees the reader, null is ignored
+///
+///# Safety
+///`reader` has to be returned by `membuffer_reader_new` and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_reader_free(reader: *mut MemBufferFfiReader) {
+    if !reader.is_null() {
+        drop(Box::from_raw(reader));
+    }
+}
+
+///Returns the number of entries, zero for null
+///
+///# Safety
+///`reader` has to be null or returned by `membuffer_reader_new`
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_reader_len(reader: *const MemBufferFfiReader) -> usize {
+    reader.as_ref().map_or(0,|x| x.reader.len())
+}
+
+///Returns the type id of the entry or `MEMBUFFER_NO_ENTRY` if there is no entry at the index
+///
+///# Safety
+///`reader` has to be null or returned by `membuffer_reader_new`
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_reader_entry_type(reader: *const MemBufferFfiReader, index: usize) -> i32 {
+    match reader.as_ref() {
+        Some(x) if index < x.reader.len() => x.reader.offsets.get(index).variable_type,
+        _ => MEMBUFFER_NO_ENTRY,
+    }
+}
+
+///Returns the pointer to the stored payload of the entry, null if there is no entry at the index
+///or it is compressed or encrypted
+///
+///# Safety
+///`reader` has to be null or returned by `membuffer_reader_new`
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_reader_entry_ptr(reader: *const MemBufferFfiReader, index: usize) -> *const u8 {
+    match reader.as_ref() {
+        Some(x) if index < x.reader.len() && !x.reader.is_compressed(index) && !x.reader.is_encrypted(index) => {
+            x.reader.entry_slice(x.reader.offsets.get(index)).map_or(core::ptr::null(),|x| x.as_ptr())
+        },
+        _ => core::ptr::null(),
+    }
+}
+
+///Returns the length of the stored payload of the entry, zero if there is no entry at the index
+///
+///# Safety
+///`reader` has to be null or returned by `membuffer_reader_new`
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_reader_entry_len(reader: *const MemBufferFfiReader, index: usize) -> usize {
+    match reader.as_ref() {
+        Some(x) if index < x.reader.len() => x.reader.offsets.get(index).len(),
+        _ => 0,
+    }
+}
+
+///Creates an empty writer
+#[no_mangle]
+pub extern "C" fn membuffer_writer_new() -> *mut MemBufferWriter {
+    Box::into_raw(Box::new(MemBufferWriter::new()))
+}
+
+///Frees the writer, null is ignored
+///
+///# Safety
+///`writer` has to be returned by `membuffer_writer_new` and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_writer_free(writer: *mut MemBufferWriter) {
+    if !writer.is_null() {
+        drop(Box::from_raw(writer));
+    }
+}
+
+///Returns the number of entries, zero for null
+///
+///# Safety
+///`writer` has to be null or returned by `membuffer_writer_new`
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_writer_len(writer: *const MemBufferWriter) -> usize {
+    writer.as_ref().map_or(0,|x| x.len())
+}
+
+///Copies the payload into a new entry with the given type id, the payload has to be in the byte
+///order of the machine. Returns false without adding the entry for type ids reserved for metadata
+///and interned text and for text which is not valid UTF-8.
+///
+///# Safety
+///`writer` has to be null or returned by `membuffer_writer_new`, `data` has to point to `len`
+///readable bytes
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_writer_add_entry(writer: *mut MemBufferWriter, variable_type: i32, data: *const u8, len: usize) -> bool {
+    let (writer,data) = match (writer.as_mut(),slice(data,len)) {
+        (Some(writer),Some(data)) => (writer,data),
+        _ => return false,
+    };
+    if is_system_type(variable_type) || variable_type == MemBufferTypes::InternedText as i32
+        || (variable_type == MemBufferTypes::Text as i32 && !is_valid_utf8(data)) {
+        return false;
+    }
+    writer.add_raw_entry(variable_type,data);
+    true
+}
+
+///Writes the buffer and returns it, its length is stored in `len`. The buffer has to be released
+///with `membuffer_buffer_free`. Returns null for a null writer.
+///
+///# Safety
+///`writer` has to be null or returned by `membuffer_writer_new`, `len` has to be writable
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_writer_finalize(writer: *const MemBufferWriter, len: *mut usize) -> *mut u8 {
+    let (writer,len) = match (writer.as_ref(),len.as_mut()) {
+        (Some(writer),Some(len)) => (writer,len),
+        _ => return core::ptr::null_mut(),
+    };
+    let data = writer.finalize().into_boxed_slice();
+    *len = data.len();
+    Box::into_raw(data).cast::<u8>()
+}
+
+///Frees a buffer returned by `membuffer_writer_finalize`, null is ignored
+///
+///# Safety
+///`data` and `len` have to be returned by `membuffer_writer_finalize` and the buffer must not be
+///used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn membuffer_buffer_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(core::ptr::slice_from_raw_parts_mut(data,len)));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_ffi_roundtrip() {
+        unsafe {
+            let writer = membuffer_writer_new();
+            assert!(membuffer_writer_add_entry(writer,MemBufferTypes::Text as i32,"Hello".as_ptr(),5));
+            assert!(membuffer_writer_add_entry(writer,MemBufferTypes::Integer32 as i32,42i32.to_ne_bytes().as_ptr(),4));
+            assert!(membuffer_writer_add_entry(writer,MemBufferTypes::VectorU8 as i32,core::ptr::null(),0));
+            assert!(!membuffer_writer_add_entry(writer,MemBufferTypes::Text as i32,[0xFFu8].as_ptr(),1));
+            assert!(!membuffer_writer_add_entry(writer,-0x1002,[0u8].as_ptr(),1));
+            assert!(!membuffer_writer_add_entry(core::ptr::null_mut(),0,core::ptr::null(),0));
+            assert_eq!(membuffer_writer_len(writer),3);
+            let mut len = 0;
+            let data = membuffer_writer_finalize(writer,&mut len);
+            membuffer_writer_free(writer);
+
+            let reader = membuffer_reader_new(data,len);
+            assert_eq!(membuffer_reader_len(reader),3);
+            assert_eq!(membuffer_reader_entry_type(reader,1),MemBufferTypes::Integer32 as i32);
+            let text = core::slice::from_raw_parts(membuffer_reader_entry_ptr(reader,0),membuffer_reader_entry_len(reader,0));
+            assert_eq!(text,b"Hello");
+            assert_eq!(membuffer_reader_entry_len(reader,2),0);
+            assert_eq!(membuffer_reader_entry_type(reader,3),MEMBUFFER_NO_ENTRY);
+            assert!(membuffer_reader_entry_ptr(reader,3).is_null());
+            assert!(MemBufferReader::new(core::slice::from_raw_parts(data,len)).unwrap().load_entry::<i32>(1).unwrap() == 42);
+            membuffer_reader_free(reader);
+            assert!(membuffer_reader_new(data,7).is_null());
+            assert!(membuffer_reader_new(core::ptr::null(),len).is_null());
+            membuffer_buffer_free(data,len);
+        }
+
+        //Every function is declared in the header
+        let header = include_str!("../include/membuffer.h");
+        for name in ["membuffer_reader_new","membuffer_reader_free","membuffer_reader_len","membuffer_reader_entry_type","membuffer_reader_entry_ptr","membuffer_reader_entry_len",
+            "membuffer_writer_new","membuffer_writer_free","membuffer_writer_len","membuffer_writer_add_entry","membuffer_writer_finalize","membuffer_buffer_free"].iter() {
+            assert!(header.contains(&format!("{}(",name)),"{} is not declared",name);
+        }
+    }
+}
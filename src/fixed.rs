@@ -0,0 +1,165 @@
+//!Reader for buffers with a schema known at compile time. The number of entries, their types and
+//!their payloads are verified once when the reader is created, afterwards loads skip the per call
+//!type checks. Entries are borrowed as they are stored, so buffers with compressed or encrypted
+//!entries or integers in the other byte order are rejected, load those with `MemBufferReader`.
+//!```rust
+//!use membuffer::{MemBufferWriter,FixedReader,MemBufferTypes};
+//!
+//!const SCHEMA: [i32;2] = [MemBufferTypes::Text as i32,MemBufferTypes::Integer32 as i32];
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("name");
+//!writer.add_entry(42);
+//!let data = writer.finalize();
+//!
+//!let reader = FixedReader::new(&data,SCHEMA).unwrap();
+//!assert_eq!(reader.load_entry::<&str>(0).unwrap(),"name");
+//!assert_eq!(reader.load_entry::<i32>(1).unwrap(),42);
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferDeserialize,MemBufferSerialize,MemBufferTypes};
+use crate::endian::swap_width;
+use crate::utf8::is_valid_utf8;
+
+///Reader verifying at construction that the buffer holds exactly N entries with the given types
+pub struct FixedReader<'a, const N: usize> {
+    entries: [&'a [u8];N],
+    types: [i32;N],
+}
+
+impl<'a, const N: usize> FixedReader<'a,N> {
+    ///Parses and validates the buffer and checks the entry count and every entry type against the
+    ///schema. Fails with `MemBufferError::CompressedEntry`, `MemBufferError::EncryptedEntry` or
+    ///`MemBufferError::ByteOrderMismatch` for entries which cannot be borrowed as they are stored
+    ///and with `MemBufferError::InvalidUtf8` for text which is not valid UTF-8.
+    pub fn new(val: &'a [u8], types: [i32;N]) -> Result<FixedReader<'a,N>,MemBufferError> {
+        let reader = MemBufferReader::new_validated(val)?;
+        if reader.len() != N {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let mut entries: [&'a [u8];N] = [&[];N];
+        for (x,entry) in reader.offsets.iter().enumerate() {
+            if entry.variable_type != types[x] {
+                return Err(MemBufferError::FieldTypeError(entry.variable_type,types[x]));
+            }
+            if reader.is_compressed(x) {
+                return Err(MemBufferError::CompressedEntry);
+            }
+            if reader.is_encrypted(x) {
+                return Err(MemBufferError::EncryptedEntry);
+            }
+            if reader.is_byte_swapped() && swap_width(entry.variable_type) > 1 {
+                return Err(MemBufferError::ByteOrderMismatch);
+            }
+            entries[x] = reader.entry_slice(entry)?;
+            if entry.variable_type == MemBufferTypes::Text as i32 && !is_valid_utf8(entries[x]) {
+                return Err(MemBufferError::InvalidUtf8);
+            }
+        }
+        Ok(FixedReader {
+            entries,
+            types,
+        })
+    }
+
+    ///Loads the entry without checking its type again, the type must match the schema the reader
+    ///was created with which is only asserted in debug builds. Loading an entry with another type
+    ///fails if its payload does not fit the type.
+    pub fn load_entry<X: MemBufferDeserialize<'a,X> + MemBufferSerialize>(&self, key: usize) -> Result<X,MemBufferError> {
+        debug_assert_eq!(self.types[key],X::get_mem_buffer_type(),"Entry {} has a different type in the schema",key);
+        X::from_mem_buffer(self.entries[key])
+    }
+
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferTypes,MemBufferError,FixedReader,Endianness};
+
+    const SCHEMA: [i32;3] = [MemBufferTypes::Text as i32,MemBufferTypes::VectorU64 as i32,MemBufferTypes::Integer32 as i32];
+
+    #[test]
+    fn check_fixed_reader() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry(7);
+        let result = writer.finalize();
+
+        let reader = FixedReader::new(&result,SCHEMA).unwrap();
+        assert_eq!(reader.len(),3);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+        assert_eq!(reader.load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+        assert_eq!(reader.load_entry::<i32>(2).unwrap(),7);
+    }
+
+    #[test]
+    fn check_fixed_reader_stored_entries() {
+        const TEXT: [i32;2] = [MemBufferTypes::Text as i32,MemBufferTypes::Integer32 as i32];
+
+        //Integers in the other byte order cannot be borrowed
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry("Hello");
+        writer.add_entry(42);
+        let result = writer.finalize();
+        assert!(matches!(FixedReader::new(&result,TEXT),Err(MemBufferError::ByteOrderMismatch)));
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Little);
+        writer.add_entry("Hello");
+        writer.add_entry(42);
+        assert_eq!(FixedReader::new(&writer.finalize(),TEXT).unwrap().load_entry::<i32>(1).unwrap(),42);
+
+        //Invalid UTF-8 is rejected up front
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Grüße");
+        writer.add_entry(42);
+        let mut result = writer.finalize();
+        let pos = result.windows(2).position(|x| x == "ü".as_bytes()).unwrap();
+        result[pos] = 0xFF;
+        assert!(matches!(FixedReader::new(&result,TEXT),Err(MemBufferError::InvalidUtf8)));
+
+        #[cfg(any(feature = "lz4", feature = "zstd"))]
+        {
+            #[cfg(feature = "lz4")]
+            let compression = crate::Compression::Lz4;
+            #[cfg(not(feature = "lz4"))]
+            let compression = crate::Compression::Zstd(3);
+            let mut writer = MemBufferWriter::new();
+            writer.add_entry_compressed("Hello ".repeat(100),compression);
+            writer.add_entry(42);
+            assert!(matches!(FixedReader::new(&writer.finalize(),TEXT),Err(MemBufferError::CompressedEntry)));
+        }
+
+        #[cfg(feature = "encryption")]
+        {
+            let mut writer = MemBufferWriter::new();
+            writer.add_entry("Hello");
+            writer.add_entry(42);
+            let result = writer.finalize_encrypted(&crate::EncryptionKey::new(&[7;32]));
+            assert!(matches!(FixedReader::new(&result,TEXT),Err(MemBufferError::EncryptedEntry)));
+        }
+    }
+
+    #[test]
+    fn check_fixed_reader_mismatch() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry("World");
+        assert!(FixedReader::new(&writer.finalize(),SCHEMA).is_err());
+
+        writer.add_entry(7);
+        match FixedReader::new(&writer.finalize(),SCHEMA) {
+            Err(MemBufferError::FieldTypeError(x,y)) => {
+                assert_eq!(x,MemBufferTypes::Text as i32);
+                assert_eq!(y,MemBufferTypes::VectorU64 as i32);
+            },
+            _ => panic!("Expected field type error"),
+        }
+    }
+}
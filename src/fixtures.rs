@@ -0,0 +1,220 @@
+//!Deterministic test fixtures, available with the `fixtures` feature which is meant to be enabled
+//!in the dev-dependencies of dependent crates. `FixtureBuilder` generates buffers with the given
+//!number of entries, payload lengths and types from a seed, the same seed always generates the
+//!same entries so property tests and benchmarks can be reproduced.
+//!```rust
+//!use membuffer::{MemBufferReader,MemBufferTypes};
+//!use membuffer::fixtures::FixtureBuilder;
+//!
+//!let data = FixtureBuilder::new(42).with_entries(100).with_len(0,64).with_types(vec![MemBufferTypes::Text,MemBufferTypes::VectorU64]).finalize();
+//!assert_eq!(data,FixtureBuilder::new(42).with_entries(100).with_len(0,64).with_types(vec![MemBufferTypes::Text,MemBufferTypes::VectorU64]).finalize());
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.len(),100);
+//!assert!(reader.entries().all(|x| x.is_ok()));
+//!```
+use crate::{MemBufferWriter,MemBufferTypes,MemBufferMap};
+
+///Words interned entries are picked from, so the interning table is shared between entries
+const LABELS: [&str;8] = ["alpha","beta","gamma","delta","epsilon","zeta","eta","theta"];
+
+///SplitMix64 generator, it is small and its sequence is fixed for every seed
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    ///Returns a number in the inclusive range
+    fn range(&mut self, min: usize, max: usize) -> usize {
+        min+(self.next_u64()%((max-min) as u64+1)) as usize
+    }
+
+    ///Returns a finite float between -1000 and 1000
+    fn float(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64/(1u64 << 53) as f64*2000.0-1000.0
+    }
+}
+
+///Generates buffers from a seed, see the module documentation
+pub struct FixtureBuilder {
+    seed: u64,
+    entries: usize,
+    min_len: usize,
+    max_len: usize,
+    types: Vec<i32>,
+    names: bool,
+}
+
+impl FixtureBuilder {
+    ///Creates a builder for 16 entries of up to 32 elements of every built in type except the
+    ///lists, which are only generated if they are passed to `with_types`
+    pub fn new(seed: u64) -> FixtureBuilder {
+        FixtureBuilder {
+            seed,
+            entries: 16,
+            min_len: 0,
+            max_len: 32,
+            types: vec![
+                MemBufferTypes::Text.into(),
+                MemBufferTypes::Integer32.into(),
+                MemBufferTypes::VectorU8.into(),
+                MemBufferTypes::VectorU32.into(),
+                MemBufferTypes::VectorU64.into(),
+                MemBufferTypes::MemBuffer.into(),
+                MemBufferTypes::InternedText.into(),
+                MemBufferTypes::Integer64.into(),
+                MemBufferTypes::Float32.into(),
+                MemBufferTypes::Float64.into(),
+                MemBufferTypes::VectorF32.into(),
+                MemBufferTypes::VectorF64.into(),
+                MemBufferTypes::Map.into(),
+                MemBufferTypes::Null.into(),
+            ],
+            names: false,
+        }
+    }
+
+    ///Sets the number of entries
+    pub fn with_entries(mut self, entries: usize) -> FixtureBuilder {
+        self.entries = entries;
+        self
+    }
+
+    ///Sets the inclusive range of the number of elements of texts, slices and nested buffers
+    pub fn with_len(mut self, min: usize, max: usize) -> FixtureBuilder {
+        assert!(min <= max,"The minimum length {} is larger than the maximum length {}",min,max);
+        self.min_len = min;
+        self.max_len = max;
+        self
+    }
+
+    ///Restricts the entries to the given types, panics for types which cannot be generated
+    pub fn with_types(mut self, types: impl IntoIterator<Item=MemBufferTypes>) -> FixtureBuilder {
+        self.types = types.into_iter().map(|x| {
+            assert!(!matches!(x,MemBufferTypes::LastPreDefienedValue),"Fixtures cannot be generated for {:?}",x);
+            x.into()
+        }).collect();
+        assert!(!self.types.is_empty(),"Fixtures need at least one type");
+        self
+    }
+
+    ///Names every entry after its index, e.g. `field7`
+    pub fn with_names(mut self) -> FixtureBuilder {
+        self.names = true;
+        self
+    }
+
+    ///Returns a writer holding the generated entries
+    pub fn writer(&self) -> MemBufferWriter {
+        let mut rng = SeededRng(self.seed);
+        let mut writer = MemBufferWriter::new();
+        for index in 0..self.entries {
+            let variable_type = self.types[rng.range(0,self.types.len()-1)];
+            let len = rng.range(self.min_len,self.max_len);
+            FixtureBuilder::add_entry(&mut writer,&mut rng,variable_type,len);
+            if self.names {
+                writer.names.insert(format!("field{}",index),index);
+            }
+        }
+        writer
+    }
+
+    ///Returns the serialized buffer holding the generated entries
+    pub fn finalize(&self) -> Vec<u8> {
+        self.writer().finalize()
+    }
+
+    fn add_entry(writer: &mut MemBufferWriter, rng: &mut SeededRng, variable_type: i32, len: usize) {
+        match variable_type {
+            x if x == MemBufferTypes::Text as i32 => {
+                let text: String = (0..len).map(|_| (b'a'+(rng.next_u64()%26) as u8) as char).collect();
+                writer.add_entry(text);
+            },
+            x if x == MemBufferTypes::Integer32 as i32 => writer.add_entry(rng.next_u64() as i32),
+            x if x == MemBufferTypes::VectorU8 as i32 => {
+                let data: Vec<u8> = (0..len).map(|_| rng.next_u64() as u8).collect();
+                writer.add_entry(&data[..]);
+            },
+            x if x == MemBufferTypes::VectorU32 as i32 => {
+                let data: Vec<u32> = (0..len).map(|_| rng.next_u64() as u32).collect();
+                writer.add_entry(&data[..]);
+            },
+            x if x == MemBufferTypes::VectorU64 as i32 => {
+                let data: Vec<u64> = (0..len).map(|_| rng.next_u64()).collect();
+                writer.add_entry(&data[..]);
+            },
+            x if x == MemBufferTypes::MemBuffer as i32 => {
+                //Nested buffers hold texts and integers only to keep the fixtures flat
+                let mut nested = MemBufferWriter::new();
+                for _ in 0..len {
+                    let nested_type = if rng.next_u64().is_multiple_of(2) { MemBufferTypes::Text } else { MemBufferTypes::Integer32 };
+                    let nested_len = rng.range(0,len);
+                    FixtureBuilder::add_entry(&mut nested,rng,nested_type as i32,nested_len);
+                }
+                writer.add_entry(nested);
+            },
+            x if x == MemBufferTypes::InternedText as i32 => writer.add_interned_entry(LABELS[rng.range(0,LABELS.len()-1)]),
+            x if x == MemBufferTypes::Integer64 as i32 => writer.add_entry(rng.next_u64()),
+            x if x == MemBufferTypes::Float32 as i32 => writer.add_entry(rng.float() as f32),
+            x if x == MemBufferTypes::Float64 as i32 => writer.add_entry(rng.float()),
+            x if x == MemBufferTypes::VectorF32 as i32 => {
+                let data: Vec<f32> = (0..len).map(|_| rng.float() as f32).collect();
+                writer.add_entry(&data[..]);
+            },
+            x if x == MemBufferTypes::VectorF64 as i32 => {
+                let data: Vec<f64> = (0..len).map(|_| rng.float()).collect();
+                writer.add_entry(&data[..]);
+            },
+            x if x == MemBufferTypes::Map as i32 => {
+                let mut map = MemBufferMap::new();
+                for _ in 0..len {
+                    let key = LABELS[rng.range(0,LABELS.len()-1)];
+                    let val: Vec<u8> = (0..rng.range(0,len)).map(|_| rng.next_u64() as u8).collect();
+                    map.insert(key,&val);
+                }
+                writer.add_entry(&map);
+            },
+            x if x == MemBufferTypes::Null as i32 => writer.add_null_entry(),
+            x if x == MemBufferTypes::StrList as i32 => {
+                let list: Vec<String> = (0..len).map(|_| LABELS[rng.range(0,LABELS.len()-1)].to_string()).collect();
+                writer.add_entry(list);
+            },
+            x if x == MemBufferTypes::BytesList as i32 => {
+                let list: Vec<Vec<u8>> = (0..len).map(|_| (0..rng.range(0,len)).map(|_| rng.next_u64() as u8).collect()).collect();
+                writer.add_entry(&list[..]);
+            },
+            _ => unreachable!("Fixture types are checked by with_types"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferReader,MemBufferTypes,Value};
+    use crate::fixtures::FixtureBuilder;
+
+    #[test]
+    fn check_fixtures() {
+        let data = FixtureBuilder::new(7).with_entries(200).with_names().finalize();
+        assert_eq!(data,FixtureBuilder::new(7).with_entries(200).with_names().finalize());
+        assert_ne!(data,FixtureBuilder::new(8).with_entries(200).with_names().finalize());
+
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        assert_eq!(reader.len(),200);
+        assert!(reader.entries().all(|x| x.is_ok()));
+        assert_eq!(reader.entry_index("field199"),Some(199));
+
+        let data = FixtureBuilder::new(1).with_entries(50).with_len(5,5).with_types(vec![MemBufferTypes::VectorU32]).finalize();
+        let reader = MemBufferReader::new(&data).unwrap();
+        for value in reader.entries() {
+            assert!(matches!(value.unwrap(),Value::U32Slice(x) if x.len() == 5));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//!Metadata of single entries. Every entry can carry 32 application defined flag bits and a
+//!creation timestamp without being wrapped into a nested buffer. The metadata is stored in a
+//!metadata entry holding the sorted entry indices, their flags and their timestamps, so the
+//!layout of buffers without entry metadata does not change.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!use std::time::{Duration,UNIX_EPOCH};
+//!
+//!const DIRTY: u32 = 1;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_with_flags("draft",DIRTY);
+//!writer.add_entry("plain");
+//!writer.set_entry_timestamp(1,UNIX_EPOCH+Duration::from_secs(1_600_000_000));
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.entry_flags(0),DIRTY);
+//!assert_eq!(reader.entry_flags(1),0);
+//!assert_eq!(reader.entry_timestamp(0),None);
+//!assert_eq!(reader.entry_timestamp(1),Some(UNIX_EPOCH+Duration::from_secs(1_600_000_000)));
+//!```
+#[cfg(feature = "std")]
+use std::time::{Duration,SystemTime,UNIX_EPOCH};
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,SYSTEM_TYPE_FLAGS};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,MemBufferSerialize};
+use crate::named::NameIndices;
+
+///Flags and creation timestamp of one entry, the timestamp is stored in nanoseconds since the
+///unix epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg(feature = "std")]
+pub(crate) struct EntryMetadata {
+    pub(crate) flags: u32,
+    pub(crate) timestamp: Option<u64>,
+}
+
+///Timestamp of entries without one in the table
+#[cfg(feature = "std")]
+const NO_TIMESTAMP: u64 = u64::MAX;
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds an entry carrying the given flags
+    pub fn add_entry_with_flags<T: MemBufferSerialize>(&mut self, val: T, flags: u32) {
+        self.entry_metadata.entry(self.types.len()).or_default().flags = flags;
+        self.add_entry(val);
+    }
+
+    ///Replaces the flags of the entry. Panics if the index is out of bounds.
+    pub fn set_entry_flags(&mut self, index: usize, flags: u32) {
+        assert!(index < self.types.len(),"Entry {} is out of bounds",index);
+        self.entry_metadata.entry(index).or_default().flags = flags;
+    }
+
+    ///Sets the creation timestamp of the entry, times before the unix epoch are stored as the
+    ///epoch. Panics if the index is out of bounds.
+    pub fn set_entry_timestamp(&mut self, index: usize, time: SystemTime) {
+        assert!(index < self.types.len(),"Entry {} is out of bounds",index);
+        let nanos = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().min(NO_TIMESTAMP as u128-1);
+        self.entry_metadata.entry(index).or_default().timestamp = Some(nanos as u64);
+    }
+
+    ///Returns the flags of the entry, entries without flags return 0
+    pub fn entry_flags(&self, index: usize) -> u32 {
+        self.entry_metadata.get(&index).map_or(0,|x| x.flags)
+    }
+
+    ///Serializes the sorted entry indices, their flags and their timestamps as nested buffer, the
+    ///timestamps are left out if no entry has one
+    pub(crate) fn flag_table(&self) -> Option<Vec<u8>> {
+        if self.entry_metadata.is_empty() {
+            return None;
+        }
+        let indices: Vec<u32> = self.entry_metadata.keys().map(|x| *x as u32).collect();
+        let flags: Vec<u32> = self.entry_metadata.values().map(|x| x.flags).collect();
+        let mut table = MemBufferWriter::new();
+        table.add_entry(&indices[..]);
+        table.add_entry(&flags[..]);
+        if self.entry_metadata.values().any(|x| x.timestamp.is_some()) {
+            let timestamps: Vec<u64> = self.entry_metadata.values().map(|x| x.timestamp.unwrap_or(NO_TIMESTAMP)).collect();
+            table.add_entry(&timestamps[..]);
+        }
+        Some(table.finalize())
+    }
+
+    ///Takes over the flags and timestamps of the entries of the reader
+    pub(crate) fn load_flags(&mut self, reader: &MemBufferReader) -> Result<(),MemBufferError> {
+        if let Some(table) = reader.flag_table()? {
+            for x in 0..table.indices.len() {
+                self.entry_metadata.insert(table.indices.get(x),EntryMetadata {
+                    flags: table.flags.get(x) as u32,
+                    timestamp: table.timestamp(x),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+///Columns of the table of entry metadata
+struct FlagTable<'a> {
+    indices: NameIndices<'a>,
+    flags: NameIndices<'a>,
+    timestamps: Option<(&'a [u8],bool)>,
+}
+
+impl<'a> FlagTable<'a> {
+    #[cfg(feature = "std")]
+    fn timestamp(&self, index: usize) -> Option<u64> {
+        let (data,swapped) = self.timestamps?;
+        Some(crate::endian::read_u64(&data[index*8..],swapped)).filter(|x| *x != NO_TIMESTAMP)
+    }
+
+    ///Returns the position of the entry in the table
+    fn position(&self, index: usize) -> Option<usize> {
+        let (mut low,mut high) = (0,self.indices.len());
+        while low < high {
+            let mid = (low+high)/2;
+            match self.indices.get(mid).cmp(&index) {
+                core::cmp::Ordering::Less => low = mid+1,
+                core::cmp::Ordering::Greater => high = mid,
+                core::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    fn flag_table(&self) -> Result<Option<FlagTable<'a>>,MemBufferError> {
+        let table = match self.system_entry(SYSTEM_TYPE_FLAGS) {
+            Some(table) => MemBufferReader::new(table)?,
+            None => return Ok(None),
+        };
+        let column = |index: usize, variable_type: MemBufferTypes, width: usize| -> Result<&'a [u8],MemBufferError> {
+            let entry = table.offsets.get(index);
+            if entry.variable_type != variable_type as i32 || entry.len()%width != 0 {
+                return Err(MemBufferError::WrongFormat);
+            }
+            table.entry_slice(entry)
+        };
+        let swapped = table.is_byte_swapped();
+        let table = match table.len() {
+            2 | 3 => FlagTable {
+                indices: NameIndices { data: column(0,MemBufferTypes::VectorU32,4)?, swapped },
+                flags: NameIndices { data: column(1,MemBufferTypes::VectorU32,4)?, swapped },
+                timestamps: if table.len() == 3 { Some((column(2,MemBufferTypes::VectorU64,8)?,swapped)) } else { None },
+            },
+            _ => return Err(MemBufferError::WrongFormat),
+        };
+        if table.flags.len() != table.indices.len() || table.timestamps.is_some_and(|x| x.0.len() != 8*table.indices.len()) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        Ok(Some(table))
+    }
+
+    ///Returns the flags of the entry, entries without flags return 0
+    pub fn entry_flags(&self, index: usize) -> u32 {
+        self.flag_table().ok().flatten().and_then(|table| table.position(index).map(|x| table.flags.get(x) as u32)).unwrap_or(0)
+    }
+
+    ///Returns the creation timestamp of the entry
+    #[cfg(feature = "std")]
+    pub fn entry_timestamp(&self, index: usize) -> Option<SystemTime> {
+        let table = self.flag_table().ok()??;
+        let nanos = table.timestamp(table.position(index)?)?;
+        Some(UNIX_EPOCH+Duration::from_nanos(nanos))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferAppender};
+    use std::time::{Duration,UNIX_EPOCH};
+
+    #[test]
+    fn check_entry_flags() {
+        let created = UNIX_EPOCH+Duration::from_nanos(1_700_000_000_123_456_789);
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry_with_flags("first",0x8000_0001);
+        writer.add_entry("second");
+        writer.add_entry_with_flags("third",4);
+        writer.set_entry_timestamp(2,created);
+        writer.set_entry_flags(2,5);
+        assert_eq!(writer.entry_flags(2),5);
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!((reader.entry_flags(0),reader.entry_flags(1),reader.entry_flags(2)),(0x8000_0001,0,5));
+        assert_eq!(reader.entry_timestamp(0),None);
+        assert_eq!(reader.entry_timestamp(2),Some(created));
+        assert_eq!(reader.entry_flags(3),0);
+
+        //The metadata moves with the entries and is kept by appends
+        let mut update = MemBufferWriter::from(&data).unwrap();
+        update.remove_entry(0);
+        let mut data = update.finalize_interleaved();
+        let mut appender = MemBufferAppender::open(&mut data).unwrap();
+        appender.add_entry_with_flags("fourth",9);
+        appender.commit().unwrap();
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!((reader.entry_flags(0),reader.entry_flags(1),reader.entry_flags(2)),(0,5,9));
+        assert_eq!(reader.entry_timestamp(1),Some(created));
+    }
+}
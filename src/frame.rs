@@ -0,0 +1,147 @@
+//!Length prefixed frames for sending buffers over sockets and pipes. `write_framed` writes the
+//!length of the buffer as little endian u64 followed by the buffer, `MemBufferFrameReader` reads
+//!exactly one frame into an owned buffer and returns a reader over it. `FramedIterator` reads back
+//!to back frames until the stream ends between two frames.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferFrameReader};
+//!
+//!let mut wire = Vec::new();
+//!for x in 0..3 {
+//!    let mut writer = MemBufferWriter::new();
+//!    writer.add_entry("message");
+//!    writer.add_entry(x);
+//!    writer.write_framed(&mut wire).unwrap();
+//!}
+//!
+//!let mut socket = &wire[..];
+//!let first = MemBufferFrameReader::read_from(&mut socket).unwrap();
+//!assert_eq!(first.load_entry::<i32>(1).unwrap(),0);
+//!let rest: Vec<i32> = MemBufferFrameReader::frames(socket).map(|x| x.unwrap().load_entry::<i32>(1).unwrap()).collect();
+//!assert_eq!(rest,vec![1,2]);
+//!```
+use std::io::{Read,Write};
+use crate::{MemBufferWriter,MemBufferOwnedReader};
+
+///Length of the prefix in front of every frame
+const FRAME_PREFIX_LEN: usize = 8;
+
+fn invalid_data(msg: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData,msg)
+}
+
+impl MemBufferWriter {
+    ///Writes the buffer prefixed with its length as little endian u64 into the given writer and
+    ///returns the number of bytes written including the prefix
+    pub fn write_framed<W: Write>(&self, mut w: W) -> std::io::Result<u64> {
+        let len = self.serialized_len();
+        w.write_all(&(len as u64).to_le_bytes())?;
+        let written = self.finalize_into(&mut w)?;
+        debug_assert_eq!(written,len as u64);
+        Ok(written+FRAME_PREFIX_LEN as u64)
+    }
+}
+
+///Reads single frames written with `MemBufferWriter::write_framed`
+pub struct MemBufferFrameReader;
+
+impl MemBufferFrameReader {
+    ///Reads exactly one frame and returns a reader owning its buffer. Fails with
+    ///`io::ErrorKind::UnexpectedEof` if the stream ends within the frame and with
+    ///`io::ErrorKind::InvalidData` if the frame holds no valid buffer.
+    pub fn read_from<R: Read>(r: R) -> std::io::Result<MemBufferOwnedReader<Vec<u8>>> {
+        FramedIterator::new(r).read_frame()?.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))
+    }
+
+    ///Returns an iterator over the frames of the stream
+    pub fn frames<R: Read>(r: R) -> FramedIterator<R> {
+        FramedIterator::new(r)
+    }
+}
+
+///Iterator over back to back frames of a stream, it ends when the stream ends between two frames
+pub struct FramedIterator<R: Read> {
+    reader: R,
+    max_frame_len: u64,
+}
+
+impl<R: Read> FramedIterator<R> {
+    pub fn new(reader: R) -> FramedIterator<R> {
+        FramedIterator {
+            reader,
+            max_frame_len: u64::MAX,
+        }
+    }
+
+    ///Rejects frames announcing more than the given number of bytes with
+    ///`io::ErrorKind::InvalidData` before reading them, use this for untrusted peers
+    pub fn with_max_frame_len(mut self, max_frame_len: u64) -> FramedIterator<R> {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    ///Reads the next frame, returns None if the stream ends before its prefix
+    pub fn read_frame(&mut self) -> std::io::Result<Option<MemBufferOwnedReader<Vec<u8>>>> {
+        let mut prefix = [0u8;FRAME_PREFIX_LEN];
+        let mut read = 0;
+        while read < prefix.len() {
+            match self.reader.read(&mut prefix[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof)),
+                Ok(x) => read += x,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        let len = u64::from_le_bytes(prefix);
+        if len > self.max_frame_len {
+            return Err(invalid_data(format!("Memory buffer error: Frame of {} bytes exceeds the maximum frame length {}",len,self.max_frame_len)));
+        }
+        //The buffer grows with the received data, a corrupted prefix cannot allocate all memory
+        let mut data = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+        }
+        MemBufferOwnedReader::new(data).map(Some).map_err(|err| invalid_data(err.to_string()))
+    }
+}
+
+impl<R: Read> Iterator for FramedIterator<R> {
+    type Item = std::io::Result<MemBufferOwnedReader<Vec<u8>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferFrameReader,FramedIterator};
+
+    #[test]
+    fn check_frames() {
+        let mut wire = Vec::new();
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("first");
+        let written = writer.write_framed(&mut wire).unwrap();
+        assert_eq!(written as usize,wire.len());
+        writer.add_entry(&vec![1u8;1000][..]);
+        writer.write_framed(&mut wire).unwrap();
+
+        let frames: Vec<_> = MemBufferFrameReader::frames(&wire[..]).collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(frames.len(),2);
+        assert_eq!(frames[1].load_entry::<&[u8]>(1).unwrap(),&[1u8;1000][..]);
+        assert_eq!(frames[0].len(),1);
+        assert_eq!(frames[0].load_entry::<&str>(0).unwrap(),"first");
+
+        //Truncated frames, oversized frames and garbage
+        assert_eq!(MemBufferFrameReader::read_from(&wire[..written as usize-1]).err().unwrap().kind(),std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(MemBufferFrameReader::read_from(&wire[..3]).err().unwrap().kind(),std::io::ErrorKind::UnexpectedEof);
+        assert!(MemBufferFrameReader::frames(&[][..]).next().is_none());
+        assert_eq!(FramedIterator::new(&wire[..]).with_max_frame_len(100).nth(1).unwrap().err().unwrap().kind(),std::io::ErrorKind::InvalidData);
+        let mut garbage = 16u64.to_le_bytes().to_vec();
+        garbage.extend_from_slice(&[0xAB;16]);
+        assert_eq!(MemBufferFrameReader::read_from(&garbage[..]).err().unwrap().kind(),std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,289 @@
+//!Memory mapped buffer files, available with the `mmap` feature. `MemBufferFile::open` maps the
+//!file and hands out readers borrowing the map, so no entry is copied out of the page cache.
+//!```rust
+//!use membuffer::MemBufferWriter;
+//!use membuffer::fs::MemBufferFile;
+//!
+//!let path = std::env::temp_dir().join("membuffer_fs_doc.mem");
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello World");
+//!MemBufferFile::create(&path,&writer).unwrap();
+//!
+//!let file = MemBufferFile::open(&path).unwrap();
+//!assert_eq!(file.reader().load_entry::<&str>(0).unwrap(),"Hello World");
+//!# std::fs::remove_file(&path).unwrap();
+//!```
+//!`EntryMappedFile::open` only maps the header and maps every entry on its own when it is
+//!requested, the mapping is released when the `MappedEntry` is dropped. This bounds the address
+//!space used per file when thousands of large archives are open at the same time.
+//!```rust
+//!use membuffer::MemBufferWriter;
+//!use membuffer::fs::{MemBufferFile,EntryMappedFile};
+//!
+//!let path = std::env::temp_dir().join("membuffer_fs_entries_doc.mem");
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello World");
+//!writer.add_entry(&vec![0u8;1<<20][..]);
+//!MemBufferFile::create(&path,&writer).unwrap();
+//!
+//!let file = EntryMappedFile::open(&path).unwrap();
+//!assert_eq!(file.len(),2);
+//!let entry = file.map_entry(0).unwrap();
+//!assert_eq!(entry.load::<&str>().unwrap(),"Hello World");
+//!# std::fs::remove_file(&path).unwrap();
+//!```
+use std::fs::File;
+use std::io::{BufWriter,Read,Write};
+use std::path::Path;
+use memmap2::{Mmap,MmapOptions};
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize,SYSTEM_TYPE_COMPRESSION};
+use crate::table::{self,EntryPosition};
+use crate::compress::{find_record,load_detached};
+
+///Converts a format error into an `io::ErrorKind::InvalidData` error
+fn invalid_data(err: MemBufferError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData,err.to_string())
+}
+
+///Buffer file mapped into memory, the file must not be modified while it is mapped
+pub struct MemBufferFile {
+    map: Mmap,
+}
+
+impl MemBufferFile {
+    ///Maps the file and checks that it holds a buffer, format errors are returned as
+    ///`io::ErrorKind::InvalidData`
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<MemBufferFile> {
+        let file = File::open(path)?;
+        let map = unsafe{ Mmap::map(&file)? };
+        MemBufferReader::from_storage(&map).map_err(invalid_data)?;
+        Ok(MemBufferFile {
+            map,
+        })
+    }
+
+    ///Maps the file like `open`, checks that it is as long as its header declares and that every
+    ///entry lies within it and verifies the checksums if it has any. Format errors are returned as
+    ///`io::ErrorKind::InvalidData` holding the `MemBufferError`, for example
+    ///`MemBufferError::Truncated` for files which were not completely written.
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferError};
+    ///use membuffer::fs::MemBufferFile;
+    ///
+    ///let path = std::env::temp_dir().join("membuffer_fs_checked_doc.mem");
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("Hello World");
+    ///let written = writer.persist_atomic(&path).unwrap();
+    ///assert!(MemBufferFile::open_checked(&path).is_ok());
+    ///
+    ///std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(written-4).unwrap();
+    ///let err = MemBufferFile::open_checked(&path).err().unwrap();
+    ///let err = err.get_ref().unwrap().downcast_ref::<MemBufferError>().unwrap();
+    ///assert!(matches!(err,MemBufferError::Truncated(x,y) if *x as u64 == written && *y as u64 == written-4));
+    ///# std::fs::remove_file(&path).unwrap();
+    ///```
+    pub fn open_checked<P: AsRef<Path>>(path: P) -> std::io::Result<MemBufferFile> {
+        let file = File::open(path)?;
+        let map = unsafe{ Mmap::map(&file)? };
+        let check = || -> Result<(),MemBufferError> {
+            MemBufferReader::declared_len(&map)?;
+            match MemBufferReader::new_validated(&map)?.verify_checksums() {
+                Err(MemBufferError::MissingChecksum) => Ok(()),
+                result => result,
+            }
+        };
+        check().map_err(|x| std::io::Error::new(std::io::ErrorKind::InvalidData,x))?;
+        Ok(MemBufferFile {
+            map,
+        })
+    }
+
+    ///Writes the buffer of the writer into a new file or truncates an existing one, returns the
+    ///number of bytes written
+    pub fn create<P: AsRef<Path>>(path: P, writer: &MemBufferWriter) -> std::io::Result<u64> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let written = writer.finalize_into(&mut file)?;
+        file.flush()?;
+        Ok(written)
+    }
+
+    ///Returns a reader over the mapped buffer
+    pub fn reader(&self) -> MemBufferReader<'_> {
+        //The buffer was checked when the file was opened
+        MemBufferReader::from_storage(&self.map).unwrap()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+///Buffer file of which only the header is mapped, entries are mapped on demand. The file must not
+///be modified while it is open.
+pub struct EntryMappedFile {
+    file: File,
+    header: Mmap,
+    file_len: u64,
+}
+
+impl EntryMappedFile {
+    ///Maps the header of the file, format errors are returned as `io::ErrorKind::InvalidData`
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<EntryMappedFile> {
+        let mut file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut words = [0u8;8];
+        file.read_exact(&mut words).map_err(|_| invalid_data(MemBufferError::WrongFormat))?;
+        let header_len = table::header_len(&words).map_err(invalid_data)?;
+        if header_len > file_len {
+            return Err(invalid_data(MemBufferError::WrongFormat));
+        }
+        let header = unsafe{ MmapOptions::new().len(header_len as usize).map(&file)? };
+        MemBufferReader::new(&header).map_err(invalid_data)?;
+        Ok(EntryMappedFile {
+            file,
+            header,
+            file_len,
+        })
+    }
+
+    ///Returns a reader over the header, it holds no payload
+    fn header_reader(&self) -> MemBufferReader<'_> {
+        //The header was checked when the file was opened
+        MemBufferReader::new(&self.header).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.header_reader().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header_reader().is_empty()
+    }
+
+    ///Maps the payload of a single entry, the mapping is released when the entry is dropped
+    pub fn map_entry(&self, index: usize) -> std::io::Result<MappedEntry> {
+        let reader = self.header_reader();
+        if index >= reader.len() {
+            return Err(invalid_data(MemBufferError::IndexOutOfBounds(index,reader.len())));
+        }
+        let entry = reader.offsets.get(index);
+        let map = self.map_range(entry)?;
+        let compression = match reader.system.iter().find(|x| x.variable_type == SYSTEM_TYPE_COMPRESSION) {
+            Some(table) => find_record(&self.map_range(table)?,index,reader.offsets.is_swapped()).map_err(invalid_data)?,
+            None => None,
+        };
+        Ok(MappedEntry {
+            map,
+            variable_type: entry.variable_type,
+            swapped: reader.offsets.is_swapped(),
+            compression,
+            encrypted: reader.is_encrypted(index),
+        })
+    }
+
+    ///Maps the payload of the entry at the given position
+    fn map_range(&self, entry: EntryPosition) -> std::io::Result<Mmap> {
+        let start = (entry.start as u64).checked_add(self.header.len() as u64);
+        let end = (entry.end as u64).checked_add(self.header.len() as u64);
+        match (start,end) {
+            (Some(start),Some(end)) if start <= end && end <= self.file_len => {
+                unsafe{ MmapOptions::new().offset(start).len((end-start) as usize).map(&self.file) }
+            },
+            _ => Err(invalid_data(MemBufferError::WrongFormat)),
+        }
+    }
+}
+
+///Mapping of the payload of a single entry
+pub struct MappedEntry {
+    map: Mmap,
+    variable_type: i32,
+    swapped: bool,
+    compression: Option<u64>,
+    encrypted: bool,
+}
+
+impl MappedEntry {
+    pub fn variable_type(&self) -> i32 {
+        self.variable_type
+    }
+
+    ///Returns the payload as stored in the file
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    ///Loads the entry with the given type like `MemBufferReader::load_entry`, interned text can
+    ///only be loaded through a reader over the whole buffer and entries of encrypted buffers fail
+    ///with `MemBufferError::EncryptedEntry`
+    pub fn load<'a, X: MemBufferDeserialize<'a,X>+MemBufferSerialize>(&'a self) -> Result<X,MemBufferError> {
+        load_detached(&self.map,self.variable_type,self.swapped,self.compression,self.encrypted)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::MemBufferWriter;
+    use crate::fs::{MemBufferFile,EntryMappedFile};
+
+    #[test]
+    fn check_mapped_file() {
+        let path = std::env::temp_dir().join(format!("membuffer_check_mapped_file_{}.mem",std::process::id()));
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        let written = MemBufferFile::create(&path,&writer).unwrap();
+
+        let file = MemBufferFile::open(&path).unwrap();
+        assert_eq!(file.as_bytes().len() as u64,written);
+        let reader = file.reader();
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+        assert_eq!(reader.load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+
+        std::fs::write(&path,b"garbage").unwrap();
+        let err = MemBufferFile::open(&path).err().unwrap();
+        assert_eq!(err.kind(),std::io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_entry_mapped_file() {
+        let path = std::env::temp_dir().join(format!("membuffer_check_entry_mapped_file_{}.mem",std::process::id()));
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry("");
+        writer.add_entry_named("answer",42);
+        MemBufferFile::create(&path,&writer).unwrap();
+
+        let file = EntryMappedFile::open(&path).unwrap();
+        assert_eq!(file.len(),4);
+        assert_eq!(file.map_entry(0).unwrap().load::<&str>().unwrap(),"Hello");
+        assert_eq!(file.map_entry(1).unwrap().as_bytes(),&[1u64,2,3].iter().flat_map(|x| x.to_be_bytes()).collect::<Vec<u8>>()[..]);
+        assert_eq!(file.map_entry(2).unwrap().load::<&str>().unwrap(),"");
+        let entry = file.map_entry(3).unwrap();
+        assert_eq!(entry.load::<i32>().unwrap(),42);
+        assert!(entry.load::<&str>().is_err());
+        assert!(file.map_entry(4).is_err());
+
+        std::fs::write(&path,b"no buffer").unwrap();
+        assert_eq!(EntryMappedFile::open(&path).err().unwrap().kind(),std::io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn check_entry_mapped_encrypted_file() {
+        let path = std::env::temp_dir().join(format!("membuffer_check_entry_mapped_encrypted_file_{}.mem",std::process::id()));
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("secret");
+        writer.add_entry(&b"secret"[..]);
+        std::fs::write(&path,writer.finalize_encrypted(&crate::EncryptionKey::new(&[7;32]))).unwrap();
+
+        let file = EntryMappedFile::open(&path).unwrap();
+        assert!(matches!(file.map_entry(1).unwrap().load::<&[u8]>(),Err(crate::MemBufferError::EncryptedEntry)));
+        assert!(matches!(file.map_entry(0).unwrap().load::<&str>(),Err(crate::MemBufferError::EncryptedEntry)));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
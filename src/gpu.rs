@@ -0,0 +1,113 @@
+//!Export of entries into staging buffers of GPU APIs, available with the `gpu` feature. The crate
+//!does not depend on any GPU API, the staging memory is passed as the mapped byte slice e.g. from
+//!`wgpu::BufferSlice::get_mapped_range_mut` or a pinned CUDA host allocation.
+//!`MemBufferReader::staging_layout` returns the size a staging buffer needs for an entry and
+//!`MemBufferReader::copy_to_staging` copies the entry in a single pass, decompressed, in native
+//!byte order and zero padded to `COPY_ALIGNMENT`. Entries which need no conversion can be uploaded
+//!from the buffer directly with `MemBufferReader::pin_entry`, slice payloads start at a multiple
+//!of `StagingLayout::alignment` from the start of the buffer.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!use membuffer::gpu::COPY_ALIGNMENT;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry::<&[f32]>(&[1.0,2.0,3.0]);
+//!writer.add_entry("abc");
+//!let data = writer.finalize();
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!
+//!let layout = reader.staging_layout(1).unwrap();
+//!assert_eq!((layout.len,layout.padded_len),(3,COPY_ALIGNMENT));
+//!//The mapped range of the staging buffer
+//!let mut staging = vec![0xffu8;layout.padded_len];
+//!assert_eq!(reader.copy_to_staging(1,&mut staging).unwrap(),4);
+//!assert_eq!(staging,b"abc\0");
+//!```
+use crate::{MemBufferReader,MemBufferError,type_alignment};
+use crate::endian::{swap_integers,swap_width};
+
+///Granularity of buffer copies, sizes and offsets of copies between GPU buffers have to be
+///multiples of it (`wgpu::COPY_BUFFER_ALIGNMENT`)
+pub const COPY_ALIGNMENT: usize = 4;
+
+///Size and alignment guarantees of an entry for uploads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StagingLayout {
+    ///Length of the payload in bytes after decompression
+    pub len: usize,
+    ///Length rounded up to `COPY_ALIGNMENT`, the size of the staging buffer
+    pub padded_len: usize,
+    ///Alignment of the payload relative to the start of the buffer
+    pub alignment: usize,
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the size and alignment of the entry for uploads without reading the payload
+    pub fn staging_layout(&self, index: usize) -> Result<StagingLayout,MemBufferError> {
+        if index >= self.offsets.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,self.offsets.len()));
+        }
+        let entry = self.offsets.get(index);
+        let len = self.uncompressed_len(index)?;
+        Ok(StagingLayout {
+            len,
+            padded_len: len.div_ceil(COPY_ALIGNMENT)*COPY_ALIGNMENT,
+            alignment: type_alignment(entry.variable_type),
+        })
+    }
+
+    ///Copies the payload of the entry into the staging memory, decompressed and with integers
+    ///in native byte order, and zeroes the padding up to `StagingLayout::padded_len`. Returns the
+    ///padded length, staging memory smaller than it fails with `MemBufferError::EntryTooLarge`.
+    pub fn copy_to_staging(&self, index: usize, staging: &mut [u8]) -> Result<usize,MemBufferError> {
+        let layout = self.staging_layout(index)?;
+        if staging.len() < layout.padded_len {
+            return Err(MemBufferError::EntryTooLarge(layout.padded_len,staging.len()));
+        }
+        let entry = self.offsets.get(index);
+        if let Some(hook) = self.access_hook {
+            hook(index,entry.variable_type,entry.len());
+        }
+        self.ensure_validated(index)?;
+        let stored = self.entry_slice(entry)?;
+        match self.decompress_entry(index,stored)? {
+            Some(decompressed) => staging[..layout.len].copy_from_slice(&decompressed),
+            None => {
+                staging[..layout.len].copy_from_slice(stored);
+                if self.is_byte_swapped() {
+                    swap_integers(&mut staging[..layout.len],swap_width(entry.variable_type));
+                }
+            },
+        }
+        staging[layout.len..layout.padded_len].iter_mut().for_each(|x| *x = 0);
+        Ok(layout.padded_len)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,Endianness};
+
+    #[test]
+    fn check_staging_copy() {
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry("Hello");
+        let data = writer.finalize();
+        let reader = MemBufferReader::new(&data).unwrap();
+
+        let layout = reader.staging_layout(0).unwrap();
+        assert_eq!((layout.len,layout.padded_len,layout.alignment),(24,24,8));
+        let mut staging = vec![0u8;24];
+        assert_eq!(reader.copy_to_staging(0,&mut staging).unwrap(),24);
+        let values: Vec<u64> = staging.chunks_exact(8).map(|x| u64::from_ne_bytes([x[0],x[1],x[2],x[3],x[4],x[5],x[6],x[7]])).collect();
+        assert_eq!(values,vec![1,2,3]);
+
+        let mut staging = vec![0xffu8;12];
+        assert_eq!(reader.copy_to_staging(1,&mut staging).unwrap(),8);
+        assert_eq!(&staging[..9],b"Hello\0\0\0\xff");
+        assert!(matches!(reader.copy_to_staging(1,&mut [0u8;5]),Err(MemBufferError::EntryTooLarge(8,5))));
+        assert!(matches!(reader.staging_layout(2),Err(MemBufferError::IndexOutOfBounds(2,2))));
+    }
+}
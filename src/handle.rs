@@ -0,0 +1,108 @@
+//!Entries handed out to other threads. `MemBufferReader` is `Send` and `Sync`, it only borrows the
+//!buffer and keeps no cursor, so one reader can be shared by any number of threads loading
+//!entries at the same time. Access hooks have to be `Sync` for this. `split_entries` returns one
+//!`EntryHandle` per entry which can be moved to a worker on its own and loads its entry with the
+//!settings of the reader.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!for x in 0..8u64 {
+//!    writer.add_entry::<&[u64]>(&vec![x;1000]);
+//!}
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let sums: Vec<u64> = std::thread::scope(|scope| {
+//!    let workers: Vec<_> = reader.split_entries().into_iter()
+//!        .map(|entry| scope.spawn(move || entry.load::<&[u64]>().unwrap().iter().sum::<u64>()))
+//!        .collect();
+//!    workers.into_iter().map(|x| x.join().unwrap()).collect()
+//!});
+//!assert_eq!(sums[3],3000);
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Single entry of a buffer which can be moved to another thread, see the module documentation
+#[derive(Debug, Clone)]
+pub struct EntryHandle<'a> {
+    reader: MemBufferReader<'a>,
+    index: usize,
+}
+
+impl<'a> EntryHandle<'a> {
+    ///Returns the position of the entry in the buffer
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn variable_type(&self) -> i32 {
+        self.reader.offsets.get(self.index).variable_type
+    }
+
+    ///Returns the length of the payload as stored
+    pub fn len(&self) -> usize {
+        self.reader.offsets.get(self.index).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Loads the entry like `MemBufferReader::load_entry`
+    pub fn load<X: MemBufferDeserialize<'a,X> + MemBufferSerialize>(&self) -> Result<X,MemBufferError> {
+        self.reader.load_entry(self.index)
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns a handle for every entry, see the module documentation
+    pub fn split_entries(&self) -> Vec<EntryHandle<'a>> {
+        (0..self.len()).map(|index| EntryHandle {
+            reader: self.clone(),
+            index,
+        }).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferTypes,EntryHandle};
+    use std::sync::atomic::{AtomicUsize,Ordering};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn check_entry_handles() {
+        assert_send_sync::<MemBufferReader>();
+        assert_send_sync::<EntryHandle>();
+
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u32]>(&[1,2,3]);
+        writer.add_entry_named("answer",42);
+        let data = writer.finalize();
+
+        let loads = AtomicUsize::new(0);
+        let hook = |_: usize, _: i32, _: usize| { loads.fetch_add(1,Ordering::Relaxed); };
+        let reader = MemBufferReader::new_lazily_validated(&data).unwrap().with_access_hook(&hook);
+        let handles = reader.split_entries();
+        assert_eq!(handles.len(),3);
+        assert_eq!(handles[1].variable_type(),MemBufferTypes::VectorU32 as i32);
+        assert_eq!(handles[1].len(),12);
+        let results: Vec<String> = std::thread::scope(|scope| {
+            let workers: Vec<_> = handles.into_iter().map(|entry| scope.spawn(move || match entry.index() {
+                0 => entry.load::<&str>().unwrap().to_string(),
+                1 => format!("{:?}",entry.load::<std::borrow::Cow<[u32]>>().unwrap()),
+                _ => entry.load::<i32>().unwrap().to_string(),
+            })).collect();
+            workers.into_iter().map(|x| x.join().unwrap()).collect()
+        });
+        assert_eq!(results,vec!["Hello","[1, 2, 3]","42"]);
+        assert_eq!(loads.load(Ordering::Relaxed),3);
+        assert!(reader.split_entries()[0].load::<i32>().is_err());
+    }
+}
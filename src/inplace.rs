@@ -0,0 +1,188 @@
+//!In place updates of fixed size entries. Counters and flags stored in a large buffer, for example
+//!a mapped file, would otherwise need the whole buffer to be written again to change one integer.
+//!`MemBufferMut` parses the header of a mutable buffer like the reader and `store_entry` overwrites
+//!the payload of an entry with a value of the same type and length directly in the buffer, so the
+//!rest of the payload is never written again. Values are converted to the byte order of the buffer.
+//!
+//!The record of the entry in the manifest of a writer created with `with_manifest` is updated with
+//!the entry. The checksums of the buffer are recalculated once when `update_checksums` is called or
+//!the `MemBufferMut` is dropped, which reads the whole payload and rewrites the checksum entry.
+//!Compressed and encrypted entries cannot be changed in place and fail with
+//!`MemBufferError::ImmutableEntry`.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferMut};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("visits");
+//!writer.add_entry(41);
+//!let mut data = writer.finalize();
+//!
+//!let mut buffer = MemBufferMut::new(&mut data).unwrap();
+//!let visits = buffer.load_entry::<i32>(1).unwrap();
+//!buffer.store_entry(1,visits+1).unwrap();
+//!assert!(buffer.store_entry(1,1u64).is_err());
+//!drop(buffer);
+//!
+//!let reader = MemBufferReader::new_verified(&data).unwrap();
+//!assert_eq!(reader.load_entry::<i32>(1).unwrap(),42);
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize};
+use crate::endian::{swap_integers,swap_width};
+
+///Buffer whose fixed size entries can be overwritten in place, see the module documentation
+pub struct MemBufferMut<'a> {
+    data: &'a mut [u8],
+    //Whether entries were stored since the checksums were last calculated
+    stale: bool,
+}
+
+impl<'a> MemBufferMut<'a> {
+    ///Parses the header of the buffer, checks that every entry lies within the payload and that
+    ///the entry table matches its checksum
+    pub fn new(data: &'a mut [u8]) -> Result<MemBufferMut<'a>,MemBufferError> {
+        MemBufferReader::new_validated(data)?.verify_header_checksum()?;
+        Ok(MemBufferMut {
+            data,
+            stale: false,
+        })
+    }
+
+    ///Returns a reader over the current content of the buffer
+    pub fn reader(&self) -> MemBufferReader<'_> {
+        //The header was checked when the buffer was created and is never modified
+        MemBufferReader::new(self.data).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reader().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Loads the entry like `MemBufferReader::load_entry`
+    pub fn load_entry<'b, X: MemBufferDeserialize<'b,X> + MemBufferSerialize>(&'b self, key: usize) -> Result<X,MemBufferError> {
+        self.reader().load_entry(key)
+    }
+
+    ///Overwrites the payload of the entry with the value and updates its digest in the manifest.
+    ///The entry must have the type of the value and the length of its payload, otherwise
+    ///`MemBufferError::FieldTypeError` or `MemBufferError::SizeMismatch` is returned and the buffer
+    ///is left unchanged. Compressed and
+    ///encrypted entries and entries sharing their payload with another entry are immutable.
+    pub fn store_entry<T: MemBufferSerialize>(&mut self, key: usize, val: T) -> Result<(),MemBufferError> {
+        let payload = val.to_mem_buffer();
+        let (start,swapped) = {
+            let reader = self.reader();
+            if key >= reader.len() {
+                return Err(MemBufferError::IndexOutOfBounds(key,reader.len()));
+            }
+            let entry = reader.offsets.get(key);
+            if entry.variable_type != T::get_mem_buffer_type() {
+                return Err(MemBufferError::FieldTypeError(entry.variable_type,T::get_mem_buffer_type()));
+            }
+            //Storing into a deduplicated payload would change every entry sharing it
+            let shared = entry.len() > 0 && reader.offsets.iter().enumerate().any(|(x,other)| x != key && other.start == entry.start && other.end == entry.end);
+            if reader.is_compressed(key) || reader.is_encrypted(key) || shared {
+                return Err(MemBufferError::ImmutableEntry(key));
+            }
+            if entry.len() != payload.len() {
+                return Err(MemBufferError::SizeMismatch(payload.len(),entry.len()));
+            }
+            (reader.entry_slice(entry)?.as_ptr() as usize-self.data.as_ptr() as usize,reader.is_byte_swapped())
+        };
+        let target = &mut self.data[start..start+payload.len()];
+        target.copy_from_slice(&payload);
+        if swapped {
+            swap_integers(target,swap_width(T::get_mem_buffer_type()));
+        }
+        self.stale = true;
+        MemBufferReader::rewrite_manifest_record(self.data,key)
+    }
+
+    ///Recalculates the checksums of the buffer if entries were stored since the last call
+    pub fn update_checksums(&mut self) {
+        if self.stale {
+            //The checksum entry was checked when the buffer was created
+            MemBufferReader::rewrite_checksums(self.data).unwrap();
+            self.stale = false;
+        }
+    }
+
+    ///Updates the checksums and returns the underlying buffer
+    pub fn into_inner(mut self) -> &'a mut [u8] {
+        self.update_checksums();
+        core::mem::take(&mut self.data)
+    }
+}
+
+impl Drop for MemBufferMut<'_> {
+    fn drop(&mut self) {
+        self.update_checksums();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferMut,Endianness};
+
+    #[test]
+    fn check_store_in_place() {
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big).with_field_checksums();
+        writer.add_entry("counters");
+        writer.add_entry(1);
+        writer.add_entry(2u64);
+        writer.add_entry(0.5f64);
+        let mut data = writer.finalize();
+        let len = data.len();
+
+        let mut buffer = MemBufferMut::new(&mut data).unwrap();
+        buffer.store_entry(1,-7).unwrap();
+        buffer.store_entry(2,u64::MAX-1).unwrap();
+        buffer.store_entry(3,2.25f64).unwrap();
+        buffer.store_entry(0,"COUNTERS").unwrap();
+        assert_eq!(buffer.load_entry::<i32>(1).unwrap(),-7);
+        assert!(matches!(buffer.store_entry(1,7u64),Err(MemBufferError::FieldTypeError(_,_))));
+        assert!(matches!(buffer.store_entry(0,"short"),Err(MemBufferError::SizeMismatch(5,8))));
+        assert!(matches!(buffer.store_entry(4,1),Err(MemBufferError::IndexOutOfBounds(4,4))));
+        let data = buffer.into_inner();
+
+        let reader = MemBufferReader::new_verified(data).unwrap();
+        assert_eq!(data.len(),len);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"COUNTERS");
+        assert_eq!(reader.load_entry::<i32>(1).unwrap(),-7);
+        assert_eq!(reader.load_entry::<u64>(2).unwrap(),u64::MAX-1);
+        assert_eq!(reader.load_entry::<f64>(3).unwrap(),2.25);
+    }
+
+    #[test]
+    fn check_store_with_manifest() {
+        for byte_order in [Endianness::Big,Endianness::Little].iter() {
+            let mut writer = MemBufferWriter::new().with_byte_order(*byte_order).with_manifest();
+            writer.add_entry("counter");
+            writer.add_entry(41);
+            let mut data = writer.finalize();
+
+            let mut buffer = MemBufferMut::new(&mut data).unwrap();
+            buffer.store_entry(1,42).unwrap();
+            assert!(buffer.reader().verify_manifest().is_ok());
+            drop(buffer);
+            let reader = MemBufferReader::new_verified(&data).unwrap();
+            assert!(reader.verify_manifest().is_ok());
+            assert_eq!(reader.load_entry::<i32>(1).unwrap(),42);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "lz4")]
+    fn check_store_compressed_entry() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry_compressed(&[0u8;64][..],crate::Compression::Lz4);
+        let mut data = writer.finalize();
+        let mut buffer = MemBufferMut::new(&mut data).unwrap();
+        assert!(matches!(buffer.store_entry(0,&[1u8;64][..]),Err(MemBufferError::ImmutableEntry(0))));
+        assert!(MemBufferMut::new(&mut [0u8;4]).is_err());
+    }
+}
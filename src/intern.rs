@@ -0,0 +1,153 @@
+//!Interning of short repeated strings. Interned entries only store an id into a table which is
+//!written once as metadata entry behind the user entries, loading them as `&str` resolves the id
+//!transparently.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_interned_entry("warning");
+//!writer.add_interned_entry("error");
+//!writer.add_interned_entry("warning");
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.len(),3);
+//!assert_eq!(reader.load_entry::<&str>(2).unwrap(),"warning");
+//!```
+use crate::{MemBufferReader,MemBufferError,SYSTEM_TYPE_INTERN_TABLE};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,MemBufferTypes};
+
+pub(crate) fn read_intern_id(data: &[u8], swapped: bool) -> Result<usize,MemBufferError> {
+    if data.len() != 4 {
+        return Err(MemBufferError::WrongFormat);
+    }
+    Ok(crate::endian::read_u32(data,swapped) as i32 as usize)
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds a text entry which is stored only once per buffer no matter how often it is added,
+    ///meant for short enum like labels which repeat across many entries
+    pub fn add_interned_entry(&mut self, val: &str) {
+        self.check_schema_field(self.types.len(),MemBufferTypes::InternedText as i32);
+        let id = self.intern(val);
+        self.types.push(MemBufferTypes::InternedText.into());
+        self.data.push(id.to_ne_bytes()[..].into());
+        self.log_payload(self.types.len()-1,crate::Change::Add);
+    }
+
+    ///Returns the id of the text in the interning table, adding it if it is not in there yet
+    pub(crate) fn intern(&mut self, val: &str) -> i32 {
+        match self.intern_ids.get(val) {
+            Some(id) => *id,
+            None => {
+                let id = self.interned.len() as i32;
+                self.interned.push(val.to_string());
+                self.intern_ids.insert(val.to_string(),id);
+                id
+            }
+        }
+    }
+
+    ///Returns the text the interned entry payload refers to
+    pub(crate) fn resolve_interned(&self, data: &[u8]) -> Result<&[u8],MemBufferError> {
+        self.interned.get(read_intern_id(data,false)?).map(|x| x.as_bytes()).ok_or(MemBufferError::WrongFormat)
+    }
+
+    ///Serializes the interning table as nested buffer holding one text entry per id
+    pub(crate) fn intern_table(&self) -> Option<Vec<u8>> {
+        if self.interned.is_empty() {
+            return None;
+        }
+        let mut table = MemBufferWriter::new();
+        for x in self.interned.iter() {
+            table.add_entry(x);
+        }
+        Some(table.finalize())
+    }
+
+    ///Takes over the interning table of the reader so the ids of copied entries stay valid
+    pub(crate) fn load_intern_table(&mut self, reader: &MemBufferReader) -> Result<(),MemBufferError> {
+        if let Some(table) = reader.system_entry(SYSTEM_TYPE_INTERN_TABLE) {
+            let table = MemBufferReader::new(table)?;
+            for x in 0..table.len() {
+                let text: &str = table.load_entry(x)?;
+                self.intern_ids.insert(text.to_string(),self.interned.len() as i32);
+                self.interned.push(text.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the text the interned entry payload refers to
+    pub(crate) fn resolve_interned(&self, data: &[u8]) -> Result<&'a [u8],MemBufferError> {
+        let table = MemBufferReader::new(self.system_entry(SYSTEM_TYPE_INTERN_TABLE).ok_or(MemBufferError::WrongFormat)?)?;
+        let id = read_intern_id(data,self.is_byte_swapped())?;
+        if id >= table.len() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        table.entry_slice(table.offsets.get(id))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferTypes};
+
+    #[test]
+    fn check_interned_entries() {
+        let mut writer = MemBufferWriter::new();
+        let mut plain = MemBufferWriter::new();
+        for x in 0..100 {
+            let label = if x%3 == 0 { "temperature" } else { "humidity" };
+            writer.add_interned_entry(label);
+            plain.add_entry(label);
+        }
+        writer.add_entry("not interned");
+        assert_eq!(writer.load_entry::<&str>(3).unwrap(),"temperature");
+        let result = writer.finalize();
+        assert!(result.len() < plain.finalize().len());
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.len(),101);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"temperature");
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"humidity");
+        assert_eq!(reader.load_entry::<&str>(99).unwrap(),"temperature");
+        assert_eq!(reader.load_entry::<&str>(100).unwrap(),"not interned");
+        assert!(reader.load_entry::<i32>(0).is_err());
+
+        //Ids beyond the interning table fail instead of panicking
+        let mut corrupted = result.clone();
+        let start = result.len()-reader.data.len()+reader.offsets.get(0).start;
+        corrupted[start..start+4].copy_from_slice(&1000i32.to_ne_bytes());
+        let reader = MemBufferReader::new(&corrupted).unwrap();
+        assert!(reader.load_entry::<&str>(0).is_err());
+        assert!(reader.load_value(0).is_err());
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"humidity");
+    }
+
+    #[test]
+    fn check_interned_reload() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_interned_entry("a");
+        writer.add_interned_entry("b");
+        let result = writer.finalize();
+
+        let mut writer2 = MemBufferWriter::from(&result).unwrap();
+        assert_eq!(writer2.len(),2);
+        writer2.add_interned_entry("b");
+        writer2.add_interned_entry("c");
+        let result2 = writer2.finalize();
+
+        let reader = MemBufferReader::new(&result2).unwrap();
+        assert_eq!(reader.len(),4);
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"b");
+        assert_eq!(reader.load_entry::<&str>(2).unwrap(),"b");
+        assert_eq!(reader.load_entry::<&str>(3).unwrap(),"c");
+        assert_eq!(reader.offsets.get(2).variable_type,MemBufferTypes::InternedText as i32);
+    }
+}
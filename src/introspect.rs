@@ -0,0 +1,109 @@
+//!Introspection of buffers without typed loads. Debuggers and migration scripts often do not know
+//!what a buffer holds, so the reader exposes the type, the length and the position of every entry
+//!and `describe` summarizes the whole buffer. Positions are relative to the start of the payload.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferTypes};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("text");
+//!writer.add_entry(42);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.entry_type(0),MemBufferTypes::Text as i32);
+//!assert_eq!(reader.entry_len(0),4);
+//!let description = reader.describe();
+//!assert_eq!(description.count,2);
+//!assert_eq!(description.types,vec![MemBufferTypes::Text as i32,MemBufferTypes::Integer32 as i32]);
+//!```
+use core::convert::TryFrom;
+use crate::{MemBufferReader,Position};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Summary of the entries of a buffer returned by `MemBufferReader::describe`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferDescription {
+    ///Number of entries, metadata entries are not counted
+    pub count: usize,
+    ///Type id of every entry
+    pub types: Vec<i32>,
+    ///Stored payload length of every entry, compressed and encrypted entries count with their
+    ///stored size
+    pub sizes: Vec<usize>,
+    ///Size of the payload of all entries, see `MemBufferReader::payload_len`
+    pub payload_len: usize,
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the type id of the entry. Panics if the index is out of bounds.
+    pub fn entry_type(&self, index: usize) -> i32 {
+        assert!(index < self.offsets.len(),"Entry {} is out of bounds",index);
+        self.offsets.get(index).variable_type
+    }
+
+    ///Returns the stored payload length of the entry. Panics if the index is out of bounds.
+    pub fn entry_len(&self, index: usize) -> usize {
+        assert!(index < self.offsets.len(),"Entry {} is out of bounds",index);
+        self.offsets.get(index).len()
+    }
+
+    ///Returns the position of the payload of the entry relative to the start of the payload.
+    ///Panics if the index is out of bounds or the entry lies beyond 2 GB which `Position` cannot
+    ///hold.
+    pub fn entry_position(&self, index: usize) -> Position {
+        assert!(index < self.offsets.len(),"Entry {} is out of bounds",index);
+        let entry = self.offsets.get(index);
+        let position = |x: usize| i32::try_from(x).expect("Entry lies beyond the range of Position");
+        Position {
+            start: position(entry.start),
+            end: position(entry.end),
+        }
+    }
+
+    ///Summarizes the entries of the buffer
+    pub fn describe(&self) -> BufferDescription {
+        BufferDescription {
+            count: self.len(),
+            types: self.offsets.iter().map(|x| x.variable_type).collect(),
+            sizes: self.offsets.iter().map(|x| x.len()).collect(),
+            payload_len: self.payload_len(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferTypes,Position,Endianness};
+
+    #[test]
+    fn check_introspection() {
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big).with_field_checksums();
+        writer.add_entry_named("name","abc");
+        writer.add_entry::<&[u64]>(&[1,2]);
+        writer.add_null_entry();
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.entry_type(1),MemBufferTypes::VectorU64 as i32);
+        assert_eq!((reader.entry_len(0),reader.entry_len(1),reader.entry_len(2)),(3,16,0));
+        //The integer slice is aligned to 8 bytes behind the text
+        assert_eq!(reader.entry_position(0),Position { start: 0, end: 3 });
+        assert_eq!(reader.entry_position(1).end-reader.entry_position(1).start,16);
+        let description = reader.describe();
+        assert_eq!(description.count,3);
+        assert_eq!(description.sizes,vec![3,16,0]);
+        assert_eq!(description.types[2],MemBufferTypes::Null as i32);
+        assert_eq!(description.payload_len,reader.payload_len());
+    }
+
+    #[test]
+    #[should_panic(expected = "Entry 3 is out of bounds")]
+    fn check_introspection_out_of_bounds() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("abc");
+        let data = writer.finalize();
+        MemBufferReader::new(&data).unwrap().entry_type(3);
+    }
+}
@@ -0,0 +1,251 @@
+//!Human readable dumps of buffers, available with the `serde_json` feature. `MemBufferReader::to_json`
+//!writes every entry with its type id, its stored length and its value: text and numbers inline,
+//!number slices and lists as arrays, bytes and entries of unknown types as base64, maps as objects
+//!of base64 values and nested buffers recursively. Floats which are not finite are written as the strings
+//!`"NaN"`, `"inf"` and `"-inf"`. `MemBufferWriter::from_json` rebuilds the entries from the
+//!document, the stored lengths are ignored. Names and other metadata of the entries are not part of
+//!the dump.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut nested = MemBufferWriter::new();
+//!nested.add_entry(7);
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello");
+//!writer.add_entry(&[1u8,2,3][..]);
+//!writer.add_entry(&nested);
+//!let data = writer.finalize();
+//!
+//!let json = MemBufferReader::new(&data).unwrap().to_json().unwrap();
+//!assert!(json.contains("\"value\": \"Hello\""));
+//!assert!(json.contains("\"value\": \"AQID\""));
+//!
+//!let copy = MemBufferWriter::from_json(&json).unwrap().finalize();
+//!let reader = MemBufferReader::new(&copy).unwrap();
+//!assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+//!assert_eq!(reader.load_recursive_reader(2).unwrap().load_entry::<i32>(0).unwrap(),7);
+//!```
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::{Value,json};
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferTypes,MemBufferMap,MemBufferMapView,BytesListView,ChunkedView,MemBufferDeserialize,is_system_type};
+
+fn serde_failure(err: serde_json::Error) -> MemBufferError {
+    MemBufferError::SerdeFailure(err.to_string())
+}
+
+fn float_to_json(val: f64) -> Value {
+    if val.is_finite() {
+        json!(val)
+    }
+    else {
+        Value::String(val.to_string())
+    }
+}
+
+fn float_from_json(val: &Value) -> Result<f64,MemBufferError> {
+    match val {
+        Value::Number(x) => x.as_f64().ok_or(MemBufferError::WrongFormat),
+        Value::String(x) => x.parse().map_err(|_| MemBufferError::WrongFormat),
+        _ => Err(MemBufferError::WrongFormat),
+    }
+}
+
+fn bytes_from_json(val: &Value) -> Result<Vec<u8>,MemBufferError> {
+    STANDARD.decode(val.as_str().ok_or(MemBufferError::WrongFormat)?).map_err(|_| MemBufferError::WrongFormat)
+}
+
+///Returns the elements of the array converted with the function
+fn array_from_json<T>(val: &Value, convert: impl Fn(&Value) -> Option<T>) -> Result<Vec<T>,MemBufferError> {
+    val.as_array().ok_or(MemBufferError::WrongFormat)?.iter().map(|x| convert(x).ok_or(MemBufferError::WrongFormat)).collect()
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the entries of the buffer as pretty printed JSON document, see the module
+    ///documentation
+    pub fn to_json(&self) -> Result<String,MemBufferError> {
+        serde_json::to_string_pretty(&self.json_value()?).map_err(serde_failure)
+    }
+
+    fn json_value(&self) -> Result<Value,MemBufferError> {
+        let mut entries = Vec::with_capacity(self.len());
+        for index in 0..self.len() {
+            entries.push(json!({
+                "type": self.entry_type(index),
+                "len": self.entry_len(index),
+                "value": self.entry_json(index)?,
+            }));
+        }
+        Ok(json!({ "entries": entries }))
+    }
+
+    fn entry_json(&self, index: usize) -> Result<Value,MemBufferError> {
+        let slice = |x: Cow<[u64]>| -> Value { x.iter().copied().collect() };
+        let floats = |x: &[f64]| -> Value { x.iter().map(|x| float_to_json(*x)).collect() };
+        Ok(match self.entry_type(index) {
+            x if x == MemBufferTypes::Text as i32 || x == MemBufferTypes::InternedText as i32 => Value::String(self.load_entry::<String>(index)?),
+            x if x == MemBufferTypes::Integer32 as i32 => json!(self.load_entry::<i32>(index)?),
+            x if x == MemBufferTypes::Integer64 as i32 => json!(self.load_entry::<u64>(index)?),
+            x if x == MemBufferTypes::Unsigned8 as i32 => json!(self.load_entry::<u8>(index)?),
+            x if x == MemBufferTypes::Unsigned16 as i32 => json!(self.load_entry::<u16>(index)?),
+            x if x == MemBufferTypes::Unsigned32 as i32 => json!(self.load_entry::<u32>(index)?),
+            x if x == MemBufferTypes::Signed8 as i32 => json!(self.load_entry::<i8>(index)?),
+            x if x == MemBufferTypes::Signed16 as i32 => json!(self.load_entry::<i16>(index)?),
+            x if x == MemBufferTypes::Signed64 as i32 => json!(self.load_entry::<i64>(index)?),
+            x if x == MemBufferTypes::Bool as i32 => json!(self.load_entry::<bool>(index)?),
+            x if x == MemBufferTypes::Char as i32 => json!(self.load_entry::<char>(index)?),
+            x if x == MemBufferTypes::Float32 as i32 => float_to_json(self.load_entry::<f32>(index)? as f64),
+            x if x == MemBufferTypes::Float64 as i32 => float_to_json(self.load_entry::<f64>(index)?),
+            x if x == MemBufferTypes::VectorU32 as i32 => slice(Cow::Owned(self.load_entry::<Cow<[u32]>>(index)?.iter().map(|x| *x as u64).collect())),
+            x if x == MemBufferTypes::VectorU64 as i32 => slice(self.load_entry::<Cow<[u64]>>(index)?),
+            x if x == MemBufferTypes::VectorF32 as i32 => floats(&self.load_entry::<Cow<[f32]>>(index)?.iter().map(|x| *x as f64).collect::<Vec<f64>>()),
+            x if x == MemBufferTypes::VectorF64 as i32 => floats(&self.load_entry::<Cow<[f64]>>(index)?),
+            x if x == MemBufferTypes::Null as i32 => Value::Null,
+            x if x == MemBufferTypes::StrList as i32 => self.load_entry::<Vec<String>>(index)?.into_iter().map(Value::String).collect(),
+            x if x == MemBufferTypes::BytesList as i32 => {
+                let data = self.load_transformed(index)?;
+                BytesListView::from_mem_buffer(&data)?.iter().map(|x| Value::String(STANDARD.encode(x))).collect()
+            },
+            x if x == MemBufferTypes::Chunked as i32 => {
+                let data = self.load_transformed(index)?;
+                ChunkedView::from_mem_buffer(&data)?.iter().map(|x| Value::String(STANDARD.encode(x))).collect()
+            },
+            //Nested buffers and maps are never byte swapped, compressed ones are decoded first
+            x if x == MemBufferTypes::MemBuffer as i32 => MemBufferReader::new(&self.load_transformed(index)?)?.json_value()?,
+            x if x == MemBufferTypes::Map as i32 => {
+                let data = self.load_transformed(index)?;
+                let map = MemBufferMapView::from_mem_buffer(&data)?;
+                Value::Object(map.iter().map(|(key,val)| (key.to_string(),Value::String(STANDARD.encode(val)))).collect())
+            },
+            _ => Value::String(STANDARD.encode(self.load_transformed(index)?)),
+        })
+    }
+}
+
+impl MemBufferWriter {
+    ///Creates a writer holding the entries of a document written by `MemBufferReader::to_json`.
+    ///Fails with `MemBufferError::SerdeFailure` if the document is no JSON and with
+    ///`MemBufferError::WrongFormat` if an entry does not match its type.
+    pub fn from_json(json: &str) -> Result<MemBufferWriter,MemBufferError> {
+        MemBufferWriter::from_json_value(&serde_json::from_str(json).map_err(serde_failure)?)
+    }
+
+    fn from_json_value(document: &Value) -> Result<MemBufferWriter,MemBufferError> {
+        let mut writer = MemBufferWriter::new();
+        for entry in document.get("entries").and_then(Value::as_array).ok_or(MemBufferError::WrongFormat)? {
+            let variable_type = entry.get("type").and_then(Value::as_i64).and_then(|x| i32::try_from(x).ok()).ok_or(MemBufferError::WrongFormat)?;
+            let val = entry.get("value").ok_or(MemBufferError::WrongFormat)?;
+            writer.add_json_entry(variable_type,val)?;
+        }
+        Ok(writer)
+    }
+
+    fn add_json_entry(&mut self, variable_type: i32, val: &Value) -> Result<(),MemBufferError> {
+        let text = || val.as_str().ok_or(MemBufferError::WrongFormat);
+        match variable_type {
+            x if x == MemBufferTypes::Text as i32 => self.add_entry(text()?),
+            x if x == MemBufferTypes::InternedText as i32 => self.add_interned_entry(text()?),
+            x if x == MemBufferTypes::Integer32 as i32 => self.add_entry(val.as_i64().and_then(|x| i32::try_from(x).ok()).ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Integer64 as i32 => self.add_entry(val.as_u64().ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Unsigned8 as i32 => self.add_entry(val.as_u64().and_then(|x| u8::try_from(x).ok()).ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Unsigned16 as i32 => self.add_entry(val.as_u64().and_then(|x| u16::try_from(x).ok()).ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Unsigned32 as i32 => self.add_entry(val.as_u64().and_then(|x| u32::try_from(x).ok()).ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Signed8 as i32 => self.add_entry(val.as_i64().and_then(|x| i8::try_from(x).ok()).ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Signed16 as i32 => self.add_entry(val.as_i64().and_then(|x| i16::try_from(x).ok()).ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Signed64 as i32 => self.add_entry(val.as_i64().ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Bool as i32 => self.add_entry(val.as_bool().ok_or(MemBufferError::WrongFormat)?),
+            x if x == MemBufferTypes::Char as i32 => {
+                let mut chars = text()?.chars();
+                match (chars.next(),chars.next()) {
+                    (Some(x),None) => self.add_entry(x),
+                    _ => return Err(MemBufferError::WrongFormat),
+                }
+            },
+            x if x == MemBufferTypes::Float32 as i32 => self.add_entry(float_from_json(val)? as f32),
+            x if x == MemBufferTypes::Float64 as i32 => self.add_entry(float_from_json(val)?),
+            x if x == MemBufferTypes::VectorU8 as i32 => self.add_entry(&bytes_from_json(val)?[..]),
+            x if x == MemBufferTypes::VectorU32 as i32 => self.add_entry(&array_from_json(val,|x| x.as_u64().and_then(|x| u32::try_from(x).ok()))?[..]),
+            x if x == MemBufferTypes::VectorU64 as i32 => self.add_entry(&array_from_json(val,Value::as_u64)?[..]),
+            x if x == MemBufferTypes::VectorF32 as i32 => self.add_entry(&array_from_json(val,|x| float_from_json(x).ok().map(|x| x as f32))?[..]),
+            x if x == MemBufferTypes::VectorF64 as i32 => self.add_entry(&array_from_json(val,|x| float_from_json(x).ok())?[..]),
+            x if x == MemBufferTypes::Null as i32 => self.add_null_entry(),
+            x if x == MemBufferTypes::StrList as i32 => self.add_entry(array_from_json(val,|x| x.as_str().map(|x| x.to_string()))?),
+            x if x == MemBufferTypes::BytesList as i32 => self.add_entry(&array_from_json(val,|x| bytes_from_json(x).ok())?[..]),
+            x if x == MemBufferTypes::MemBuffer as i32 => self.add_entry(MemBufferWriter::from_json_value(val)?),
+            x if x == MemBufferTypes::Chunked as i32 => {
+                let mut chunks = MemBufferWriter::new();
+                for chunk in array_from_json(val,|x| bytes_from_json(x).ok())? {
+                    chunks.add_entry(&chunk[..]);
+                }
+                self.add_chunks(chunks);
+            },
+            x if x == MemBufferTypes::Map as i32 => {
+                let mut map = MemBufferMap::new();
+                for (key,val) in val.as_object().ok_or(MemBufferError::WrongFormat)? {
+                    map.insert(key,&bytes_from_json(val)?);
+                }
+                self.add_entry(&map);
+            },
+            x if is_system_type(x) => return Err(MemBufferError::WrongFormat),
+            x => self.add_raw_entry(x,&bytes_from_json(val)?),
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferMap,Endianness,Compression};
+
+    #[test]
+    fn check_json_round_trip() {
+        let mut map = MemBufferMap::new();
+        map.insert("key",b"\x00\xFF");
+        let mut nested = MemBufferWriter::new();
+        nested.add_entry(&[1.5f32,f32::INFINITY][..]);
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry("Grüße");
+        writer.add_interned_entry("label");
+        writer.add_entry(-5);
+        writer.add_entry(u64::MAX);
+        writer.add_entry(f64::NAN);
+        writer.add_entry(&[1u32,u32::MAX][..]);
+        writer.add_entry(&[3u64][..]);
+        writer.add_entry(&[0.1f64,-2.0][..]);
+        writer.add_entry(&nested);
+        writer.add_entry(&map);
+        writer.add_null_entry();
+        writer.add_raw_entry(5000,&[9,8]);
+        writer.add_entry::<&[&str]>(&["a","b"]);
+        writer.add_entry::<&[&[u8]]>(&[b"\x00"]);
+        writer.add_entry_compressed("compressed",Compression::None);
+        writer.add_entry(-2i16);
+        writer.add_entry(true);
+        writer.add_entry('ß');
+        writer.add_entry_chunked(&b"chunked"[..],3).unwrap();
+        let data = writer.finalize();
+
+        let json = MemBufferReader::new(&data).unwrap().to_json().unwrap();
+        assert!(json.contains("\"NaN\""));
+        let copy = MemBufferWriter::from_json(&json).unwrap().finalize();
+        let reader = MemBufferReader::new(&copy).unwrap();
+        assert_eq!(reader.to_json().unwrap(),json);
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"label");
+        assert_eq!(reader.load_entry::<u64>(3).unwrap(),u64::MAX);
+        assert!(reader.load_entry::<f64>(4).unwrap().is_nan());
+        assert_eq!(reader.load_entry::<&[f64]>(7).unwrap(),&[0.1,-2.0]);
+        assert_eq!(reader.load_recursive_reader(8).unwrap().load_entry::<&[f32]>(0).unwrap(),&[1.5,f32::INFINITY]);
+        assert_eq!(reader.load_entry::<crate::MemBufferMapView>(9).unwrap().get("key"),Some(&b"\x00\xFF"[..]));
+        assert_eq!(reader.load_entry::<&str>(14).unwrap(),"compressed");
+        assert_eq!(reader.load_entry::<Vec<String>>(12).unwrap(),vec!["a","b"]);
+        assert_eq!((reader.load_entry::<i16>(15).unwrap(),reader.load_entry::<bool>(16).unwrap(),reader.load_entry::<char>(17).unwrap()),(-2,true,'ß'));
+        assert_eq!(reader.load_chunked(18).unwrap().collect::<Vec<_>>(),vec![&b"chu"[..],b"nke",b"d"]);
+
+        assert!(matches!(MemBufferWriter::from_json("{"),Err(MemBufferError::SerdeFailure(_))));
+        assert!(matches!(MemBufferWriter::from_json(r#"{"entries":[{"type":1,"value":"text"}]}"#),Err(MemBufferError::WrongFormat)));
+        assert!(matches!(MemBufferWriter::from_json(r#"{"entries":[{"type":-4096,"value":""}]}"#),Err(MemBufferError::WrongFormat)));
+    }
+}
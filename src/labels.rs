@@ -0,0 +1,192 @@
+//!Ownership labels of entries. Services aggregating the entries of many tenants into one buffer
+//!label every entry with its producer, `MemBufferReader::stats_by_label` then sums up the entries
+//!and payload bytes per label so the size of a buffer can be attributed. The labels are stored in
+//!a metadata entry holding every distinct label once, followed by the sorted entry indices and the
+//!label of every index.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_labeled("small","tenant-a");
+//!writer.add_entry_labeled(&[0u8;1000][..],"tenant-b");
+//!writer.add_entry("unlabeled");
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.label(1),Some("tenant-b"));
+//!let stats = reader.stats_by_label();
+//!assert_eq!(stats[&Some("tenant-b")].bytes,1000);
+//!assert_eq!(stats[&None].entries,1);
+//!```
+use alloc::collections::BTreeMap;
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,SYSTEM_TYPE_LABELS};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,MemBufferSerialize};
+use crate::named::NameIndices;
+
+///Number of entries and payload bytes carrying one label
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LabelStats {
+    pub entries: usize,
+    ///Bytes of the payloads as stored, compressed entries count with their compressed size
+    pub bytes: u64,
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds an entry owned by the given label
+    pub fn add_entry_labeled<T: MemBufferSerialize>(&mut self, val: T, label: &str) {
+        self.labels.insert(self.types.len(),label.to_string());
+        self.add_entry(val);
+    }
+
+    ///Labels the entry, replacing an existing label. Panics if the index is out of bounds.
+    pub fn set_label(&mut self, index: usize, label: &str) {
+        assert!(index < self.types.len(),"Entry {} is out of bounds",index);
+        self.labels.insert(index,label.to_string());
+    }
+
+    ///Returns the label of the entry
+    pub fn label(&self, index: usize) -> Option<&str> {
+        self.labels.get(&index).map(|x| &x[..])
+    }
+
+    ///Serializes the distinct labels as nested buffer followed by the entry indices and the
+    ///position of the label of every index
+    pub(crate) fn label_table(&self) -> Option<Vec<u8>> {
+        if self.labels.is_empty() {
+            return None;
+        }
+        let distinct: Vec<&String> = self.labels.values().collect::<alloc::collections::BTreeSet<_>>().into_iter().collect();
+        let mut table = MemBufferWriter::new();
+        for label in distinct.iter() {
+            table.add_entry(&label[..]);
+        }
+        let indices: Vec<u32> = self.labels.keys().map(|x| *x as u32).collect();
+        let ids: Vec<u32> = self.labels.values().map(|x| distinct.binary_search(&x).unwrap() as u32).collect();
+        table.add_entry(&indices[..]);
+        table.add_entry(&ids[..]);
+        Some(table.finalize())
+    }
+
+    ///Takes over the labels of the entries of the reader
+    pub(crate) fn load_labels(&mut self, reader: &MemBufferReader) -> Result<(),MemBufferError> {
+        if let Some((table,indices,ids)) = reader.label_table()? {
+            for x in 0..indices.len() {
+                let label: &str = table.load_entry(ids.get(x))?;
+                self.labels.insert(indices.get(x),label.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the reader over the labels, the entry indices and the label of every index
+    fn label_table(&self) -> Result<Option<(MemBufferReader<'a>,NameIndices<'a>,NameIndices<'a>)>,MemBufferError> {
+        let table = match self.system_entry(SYSTEM_TYPE_LABELS) {
+            Some(table) => MemBufferReader::new(table)?,
+            None => return Ok(None),
+        };
+        if table.len() < 2 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let slice = |index: usize| -> Result<NameIndices<'a>,MemBufferError> {
+            let entry = table.offsets.get(index);
+            if entry.variable_type != MemBufferTypes::VectorU32 as i32 || entry.len()%4 != 0 {
+                return Err(MemBufferError::WrongFormat);
+            }
+            Ok(NameIndices {
+                data: table.entry_slice(entry)?,
+                swapped: table.is_byte_swapped(),
+            })
+        };
+        let indices = slice(table.len()-2)?;
+        let ids = slice(table.len()-1)?;
+        if indices.len() != ids.len() || (0..ids.len()).any(|x| ids.get(x) >= table.len()-2) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        Ok(Some((table,indices,ids)))
+    }
+
+    ///Returns the label of the entry
+    pub fn label(&self, index: usize) -> Option<&'a str> {
+        let (table,indices,ids) = self.label_table().ok()??;
+        let mut low = 0;
+        let mut high = indices.len();
+        while low < high {
+            let mid = (low+high)/2;
+            match indices.get(mid).cmp(&index) {
+                core::cmp::Ordering::Less => low = mid+1,
+                core::cmp::Ordering::Greater => high = mid,
+                core::cmp::Ordering::Equal => return table.load_entry::<&str>(ids.get(mid)).ok(),
+            }
+        }
+        None
+    }
+
+    ///Sums up the entries and payload bytes per label, unlabeled entries are counted under None.
+    ///Metadata entries are not counted and entries whose end lies before their start count with
+    ///0 bytes. Readers created with `new_lazily_validated` validate
+    ///every entry like a load does and leave out the entries which fail.
+    pub fn stats_by_label(&self) -> BTreeMap<Option<&'a str>,LabelStats> {
+        let mut stats: BTreeMap<Option<&'a str>,LabelStats> = BTreeMap::new();
+        for (index,entry) in self.offsets.iter().enumerate() {
+            if self.ensure_validated(index).is_err() {
+                continue;
+            }
+            let label = stats.entry(self.label(index)).or_default();
+            label.entries += 1;
+            label.bytes = label.bytes.saturating_add(entry.end.saturating_sub(entry.start) as u64);
+        }
+        stats
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferAppender};
+    use crate::labels::LabelStats;
+
+    #[test]
+    fn check_labels() {
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry_labeled("first","tenant-a");
+        writer.add_entry_labeled(&[1u64,2,3][..],"tenant-b");
+        writer.add_entry("unlabeled");
+        writer.add_entry_labeled("second","tenant-a");
+        writer.set_label(2,"tenant-c");
+        writer.set_label(2,"tenant-b");
+        assert_eq!(writer.label(2),Some("tenant-b"));
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.label(0),Some("tenant-a"));
+        assert_eq!(reader.label(2),Some("tenant-b"));
+        assert_eq!(reader.label(4),None);
+        let stats = reader.stats_by_label();
+        assert_eq!(stats.len(),2);
+        assert_eq!(stats[&Some("tenant-a")],LabelStats { entries: 2, bytes: 11 });
+        assert_eq!(stats[&Some("tenant-b")],LabelStats { entries: 2, bytes: 33 });
+
+        //Corrupted positions do not overflow the sums
+        let mut corrupted = data.clone();
+        corrupted[8+12+4..8+12+8].copy_from_slice(&0u32.to_be_bytes());
+        let stats = MemBufferReader::new(&corrupted).unwrap().stats_by_label();
+        assert_eq!(stats[&Some("tenant-b")],LabelStats { entries: 2, bytes: 9 });
+
+        //Labels move with their entries and are kept by appends
+        let mut update = MemBufferWriter::from(&data).unwrap();
+        update.remove_entry(0);
+        let mut data = update.finalize_interleaved();
+        let mut appender = MemBufferAppender::open(&mut data).unwrap();
+        appender.add_entry_labeled("third","tenant-c");
+        appender.commit().unwrap();
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.label(0),Some("tenant-b"));
+        assert_eq!(reader.label(2),Some("tenant-a"));
+        assert_eq!(reader.label(3),Some("tenant-c"));
+        assert_eq!(reader.stats_by_label()[&Some("tenant-c")].bytes,5);
+    }
+}
@@ -0,0 +1,261 @@
+//!Reader for buffers in storage which fetches byte ranges on request like object stores or huge
+//!files. `LazyMemBufferReader::new` only reads the header, every entry is fetched on its own when
+//!it is loaded, so a single field of a large remote buffer costs two range requests. Any storage
+//!implementing `ReadAt` can be used, storage in memory hands out the entries without copying.
+//!```rust
+//!use membuffer::{MemBufferWriter,LazyMemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("title");
+//!writer.add_entry(&vec![0u8;1<<20][..]);
+//!let data = writer.finalize();
+//!
+//!let reader = LazyMemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.len(),2);
+//!let range = reader.entry_range(1).unwrap();
+//!assert_eq!(range.end-range.start,1<<20);
+//!assert_eq!(reader.load_entry::<String>(0).unwrap(),"title");
+//!assert_eq!(reader.fetch_entry(1).unwrap().load::<&[u8]>().unwrap().len(),1<<20);
+//!```
+use std::borrow::Cow;
+use std::ops::Range;
+use crate::{MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize,ReadAt,SYSTEM_TYPE_COMPRESSION,SYSTEM_TYPE_CHECKSUM};
+use crate::compress::{find_record,load_detached};
+use crate::table::{self,EntryPosition};
+
+///Converts a format error into an `io::ErrorKind::InvalidData` error
+fn invalid_data(err: MemBufferError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData,err.to_string())
+}
+
+///Reader holding only the header of a buffer, see the module documentation
+pub struct LazyMemBufferReader<'s,S: ?Sized> {
+    storage: &'s S,
+    header: Vec<u8>,
+}
+
+impl<'s,S: ReadAt + ?Sized> LazyMemBufferReader<'s,S> {
+    ///Reads and checks the header, format errors are returned as `io::ErrorKind::InvalidData`.
+    ///Interleaved buffers do not start with a header and are rejected.
+    pub fn new(storage: &'s S) -> std::io::Result<LazyMemBufferReader<'s,S>> {
+        if storage.size() < 8 {
+            return Err(invalid_data(MemBufferError::WrongFormat));
+        }
+        let header_len = table::header_len(&storage.read_at(0..8)?).map_err(invalid_data)?;
+        if header_len > storage.size() {
+            return Err(invalid_data(MemBufferError::WrongFormat));
+        }
+        let header = storage.read_at(0..header_len)?.into_owned();
+        MemBufferReader::new(&header).map_err(invalid_data)?;
+        Ok(LazyMemBufferReader {
+            storage,
+            header,
+        })
+    }
+
+    ///Returns a reader over the header, it holds no payload
+    fn header_reader(&self) -> MemBufferReader<'_> {
+        //The header was checked when it was read
+        MemBufferReader::new(&self.header).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.header_reader().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header_reader().is_empty()
+    }
+
+    ///Returns the type of the entry without fetching it
+    pub fn variable_type(&self, index: usize) -> Result<i32,MemBufferError> {
+        let reader = self.header_reader();
+        if index >= reader.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,reader.len()));
+        }
+        Ok(reader.offsets.get(index).variable_type)
+    }
+
+    ///Returns the byte range the payload of the entry occupies in the storage
+    pub fn entry_range(&self, index: usize) -> Result<Range<u64>,MemBufferError> {
+        let reader = self.header_reader();
+        if index >= reader.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,reader.len()));
+        }
+        self.storage_range(reader.offsets.get(index))
+    }
+
+    fn storage_range(&self, entry: EntryPosition) -> Result<Range<u64>,MemBufferError> {
+        let start = (entry.start as u64).checked_add(self.header.len() as u64);
+        let end = (entry.end as u64).checked_add(self.header.len() as u64);
+        match (start,end) {
+            (Some(start),Some(end)) if start <= end => Ok(start..end),
+            _ => Err(MemBufferError::WrongFormat),
+        }
+    }
+
+    ///Fetches the payload of a single entry, entries of compressed buffers fetch the compression
+    ///table as well
+    pub fn fetch_entry(&self, index: usize) -> std::io::Result<LazyEntry<'s>> {
+        let reader = self.header_reader();
+        if index >= reader.len() {
+            return Err(invalid_data(MemBufferError::IndexOutOfBounds(index,reader.len())));
+        }
+        let entry = reader.offsets.get(index);
+        let data = self.storage.read_at(self.storage_range(entry).map_err(invalid_data)?)?;
+        let compression = match reader.system.iter().find(|x| x.variable_type == SYSTEM_TYPE_COMPRESSION) {
+            Some(table) => {
+                let table = self.storage.read_at(self.storage_range(table).map_err(invalid_data)?)?;
+                find_record(&table,index,reader.is_byte_swapped()).map_err(invalid_data)?
+            },
+            None => None,
+        };
+        Ok(LazyEntry {
+            data,
+            variable_type: entry.variable_type,
+            swapped: reader.is_byte_swapped(),
+            compression,
+            encrypted: reader.is_encrypted(index),
+        })
+    }
+
+    ///Fetches the entry like `fetch_entry` and verifies it against its digest stored by a writer
+    ///created with `with_entry_digests`, fails with `io::ErrorKind::InvalidData` wrapping
+    ///`MemBufferError::ChecksumMismatch` if the entry or the header was changed and
+    ///`MemBufferError::MissingChecksum` if the buffer has no entry digests
+    pub fn fetch_verified_entry(&self, index: usize) -> std::io::Result<LazyEntry<'s>> {
+        let entry = self.fetch_entry(index)?;
+        let reader = self.header_reader();
+        let stored = match reader.system.iter().find(|x| x.variable_type == SYSTEM_TYPE_CHECKSUM) {
+            Some(checksums) => self.storage.read_at(self.storage_range(checksums).map_err(invalid_data)?)?,
+            None => return Err(invalid_data(MemBufferError::MissingChecksum)),
+        };
+        reader.verify_entry_digest(&stored,index,&entry.data).map_err(invalid_data)?;
+        Ok(entry)
+    }
+
+    ///Fetches the entry and loads it as an owned value, use `fetch_entry` to borrow it
+    pub fn load_entry<X: for<'x> MemBufferDeserialize<'x,X>+MemBufferSerialize>(&self, index: usize) -> std::io::Result<X> {
+        self.fetch_entry(index)?.load::<X>().map_err(invalid_data)
+    }
+}
+
+///Payload of a single entry fetched from the storage
+pub struct LazyEntry<'s> {
+    data: Cow<'s,[u8]>,
+    variable_type: i32,
+    swapped: bool,
+    compression: Option<u64>,
+    encrypted: bool,
+}
+
+impl<'s> LazyEntry<'s> {
+    pub fn variable_type(&self) -> i32 {
+        self.variable_type
+    }
+
+    ///Returns the payload as stored in the buffer
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    ///Loads the entry with the given type like `MemBufferReader::load_entry`, interned text can
+    ///only be loaded through a reader over the whole buffer and entries of encrypted buffers fail
+    ///with `MemBufferError::EncryptedEntry`
+    pub fn load<'a, X: MemBufferDeserialize<'a,X>+MemBufferSerialize>(&'a self) -> Result<X,MemBufferError> {
+        load_detached(&self.data,self.variable_type,self.swapped,self.compression,self.encrypted)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,LazyMemBufferReader,ReadAt};
+    use std::borrow::Cow;
+    use std::cell::RefCell;
+    use std::ops::Range;
+
+    ///Remote storage which copies every range and records the requests
+    struct Remote {
+        data: Vec<u8>,
+        requests: RefCell<Vec<Range<u64>>>,
+    }
+
+    impl ReadAt for Remote {
+        fn read_at(&self, range: Range<u64>) -> std::io::Result<Cow<'_,[u8]>> {
+            self.requests.borrow_mut().push(range.clone());
+            self.data.read_at(range).map(|x| Cow::Owned(x.into_owned()))
+        }
+
+        fn size(&self) -> u64 {
+            self.data.len() as u64
+        }
+    }
+
+    #[test]
+    fn check_lazy_reader() {
+        let large = vec![3u8;1<<20];
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry("Hello");
+        writer.add_entry(&large[..]);
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry_named("answer",42);
+        let remote = Remote {
+            data: writer.finalize(),
+            requests: RefCell::new(Vec::new()),
+        };
+
+        let reader = LazyMemBufferReader::new(&remote).unwrap();
+        assert_eq!(reader.len(),4);
+        let header_len: u64 = remote.requests.borrow().iter().map(|x| x.end-x.start).sum();
+        assert!(header_len < 100);
+        assert_eq!(reader.load_entry::<i32>(3).unwrap(),42);
+        assert_eq!(reader.load_entry::<String>(0).unwrap(),"Hello");
+        assert_eq!(reader.fetch_entry(2).unwrap().load::<Cow<[u64]>>().unwrap().into_owned(),vec![1,2,3]);
+        assert!(reader.load_entry::<String>(3).is_err());
+        assert!(reader.fetch_entry(4).is_err());
+        //The large entry was never fetched
+        assert!(remote.requests.borrow().iter().all(|x| x.end-x.start < 1000));
+        assert_eq!(reader.entry_range(1).unwrap(),reader.entry_range(0).unwrap().end..reader.entry_range(0).unwrap().end+(1<<20));
+
+        assert!(reader.fetch_verified_entry(0).is_err());
+
+        assert!(LazyMemBufferReader::new(&b"no buffer"[..]).is_err());
+        assert!(LazyMemBufferReader::new(&remote.data[..20]).is_err());
+    }
+
+    #[cfg(feature = "entry_digests")]
+    #[test]
+    fn check_lazy_verified_entries() {
+        //Entries of buffers with entry digests are verified without fetching the other entries
+        let large = vec![3u8;1<<20];
+        let mut writer = MemBufferWriter::new().with_entry_digests();
+        writer.add_entry("Hello");
+        writer.add_entry(&large[..]);
+        writer.add_entry("World");
+        let mut data = writer.finalize();
+        let pos = data.windows(5).position(|x| x == b"World").unwrap();
+        data[pos] ^= 1;
+        let remote = Remote {
+            data,
+            requests: RefCell::new(Vec::new()),
+        };
+        let reader = LazyMemBufferReader::new(&remote).unwrap();
+        assert_eq!(reader.fetch_verified_entry(0).unwrap().as_bytes(),b"Hello");
+        assert!(reader.fetch_verified_entry(2).is_err());
+        assert!(remote.requests.borrow().iter().all(|x| x.end-x.start < 1000));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn check_lazy_encrypted_entries() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("secret");
+        writer.add_entry(&b"secret"[..]);
+        let data = writer.finalize_encrypted(&crate::EncryptionKey::new(&[7;32]));
+        let reader = LazyMemBufferReader::new(&data[..]).unwrap();
+        assert!(matches!(reader.fetch_entry(1).unwrap().load::<&[u8]>(),Err(crate::MemBufferError::EncryptedEntry)));
+        assert!(matches!(reader.fetch_entry(0).unwrap().load::<&str>(),Err(crate::MemBufferError::EncryptedEntry)));
+        assert!(reader.load_entry::<String>(0).is_err());
+    }
+}
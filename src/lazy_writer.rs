@@ -0,0 +1,190 @@
+//!Editing large buffers without copying them into a writer. `MemBufferWriter::from_reader_lazy`
+//!keeps every entry which can be written as it is stored as slice borrowed from the original
+//!buffer, only entries which are added or replaced are held by the writer. Writing the edited
+//!buffer copies the borrowed entries straight into the output, so changing one field of a large
+//!document costs the changed bytes plus one pass over the buffer. Compressed and encrypted entries
+//!and entries of buffers written with the other byte order are decoded when the writer is created
+//!like with `MemBufferWriter::from`.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_named("title","Frankenstein");
+//!writer.add_entry_named::<&[u8]>("scan",&vec![0u8;1<<20]);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let mut edit = MemBufferWriter::from_reader_lazy(&reader).unwrap();
+//!edit.add_entry_named("title","The Modern Prometheus");
+//!assert!(edit.is_borrowed(1));
+//!let data = edit.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"The Modern Prometheus");
+//!assert_eq!(reader.load_entry_by_name::<&[u8]>("scan").unwrap().len(),1<<20);
+//!```
+use std::io::Write;
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferSerialize,Payload};
+
+///Writer whose untouched entries are borrowed from the buffer it was created from, see the module
+///documentation
+pub struct LazyMemBufferWriter<'r> {
+    writer: MemBufferWriter,
+    //Payload of every entry which is still the one of the original buffer, the payload held by
+    //the writer is empty for those
+    borrowed: Vec<Option<&'r [u8]>>,
+}
+
+impl MemBufferWriter {
+    ///Creates a writer holding the entries and the settings of the buffer the reader refers to
+    ///like `MemBufferWriter::from`, entries which can be written unchanged are borrowed instead of
+    ///copied
+    pub fn from_reader_lazy<'r>(reader: &MemBufferReader<'r>) -> Result<LazyMemBufferWriter<'r>,MemBufferError> {
+        let mut writer = MemBufferWriter::new();
+        let mut borrowed = Vec::with_capacity(reader.len());
+        for (index,x) in reader.offsets.iter().enumerate() {
+            match writer.decoded_entry(reader,index)? {
+                Some(data) => {
+                    writer.counters.add_copied(data.len());
+                    writer.data.push(data.into());
+                    borrowed.push(None);
+                },
+                None => {
+                    writer.data.push(Vec::new().into());
+                    borrowed.push(Some(reader.entry_slice(x)?));
+                },
+            }
+            writer.types.push(x.variable_type);
+        }
+        writer.load_settings(reader)?;
+        Ok(LazyMemBufferWriter {
+            writer,
+            borrowed,
+        })
+    }
+}
+
+impl<'r> LazyMemBufferWriter<'r> {
+    pub fn len(&self) -> usize {
+        self.writer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writer.is_empty()
+    }
+
+    ///Returns true if the entry at the index is still borrowed from the original buffer
+    pub fn is_borrowed(&self, index: usize) -> bool {
+        matches!(self.borrowed.get(index),Some(Some(_)))
+    }
+
+    pub fn add_entry<T: MemBufferSerialize>(&mut self, val: T) {
+        self.writer.add_entry(val);
+        self.borrowed.push(None);
+    }
+
+    ///Adds a named entry, adding a name which already exists replaces the value of the existing
+    ///entry like `MemBufferWriter::add_entry_named`
+    pub fn add_entry_named<T: MemBufferSerialize>(&mut self, name: &str, val: T) {
+        match self.writer.entry_index(name) {
+            Some(index) => self.borrowed[index] = None,
+            None => self.borrowed.push(None),
+        }
+        self.writer.add_entry_named(name,val);
+    }
+
+    ///Replaces the entry at the index, fails with `MemBufferError::IndexOutOfBounds` if there is no
+    ///entry at the index
+    pub fn set_entry<T: MemBufferSerialize>(&mut self, val: T, index: usize) -> Result<(),MemBufferError> {
+        self.writer.set_entry(val,index)?;
+        self.borrowed[index] = None;
+        Ok(())
+    }
+
+    ///Removes the entry at the given index and shifts the following entries down. Panics if the
+    ///index is out of bounds.
+    pub fn remove_entry(&mut self, index: usize) {
+        self.writer.remove_entry(index);
+        self.borrowed.remove(index);
+    }
+
+    ///Writes the buffer into the given writer and returns the number of bytes written, the
+    ///borrowed entries are copied from the original buffer on the way
+    pub fn finalize_into<W: Write>(&self, w: W) -> std::io::Result<u64> {
+        let system = self.writer.system_entries();
+        let types: Vec<i32> = self.writer.types.iter().copied().chain(system.iter().map(|x| x.0)).collect();
+        let mut payloads: Vec<Payload> = self.writer.payloads().into_iter().zip(&self.borrowed).map(|(payload,borrowed)| match borrowed {
+            Some(data) => Payload::Bytes(data),
+            None => payload,
+        }).collect();
+        payloads.extend(system.iter().map(|x| Payload::Bytes(&x.1)));
+        self.writer.write_entries(&types,payloads,w)
+    }
+
+    pub fn finalize(&self) -> Vec<u8> {
+        let mut var = Vec::new();
+        //Writing into a vector only fails if a spooled payload cannot be read
+        self.finalize_into(&mut var).unwrap();
+        var
+    }
+
+    ///Copies the borrowed entries into the writer, which no longer depends on the original buffer
+    pub fn into_writer(self) -> MemBufferWriter {
+        let mut writer = self.writer;
+        for (index,borrowed) in self.borrowed.into_iter().enumerate() {
+            if let Some(data) = borrowed {
+                writer.counters.add_copied(data.len());
+                writer.data[index] = data.into();
+            }
+        }
+        writer
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,Endianness};
+
+    #[test]
+    fn check_lazy_writer() {
+        let large = vec![3u64;1<<16];
+        let mut writer = MemBufferWriter::new().with_field_checksums();
+        writer.add_entry_named("title","Frankenstein");
+        writer.add_entry_named::<&[u64]>("samples",&large);
+        writer.add_entry(1818);
+        writer.add_entry("text");
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        let mut edit = MemBufferWriter::from_reader_lazy(&reader).unwrap();
+        assert_eq!(edit.len(),4);
+        assert!(edit.is_borrowed(1));
+        edit.set_entry(1831,2).unwrap();
+        assert!(!edit.is_borrowed(2));
+        edit.add_entry("appended");
+        edit.remove_entry(0);
+        assert!(edit.is_borrowed(0));
+        let lazy = edit.finalize();
+
+        let reader = MemBufferReader::new_verified(&lazy).unwrap();
+        assert_eq!(reader.load_entry_by_name::<&[u64]>("samples").unwrap(),&large[..]);
+        assert!(reader.entry_index("title").is_none());
+        assert_eq!(reader.load_entry::<i32>(1).unwrap(),1831);
+        assert_eq!(reader.load_entry::<&str>(2).unwrap(),"text");
+        assert_eq!(reader.load_entry::<&str>(3).unwrap(),"appended");
+        assert_eq!(edit.into_writer().finalize(),lazy);
+
+        //Buffers with the other byte order are converted
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry("text");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        let data = writer.finalize();
+        let reader = MemBufferReader::new(&data).unwrap();
+        let edit = MemBufferWriter::from_reader_lazy(&reader).unwrap();
+        assert!(edit.is_borrowed(0));
+        assert_eq!(edit.is_borrowed(1),!reader.is_byte_swapped());
+        let data = edit.finalize();
+        assert_eq!(MemBufferReader::new(&data).unwrap().load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+    }
+}
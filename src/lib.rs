@@ -1,44 +1,221 @@
+//!This crate will provide a extremely fast deserialization of dynamic data structures with big
+//!fields. This is very MMAP friendly since it only parses the header and does not parse the fields
+//!until requested.
+//!**Easy example:**
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!fn main() {
+//!  //Creates a new empty MemBufferWriter
+//!  let mut writer = MemBufferWriter::new();
+//!  
+//!  //Adds this as immutable field, no more changing after adding it
+//!  //The first entry is the key and must be a type that implements Into<i32>
+//!  writer.add_entry("Very long value");
+//!
+//!  //Creates a Vec<u8> out of all the collected data
+//!  let result = writer.finalize();
+//!
+//!  //Try to read the created vector. Will return an error if the header is not terminated, use
+//!  //MemBufferReader::new_verified to check the CRC32 of the data as well. Will panic if the memory
+//!  //is corrupted beyond recognition
+//!  let reader = MemBufferReader::new(&result).unwrap();
+//!
+//!  //Will return an error if the selected key could not be found or if the value types dont match
+//!  assert_eq!(reader.load_entry::<&str>(0).unwrap(), "Very long value");
+//!}
+//!```
+//!Without the default `std` feature the crate is `no_std` and only needs `alloc`. Such builds read
+//!buffers from plain slices with the zero-copy reader, writing buffers and everything doing io
+//!requires `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(feature = "bench", feature(test))]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 
 #[cfg(feature = "bench")]
 extern crate test;
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate alloc;
 
+//Lets the code generated by the derive macro refer to the crate by name inside of it
+extern crate self as membuffer;
 
-use byteorder::{WriteBytesExt, ReadBytesExt, NativeEndian,ByteOrder};
-use serde::{Serialize,Deserialize};
-use bincode;
-use std::borrow::Cow;
 
+use byteorder::{NativeEndian,ByteOrder};
+#[cfg(feature = "std")]
+use serde::{Serialize,Deserialize};
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+///The parts of the std prelude which are in alloc, imported by the modules of no_std builds
+#[cfg(not(feature = "std"))]
+mod prelude {
+    pub(crate) use alloc::{vec::Vec,string::{String,ToString},boxed::Box};
+}
 
-///!This crate will provide a extremely fast deserialization of dynamic data structures with big
-///fields. This is very MMAP friendly since it only parses the header and does not parse the fields
-///until requested.
-///**Easy example:**
-///```rust
-///use membuffer::{MemBufferWriter,MemBufferReader};
-///
-///fn main() {
-///  //Creates a new empty MemBufferWriter
-///  let mut writer = MemBufferWriter::new();
-///  
-///  //Adds this as immutable field, no more changing after adding it
-///  //The first entry is the key and must be a type that implements Into<i32>
-///  writer.add_entry("Very long value");
-///
-///  //Creates a Vec<u8> out of all the collected data
-///  let result = writer.finalize();
-///
-///  //Try to read the created vector. Will return an error if the CRC32 does not fit
-///  //or if the header is not terminated. Will panic if the memory is corrupted beyond recognition
-///  let reader = MemBufferReader::new(&result).unwrap();
-///
-///  //Will return an error if the selected key could not be found or if the value types dont match
-///  assert_eq!(reader.load_entry::<&str>(0).unwrap(), "Very long value");
-///}
-///```
+mod stream;
+mod intern;
+mod view;
+pub use view::MemBufferView;
+mod fixed;
+pub use fixed::FixedReader;
+mod inplace;
+pub use inplace::MemBufferMut;
+mod named;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+pub use stats::WriteStats;
+mod checksum;
+pub use checksum::ChecksumAlgorithm;
+#[cfg(feature = "std")]
+mod sink;
+mod utf8;
+mod table;
+mod compact;
+#[cfg(feature = "std")]
+mod dedup;
+#[cfg(feature = "std")]
+mod spool;
+#[cfg(feature = "std")]
+mod persist;
+mod storage;
+mod placement;
+mod endian;
+mod value;
+pub use value::{Value,Values,MemBufferValue,EntryIter};
+mod serde_format;
+#[cfg(feature = "std")]
+mod doc;
+#[cfg(feature = "std")]
+pub use doc::MemDoc;
+mod compress;
+pub use compress::Compression;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(feature = "std")]
+pub use snapshot::FrozenBuffer;
+mod registry;
+pub use registry::TypeRegistry;
+mod owned;
+pub use owned::MemBufferOwnedReader;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+pub use cache::{CachedBuffer,CachedGuard};
+mod pin;
+pub use pin::PinnedEntry;
+mod handle;
+pub use handle::EntryHandle;
+mod schema;
+pub use schema::{Schema,SchemaBuilder};
+#[cfg(feature = "std")]
+mod projection;
+#[cfg(feature = "std")]
+mod edit;
+mod content;
+mod manifest;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+mod lazy_writer;
+#[cfg(feature = "std")]
+pub use lazy_writer::LazyMemBufferWriter;
+#[cfg(feature = "std")]
+pub use lazy::{LazyMemBufferReader,LazyEntry};
+#[cfg(feature = "std")]
+mod append;
+#[cfg(feature = "std")]
+pub use append::MemBufferAppender;
+#[cfg(feature = "std")]
+mod patch;
+#[cfg(feature = "std")]
+mod nested;
+#[cfg(feature = "std")]
+mod frame;
+#[cfg(feature = "std")]
+pub use frame::{MemBufferFrameReader,FramedIterator};
+#[cfg(feature = "std")]
+pub use patch::{MemBufferPatch,PatchEntry,diff,apply_patch};
+mod map;
+pub use map::{MemBufferMap,MemBufferMapView};
+mod list;
+mod scalar;
+pub use list::{StrListView,BytesListView};
+mod chunked;
+pub use chunked::{ChunkedView,Chunks};
+mod columns;
+#[cfg(feature = "std")]
+pub use columns::MemBufferTableWriter;
+pub use columns::{MemBufferTable,ColumnType,CellValue,Row};
+mod labels;
+pub use labels::LabelStats;
+mod changelog;
+pub use changelog::Change;
+mod flags;
+mod optional;
+mod version;
+mod transform;
+mod validate;
+mod introspect;
+pub use introspect::BufferDescription;
+pub use transform::{TransformRegistry,Transform};
+pub use version::{FORMAT_VERSION,LEGACY_FORMAT_VERSION};
+pub use serde_format::from_slice;
+#[cfg(feature = "std")]
+pub use serde_format::to_vec;
+#[cfg(feature = "allocator_api")]
+mod allocator;
+#[cfg(feature = "bytemuck")]
+mod pod;
+#[cfg(feature = "bytemuck")]
+pub use pod::{PodEntry,PodSlice,pod_type_id};
+#[cfg(feature = "encryption")]
+mod encrypt;
+#[cfg(feature = "encryption")]
+pub use encrypt::EncryptionKey;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "serde_json")]
+mod json;
+#[cfg(feature = "derive")]
+mod derive;
+#[cfg(feature = "derive")]
+pub use derive::MemBuffer;
+pub use endian::Endianness;
+#[cfg(feature = "std")]
+pub use placement::AccessRecorder;
+pub use storage::Storage;
+#[cfg(feature = "std")]
+pub use storage::ReadAt;
+use table::{EntryTable,EntryPosition};
+#[cfg(feature = "mmap")]
+pub mod fs;
+#[cfg(feature = "tokio")]
+pub mod aio;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub use sink::MemBufferStreamWriter;
+pub use stream::StreamEntry;
+#[cfg(feature = "std")]
+pub use stream::MemBufferStreamReader;
 
 ///Refers to a position given to every deserialize and serialize operation, can be used to store
 ///data if one does not need to store data in the payload e. g. Field smaller than 8 Bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
     pub start: i32,
     pub end: i32,
@@ -46,7 +223,9 @@ pub struct Position {
 
 
 ///Refers to the different types when implementing your own types use an own enum like
-///this:
+///this and register the ids in a `TypeRegistry` to detect collisions with other libraries. The ids
+///from 1021 to `LAST_RESERVED_TYPE` are reserved for the types of this crate, the registry refuses
+///them:
 ///```rust
 ///use membuffer::MemBufferTypes;
 ///enum MyImplementedTypes {
@@ -63,16 +242,105 @@ pub enum MemBufferTypes {
     VectorU32,
     VectorU64,
     MemBuffer,
-    LastPreDefienedValue
+    LastPreDefienedValue,
+    ///Text stored once in the interning table of the buffer, the entry only holds the id
+    InternedText = -1,
+    ///Unsigned 64 bit integer
+    Integer64 = 1021,
+    Float32,
+    Float64,
+    VectorF32,
+    VectorF64,
+    ///Text keys mapped to byte values, see `MemBufferMap`
+    Map,
+    ///Absent value without payload, see `MemBufferWriter::add_optional_entry`
+    Null,
+    ///List of texts, see `StrListView`
+    StrList,
+    ///List of byte strings, see `BytesListView`
+    BytesList,
+    ///Scalars of the remaining integer widths, `bool` stored as one byte and `char` stored as its
+    ///code point
+    Unsigned8,
+    Unsigned16,
+    Unsigned32,
+    Signed8,
+    Signed16,
+    Signed64,
+    Bool,
+    Char,
+    ///Byte string split into chunks, see `MemBufferWriter::add_entry_chunked`
+    Chunked,
 }
 
-impl Into<i32> for MemBufferTypes {
-    fn into(self) -> i32 {
-        self as i32
+///Last type id reserved for the types of this crate, see `MemBufferTypes`
+pub const LAST_RESERVED_TYPE: i32 = 1279;
+
+///Type ids of the metadata entries the writer stores behind the user entries, the reader hides
+///them from the entry list
+pub(crate) const SYSTEM_TYPE_INTERN_TABLE: i32 = -0x1000;
+pub(crate) const SYSTEM_TYPE_NAMES: i32 = -0x1001;
+pub(crate) const SYSTEM_TYPE_COMPRESSION: i32 = -0x1003;
+pub(crate) const SYSTEM_TYPE_REGISTRY: i32 = -0x1004;
+pub(crate) const SYSTEM_TYPE_CONTENT_TYPES: i32 = -0x1005;
+pub(crate) const SYSTEM_TYPE_LABELS: i32 = -0x1007;
+pub(crate) const SYSTEM_TYPE_CHANGELOG: i32 = -0x1008;
+pub(crate) const SYSTEM_TYPE_FLAGS: i32 = -0x1009;
+pub(crate) const SYSTEM_TYPE_ENCRYPTION: i32 = -0x100A;
+///Placed in front of the checksum entry
+pub(crate) const SYSTEM_TYPE_MANIFEST: i32 = -0x1006;
+///Always the last entry of the buffer
+pub(crate) const SYSTEM_TYPE_CHECKSUM: i32 = -0x1002;
+
+fn is_system_type(variable_type: i32) -> bool {
+    variable_type <= -0x1000
+}
+
+///Length of the nonce and the authentication tag stored with every encrypted entry
+pub(crate) const SEALING_OVERHEAD: usize = 28;
+
+///Returns whether entries of the type are encrypted in encrypted buffers, interned text only holds
+///an id into the interning table and null entries have no payload
+fn is_sealed_type(variable_type: i32) -> bool {
+    variable_type != MemBufferTypes::InternedText as i32 && variable_type != MemBufferTypes::Null as i32
+}
+
+///Type ids of slices of plain old data structs, the low 16 bits hold the tag of the struct
+#[cfg(any(feature = "std", feature = "bytemuck", feature = "gpu"))]
+pub(crate) const POD_TYPE_BASE: i32 = 0x4000_0000;
+
+///Returns whether the type id belongs to a slice of plain old data structs, their entries are
+///aligned to 8 bytes
+#[cfg(any(feature = "std", feature = "gpu"))]
+fn is_pod_type(variable_type: i32) -> bool {
+    (POD_TYPE_BASE..=POD_TYPE_BASE+0xFFFF).contains(&variable_type)
+}
+
+impl From<MemBufferTypes> for i32 {
+    fn from(val: MemBufferTypes) -> i32 {
+        val as i32
     }
 }
 
+///Returns the alignment the payload of the given type needs to be cast without copying, the
+///writer pads the start of such entries accordingly
+#[cfg(any(feature = "std", feature = "gpu"))]
+pub(crate) fn type_alignment(variable_type: i32) -> usize {
+    if variable_type == MemBufferTypes::VectorU32 as i32 || variable_type == MemBufferTypes::VectorF32 as i32 {
+        core::mem::align_of::<u32>()
+    }
+    else if variable_type == MemBufferTypes::VectorU64 as i32 || variable_type == MemBufferTypes::VectorF64 as i32 || variable_type == MemBufferTypes::MemBuffer as i32
+        || variable_type == MemBufferTypes::Map as i32 || variable_type == MemBufferTypes::StrList as i32 || variable_type == MemBufferTypes::BytesList as i32
+        || variable_type == MemBufferTypes::Chunked as i32
+        || is_system_type(variable_type) || is_pod_type(variable_type) {
+        core::mem::align_of::<u64>()
+    }
+    else {
+        1
+    }
+}
 
+#[repr(C)]
 struct InternPosition {
     pub pos: Position,
     pub variable_type: i32,
@@ -85,42 +353,158 @@ struct InternPosition {
 pub enum MemBufferError {
     FieldTypeError(i32,i32),
     WrongFormat,
+    ///The entry has the given length which exceeds the configured maximum entry size
+    EntryTooLarge(usize,usize),
+    ///There is no entry with the given name
+    KeyNotFound(String),
+    ///The checksums stored in the buffer do not match its content
+    ChecksumMismatch,
+    ///Verification was requested but the buffer has no checksums
+    MissingChecksum,
+    ///The checksums were written with an unknown algorithm or one whose feature is not enabled
+    UnsupportedChecksum(u32),
+    ///The buffer was written with a newer format version than this crate supports
+    UnsupportedVersion(u32),
+    ///The requested index is not below the given number of entries
+    IndexOutOfBounds(usize,usize),
+    ///The entry depends on the byte order and cannot be borrowed from a buffer written with the
+    ///other byte order
+    ByteOrderMismatch,
+    ///Serde failed to serialize or deserialize a value with the given message
+    SerdeFailure(String),
+    ///The entry is stored compressed and can only be loaded as owned value
+    CompressedEntry,
+    ///The entry was compressed with an unknown codec or one whose feature is not enabled
+    UnsupportedCompression(u32),
+    ///The type id is already taken by the type with the given name
+    TypeConflict(i32,String),
+    ///The buffer holds an entry with a type id which is not registered
+    UnknownType(i32),
+    ///The integer slice does not start at the alignment of its integers and cannot be borrowed,
+    ///load it as `Cow` to copy it instead
+    UnalignedEntry,
+    ///The entry is encrypted and the reader was not created with `new_encrypted`
+    EncryptedEntry,
+    ///The entry could not be decrypted, the key is wrong or the buffer was modified
+    DecryptionFailed,
+    ///The text entry is not valid UTF-8
+    InvalidUtf8,
+    ///The entry with the given index is compressed, encrypted or covered by the checksums of the
+    ///buffer and cannot be modified in place
+    ImmutableEntry(usize),
+    ///The value has the given length which differs from the length of the entry it should replace
+    SizeMismatch(usize,usize),
+    ///The buffer declares the first length but ends after the second one, it was not completely
+    ///written
+    Truncated(usize,usize),
 }
 
-impl<'a> std::fmt::Display for MemBufferError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for MemBufferError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
-            MemBufferError::FieldTypeError(x,y) => write!(f,"Memory buffer error: Field has type {} and not requested type {}",x.to_string(),y.to_string()),
-            MemBufferError::WrongFormat => write!(f,"Memory buffer error: Reached end of slice before end of header, memory seems to be corrupted")
+            MemBufferError::FieldTypeError(x,y) => write!(f,"Memory buffer error: Field has type {} and not requested type {}",x,y),
+            MemBufferError::WrongFormat => write!(f,"Memory buffer error: Reached end of slice before end of header, memory seems to be corrupted"),
+            MemBufferError::EntryTooLarge(x,y) => write!(f,"Memory buffer error: Entry has size {} which exceeds the maximum entry size {}",x,y),
+            MemBufferError::KeyNotFound(x) => write!(f,"Memory buffer error: Could not find an entry named {}",x),
+            MemBufferError::ChecksumMismatch => write!(f,"Memory buffer error: Checksum does not match, memory seems to be corrupted"),
+            MemBufferError::MissingChecksum => write!(f,"Memory buffer error: Buffer has no checksum to verify"),
+            MemBufferError::UnsupportedChecksum(x) => write!(f,"Memory buffer error: Checksum algorithm {} is not supported",x),
+            MemBufferError::UnsupportedVersion(x) => write!(f,"Memory buffer error: Format version {} is not supported",x),
+            MemBufferError::IndexOutOfBounds(x,y) => write!(f,"Memory buffer error: Entry {} is out of bounds for a buffer with {} entries",x,y),
+            MemBufferError::ByteOrderMismatch => write!(f,"Memory buffer error: Entry was written with the other byte order and cannot be borrowed"),
+            MemBufferError::SerdeFailure(x) => write!(f,"Memory buffer error: Serde failed with {}",x),
+            MemBufferError::CompressedEntry => write!(f,"Memory buffer error: Entry is compressed and cannot be borrowed"),
+            MemBufferError::UnsupportedCompression(x) => write!(f,"Memory buffer error: Compression codec {} is not supported",x),
+            MemBufferError::TypeConflict(x,y) => write!(f,"Memory buffer error: Type id {} is already taken by type {}",x,y),
+            MemBufferError::UnknownType(x) => write!(f,"Memory buffer error: Type id {} is not registered",x),
+            MemBufferError::UnalignedEntry => write!(f,"Memory buffer error: Entry is not aligned for its integer type and cannot be borrowed"),
+            MemBufferError::EncryptedEntry => write!(f,"Memory buffer error: Entry is encrypted and the reader has no key"),
+            MemBufferError::DecryptionFailed => write!(f,"Memory buffer error: Decryption failed, the key is wrong or the memory seems to be corrupted"),
+            MemBufferError::InvalidUtf8 => write!(f,"Memory buffer error: Text entry is not valid UTF-8, memory seems to be corrupted"),
+            MemBufferError::ImmutableEntry(x) => write!(f,"Memory buffer error: Entry {} cannot be modified in place",x),
+            MemBufferError::SizeMismatch(x,y) => write!(f,"Memory buffer error: Value has size {} and cannot replace an entry of size {}",x,y),
+            MemBufferError::Truncated(x,y) => write!(f,"Memory buffer error: Buffer declares {} bytes but ends after {} bytes",x,y),
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for MemBufferError {}
+
 
 pub trait MemBufferDeserialize<'a,T> {
     fn from_mem_buffer(mem: &'a [u8]) -> Result<T,MemBufferError> where Self: Sized;
+
+    ///Deserializes an entry of a buffer written with the other byte order, types whose payload
+    ///depends on the byte order have to swap it or fail with `MemBufferError::ByteOrderMismatch`
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<T,MemBufferError> where Self: Sized {
+        Self::from_mem_buffer(mem)
+    }
+
+    ///Deserializes the entry like `from_mem_buffer` while skipping the validation of the payload
+    ///which is only needed for untrusted buffers, used by `MemBufferReader::load_entry_unchecked`
+    ///
+    ///# Safety
+    ///The payload has to be valid for the type, text has to be valid UTF-8
+    unsafe fn from_mem_buffer_unchecked(mem: &'a [u8]) -> Result<T,MemBufferError> where Self: Sized {
+        Self::from_mem_buffer(mem)
+    }
+
+    ///Deserializes a compressed entry from its decompressed payload, only owned types can be
+    ///loaded from compressed entries
+    fn from_decompressed(_mem: Vec<u8>) -> Result<T,MemBufferError> where Self: Sized {
+        Err(MemBufferError::CompressedEntry)
+    }
 }
 
 impl<'a> MemBufferDeserialize<'a,&'a str> for &str {
     fn from_mem_buffer(mem: &'a [u8]) -> Result<&'a str,MemBufferError> {
-        //This should always be safe as long as the saved string was utf-8 encoded and no one
-        //messed with the file on disk.
-        unsafe{ Ok(std::str::from_utf8_unchecked(mem)) }
+        if !utf8::is_valid_utf8(mem) {
+            return Err(MemBufferError::InvalidUtf8);
+        }
+        //Validated right above
+        unsafe{ Ok(core::str::from_utf8_unchecked(mem)) }
+    }
+
+    unsafe fn from_mem_buffer_unchecked(mem: &'a [u8]) -> Result<&'a str,MemBufferError> {
+        Ok(core::str::from_utf8_unchecked(mem))
     }
 }
 
 impl<'a> MemBufferDeserialize<'a,i32> for i32 {
     fn from_mem_buffer(mem: &'a [u8]) -> Result<i32,MemBufferError> {
+        if mem.len() != core::mem::size_of::<i32>() {
+            return Err(MemBufferError::WrongFormat);
+        }
         //Fast load integer since no memory is required to store integer
         Ok(NativeEndian::read_i32(mem))
     }
+
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<i32,MemBufferError> {
+        Ok(i32::from_mem_buffer(mem)?.swap_bytes())
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<i32,MemBufferError> {
+        i32::from_mem_buffer(&mem)
+    }
 }
 
 impl<'a> MemBufferDeserialize<'a,u64> for u64 {
     fn from_mem_buffer(mem: &'a [u8]) -> Result<u64,MemBufferError> {
+        if mem.len() != core::mem::size_of::<u64>() {
+            return Err(MemBufferError::WrongFormat);
+        }
         //Fast load integer since no memory is required to store integer
         Ok(NativeEndian::read_u64(mem))
     }
+
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<u64,MemBufferError> {
+        Ok(u64::from_mem_buffer(mem)?.swap_bytes())
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<u64,MemBufferError> {
+        u64::from_mem_buffer(&mem)
+    }
 }
 
 impl<'a> MemBufferDeserialize<'a,&'a [u8]> for &[u8] {
@@ -129,30 +513,181 @@ impl<'a> MemBufferDeserialize<'a,&'a [u8]> for &[u8] {
     }
 }
 
+impl<'a> MemBufferDeserialize<'a,String> for String {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<String,MemBufferError> {
+        String::from_utf8(mem.to_vec()).map_err(|_| MemBufferError::InvalidUtf8)
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<String,MemBufferError> {
+        String::from_utf8(mem).map_err(|_| MemBufferError::InvalidUtf8)
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,Cow<'a,[u8]>> for Cow<'a,[u8]> {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<Cow<'a,[u8]>,MemBufferError> {
+        Ok(Cow::Borrowed(mem))
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<Cow<'a,[u8]>,MemBufferError> {
+        Ok(Cow::Owned(mem))
+    }
+}
+
+///Borrows the payload as slice of integers, the payload has to start at the alignment of the
+///integers which the writer guarantees for buffers loaded from aligned memory
+fn cast_slice<T>(mem: &[u8]) -> Result<&[T],MemBufferError> {
+    //Empty entries are valid but their pointer does not need to be aligned
+    if mem.is_empty() {
+        return Ok(&[]);
+    }
+    if !mem.len().is_multiple_of(core::mem::size_of::<T>()) {
+        return Err(MemBufferError::WrongFormat);
+    }
+    if mem.as_ptr().align_offset(core::mem::align_of::<T>()) != 0 {
+        return Err(MemBufferError::UnalignedEntry);
+    }
+    //The length and the alignment were checked above
+    Ok(unsafe{core::slice::from_raw_parts(mem.as_ptr().cast::<T>(), mem.len()/core::mem::size_of::<T>())})
+}
+
 impl<'a> MemBufferDeserialize<'a,&'a [u64]> for &[u64] {
     fn from_mem_buffer(mem: &'a [u8]) -> Result<&'a [u64],MemBufferError> {
-        let val: *const u8 = mem.as_ptr();
-        let cast_memory = val.cast::<u64>();
-        //Divide by eight as u64 should be 8 bytes on any system
-        let mem_length = mem.len()>>3;
+        cast_slice(mem)
+    }
 
-        //This should always be safe as long as no one messed with the serialized data
-        Ok(unsafe{std::slice::from_raw_parts(cast_memory, mem_length as usize)})
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<&'a [u64],MemBufferError> {
+        if mem.is_empty() {
+            return Ok(&[]);
+        }
+        Err(MemBufferError::ByteOrderMismatch)
     }
 }
 
 impl<'a> MemBufferDeserialize<'a,&'a [u32]> for &[u32] {
     fn from_mem_buffer(mem: &'a [u8]) -> Result<&'a [u32],MemBufferError> {
-        let val: *const u8 = mem.as_ptr();
-        let cast_memory = val.cast::<u32>();
-        //Divide by four as u32 should be 4 bytes on any system
-        let mem_length = mem.len()>>2;
+        cast_slice(mem)
+    }
 
-        //This should always be safe as long as no one messed with the serialized data
-        Ok(unsafe{std::slice::from_raw_parts(cast_memory, mem_length as usize)})
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<&'a [u32],MemBufferError> {
+        if mem.is_empty() {
+            return Ok(&[]);
+        }
+        Err(MemBufferError::ByteOrderMismatch)
     }
 }
 
+impl<'a> MemBufferDeserialize<'a,&'a [f32]> for &[f32] {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<&'a [f32],MemBufferError> {
+        cast_slice(mem)
+    }
+
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<&'a [f32],MemBufferError> {
+        if mem.is_empty() {
+            return Ok(&[]);
+        }
+        Err(MemBufferError::ByteOrderMismatch)
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,&'a [f64]> for &[f64] {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<&'a [f64],MemBufferError> {
+        cast_slice(mem)
+    }
+
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<&'a [f64],MemBufferError> {
+        if mem.is_empty() {
+            return Ok(&[]);
+        }
+        Err(MemBufferError::ByteOrderMismatch)
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,f32> for f32 {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<f32,MemBufferError> {
+        if mem.len() != core::mem::size_of::<f32>() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        Ok(NativeEndian::read_f32(mem))
+    }
+
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<f32,MemBufferError> {
+        Ok(f32::from_bits(f32::from_mem_buffer(mem)?.to_bits().swap_bytes()))
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<f32,MemBufferError> {
+        f32::from_mem_buffer(&mem)
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,f64> for f64 {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<f64,MemBufferError> {
+        if mem.len() != core::mem::size_of::<f64>() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        Ok(NativeEndian::read_f64(mem))
+    }
+
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<f64,MemBufferError> {
+        Ok(f64::from_bits(f64::from_mem_buffer(mem)?.to_bits().swap_bytes()))
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<f64,MemBufferError> {
+        f64::from_mem_buffer(&mem)
+    }
+}
+
+///Byte order of buffers written on systems with the other byte order
+#[cfg(target_endian = "little")]
+type SwappedEndian = byteorder::BigEndian;
+#[cfg(target_endian = "big")]
+type SwappedEndian = byteorder::LittleEndian;
+
+///Implements loading integer and float slices as `Cow`, which borrows aligned payloads and copies
+///the numbers out of unaligned, swapped or compressed ones
+macro_rules! number_slice_cow {
+    ($num:ty,$read:ident) => {
+        impl<'a> MemBufferDeserialize<'a,Cow<'a,[$num]>> for Cow<'a,[$num]> {
+            fn from_mem_buffer(mem: &'a [u8]) -> Result<Cow<'a,[$num]>,MemBufferError> {
+                match cast_slice::<$num>(mem) {
+                    Ok(x) => Ok(Cow::Borrowed(x)),
+                    Err(MemBufferError::UnalignedEntry) => Ok(Cow::Owned(mem.chunks_exact(core::mem::size_of::<$num>()).map(NativeEndian::$read).collect())),
+                    Err(err) => Err(err),
+                }
+            }
+
+            fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<Cow<'a,[$num]>,MemBufferError> {
+                if !mem.len().is_multiple_of(core::mem::size_of::<$num>()) {
+                    return Err(MemBufferError::WrongFormat);
+                }
+                Ok(Cow::Owned(mem.chunks_exact(core::mem::size_of::<$num>()).map(SwappedEndian::$read).collect()))
+            }
+
+            fn from_decompressed(mem: Vec<u8>) -> Result<Cow<'a,[$num]>,MemBufferError> {
+                if !mem.len().is_multiple_of(core::mem::size_of::<$num>()) {
+                    return Err(MemBufferError::WrongFormat);
+                }
+                Ok(Cow::Owned(mem.chunks_exact(core::mem::size_of::<$num>()).map(NativeEndian::$read).collect()))
+            }
+        }
+
+        impl MemBufferSerialize for Cow<'_,[$num]> {
+            fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+                let mem_length = core::mem::size_of_val(&**self);
+                Cow::Borrowed(unsafe{ core::slice::from_raw_parts(self.as_ptr().cast::<u8>(), mem_length)})
+            }
+
+            fn get_mem_buffer_type() -> i32 {
+                <&[$num]>::get_mem_buffer_type()
+            }
+        }
+    };
+}
+
+number_slice_cow!(u64,read_u64);
+number_slice_cow!(u32,read_u32);
+number_slice_cow!(f32,read_f32);
+number_slice_cow!(f64,read_f64);
+
 impl<'a> MemBufferDeserialize<'a,MemBufferReader<'a>> for MemBufferReader<'a> {
     fn from_mem_buffer(mem: &'a [u8]) -> Result<MemBufferReader<'a>,MemBufferError> {
         let reader = MemBufferReader::new(mem)?;
@@ -161,8 +696,9 @@ impl<'a> MemBufferDeserialize<'a,MemBufferReader<'a>> for MemBufferReader<'a> {
 }
 
 ///The reader which is used for reading the memory area produced by the writer, **Important notice:
-///The reader uses the native endian of the system used therefore sending between big endian and
-///little endian systems wont work**
+///Buffers use the native endian of the writing system unless the writer is created with
+///`MemBufferWriter::new_portable`, integer slices of buffers from systems with the other byte
+///order cannot be loaded**
 ///```rust
 ///use membuffer::{MemBufferWriter,MemBufferReader};
 ///
@@ -174,52 +710,207 @@ impl<'a> MemBufferDeserialize<'a,MemBufferReader<'a>> for MemBufferReader<'a> {
 /////We load the first entry, try not to get this mixed up
 ///assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Add some data to save to file or send over the network");
 ///```
+#[derive(Clone)]
 pub struct MemBufferReader<'a> {
-    offsets: &'a [InternPosition],
-    data: &'a [u8]
+    offsets: EntryTable<'a>,
+    system: EntryTable<'a>,
+    data: &'a [u8],
+    max_entry_size: usize,
+    access_hook: Option<&'a (dyn Fn(usize,i32,usize) + Sync)>,
+    version: u32,
+    transforms: Option<&'a TransformRegistry>,
+    validated: Option<alloc::sync::Arc<validate::ValidatedEntries>>,
+    #[cfg(feature = "encryption")]
+    key: Option<&'a EncryptionKey>,
 }
 
 impl<'a> MemBufferReader<'a> {
     ///Deserialize data from a buffer to an i32 integer
-    pub fn deserialize_i32_from(mut buffer: &[u8]) -> i32 {
-        buffer.read_i32::<NativeEndian>().unwrap()
+    pub fn deserialize_i32_from(buffer: &[u8]) -> i32 {
+        NativeEndian::read_i32(buffer)
     }
 
     pub fn len(&self) -> usize {
         self.offsets.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    ///Returns the size of the payload of all entries, metadata stored behind the entries is not
+    ///counted
     pub fn payload_len(&self) -> usize {
-        self.data.len()
+        if self.system.is_empty() {
+            return self.data.len();
+        }
+        self.offsets.iter().map(|x| x.end).max().unwrap_or(0)
+    }
+
+    ///Rejects every entry larger than the given amount of bytes with
+    ///`MemBufferError::EntryTooLarge` before it is sliced out of the payload, use this to bound
+    ///the memory a single load may touch when reading untrusted buffers. The limit is passed on to
+    ///nested readers.
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferReader};
+    ///
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("short");
+    ///writer.add_entry("this one is way too long");
+    ///let data = writer.finalize();
+    ///
+    ///let reader = MemBufferReader::new(&data).unwrap().with_max_entry_size(10);
+    ///assert_eq!(reader.load_entry::<&str>(0).unwrap(),"short");
+    ///assert!(reader.load_entry::<&str>(1).is_err());
+    ///```
+    pub fn with_max_entry_size(mut self, max_entry_size: usize) -> MemBufferReader<'a> {
+        self.max_entry_size = max_entry_size;
+        self
+    }
+
+    ///Calls the hook with the index, the stored type and the payload length of every entry a load
+    ///is attempted on, use it to record which fields of a document are actually accessed. Loads
+    ///from nested readers are not reported.
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferReader};
+    ///use std::sync::atomic::{AtomicUsize,Ordering};
+    ///
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("cold");
+    ///writer.add_entry("hot");
+    ///let data = writer.finalize();
+    ///
+    ///let accesses = [AtomicUsize::new(0),AtomicUsize::new(0)];
+    ///let hook = |index: usize, _: i32, _: usize| { accesses[index].fetch_add(1,Ordering::Relaxed); };
+    ///let reader = MemBufferReader::new(&data).unwrap().with_access_hook(&hook);
+    ///for _ in 0..3 {
+    ///    reader.load_entry::<&str>(1).unwrap();
+    ///}
+    ///assert_eq!(accesses[1].load(Ordering::Relaxed),3);
+    ///```
+    pub fn with_access_hook(mut self, hook: &'a (dyn Fn(usize,i32,usize) + Sync)) -> MemBufferReader<'a> {
+        self.access_hook = Some(hook);
+        self
     }
     
     ///Internal load function this is needed to enable loading nested MemBufferWriters which does
     ///not implement the Deserialize trait
-    fn intern_load_entry<X: MemBufferDeserialize<'a,X>>(&self, key: usize, expected_type: i32) -> Result<X,MemBufferError> {
-        let entry = &self.offsets[key];
+    fn intern_load_entry<X: MemBufferDeserialize<'a,X>>(&self, key: usize, expected_type: i32, checked: bool) -> Result<X,MemBufferError> {
+        if key >= self.offsets.len() {
+            return Err(MemBufferError::IndexOutOfBounds(key,self.offsets.len()));
+        }
+        let entry = self.offsets.get(key);
+        if let Some(hook) = self.access_hook {
+            hook(key,entry.variable_type,entry.len());
+        }
+        self.ensure_validated(key)?;
+        //Only load_entry_unchecked skips the validation, its caller guarantees the payload is valid
+        let borrow = |data: &'a [u8]| if checked { X::from_mem_buffer(data) } else { unsafe{ X::from_mem_buffer_unchecked(data) } };
         let is_type = entry.variable_type;
+        if is_type == MemBufferTypes::InternedText as i32 && expected_type == MemBufferTypes::Text as i32 {
+            let text = self.resolve_interned(self.entry_slice(entry)?)?;
+            if text.len() > self.max_entry_size {
+                return Err(MemBufferError::EntryTooLarge(text.len(),self.max_entry_size));
+            }
+            return borrow(text);
+        }
         if is_type != expected_type {
             return Err(MemBufferError::FieldTypeError(is_type,expected_type));
         }
-        return X::from_mem_buffer(&self.data[entry.pos.start as usize..entry.pos.end as usize]);
+        let entry_len = entry.len();
+        if entry_len > self.max_entry_size {
+            return Err(MemBufferError::EntryTooLarge(entry_len,self.max_entry_size));
+        }
+        let slice = self.entry_slice(entry)?;
+        if self.has_transform(is_type) {
+            return match self.transform_entry(key,slice)? {
+                Cow::Borrowed(data) if self.offsets.is_swapped() => X::from_swapped_mem_buffer(data),
+                Cow::Borrowed(data) => borrow(data),
+                Cow::Owned(data) => X::from_decompressed(data),
+            };
+        }
+        if let Some(data) = self.decompress_entry(key,slice)? {
+            return X::from_decompressed(data);
+        }
+        if self.offsets.is_swapped() {
+            return X::from_swapped_mem_buffer(slice);
+        }
+        borrow(slice)
+    }
+
+    ///Returns the payload of the entry, fails with `MemBufferError::WrongFormat` if the entry does
+    ///not lie within the payload. Nested metadata tables are never validated up front, so every
+    ///slice goes through this check.
+    fn entry_slice(&self, entry: EntryPosition) -> Result<&'a [u8],MemBufferError> {
+        self.data.get(entry.start..entry.end).ok_or(MemBufferError::WrongFormat)
+    }
+
+    ///Returns the payload of the metadata entry with the given type if the buffer has one, metadata
+    ///entries which do not lie within the payload are ignored
+    fn system_entry(&self, variable_type: i32) -> Option<&'a [u8]> {
+        self.system.iter().find(|x| x.variable_type == variable_type).and_then(|x| self.entry_slice(x).ok())
+    }
+
+    ///Returns whether the payload of the entry is encrypted, see `MemBufferWriter::finalize_encrypted`
+    pub(crate) fn is_encrypted(&self, index: usize) -> bool {
+        is_sealed_type(self.offsets.get(index).variable_type) && self.system.iter().any(|x| x.variable_type == SYSTEM_TYPE_ENCRYPTION)
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    pub(crate) fn decrypt_entry(&self, _index: usize, _data: &[u8]) -> Result<Vec<u8>,MemBufferError> {
+        Err(MemBufferError::EncryptedEntry)
     }
 
     ///Load one entry with the given type, expecting the serializable trait as well to determine
     ///the integer type, when doing polymorphismus of structures use the same integer for multiple
     ///types
     pub fn load_entry<X: MemBufferDeserialize<'a,X> + MemBufferSerialize>(&self,key: usize) -> Result<X,MemBufferError> {
-        self.intern_load_entry(key.into(), X::get_mem_buffer_type())
+        self.intern_load_entry(key, X::get_mem_buffer_type(), true)
+    }
+
+    ///Loads the entry like `load_entry` without validating the payload, text is not checked for
+    ///valid UTF-8. Meant for trusted buffers like mmaps of files written by the application itself
+    ///where the validation shows up in profiles.
+    ///
+    ///# Safety
+    ///The payload of the entry has to be valid for the type, text has to be valid UTF-8
+    pub unsafe fn load_entry_unchecked<X: MemBufferDeserialize<'a,X> + MemBufferSerialize>(&self,key: usize) -> Result<X,MemBufferError> {
+        self.intern_load_entry(key, X::get_mem_buffer_type(), false)
     }
 
-    ///Loads an entry stored with serde_json and returns it.
+    ///Loads an entry stored with `MemBufferWriter::add_serde_entry` and returns it, fails with
+    ///`MemBufferError::SerdeFailure` if the entry cannot be deserialized as `T`
+    #[cfg(feature = "std")]
     pub fn load_serde_entry<T: Deserialize<'a>>(&self,key: usize) -> Result<T,MemBufferError> {
-        let data: &[u8] = self.load_entry(key.into())?;
-        Ok(bincode::deserialize(data).unwrap())
+        let data: &[u8] = self.load_entry(key)?;
+        bincode::deserialize(data).map_err(|err| MemBufferError::SerdeFailure(err.to_string()))
     }
 
     ///Loads a nested MembufferWriter as reader
     pub fn load_recursive_reader(&self, key: usize) -> Result<MemBufferReader<'a>,MemBufferError> {
-        self.intern_load_entry(key.into(), MemBufferWriter::get_mem_buffer_type())
+        let reader: MemBufferReader<'a> = self.intern_load_entry(key, MemBufferTypes::MemBuffer as i32, true)?;
+        Ok(reader.with_max_entry_size(self.max_entry_size))
+    }
+
+    ///Returns the payload of a nested buffer entry, it is a standalone buffer which can be stored
+    ///or sent on without serializing it again. Nested buffers which are compressed or encrypted
+    ///fail with `MemBufferError::CompressedEntry` as they are no valid buffer in the stored form.
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferReader};
+    ///
+    ///let mut nested = MemBufferWriter::new();
+    ///nested.add_entry("inner");
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry(&nested);
+    ///let data = writer.finalize();
+    ///
+    ///let extracted = MemBufferReader::new(&data).unwrap().extract_nested(0).unwrap();
+    ///assert_eq!(extracted,&nested.finalize()[..]);
+    ///```
+    pub fn extract_nested(&self, key: usize) -> Result<&'a [u8],MemBufferError> {
+        let data: &'a [u8] = self.intern_load_entry(key,MemBufferTypes::MemBuffer as i32,true)?;
+        MemBufferReader::new_validated(data)?;
+        Ok(data)
     }
 
 
@@ -230,43 +921,174 @@ impl<'a> MemBufferReader<'a> {
             return Err(MemBufferError::WrongFormat);
         }
 
-        let vec_len = MemBufferReader::deserialize_i32_from(val) as usize;
-        let checksum = MemBufferReader::deserialize_i32_from(&val[4..]) as usize;
-        let start = vec_len*std::mem::size_of::<InternPosition>()+8;
-        if val.len() < start || std::num::Wrapping(checksum)+std::num::Wrapping(0x7AFECAFE) != std::num::Wrapping(vec_len) {
+        let count = MemBufferReader::deserialize_i32_from(val);
+        let checksum = MemBufferReader::deserialize_i32_from(&val[4..]);
+        if stream::is_stream_magic(count) {
+            return MemBufferReader::new_interleaved(val);
+        }
+        //The magic word also tells whether the buffer was written with the other byte order
+        let layout = table::header_layout(count,checksum)?;
+        let (vec_len,large,version) = (layout.count,layout.large,layout.version);
+        let start = layout.header_len().ok_or(MemBufferError::WrongFormat)?;
+        if val.len() < start {
             return Err(MemBufferError::WrongFormat);
         }
 
+        if layout.compact {
+            let table = compact::CompactTable::decode(&val[8..start],layout.swapped)?;
+            return Ok(MemBufferReader::from_parts(EntryTable::Compact(table),&val[start..],version));
+        }
+
+        if layout.swapped {
+            return Ok(MemBufferReader::from_parts(EntryTable::Swapped(&val[8..start],large),&val[start..],version));
+        }
+
+        //The positions can only be read in place from a buffer aligned for their integers
+        let align = if large { core::mem::align_of::<table::InternPosition64>() } else { core::mem::align_of::<InternPosition>() };
+        if val.as_ptr().align_offset(align) != 0 {
+            return Ok(MemBufferReader::from_parts(EntryTable::Unaligned(&val[8..start],large),&val[start..],version));
+        }
+
+        //The header holds `vec_len` entries as checked against the buffer length above and starts
+        //aligned for them, the entries consist of integers only so any content is a valid entry
         unsafe {
-        Ok(MemBufferReader {
-            offsets: std::slice::from_raw_parts(val[8..].as_ptr().cast::<InternPosition>(),vec_len),
-            data: &val[start..]
-        })
+        let table = if large {
+            EntryTable::Large(core::slice::from_raw_parts(val[8..].as_ptr().cast::<table::InternPosition64>(),vec_len))
+        }
+        else {
+            EntryTable::Small(core::slice::from_raw_parts(val[8..].as_ptr().cast::<InternPosition>(),vec_len))
+        };
+        Ok(MemBufferReader::from_parts(table,&val[start..],version))
+        }
+    }
+
+    ///Creates the reader like `MemBufferReader::new` and additionally checks that every entry lies
+    ///within the payload and that the uncompressed lengths of compressed entries are possible, use
+    ///this for buffers from untrusted sources. The metadata tables stored as nested buffers like
+    ///the names are checked on every access and fail with `MemBufferError::WrongFormat` instead.
+    pub fn new_validated(val: &'a [u8]) -> Result<MemBufferReader<'a>,MemBufferError> {
+        let reader = MemBufferReader::new(val)?;
+        reader.check_positions()?;
+        reader.check_compression()?;
+        Ok(reader)
+    }
+
+    ///Creates a reader over the buffer embedded at `offset` with `len` bytes in a larger region,
+    ///for example a mapped archive. Fails with `MemBufferError::WrongFormat` if the range or any
+    ///entry of the embedded buffer does not lie within the region.
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferReader};
+    ///
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("embedded");
+    ///let buffer = writer.finalize();
+    ///
+    ///let mut archive = vec![0u8;16];
+    ///archive.extend_from_slice(&buffer);
+    ///let reader = MemBufferReader::subslice_reader(&archive,16,buffer.len()).unwrap();
+    ///assert_eq!(reader.load_entry::<&str>(0).unwrap(),"embedded");
+    ///assert!(MemBufferReader::subslice_reader(&archive,16,buffer.len()+1).is_err());
+    ///```
+    pub fn subslice_reader(region: &'a [u8], offset: usize, len: usize) -> Result<MemBufferReader<'a>,MemBufferError> {
+        let end = offset.checked_add(len).ok_or(MemBufferError::WrongFormat)?;
+        if end > region.len() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        MemBufferReader::new_validated(&region[offset..end])
+    }
+
+    ///Checks that every entry including the metadata entries lies within the payload
+    fn check_positions(&self) -> Result<(),MemBufferError> {
+        for entry in self.offsets.iter().chain(self.system.iter()) {
+            if entry.start > entry.end || entry.end > self.data.len() {
+                return Err(MemBufferError::WrongFormat);
+            }
+        }
+        Ok(())
+    }
+
+    ///Creates the reader from the parsed header, splitting off the metadata entries at the end
+    fn from_parts(table: EntryTable<'a>, data: &'a [u8], version: u32) -> MemBufferReader<'a> {
+        let visible = (0..table.len()).rev().find(|x| !is_system_type(table.get(*x).variable_type)).map_or(0,|x| x+1);
+        let (offsets,system) = table.split_at(visible);
+        MemBufferReader {
+            offsets,
+            system,
+            data,
+            max_entry_size: usize::MAX,
+            access_hook: None,
+            version,
+            transforms: None,
+            validated: None,
+            #[cfg(feature = "encryption")]
+            key: None,
         }
     }
 }
 
-impl<'a> std::fmt::Debug for MemBufferReader<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f,"Found memory buffer with payload size {}",self.data.len())
+impl<'a> core::fmt::Debug for MemBufferReader<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f,"Found memory buffer with payload size {}",self.payload_len())
     }
 }
 
 
 ///The Writer class which sets up the schema and writes it into the memory when finished building
+#[cfg(feature = "std")]
 pub struct MemBufferWriter {
     types: Vec<i32>,
-    data: Vec<Vec<u8>>
+    //Entry payloads are shared with the snapshots of the writer
+    data: Vec<alloc::sync::Arc<[u8]>>,
+    interned: Vec<String>,
+    intern_ids: std::collections::HashMap<String,i32>,
+    names: alloc::collections::BTreeMap<String,usize>,
+    counters: stats::WriteCounters,
+    field_checksums: bool,
+    checksum_algorithm: ChecksumAlgorithm,
+    large_offsets: bool,
+    placement: Vec<u64>,
+    byte_order: Endianness,
+    compression: alloc::collections::BTreeMap<usize,Compression>,
+    type_names: alloc::collections::BTreeMap<i32,String>,
+    content_types: alloc::collections::BTreeMap<usize,String>,
+    labels: alloc::collections::BTreeMap<usize,String>,
+    entry_metadata: alloc::collections::BTreeMap<usize,flags::EntryMetadata>,
+    changelog: Option<Vec<Change>>,
+    manifest: bool,
+    #[cfg(feature = "encryption")]
+    encryption: Option<EncryptionKey>,
+    //Expected payload size, reserved by finalize for buffers whose size is not known up front
+    payload_capacity: usize,
+    format_version: u32,
+    //Entries are checked against the schema when they are added or replaced
+    schema: Option<Schema>,
+    //Nested writers are serialized into the buffer when it is written, their payload is empty
+    nested: alloc::collections::BTreeMap<usize,MemBufferWriter>,
+    compact_header: bool,
+    dedup: bool,
+    spool: spool::SpoolSettings,
+    //Payloads read from streams which did not fit into memory, their payload is empty
+    spooled: alloc::collections::BTreeMap<usize,alloc::sync::Arc<spool::SpooledPayload>>,
 }
 
 pub trait MemBufferSerialize {
-    fn to_mem_buffer<'a>(&'a self) -> std::borrow::Cow<'a,[u8]>;
+    fn to_mem_buffer<'a>(&'a self) -> alloc::borrow::Cow<'a,[u8]>;
     fn get_mem_buffer_type() -> i32; 
 }
 
 impl MemBufferSerialize for &str {
-    fn to_mem_buffer<'a>(&'a self) -> std::borrow::Cow<'a,[u8]> {
-        std::borrow::Cow::Borrowed(self.as_bytes())
+    fn to_mem_buffer<'a>(&'a self) -> alloc::borrow::Cow<'a,[u8]> {
+        alloc::borrow::Cow::Borrowed(self.as_bytes())
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::Text.into()
+    }
+}
+
+impl MemBufferSerialize for String {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Borrowed(self.as_bytes())
     }
 
     fn get_mem_buffer_type() -> i32 {
@@ -286,7 +1108,7 @@ impl MemBufferSerialize for &String {
 
 impl MemBufferSerialize for i32 {
     fn to_mem_buffer<'a>(&'a self) -> Cow<'a, [u8]> {
-        Cow::Owned(unsafe{std::mem::transmute::<i32,[u8;4]>(*self)}.to_vec())
+        Cow::Owned(self.to_ne_bytes().to_vec())
     }
 
     fn get_mem_buffer_type() -> i32 {
@@ -297,11 +1119,31 @@ impl MemBufferSerialize for i32 {
 
 impl MemBufferSerialize for u64 {
     fn to_mem_buffer<'a>(&'a self) -> Cow<'a, [u8]> {
-        Cow::Owned(unsafe{std::mem::transmute::<u64,[u8;8]>(*self)}.to_vec())
+        Cow::Owned(self.to_ne_bytes().to_vec())
     }
 
     fn get_mem_buffer_type() -> i32 {
-        1021
+        MemBufferTypes::Integer64.into()
+    }
+}
+
+impl MemBufferSerialize for f32 {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a, [u8]> {
+        Cow::Owned(self.to_ne_bytes().to_vec())
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::Float32.into()
+    }
+}
+
+impl MemBufferSerialize for f64 {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a, [u8]> {
+        Cow::Owned(self.to_ne_bytes().to_vec())
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::Float64.into()
     }
 }
 
@@ -315,12 +1157,22 @@ impl MemBufferSerialize for &[u8] {
     }
 }
 
+impl MemBufferSerialize for Cow<'_,[u8]> {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Borrowed(self)
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::VectorU8.into()
+    }
+}
+
 impl MemBufferSerialize for &[u64] {
     fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
         let val: *const u64 = self.as_ptr();
         let cast_memory = val.cast::<u8>();
-        let mem_length = self.len() * std::mem::size_of::<u64>();
-        Cow::Borrowed(unsafe{ std::slice::from_raw_parts(cast_memory, mem_length)})
+        let mem_length = core::mem::size_of_val(*self);
+        Cow::Borrowed(unsafe{ core::slice::from_raw_parts(cast_memory, mem_length)})
     }
 
     fn get_mem_buffer_type() -> i32 {
@@ -328,12 +1180,34 @@ impl MemBufferSerialize for &[u64] {
     }
 }
 
+impl MemBufferSerialize for &[f32] {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        let mem_length = core::mem::size_of_val(*self);
+        Cow::Borrowed(unsafe{ core::slice::from_raw_parts(self.as_ptr().cast::<u8>(), mem_length)})
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::VectorF32.into()
+    }
+}
+
+impl MemBufferSerialize for &[f64] {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        let mem_length = core::mem::size_of_val(*self);
+        Cow::Borrowed(unsafe{ core::slice::from_raw_parts(self.as_ptr().cast::<u8>(), mem_length)})
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::VectorF64.into()
+    }
+}
+
 impl MemBufferSerialize for &[u32] {
     fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
         let val: *const u32 = self.as_ptr();
         let cast_memory = val.cast::<u8>();
-        let mem_length = self.len() * std::mem::size_of::<u32>();
-        Cow::Borrowed(unsafe{ std::slice::from_raw_parts(cast_memory, mem_length)})
+        let mem_length = core::mem::size_of_val(*self);
+        Cow::Borrowed(unsafe{ core::slice::from_raw_parts(cast_memory, mem_length)})
     }
 
     fn get_mem_buffer_type() -> i32 {
@@ -342,6 +1216,8 @@ impl MemBufferSerialize for &[u32] {
 }
 
 
+
+#[cfg(feature = "std")]
 impl MemBufferSerialize for MemBufferWriter {
     fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
         let ret = self.finalize();
@@ -353,15 +1229,112 @@ impl MemBufferSerialize for MemBufferWriter {
     }
 }
 
+
+#[cfg(feature = "std")]
+impl MemBufferSerialize for &MemBufferWriter {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Owned(self.finalize())
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::MemBuffer.into()
+    }
+}
+
+///Cloning is cheap as the payloads of the entries are shared, the clone starts with fresh write
+///counters
+#[cfg(feature = "std")]
+impl Clone for MemBufferWriter {
+    fn clone(&self) -> MemBufferWriter {
+        MemBufferWriter {
+            types: self.types.clone(),
+            data: self.data.clone(),
+            interned: self.interned.clone(),
+            intern_ids: self.intern_ids.clone(),
+            names: self.names.clone(),
+            counters: stats::WriteCounters::default(),
+            field_checksums: self.field_checksums,
+            checksum_algorithm: self.checksum_algorithm,
+            large_offsets: self.large_offsets,
+            placement: self.placement.clone(),
+            byte_order: self.byte_order,
+            compression: self.compression.clone(),
+            type_names: self.type_names.clone(),
+            content_types: self.content_types.clone(),
+            labels: self.labels.clone(),
+            entry_metadata: self.entry_metadata.clone(),
+            changelog: self.changelog.clone(),
+            manifest: self.manifest,
+            #[cfg(feature = "encryption")]
+            encryption: self.encryption.clone(),
+            payload_capacity: self.payload_capacity,
+            format_version: self.format_version,
+            schema: self.schema.clone(),
+            nested: self.nested.clone(),
+            compact_header: self.compact_header,
+            dedup: self.dedup,
+            spool: self.spool.clone(),
+            spooled: self.spooled.clone(),
+        }
+    }
+}
+
+
+#[cfg(feature = "std")]
+impl Default for MemBufferWriter {
+    fn default() -> MemBufferWriter {
+        MemBufferWriter::new()
+    }
+}
+
+
+#[cfg(feature = "std")]
 impl MemBufferWriter {
     ///Creates a new empty memory format writer
     pub fn new() -> MemBufferWriter {
         MemBufferWriter {
             types: Vec::new(),
-            data: Vec::new()
+            data: Vec::new(),
+            interned: Vec::new(),
+            intern_ids: std::collections::HashMap::new(),
+            names: alloc::collections::BTreeMap::new(),
+            counters: stats::WriteCounters::default(),
+            field_checksums: false,
+            checksum_algorithm: ChecksumAlgorithm::Crc32,
+            large_offsets: false,
+            placement: Vec::new(),
+            byte_order: Endianness::Native,
+            compression: alloc::collections::BTreeMap::new(),
+            type_names: alloc::collections::BTreeMap::new(),
+            content_types: alloc::collections::BTreeMap::new(),
+            labels: alloc::collections::BTreeMap::new(),
+            entry_metadata: alloc::collections::BTreeMap::new(),
+            changelog: None,
+            manifest: false,
+            #[cfg(feature = "encryption")]
+            encryption: None,
+            payload_capacity: 0,
+            format_version: FORMAT_VERSION,
+            schema: None,
+            nested: alloc::collections::BTreeMap::new(),
+            compact_header: false,
+            dedup: false,
+            spool: spool::SpoolSettings::default(),
+            spooled: alloc::collections::BTreeMap::new(),
         }
     }
 
+    ///Creates a writer with room for the given number of entries. The size of the written buffer
+    ///is calculated exactly by `finalize`, except for buffers with compressed entries, for them
+    ///`payload_bytes` is reserved instead.
+    pub fn with_capacity(fields: usize, payload_bytes: usize) -> MemBufferWriter {
+        let mut writer = MemBufferWriter::new();
+        writer.types.reserve(fields);
+        writer.data.reserve(fields);
+        writer.payload_capacity = payload_bytes;
+        writer
+    }
+
     ///Create a new Membuffer writer from the given memory, this will enable the writer to add
     ///more data to the previous version, to do so the writer does a full reload of the memory
     ///therefore it is an expensive operation if the structure adding fields to is huge.
@@ -387,82 +1360,507 @@ impl MemBufferWriter {
     /////for "Damn I forgot" 
     ///
     ///```
-    pub fn from<'a>(raw_memory: &'a [u8]) -> Result<MemBufferWriter,MemBufferError> {
-        let reader = MemBufferReader::new(raw_memory)?;
-        let mut types : Vec<i32> = Vec::new();
-        let mut data : Vec<Vec<u8>> = Vec::new();
-        for x in reader.offsets.iter() {
-            types.push(x.variable_type);
-            data.push(reader.data[x.pos.start as usize..x.pos.end as usize].to_vec())
+    pub fn from(raw_memory: &[u8]) -> Result<MemBufferWriter,MemBufferError> {
+        MemBufferWriter::from_reader(&MemBufferReader::new(raw_memory)?)
+    }
+
+    ///Creates a writer holding the entries and the settings of the buffer the reader refers to
+    pub(crate) fn from_reader(reader: &MemBufferReader) -> Result<MemBufferWriter,MemBufferError> {
+        let mut writer = MemBufferWriter::new();
+        for (index,x) in reader.offsets.iter().enumerate() {
+            let data = match writer.decoded_entry(reader,index)? {
+                Some(data) => data,
+                None => reader.entry_slice(x)?.to_vec(),
+            };
+            writer.counters.add_copied(data.len());
+            writer.types.push(x.variable_type);
+            writer.data.push(data.into());
         }
+        writer.load_settings(reader)?;
+        Ok(writer)
+    }
+
+    ///Decodes the entry of the reader into native byte order and takes over its compression, returns
+    ///None if the stored payload can be used as it is
+    pub(crate) fn decoded_entry(&mut self, reader: &MemBufferReader, index: usize) -> Result<Option<Vec<u8>>,MemBufferError> {
+        let x = reader.offsets.get(index);
+        //Decoded entries are already in native byte order
+        match reader.decompress_entry(index,reader.entry_slice(x)?)? {
+            Some(data) => {
+                if let Some(compression) = reader.compression_for_copy(index)? {
+                    self.compression.insert(index,compression);
+                }
+                Ok(Some(data))
+            },
+            None if reader.is_byte_swapped() && endian::swap_width(x.variable_type) > 1 => {
+                let mut data = reader.entry_slice(x)?.to_vec();
+                endian::swap_integers(&mut data,endian::swap_width(x.variable_type));
+                Ok(Some(data))
+            },
+            None => Ok(None),
+        }
+    }
 
-        Ok(MemBufferWriter {
-            types,
-            data
-        })
+    ///Takes over the metadata and the settings of the buffer the reader refers to, except for the
+    ///compression of the entries
+    pub(crate) fn load_settings(&mut self, reader: &MemBufferReader) -> Result<(),MemBufferError> {
+        self.load_intern_table(reader)?;
+        self.load_name_table(reader)?;
+        self.type_names = reader.type_names()?.into_iter().map(|(id,name)| (id,name.to_string())).collect();
+        self.load_content_types(reader)?;
+        self.load_labels(reader)?;
+        self.load_flags(reader)?;
+        if let Some((algorithm,field_checksums)) = reader.checksum_settings() {
+            self.checksum_algorithm = algorithm;
+            self.field_checksums = field_checksums;
+        }
+        self.load_changelog(reader)?;
+        self.manifest = reader.system_entry(SYSTEM_TYPE_MANIFEST).is_some();
+        self.format_version = reader.format_version();
+        self.compact_header = reader.offsets.is_compact();
+        self.dedup = MemBufferWriter::has_shared_payloads(reader);
+        Ok(())
     }
 
     ///Serializes the integer to the memory slice
     pub fn serialize_i32_to(val: i32, to: &mut Vec<u8>) {
-        to.write_i32::<NativeEndian>(val).unwrap();
+        to.extend_from_slice(&val.to_ne_bytes());
     }
 
     ///Adds an entry to the writer the only requirement is the serializable trait
     pub fn add_entry<T: MemBufferSerialize>(&mut self, val: T) {
+        self.add_entry_ref(&val);
+    }
+
+    ///Adds an entry from a reference, useful for values which are not `Copy`
+    pub fn add_entry_ref<T: MemBufferSerialize>(&mut self, val: &T) {
+        self.check_schema_field(self.types.len(),T::get_mem_buffer_type());
         let slice = val.to_mem_buffer();
         self.types.push(T::get_mem_buffer_type());
-        self.data.push(slice.to_vec());
+        self.data.push(slice[..].into());
+        self.log_payload(self.types.len()-1,Change::Add);
     }
 
-    pub fn set_entry<T: MemBufferSerialize>(&mut self, val: T, index: usize) {
-        self.data[index] = val.to_mem_buffer().to_vec();
+    ///Replaces the entry at the index, fails with `MemBufferError::IndexOutOfBounds` if there is no
+    ///entry at the index
+    pub fn set_entry<T: MemBufferSerialize>(&mut self, val: T, index: usize) -> Result<(),MemBufferError> {
+        if index >= self.types.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,self.types.len()));
+        }
+        self.replace_entry(val,index);
+        Ok(())
+    }
+
+    ///Replaces the entry at the index which has to exist
+    pub(crate) fn replace_entry<T: MemBufferSerialize>(&mut self, val: T, index: usize) {
+        self.check_schema_field(index,T::get_mem_buffer_type());
+        self.compression.remove(&index);
+        self.nested.remove(&index);
+        self.spooled.remove(&index);
+        self.data[index] = val.to_mem_buffer()[..].into();
         self.types[index] = T::get_mem_buffer_type();
+        self.log_payload(index,Change::Set);
     }
 
     pub fn load_entry<'a, T: MemBufferDeserialize<'a,T>+MemBufferSerialize>(&'a self, index: usize) -> Result<T,MemBufferError> {
+        if index >= self.types.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,self.types.len()));
+        }
+        if self.types[index] == MemBufferTypes::InternedText as i32 && T::get_mem_buffer_type() == MemBufferTypes::Text as i32 {
+            return T::from_mem_buffer(self.resolve_interned(&self.data[index])?);
+        }
         if T::get_mem_buffer_type() != self.types[index] {
             return Err(MemBufferError::FieldTypeError(self.types[index],T::get_mem_buffer_type()));
         }
-        return T::from_mem_buffer(&self.data[index]);
+        T::from_mem_buffer(&self.data[index])
     }
 
     pub fn len(&self) -> usize {
         self.types.len()
     }
 
-    ///Adds a serde serializable entry into the structure as serializer serde_json is used.
-    ///Internally it is saved as a string.
-    pub fn add_serde_entry<T: Serialize>(&mut self,val: &T) {
-        let as_str = bincode::serialize(val).unwrap();
-        self.add_entry(&as_str[..]);
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    ///Adds a serde serializable entry into the structure, it is serialized with bincode and saved
+    ///as byte slice. Fails with `MemBufferError::SerdeFailure` if the value cannot be serialized.
+    pub fn add_serde_entry<T: Serialize>(&mut self,val: &T) -> Result<(),MemBufferError> {
+        let as_bytes = bincode::serialize(val).map_err(|err| MemBufferError::SerdeFailure(err.to_string()))?;
+        self.add_entry(&as_bytes[..]);
+        Ok(())
+    }
+
+
+    ///Returns the metadata entries which are stored behind the user entries, the checksum entry
+    ///is added when the buffer is written
+    pub(crate) fn system_entries(&self) -> Vec<(i32,Vec<u8>)> {
+        let mut entries = Vec::new();
+        if let Some(table) = self.intern_table() {
+            entries.push((SYSTEM_TYPE_INTERN_TABLE,table));
+        }
+        if let Some(table) = self.name_table() {
+            entries.push((SYSTEM_TYPE_NAMES,table));
+        }
+        if let Some(table) = self.compression_table() {
+            entries.push((SYSTEM_TYPE_COMPRESSION,table));
+        }
+        if let Some(table) = self.type_table() {
+            entries.push((SYSTEM_TYPE_REGISTRY,table));
+        }
+        if let Some(table) = self.content_type_table() {
+            entries.push((SYSTEM_TYPE_CONTENT_TYPES,table));
+        }
+        if let Some(table) = self.label_table() {
+            entries.push((SYSTEM_TYPE_LABELS,table));
+        }
+        if let Some(table) = self.changelog_table() {
+            entries.push((SYSTEM_TYPE_CHANGELOG,table));
+        }
+        if let Some(table) = self.flag_table() {
+            entries.push((SYSTEM_TYPE_FLAGS,table));
+        }
+        #[cfg(feature = "encryption")]
+        if let Some(table) = self.key_check_table() {
+            entries.push((SYSTEM_TYPE_ENCRYPTION,table));
+        }
+        entries
     }
 
+    ///Calls the given function with the types and payloads of all entries to serialize, the
+    ///payloads are converted to the byte order of the writer before they are compressed. The
+    ///manifest and the checksum entry are empty and have to be filled with `write_manifest` and
+    ///`write_checksums`
+    fn with_all_entries<R>(&self, func: impl FnOnce(&[i32],&[&[u8]]) -> R) -> R {
+        let mut system = self.system_entries();
+        if self.manifest {
+            system.push((SYSTEM_TYPE_MANIFEST,self.manifest_placeholder(self.types.len()+system.len())));
+        }
+        system.push((SYSTEM_TYPE_CHECKSUM,self.checksum_placeholder()));
+        let types: Vec<i32> = self.types.iter().copied().chain(system.iter().map(|x| x.0)).collect();
+        let stored: Vec<Cow<[u8]>> = (0..self.data.len()).map(|index| {
+            let width = endian::swap_width(self.types[index]);
+            match self.entry_payload(index) {
+                Cow::Borrowed(x) if !self.byte_order.is_swapped() || width == 1 => self.stored_payload(index,x),
+                payload => {
+                    let mut x = payload.into_owned();
+                    if self.byte_order.is_swapped() {
+                        endian::swap_integers(&mut x,width);
+                    }
+                    Cow::Owned(self.stored_payload(index,&x).into_owned())
+                },
+            }
+        }).collect();
+        let data: Vec<&[u8]> = stored.iter().map(|x| &x[..]).chain(system.iter().map(|x| &x.1[..])).collect();
+        func(&types,&data)
+    }
 
     ///Finalize the schema and return the memory slice holding the whole vector
     pub fn finalize(&self) -> Vec<u8> {
-        let mut var: Vec<u8> = Vec::with_capacity(10_000_000);
-        MemBufferWriter::serialize_i32_to(self.types.len() as i32,&mut var);
-        MemBufferWriter::serialize_i32_to((std::num::Wrapping(self.types.len() as i32)-std::num::Wrapping(0x7AFECAFE as i32)).0,&mut var);
-        let mut offset = 0;
-        for val in 0..self.types.len() {
-            MemBufferWriter::serialize_i32_to(offset as i32, &mut var);
-            MemBufferWriter::serialize_i32_to(self.data[val].len() as i32+offset as i32, &mut var);
-            MemBufferWriter::serialize_i32_to(self.types[val], &mut var);
-            offset+=self.data[val].len();
-        }
-        for x in self.data.iter() {
-            var.extend_from_slice(x);
-        }
+        let mut var: Vec<u8> = Vec::with_capacity(self.finalize_capacity());
+        //Writing into a vector cannot fail
+        self.finalize_into(&mut var).unwrap();
         var
     }
+
+    ///Returns the length of the buffer `finalize` writes, compressed entries are compressed to
+    ///calculate it
+    ///```rust
+    ///use membuffer::MemBufferWriter;
+    ///
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("Hello");
+    ///let mut out = Vec::with_capacity(writer.serialized_len());
+    ///writer.finalize_into(&mut out).unwrap();
+    ///assert_eq!(out.len(),writer.serialized_len());
+    ///```
+    pub fn serialized_len(&self) -> usize {
+        let system = self.system_entries();
+        let types: Vec<i32> = self.types.iter().copied().chain(system.iter().map(|x| x.0)).collect();
+        let mut payloads = self.payloads();
+        payloads.extend(system.iter().map(|x| Payload::Bytes(&x.1)));
+        //Payloads in memory are compressed without any IO
+        self.compress_payloads(&types,&mut payloads).unwrap();
+        let lens = payloads.iter().enumerate().map(|(index,x)| self.sealed_len(index,x.len())).collect();
+        let (types,lens) = self.with_trailing_entries(&types,lens);
+        let order = self.placement_order(types.len());
+        let shared = self.shared_payloads(&types,&payloads,&order);
+        let (header,starts) = self.serialize_header(&types,&lens,&order,&shared);
+        header.len()+starts[types.len()-1]+lens[types.len()-1]
+    }
+
+    ///Returns the capacity to reserve for the written buffer, compressing the entries only to
+    ///know the size would double the work
+    pub(crate) fn finalize_capacity(&self) -> usize {
+        if self.compression.is_empty() {
+            self.serialized_len()
+        }
+        else {
+            self.payload_capacity
+        }
+    }
+
+    ///Returns whether the payload of the entry is compressed or encrypted when the buffer is
+    ///written, these payloads are converted into the byte order of the buffer before
+    pub(crate) fn is_encoded(&self, index: usize) -> bool {
+        self.is_compressed(index) || self.is_sealed(index)
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    pub(crate) fn is_sealed(&self, _index: usize) -> bool {
+        false
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    fn sealed_len(&self, _index: usize, len: usize) -> usize {
+        len
+    }
+
+    ///Appends the manifest if requested and the checksum entry to the types and lengths of the
+    ///entries to write, both are placed behind all other entries
+    fn with_trailing_entries(&self, types: &[i32], mut lens: Vec<usize>) -> (Vec<i32>,Vec<usize>) {
+        let mut types = types.to_vec();
+        if self.manifest {
+            lens.push(self.manifest_placeholder(types.len()).len());
+            types.push(SYSTEM_TYPE_MANIFEST);
+        }
+        lens.push(self.checksum_placeholder().len());
+        types.push(SYSTEM_TYPE_CHECKSUM);
+        (types,lens)
+    }
+
+    ///Finalize the schema and write the buffer into the given writer without building it in
+    ///memory first, returns the number of bytes written
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferReader};
+    ///
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("Hello");
+    ///
+    ///let mut file = std::io::Cursor::new(Vec::new());
+    ///let written = writer.finalize_into(&mut file).unwrap();
+    ///assert_eq!(written as usize,file.get_ref().len());
+    ///assert_eq!(file.into_inner(),writer.finalize());
+    ///```
+    pub fn finalize_into<W: std::io::Write>(&self, w: W) -> std::io::Result<u64> {
+        let system = self.system_entries();
+        let types: Vec<i32> = self.types.iter().copied().chain(system.iter().map(|x| x.0)).collect();
+        let mut payloads = self.payloads();
+        payloads.extend(system.iter().map(|x| Payload::Bytes(&x.1)));
+        self.write_entries(&types,payloads,w)
+    }
+
+    ///Writes the header, the given entries, the manifest if requested and the checksum entry
+    ///behind them, the manifest and the checksums are calculated while the payloads are written
+    pub(crate) fn write_entries<W: std::io::Write>(&self, types: &[i32], mut payloads: Vec<Payload>, mut w: W) -> std::io::Result<u64> {
+        self.compress_payloads(types,&mut payloads)?;
+        #[cfg(feature = "encryption")]
+        self.encrypt_payloads(types,&mut payloads)?;
+        let (types,lens) = self.with_trailing_entries(types,payloads.iter().map(|x| x.len()).collect());
+        let checksum_len = lens[lens.len()-1];
+        let manifest_len = if self.manifest { Some(lens[lens.len()-2]) } else { None };
+
+        let order = self.placement_order(types.len());
+        let shared = self.shared_payloads(&types,&payloads,&order);
+        let (header,starts) = self.serialize_header(&types,&lens,&order,&shared);
+        let header_len = header.len();
+        #[cfg(feature = "encryption")]
+        self.seal_key_check_payload(&types,&lens,&starts,&mut payloads);
+
+        let algorithm = self.checksum_algorithm;
+        let mut header_hasher = algorithm.hasher();
+        header_hasher.update(&header[8..]);
+        let mut checksums = checksum::Checksums {
+            algorithm,
+            header: header_hasher.finalize(),
+            payload: Vec::new(),
+            fields: Vec::new(),
+        };
+        w.write_all(&header)?;
+
+        let padding = [0u8;8];
+        let mut position = 0;
+        let mut payload_hasher = algorithm.hasher();
+        let mut payloads: Vec<Option<Payload>> = payloads.into_iter().map(Some).collect();
+        checksums.fields = vec![Vec::new();if self.field_checksums { self.types.len() } else { 0 }];
+        let mut manifest = manifest::Manifest {
+            algorithm,
+            records: Vec::new(),
+        };
+        //The manifest and the checksum entry are always placed last
+        let trailing = if self.manifest { 2 } else { 1 };
+        //Digests of the written payloads, entries sharing a payload take the digest of its entry
+        let mut digests = vec![None;if self.dedup { self.types.len() } else { 0 }];
+        for x in order[..order.len()-trailing].iter().copied() {
+            let digest = match shared[x] {
+                Some(first) => digests[first].clone(),
+                None => {
+                    let mut payload = payloads[x].take().unwrap();
+                    if self.byte_order.is_swapped() && endian::swap_width(types[x]) > 1 && !self.is_encoded(x) {
+                        payload = payload.swapped(endian::swap_width(types[x]))?;
+                    }
+                    w.write_all(&padding[..starts[x]-position])?;
+                    let mut field_hasher = if (self.field_checksums && x < self.types.len()) || self.manifest { Some(algorithm.hasher()) } else { None };
+                    payload.write_to(&mut w,|chunk| {
+                        payload_hasher.update(chunk);
+                        if let Some(hasher) = field_hasher.as_mut() {
+                            hasher.update(chunk);
+                        }
+                    })?;
+                    position = starts[x]+lens[x];
+                    field_hasher.map(|x| x.finalize())
+                },
+            };
+            if let Some(digest) = digest {
+                if self.field_checksums && x < self.types.len() {
+                    checksums.fields[x] = digest.clone();
+                }
+                if self.manifest {
+                    manifest.records.push(manifest::ManifestRecord {
+                        variable_type: types[x],
+                        len: lens[x],
+                        digest: digest.clone(),
+                    });
+                }
+                if x < digests.len() {
+                    digests[x] = Some(digest);
+                }
+            }
+        }
+        if let Some(manifest_len) = manifest_len {
+            //The records were collected in the order the entries were placed in
+            let index = types.len()-2;
+            let mut placed: Vec<(usize,manifest::ManifestRecord)> = order[..index].iter().copied().zip(manifest.records.drain(..)).collect();
+            placed.sort_by_key(|x| x.0);
+            manifest.records = placed.into_iter().map(|x| x.1).collect();
+            let mut stored = vec![0u8;manifest_len];
+            manifest.serialize(&mut stored,self.byte_order.is_swapped());
+            payload_hasher.update(&stored);
+            w.write_all(&padding[..starts[index]-position])?;
+            w.write_all(&stored)?;
+            position = starts[index]+manifest_len;
+        }
+        checksums.payload = payload_hasher.finalize();
+
+        let last = types.len()-1;
+        let mut stored = vec![0u8;checksum_len];
+        checksums.serialize(&mut stored,self.byte_order.is_swapped());
+        w.write_all(&padding[..starts[last]-position])?;
+        w.write_all(&stored)?;
+
+        let written = header_len+starts[last]+checksum_len;
+        self.counters.add_written(written);
+        Ok(written as u64)
+    }
+}
+
+///Payload of one entry while the buffer is written
+#[cfg(feature = "std")]
+pub(crate) enum Payload<'x> {
+    Bytes(&'x [u8]),
+    Owned(Vec<u8>),
+    Reader(Box<dyn std::io::Read + 'x>,usize),
+    ///Nested writer which is serialized straight into the output
+    Nested(&'x MemBufferWriter),
+}
+
+///Passes every chunk written to a function before forwarding it, both are trait objects as nested
+///writers would otherwise instantiate the writing code for every level of nesting
+#[cfg(feature = "std")]
+struct ChunkWriter<'w> {
+    w: &'w mut dyn std::io::Write,
+    on_chunk: &'w mut dyn FnMut(&[u8]),
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for ChunkWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.w.write(buf)?;
+        (self.on_chunk)(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.w.flush()
+    }
+}
+
+
+#[cfg(feature = "std")]
+impl<'x> Payload<'x> {
+    fn len(&self) -> usize {
+        match self {
+            Payload::Bytes(x) => x.len(),
+            Payload::Owned(x) => x.len(),
+            Payload::Reader(_,len) => *len,
+            Payload::Nested(x) => x.serialized_len(),
+        }
+    }
+
+    ///Returns the payload with the bytes of every integer of the given width swapped, streamed
+    ///payloads are read completely for this
+    fn swapped(self, width: usize) -> std::io::Result<Payload<'x>> {
+        let mut data = self.into_vec()?;
+        endian::swap_integers(&mut data,width);
+        Ok(Payload::Owned(data))
+    }
+
+    ///Returns the payload as owned bytes, streamed payloads are read completely for this
+    fn into_vec(self) -> std::io::Result<Vec<u8>> {
+        match self {
+            Payload::Bytes(x) => Ok(x.to_vec()),
+            Payload::Owned(x) => Ok(x),
+            Payload::Nested(x) => Ok(x.finalize()),
+            Payload::Reader(reader,len) => {
+                let mut data = Vec::with_capacity(len);
+                Payload::Reader(reader,len).write_to(&mut data,|_| ())?;
+                Ok(data)
+            }
+        }
+    }
+
+    ///Writes the payload and calls the given function with every chunk written
+    fn write_to<W: std::io::Write>(self, w: &mut W, mut on_chunk: impl FnMut(&[u8])) -> std::io::Result<()> {
+        match self {
+            Payload::Bytes(x) => {
+                on_chunk(x);
+                w.write_all(x)
+            },
+            Payload::Owned(x) => {
+                on_chunk(&x);
+                w.write_all(&x)
+            },
+            Payload::Reader(mut reader,len) => {
+                let mut buffer = vec![0u8;core::cmp::min(len,1<<16)];
+                let mut remaining = len;
+                while remaining > 0 {
+                    let chunk = core::cmp::min(remaining,buffer.len());
+                    let read = reader.read(&mut buffer[..chunk])?;
+                    if read == 0 {
+                        return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof,"Memory buffer error: Entry reader ended before the announced length"));
+                    }
+                    on_chunk(&buffer[..read]);
+                    w.write_all(&buffer[..read])?;
+                    remaining -= read;
+                }
+                Ok(())
+            },
+            Payload::Nested(x) => {
+                let mut chunks = ChunkWriter { w, on_chunk: &mut on_chunk };
+                x.finalize_into(&mut chunks as &mut dyn std::io::Write)?;
+                Ok(())
+            }
+        }
+    }
 }
 
 
 
 #[cfg(test)]
 mod tests {
-    use super::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferTypes,MemBufferSerialize};
+    use super::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferTypes,MemBufferSerialize,MemBufferDeserialize};
     use serde::{Serialize,Deserialize};
+    use std::borrow::Cow;
 
     #[derive(Serialize,Deserialize)]
     struct HeavyStruct {
@@ -477,7 +1875,7 @@ mod tests {
         let mut writer = MemBufferWriter::new();
         writer.add_entry("Der moderne Prometheus");
         writer.add_entry("Dies hier ist nur ein Satz");
-        writer.add_entry::<&[u64]>(&vec![0,1,2,3,4,5]);
+        writer.add_entry::<&[u64]>(&[0,1,2,3,4,5]);
 
         let result = writer.finalize();
 
@@ -491,7 +1889,7 @@ mod tests {
     #[test]
     fn check_vec32() {
         let mut writer = MemBufferWriter::new();
-        writer.add_entry::<&[u32]>(&vec![0,1,2,3,4,5]);
+        writer.add_entry::<&[u32]>(&[0,1,2,3,4,5]);
 
         let result = writer.finalize();
 
@@ -508,7 +1906,8 @@ mod tests {
         assert_eq!(<i32 as MemBufferSerialize>::get_mem_buffer_type(),MemBufferTypes::Integer32 as i32);
         assert_eq!(<&[u8] as MemBufferSerialize>::get_mem_buffer_type(),MemBufferTypes::VectorU8 as i32);
         assert_eq!(<&[u64] as MemBufferSerialize>::get_mem_buffer_type(),MemBufferTypes::VectorU64 as i32);
-        assert_eq!(MemBufferWriter::get_mem_buffer_type(),MemBufferTypes::MemBuffer as i32);
+        assert_eq!(<MemBufferWriter as MemBufferSerialize>::get_mem_buffer_type(),MemBufferTypes::MemBuffer as i32);
+        assert_eq!(<&MemBufferWriter as MemBufferSerialize>::get_mem_buffer_type(),MemBufferTypes::MemBuffer as i32);
     }
 
     #[test]
@@ -516,14 +1915,14 @@ mod tests {
         let mut writer = MemBufferWriter::new();
         writer.add_entry("Der moderne Prometheus");
         writer.add_entry("Dies hier ist nur ein Satz");
-        writer.add_entry::<&[u64]>(&vec![0,1,2,3,4,5]);
+        writer.add_entry::<&[u64]>(&[0,1,2,3,4,5]);
 
         let mut result = writer.finalize();
         result[0] = 100;
 
 
         let reader = MemBufferReader::new(&result);
-        assert_eq!(reader.is_err(),true);
+        assert!(reader.is_err());
     }
 
     #[test]
@@ -541,20 +1940,20 @@ mod tests {
         let positions = &reader.offsets;
 
         assert_eq!(positions.len(),3);
-        let zero = &positions[0];
+        let zero = positions.get(0);
         assert_eq!(zero.variable_type,MemBufferTypes::Text as i32);
-        assert_eq!(zero.pos.start,0);
-        assert_eq!(zero.pos.end - zero.pos.start,str1.as_bytes().len() as i32);
+        assert_eq!(zero.start,0);
+        assert_eq!(zero.end - zero.start,str1.len());
 
-        let one = &positions[1];
+        let one = positions.get(1);
         assert_eq!(one.variable_type,MemBufferTypes::Text as i32);
-        assert_eq!(one.pos.start,str1.as_bytes().len() as i32);
-        assert_eq!(one.pos.end - one.pos.start,str2.as_bytes().len() as i32);
+        assert_eq!(one.start,str1.len());
+        assert_eq!(one.end - one.start,str2.len());
 
-        let two = &positions[2];
+        let two = positions.get(2);
         assert_eq!(two.variable_type,MemBufferTypes::Text as i32);
-        assert_eq!(two.pos.start as usize,str1.as_bytes().len() + str2.as_bytes().len());
-        assert_eq!(two.pos.end - two.pos.start,str3.as_bytes().len() as i32);
+        assert_eq!(two.start,str1.len() + str2.len());
+        assert_eq!(two.end - two.start,str3.len());
 
         assert_eq!(reader.load_entry::<&str>(2).unwrap(),str3);
     }
@@ -568,7 +1967,7 @@ mod tests {
             id: 200,
         };
         let mut writer = MemBufferWriter::new();
-        writer.add_serde_entry(&value);
+        writer.add_serde_entry(&value).unwrap();
         let result = writer.finalize();
  
         let reader = MemBufferReader::new(&result).unwrap();
@@ -578,6 +1977,7 @@ mod tests {
         assert_eq!(struc.name,"membuffer!");
         assert_eq!(struc.frequency,10);
         assert_eq!(struc.id,200);
+        assert!(matches!(reader.load_serde_entry::<(HeavyStruct,HeavyStruct)>(0),Err(MemBufferError::SerdeFailure(_))));
     }
 
     #[test]
@@ -604,7 +2004,6 @@ mod tests {
         assert_eq!(reader.load_entry::<&[u8]>(0).unwrap(), vec![100,200,100,200,1,2,3,4,5,6,7,8,9,10]);
     }
 
-    #[should_panic]
     #[test]
     fn check_wrong_key() {
         let mut writer = MemBufferWriter::new();
@@ -615,8 +2014,7 @@ mod tests {
 
         let reader = MemBufferReader::new(&result).unwrap();
         assert_eq!(reader.load_entry::<&[u64]>(0).unwrap(), vec![100,200,100,200,1,2,3,4,5,6,7,8,9,10]);
-        //TODO check index overflow
-        assert_eq!(reader.load_entry::<&[u64]>(3).unwrap(), vec![100,200,100,200,1,2,3,4,5,6,7,8,9,10]);
+        assert!(matches!(reader.load_entry::<&[u64]>(3),Err(MemBufferError::IndexOutOfBounds(3,2))));
     }
 
     #[test]
@@ -682,7 +2080,7 @@ mod tests {
         let writer = MemBufferWriter::new();
         let result = writer.finalize();
         let reader = MemBufferReader::new(&result[0..1]);
-        assert_eq!(reader.is_err(),true);
+        assert!(reader.is_err());
         println!("Error: {}",reader.unwrap_err());
     }
 
@@ -691,20 +2089,20 @@ mod tests {
     fn check_payload_len() {
         let mut writer = MemBufferWriter::new();
         let some_bytes = "Hello how are you?";
-        writer.add_entry(&some_bytes[..]);
-        writer.add_entry(&some_bytes[..]);
-        writer.add_entry(&some_bytes[..]);
+        writer.add_entry(some_bytes);
+        writer.add_entry(some_bytes);
+        writer.add_entry(some_bytes);
         let result = writer.finalize();
 
         let reader = MemBufferReader::new(&result).unwrap();
-        assert_eq!(reader.payload_len(), some_bytes.as_bytes().len()*3);
+        assert_eq!(reader.payload_len(), some_bytes.len()*3);
     }
 
     #[test]
     fn check_recursive_readers() {
         let mut writer = MemBufferWriter::new();
         let some_bytes = "Hello how are you?";
-        writer.add_entry(&some_bytes[..]);
+        writer.add_entry(some_bytes);
 
         let mut writer2 = MemBufferWriter::new();
         writer2.add_entry(some_bytes);
@@ -717,12 +2115,12 @@ mod tests {
         assert_eq!(reader.len(), 2);
         assert_eq!(reader.load_entry::<&str>(0).unwrap(), "Hello how are you?");
         let second = reader.load_recursive_reader(1);
-        assert_eq!(second.is_err(),false);
+        assert!(second.is_ok());
         let reader2 = second.unwrap();
         assert_eq!(reader2.len(), 1);
         assert_eq!(reader2.load_entry::<&str>(0).unwrap(), "Hello how are you?");
 
-        assert_eq!(reader.load_recursive_reader(0).is_err(),true);
+        assert!(reader.load_recursive_reader(0).is_err());
     }
 
     #[test]
@@ -732,14 +2130,15 @@ mod tests {
         let result = writer.finalize();
 
         let reader = MemBufferReader::new(&result[1..]);
-        assert_eq!(reader.is_err(),true);
+        assert!(reader.is_err());
     }
 
     #[test]
     fn check_mem_set_entry() {
         let mut writer = MemBufferWriter::new();
         writer.add_entry("earth");
-        writer.set_entry("cool", 0);
+        writer.set_entry("cool", 0).unwrap();
+        assert!(matches!(writer.set_entry("moon", 1),Err(MemBufferError::IndexOutOfBounds(1,1))));
         let result = writer.finalize();
 
         let reader = MemBufferReader::new(&result).unwrap();
@@ -762,7 +2161,7 @@ mod tests {
         let result = writer.finalize();
 
         let reader = MemBufferReader::new(&result);
-        assert_eq!(reader.is_err(),false);
+        assert!(reader.is_ok());
         let err = reader.unwrap().load_entry::<i32>(0).unwrap_err();
         if let MemBufferError::FieldTypeError(x,y) = err {
                 println!("Error {} ",MemBufferError::FieldTypeError(x,y));
@@ -772,6 +2171,223 @@ mod tests {
     }
 
 
+    #[test]
+    fn check_max_entry_size() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Earth");
+        writer.add_entry("Hello how are you?");
+        let mut nested = MemBufferWriter::new();
+        nested.add_entry("Hello how are you?");
+        writer.add_entry(nested);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap().with_max_entry_size(5);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Earth");
+        if let MemBufferError::EntryTooLarge(x,y) = reader.load_entry::<&str>(1).unwrap_err() {
+            assert_eq!(x,18);
+            assert_eq!(y,5);
+        }
+        else {
+            panic!("Expected entry too large error");
+        }
+        assert!(reader.load_recursive_reader(2).is_err());
+
+        let reader = MemBufferReader::new(&result).unwrap().with_max_entry_size(100);
+        let nested = reader.load_recursive_reader(2).unwrap().with_max_entry_size(10);
+        assert!(nested.load_entry::<&str>(0).is_err());
+    }
+
+    #[test]
+    fn check_uncompressed_len() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Earth");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.uncompressed_len(0).unwrap(),5);
+        assert_eq!(reader.uncompressed_len(1).unwrap(),24);
+        assert!(reader.uncompressed_len(2).is_err());
+    }
+
+    #[test]
+    fn check_empty_entries() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("");
+        writer.add_entry::<&[u8]>(&[]);
+        writer.add_entry::<&[u32]>(&[]);
+        writer.add_entry::<&[u64]>(&[]);
+        writer.add_entry("after");
+        writer.add_entry::<&[u64]>(&[]);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.len(),6);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"");
+        assert!(reader.load_entry::<&[u8]>(1).unwrap().is_empty());
+        assert!(reader.load_entry::<&[u32]>(2).unwrap().is_empty());
+        assert!(reader.load_entry::<&[u64]>(3).unwrap().is_empty());
+        assert_eq!(reader.load_entry::<&str>(4).unwrap(),"after");
+        assert!(reader.load_entry::<&[u64]>(5).unwrap().is_empty());
+
+        let interleaved = writer.finalize_interleaved();
+        let reader = MemBufferReader::new(&interleaved).unwrap();
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"");
+        assert!(reader.load_entry::<&[u64]>(5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn check_validated_reader() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry("World");
+        let mut result = writer.finalize();
+        assert!(matches!(writer.load_entry::<&str>(2),Err(MemBufferError::IndexOutOfBounds(2,2))));
+
+        let reader = MemBufferReader::new_validated(&result).unwrap();
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"World");
+        assert!(matches!(reader.load_entry::<&str>(2),Err(MemBufferError::IndexOutOfBounds(2,2))));
+        assert!(matches!(reader.load_recursive_reader(7),Err(MemBufferError::IndexOutOfBounds(7,2))));
+
+        //Let the end of the second entry point far behind the payload
+        result[8+12+4..8+12+8].copy_from_slice(&1_000_000i32.to_ne_bytes());
+        assert!(matches!(MemBufferReader::new_validated(&result),Err(MemBufferError::WrongFormat)));
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert!(matches!(reader.load_entry::<&str>(1),Err(MemBufferError::WrongFormat)));
+    }
+
+    #[test]
+    fn check_validated_metadata() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry_named("title","Frankenstein");
+        writer.add_entry_labeled("Victor","characters");
+        writer.add_entry_with_content_type("{}","application/json");
+        writer.add_entry_with_flags(&[1u8,2][..],7);
+        writer.add_interned_entry("Geneva");
+        let result = writer.finalize();
+        let reader = MemBufferReader::new(&result).unwrap();
+        let metadata = result.len()-reader.data.len()+reader.system.iter().map(|x| x.start).min().unwrap();
+
+        //The metadata tables are nested buffers whose positions are checked on every access
+        for pos in metadata..result.len() {
+            let mut corrupted = result.clone();
+            corrupted[pos] ^= 0xFF;
+            let reader = match MemBufferReader::new_validated(&corrupted) {
+                Ok(reader) => reader,
+                Err(_) => continue,
+            };
+            let _ = reader.load_entry_by_name::<&str>("title");
+            let _ = reader.load_entry::<&str>(4);
+            let _ = reader.stats_by_label();
+            for index in 0..reader.len() {
+                let _ = (reader.label(index),reader.content_type(index),reader.entry_flags(index),reader.load_value(index));
+            }
+        }
+    }
+
+    #[test]
+    fn check_serialized_len() {
+        let writers = vec![
+            MemBufferWriter::new(),
+            MemBufferWriter::with_capacity(4,100),
+            MemBufferWriter::new().with_large_offsets().with_field_checksums(),
+            MemBufferWriter::new().with_manifest().with_byte_order(crate::Endianness::Big),
+        ];
+        for mut writer in writers {
+            assert_eq!(writer.serialized_len(),writer.finalize().len());
+            writer.add_entry("Hello");
+            writer.add_entry::<&[u64]>(&[1,2,3]);
+            writer.add_interned_entry("label");
+            writer.add_entry_named("answer",42);
+            writer.set_placement_priorities(&[0,5]);
+            let data = writer.finalize();
+            assert_eq!(writer.serialized_len(),data.len());
+            assert_eq!(data.capacity(),data.len());
+        }
+
+        let mut writer = MemBufferWriter::with_capacity(1,64);
+        writer.add_entry_compressed("compressed ".repeat(100),crate::Compression::None);
+        assert_eq!(writer.serialized_len(),writer.finalize().len());
+        #[cfg(feature = "lz4")]
+        {
+            writer.add_entry_compressed("compressed ".repeat(100),crate::Compression::Lz4);
+            assert_eq!(writer.serialized_len(),writer.finalize().len());
+            assert!(writer.serialized_len() < 2*1100);
+        }
+    }
+
+    #[test]
+    fn check_extract_nested() {
+        let mut nested = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        nested.add_entry("inner");
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("outer");
+        writer.add_entry(&nested);
+        writer.add_entry_compressed(&nested,crate::Compression::None);
+        writer.add_entry(&[1u8,2,3][..]);
+        writer.types.push(MemBufferTypes::MemBuffer as i32);
+        writer.data.push(vec![1,2,3].into());
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        let extracted = reader.extract_nested(1).unwrap();
+        assert_eq!(extracted,&nested.finalize()[..]);
+        assert!(data.as_ptr_range().contains(&extracted.as_ptr()));
+        assert_eq!(MemBufferReader::new(extracted).unwrap().load_entry::<String>(0).unwrap(),"inner");
+        //Entries stored without a codec stay valid buffers
+        assert_eq!(reader.extract_nested(2).unwrap(),extracted);
+        assert!(matches!(reader.extract_nested(3),Err(MemBufferError::FieldTypeError(_,_))));
+        assert!(matches!(reader.extract_nested(4),Err(MemBufferError::WrongFormat)));
+    }
+
+    #[test]
+    fn check_access_hook() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry_named("answer",42);
+        let result = writer.finalize();
+
+        let accesses = std::sync::Mutex::new(Vec::new());
+        let hook = |index: usize, variable_type: i32, len: usize| accesses.lock().unwrap().push((index,variable_type,len));
+        let reader = MemBufferReader::new(&result).unwrap().with_access_hook(&hook);
+        reader.load_entry::<&str>(0).unwrap();
+        assert!(reader.load_entry::<i32>(0).is_err());
+        reader.load_entry_by_name::<i32>("answer").unwrap();
+        assert_eq!(*accesses.lock().unwrap(),vec![(0,MemBufferTypes::Text as i32,5),(0,MemBufferTypes::Text as i32,5),(1,MemBufferTypes::Integer32 as i32,4)]);
+    }
+
+    #[test]
+    fn check_subslice_reader() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        let buffer = writer.finalize();
+        let mut region = vec![0xAAu8;8];
+        region.extend_from_slice(&buffer);
+        region.extend_from_slice(&[0xBB;8]);
+
+        let reader = MemBufferReader::subslice_reader(&region,8,buffer.len()).unwrap();
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+        assert_eq!(reader.load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+        assert!(MemBufferReader::subslice_reader(&region,usize::MAX,2).is_err());
+        assert!(MemBufferReader::subslice_reader(&region,region.len(),1).is_err());
+        //Cutting off the end leaves entries pointing outside of the range
+        assert!(MemBufferReader::subslice_reader(&region,8,buffer.len()-1).is_err());
+    }
+
+    #[test]
+    fn check_empty_scalars() {
+        assert!(<i32 as MemBufferDeserialize<i32>>::from_mem_buffer(&[]).is_err());
+        assert!(<u64 as MemBufferDeserialize<u64>>::from_mem_buffer(&[1,2,3,4]).is_err());
+        assert!(<&[u32] as MemBufferDeserialize<&[u32]>>::from_mem_buffer(&[]).unwrap().is_empty());
+
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry(MemBufferWriter::new());
+        let result = writer.finalize();
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert!(reader.load_recursive_reader(0).unwrap().is_empty());
+    }
+
     #[test]
     fn check_serialize_i32_deserialize() {
         let mut writer = MemBufferWriter::new();
@@ -781,6 +2397,59 @@ mod tests {
         let reader = MemBufferReader::new(&result).unwrap();
         assert_eq!(reader.load_entry::<i32>(0).unwrap(), 100);
     }
+
+    #[test]
+    fn check_floats() {
+        let embedding = [0.5f32,-1.25,3.0];
+        let samples = [0.1f64,0.2];
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry(1.5f32);
+        writer.add_entry(-2.25f64);
+        writer.add_entry(&embedding[..]);
+        writer.add_entry(&samples[..]);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.load_entry::<f32>(0).unwrap(),1.5);
+        assert_eq!(reader.load_entry::<f64>(1).unwrap(),-2.25);
+        assert_eq!(reader.load_entry::<&[f32]>(2).unwrap(),&embedding);
+        assert_eq!(reader.load_entry::<&[f64]>(3).unwrap(),&samples);
+        assert!(matches!(reader.load_entry::<&[u32]>(2),Err(MemBufferError::FieldTypeError(_,_))));
+
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry(1.5f32);
+        writer.add_entry(&samples[..]);
+        let result = writer.finalize();
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.load_entry::<f32>(0).unwrap(),1.5);
+        assert_eq!(reader.load_entry::<Cow<[f64]>>(1).unwrap(),&samples[..]);
+    }
+
+    #[test]
+    fn check_unaligned_slices() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry(Cow::<[u32]>::Owned(vec![4,5]));
+        let result = writer.finalize();
+
+        //Copy the buffer to an address which is not aligned for the integers
+        let mut storage = vec![0u64;result.len()/8+2];
+        let bytes = unsafe{ std::slice::from_raw_parts_mut(storage.as_mut_ptr().cast::<u8>().add(1),result.len()) };
+        bytes.copy_from_slice(&result);
+        let reader = MemBufferReader::new(bytes).unwrap();
+        assert!(matches!(reader.load_entry::<&[u64]>(0),Err(MemBufferError::UnalignedEntry)));
+        assert!(matches!(reader.load_entry::<&[u32]>(1),Err(MemBufferError::UnalignedEntry)));
+        assert!(matches!(reader.load_entry::<Cow<[u64]>>(0).unwrap(),Cow::Owned(x) if x == [1,2,3]));
+        assert_eq!(reader.load_entry::<Cow<[u32]>>(1).unwrap(),&[4,5][..]);
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert!(matches!(reader.load_entry::<Cow<[u64]>>(0).unwrap(),Cow::Borrowed(&[1,2,3])));
+
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        let result = writer.finalize();
+        assert_eq!(MemBufferReader::new(&result).unwrap().load_entry::<Cow<[u64]>>(0).unwrap(),&[1,2,3][..]);
+    }
 }
 
 #[cfg(feature="bench")]
@@ -842,6 +2511,20 @@ mod bench {
         });
     }
 
+    #[bench]
+    fn benchmark_few_keys_payload_100mb_verified(b: &mut Bencher) {
+        let huge_string = "a".repeat(100_000_000);
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry(&huge_string);
+        let result = writer.finalize();
+
+        b.iter(|| {
+            let reader = MemBufferReader::new_verified(&result).unwrap();
+            let string = reader.load_entry::<&str>(0).unwrap();
+            assert_eq!(string.len(), 100_000_000);
+        });
+    }
+
     #[bench]
     fn benchmark_few_keys_payload_1mb_times_3(b: &mut Bencher) {
         let mut huge_string = String::with_capacity(1_000_000);
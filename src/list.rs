@@ -0,0 +1,262 @@
+//!Lists of texts and byte strings stored as one entry. `&[&str]`, `&[String]` and `Vec<String>`
+//!are stored as `MemBufferTypes::StrList`, `&[&[u8]]` and `&[Vec<u8>]` as
+//!`MemBufferTypes::BytesList`. The entry holds a table with the end offset of every element
+//!followed by the concatenated elements, so `StrListView` and `BytesListView` borrow the entry and
+//!slice single elements out of the buffer without copying. The table and the encoding of the texts
+//!are checked once when the view is loaded. Lists are limited to 4 GB.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,StrListView,BytesListView};
+//!
+//!let tags = vec!["red".to_string(),"green".to_string(),"blue".to_string()];
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry(&tags[..]);
+//!writer.add_entry::<&[&[u8]]>(&[b"\x00\x01",b""]);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let list = reader.load_entry::<StrListView>(0).unwrap();
+//!assert_eq!(list.len(),3);
+//!assert_eq!(list.get(1),Some("green"));
+//!assert_eq!(list.iter().collect::<Vec<&str>>(),vec!["red","green","blue"]);
+//!assert_eq!(reader.load_entry::<Vec<String>>(0).unwrap(),tags);
+//!assert_eq!(reader.load_entry::<BytesListView>(1).unwrap().get(0),Some(&b"\x00\x01"[..]));
+//!```
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use core::convert::TryFrom;
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,MemBufferSerialize,MemBufferDeserialize};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+use crate::named::NameIndices;
+use crate::utf8::is_valid_utf8;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Serializes the elements as nested buffer holding the end offsets and the concatenated elements
+#[cfg(feature = "std")]
+fn serialize_list<'x>(elements: impl Iterator<Item=&'x [u8]>) -> Vec<u8> {
+    let mut ends: Vec<u32> = Vec::new();
+    let mut bytes: Vec<u8> = Vec::new();
+    for element in elements {
+        bytes.extend_from_slice(element);
+        ends.push(u32::try_from(bytes.len()).expect("Lists are limited to 4 GB"));
+    }
+    let mut writer = MemBufferWriter::new();
+    writer.add_entry(&ends[..]);
+    writer.add_entry(&bytes[..]);
+    writer.finalize()
+}
+
+///Elements of a list entry shared by the text and the byte lists
+#[derive(Debug)]
+struct RawList<'a> {
+    data: &'a [u8],
+    ends: NameIndices<'a>,
+    bytes: &'a [u8],
+}
+
+impl<'a> RawList<'a> {
+    ///Checks the end offsets of the elements once, so single elements can be sliced out unchecked
+    fn new(mem: &'a [u8]) -> Result<RawList<'a>,MemBufferError> {
+        let reader = MemBufferReader::new_validated(mem)?;
+        if reader.len() != 2 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let (ends,bytes) = (reader.offsets.get(0),reader.offsets.get(1));
+        if ends.variable_type != MemBufferTypes::VectorU32 as i32 || ends.len()%4 != 0 || bytes.variable_type != MemBufferTypes::VectorU8 as i32 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let list = RawList {
+            data: mem,
+            ends: NameIndices { data: reader.entry_slice(ends)?, swapped: reader.is_byte_swapped() },
+            bytes: reader.entry_slice(bytes)?,
+        };
+        let mut start = 0;
+        for x in 0..list.ends.len() {
+            let end = list.ends.get(x);
+            if end < start {
+                return Err(MemBufferError::WrongFormat);
+            }
+            start = end;
+        }
+        if start != list.bytes.len() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        Ok(list)
+    }
+
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    fn get(&self, index: usize) -> Option<&'a [u8]> {
+        if index >= self.len() {
+            return None;
+        }
+        let start = if index == 0 { 0 } else { self.ends.get(index-1) };
+        Some(&self.bytes[start..self.ends.get(index)])
+    }
+}
+
+///Text list entry borrowed from a buffer, see the module documentation
+#[derive(Debug)]
+pub struct StrListView<'a> {
+    list: RawList<'a>,
+}
+
+impl<'a> StrListView<'a> {
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Returns the text at the index without copying it
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        //Every element was checked to be valid UTF-8 when the view was loaded
+        self.list.get(index).map(|x| unsafe{ core::str::from_utf8_unchecked(x) })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&'a str> + '_ {
+        (0..self.len()).map(move |x| self.get(x).unwrap())
+    }
+}
+
+///Byte string list entry borrowed from a buffer, see the module documentation
+#[derive(Debug)]
+pub struct BytesListView<'a> {
+    list: RawList<'a>,
+}
+
+impl<'a> BytesListView<'a> {
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Returns the byte string at the index without copying it
+    pub fn get(&self, index: usize) -> Option<&'a [u8]> {
+        self.list.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&'a [u8]> + '_ {
+        (0..self.len()).map(move |x| self.get(x).unwrap())
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,StrListView<'a>> for StrListView<'a> {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<StrListView<'a>,MemBufferError> {
+        let list = RawList::new(mem)?;
+        for x in 0..list.len() {
+            if !is_valid_utf8(list.get(x).unwrap()) {
+                return Err(MemBufferError::InvalidUtf8);
+            }
+        }
+        Ok(StrListView {
+            list,
+        })
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,BytesListView<'a>> for BytesListView<'a> {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<BytesListView<'a>,MemBufferError> {
+        Ok(BytesListView {
+            list: RawList::new(mem)?,
+        })
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,Vec<String>> for Vec<String> {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<Vec<String>,MemBufferError> {
+        Ok(StrListView::from_mem_buffer(mem)?.iter().map(|x| x.to_string()).collect())
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<Vec<String>,MemBufferError> {
+        Vec::<String>::from_mem_buffer(&mem)
+    }
+}
+
+impl MemBufferSerialize for StrListView<'_> {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Borrowed(self.list.data)
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::StrList as i32
+    }
+}
+
+impl MemBufferSerialize for BytesListView<'_> {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Borrowed(self.list.data)
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::BytesList as i32
+    }
+}
+
+///Implements storing a collection of texts or byte strings as list entry
+macro_rules! list_serialize {
+    ($list:ty,$variable_type:ident) => {
+        #[cfg(feature = "std")]
+        impl MemBufferSerialize for $list {
+            fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+                Cow::Owned(serialize_list(self.iter().map(|x| x.as_ref())))
+            }
+
+            fn get_mem_buffer_type() -> i32 {
+                MemBufferTypes::$variable_type as i32
+            }
+        }
+    };
+}
+
+list_serialize!(&[&str],StrList);
+list_serialize!(&[String],StrList);
+list_serialize!(Vec<String>,StrList);
+list_serialize!(&[&[u8]],BytesList);
+list_serialize!(&[Vec<u8>],BytesList);
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,StrListView,BytesListView,Value,Endianness};
+
+    #[test]
+    fn check_lists() {
+        let texts: Vec<String> = (0..100).map(|x| "ä".repeat(x%7)).collect();
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry(texts.clone());
+        writer.add_entry::<&[&str]>(&[]);
+        writer.add_entry::<&[Vec<u8>]>(&[vec![0xFF],vec![],vec![1,2]]);
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        let list = reader.load_entry::<StrListView>(0).unwrap();
+        assert_eq!(list.len(),100);
+        assert_eq!(list.get(9),Some("ää"));
+        assert_eq!(list.get(100),None);
+        assert!(reader.load_entry::<StrListView>(1).unwrap().is_empty());
+        assert_eq!(reader.load_entry::<BytesListView>(2).unwrap().iter().collect::<Vec<&[u8]>>(),vec![&[0xFF][..],&[],&[1,2]]);
+        assert!(matches!(reader.load_entry::<StrListView>(2),Err(MemBufferError::FieldTypeError(_,_))));
+        assert!(matches!(reader.load_value(0).unwrap(),Value::StrList(x) if x.len() == 100));
+
+        //Copies keep the lists and texts are checked when the view is loaded
+        let mut copy = MemBufferWriter::new();
+        copy.add_entry_ref(&list);
+        copy.add_entry_ref(&reader.load_entry::<BytesListView>(2).unwrap());
+        let data = copy.finalize();
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.load_entry::<Vec<String>>(0).unwrap(),texts);
+        let mut corrupted = data.clone();
+        let pos = corrupted.windows(2).position(|x| x == "ä".as_bytes()).unwrap();
+        corrupted[pos+1] = b'a';
+        assert!(matches!(MemBufferReader::new(&corrupted).unwrap().load_entry::<StrListView>(0),Err(MemBufferError::InvalidUtf8)));
+    }
+}
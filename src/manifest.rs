@@ -0,0 +1,196 @@
+//!Integrity manifest listing the index, type, length and digest of every other entry. Writers
+//!created with `with_manifest` store it as metadata entry in front of the checksum entry, the
+//!digests are calculated with the checksum algorithm of the writer over the payloads as they are
+//!stored. `MemBufferReader::verify_manifest` checks every entry against it, so the integrity of
+//!the entries can be checked end to end even if a transport strips the checksums of its own
+//!framing.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new().with_manifest();
+//!writer.add_entry("Hello World");
+//!writer.add_entry(42);
+//!let mut data = writer.finalize();
+//!assert!(MemBufferReader::new(&data).unwrap().verify_manifest().is_ok());
+//!
+//!let pos = data.windows(5).position(|x| x == b"World").unwrap();
+//!data[pos] ^= 1;
+//!assert!(MemBufferReader::new(&data).unwrap().verify_manifest().is_err());
+//!```
+use crate::{MemBufferReader,MemBufferError,ChecksumAlgorithm,SYSTEM_TYPE_MANIFEST};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+use crate::endian::{read_u32,read_u64};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Listing of one entry in the manifest
+#[cfg(feature = "std")]
+pub(crate) struct ManifestRecord {
+    pub(crate) variable_type: i32,
+    pub(crate) len: usize,
+    pub(crate) digest: Vec<u8>,
+}
+
+///Manifest of a buffer, the records are stored in the order of the entries they describe
+//Only the writer builds manifests, readers verify the stored records in place
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) struct Manifest {
+    pub(crate) algorithm: ChecksumAlgorithm,
+    #[cfg(feature = "std")]
+    pub(crate) records: Vec<ManifestRecord>,
+}
+
+impl Manifest {
+    ///Size of one record without its digest, the index, the type and the length
+    const RECORD_LEN: usize = 16;
+
+    pub(crate) fn serialized_len(algorithm: ChecksumAlgorithm, entries: usize) -> usize {
+        4+entries*(Manifest::RECORD_LEN+algorithm.digest_len())
+    }
+
+    ///Writes the manifest, the integers are swapped for buffers with the other byte order while
+    ///the digests are always stored little endian
+    #[cfg(feature = "std")]
+    pub(crate) fn serialize(&self, to: &mut [u8], swapped: bool) {
+        let u32_bytes = |x: u32| if swapped { x.swap_bytes().to_ne_bytes() } else { x.to_ne_bytes() };
+        to[..4].copy_from_slice(&u32_bytes(self.algorithm.id()));
+        let mut pos = 4;
+        for (index,record) in self.records.iter().enumerate() {
+            let len = record.len as u64;
+            to[pos..pos+4].copy_from_slice(&u32_bytes(index as u32));
+            to[pos+4..pos+8].copy_from_slice(&u32_bytes(record.variable_type as u32));
+            to[pos+8..pos+16].copy_from_slice(&if swapped { len.swap_bytes() } else { len }.to_ne_bytes());
+            to[pos+16..pos+16+record.digest.len()].copy_from_slice(&record.digest);
+            pos += Manifest::RECORD_LEN+record.digest.len();
+        }
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Verifies every entry of the buffer against the manifest stored by a writer created with
+    ///`with_manifest`. Returns `MemBufferError::MissingChecksum` for buffers without manifest and
+    ///`MemBufferError::ChecksumMismatch` if an entry was changed, added or removed.
+    pub fn verify_manifest(&self) -> Result<(),MemBufferError> {
+        let stored = self.system_entry(SYSTEM_TYPE_MANIFEST).ok_or(MemBufferError::MissingChecksum)?;
+        if stored.len() < 4 {
+            return Err(MemBufferError::ChecksumMismatch);
+        }
+        let swapped = self.is_byte_swapped();
+        let algorithm = ChecksumAlgorithm::from_id(read_u32(stored,swapped))?;
+        //The manifest lists all entries in front of itself, only the checksum entry follows it
+        let entries: Vec<_> = self.offsets.iter().chain(self.system.iter()).take_while(|x| x.variable_type != SYSTEM_TYPE_MANIFEST).collect();
+        if stored.len() != Manifest::serialized_len(algorithm,entries.len()) {
+            return Err(MemBufferError::ChecksumMismatch);
+        }
+        let record_len = Manifest::RECORD_LEN+algorithm.digest_len();
+        for (index,(entry,record)) in entries.iter().zip(stored[4..].chunks_exact(record_len)).enumerate() {
+            if read_u32(record,swapped) as usize != index
+                || read_u32(&record[4..],swapped) as i32 != entry.variable_type
+                || read_u64(&record[8..],swapped) != entry.len() as u64
+                || algorithm.digest(core::iter::once(self.entry_slice(*entry)?)) != record[16..] {
+                return Err(MemBufferError::ChecksumMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    ///Recalculates the digest of the entry in the manifest of a buffer whose entry was modified in
+    ///place, buffers without manifest are left unchanged
+    pub(crate) fn rewrite_manifest_record(var: &mut [u8], index: usize) -> Result<(),MemBufferError> {
+        let (digest,offset) = {
+            let reader = MemBufferReader::new(var)?;
+            let stored = match reader.system_entry(SYSTEM_TYPE_MANIFEST) {
+                Some(stored) if stored.len() >= 4 => stored,
+                Some(_) => return Err(MemBufferError::ChecksumMismatch),
+                None => return Ok(()),
+            };
+            let algorithm = ChecksumAlgorithm::from_id(read_u32(stored,reader.is_byte_swapped()))?;
+            let record_len = Manifest::RECORD_LEN+algorithm.digest_len();
+            let start = 4+index*record_len+Manifest::RECORD_LEN;
+            if index >= reader.len() || start+algorithm.digest_len() > stored.len() {
+                return Err(MemBufferError::ChecksumMismatch);
+            }
+            let digest = algorithm.digest(core::iter::once(reader.entry_slice(reader.offsets.get(index))?));
+            (digest,stored.as_ptr() as usize-var.as_ptr() as usize+start)
+        };
+        var[offset..offset+digest.len()].copy_from_slice(&digest);
+        Ok(())
+    }
+
+    ///Calculates the manifest of the entries in front of the manifest entry
+    #[cfg(feature = "std")]
+    pub(crate) fn calculate_manifest(&self, algorithm: ChecksumAlgorithm) -> Result<Manifest,MemBufferError> {
+        let records = self.offsets.iter().chain(self.system.iter()).take_while(|x| x.variable_type != SYSTEM_TYPE_MANIFEST).map(|entry| Ok(ManifestRecord {
+            variable_type: entry.variable_type,
+            len: entry.len(),
+            digest: algorithm.digest(core::iter::once(self.entry_slice(entry)?)),
+        })).collect::<Result<_,MemBufferError>>()?;
+        Ok(Manifest {
+            algorithm,
+            records,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Appends a manifest with the type, length and digest of every other entry, see the module
+    ///documentation
+    pub fn with_manifest(mut self) -> MemBufferWriter {
+        self.manifest = true;
+        self
+    }
+
+    ///Returns the placeholder of the manifest entry for the given number of entries in front of it
+    pub(crate) fn manifest_placeholder(&self, entries: usize) -> Vec<u8> {
+        vec![0;Manifest::serialized_len(self.checksum_algorithm,entries)]
+    }
+
+    ///Calculates the manifest of the finalized buffer and writes it into the manifest entry
+    pub(crate) fn write_manifest(&self, var: &mut [u8]) {
+        let (manifest,offset,swapped) = {
+            let reader = MemBufferReader::new(var).unwrap();
+            let entry = reader.system_entry(SYSTEM_TYPE_MANIFEST).unwrap();
+            (reader.calculate_manifest(self.checksum_algorithm).unwrap(),entry.as_ptr() as usize-var.as_ptr() as usize,reader.is_byte_swapped())
+        };
+        manifest.serialize(&mut var[offset..],swapped);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError};
+
+    #[test]
+    fn check_manifest() {
+        let mut writer = MemBufferWriter::new().with_manifest();
+        writer.add_entry("Frankenstein");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry_named("answer",42);
+        let result = writer.finalize();
+        let reader = MemBufferReader::new_verified(&result).unwrap();
+        assert!(reader.verify_manifest().is_ok());
+        assert_eq!(reader.load_entry_by_name::<i32>("answer").unwrap(),42);
+
+        //The setting is carried over and the interleaved and swapped layouts carry it as well
+        assert_eq!(MemBufferWriter::from(&result).unwrap().finalize(),result);
+        let interleaved = writer.finalize_interleaved();
+        assert!(MemBufferReader::new_verified(&interleaved).unwrap().verify_manifest().is_ok());
+        let mut swapped = MemBufferWriter::from(&result).unwrap().with_byte_order(crate::Endianness::Big);
+        swapped.add_entry(7u64);
+        let swapped = swapped.finalize();
+        assert!(MemBufferReader::new(&swapped).unwrap().verify_manifest().is_ok());
+
+        //Changing a payload is noticed even without the checksum entry
+        let mut corrupted = result.clone();
+        let pos = corrupted.windows(5).position(|x| x == b"Frank").unwrap();
+        corrupted[pos] ^= 1;
+        assert!(matches!(MemBufferReader::new(&corrupted).unwrap().verify_manifest(),Err(MemBufferError::ChecksumMismatch)));
+
+        let mut plain = MemBufferWriter::new();
+        plain.add_entry("Frankenstein");
+        assert!(matches!(MemBufferReader::new(&plain.finalize()).unwrap().verify_manifest(),Err(MemBufferError::MissingChecksum)));
+    }
+}
@@ -0,0 +1,240 @@
+//!Map entries from text keys to byte values. `MemBufferMap` collects the pairs and is stored as
+//!one entry, the keys are stored sorted in front of the values. `MemBufferMapView` borrows the
+//!entry from the buffer and looks up keys with a binary search over the stored keys, the values
+//!are sliced out of the buffer without copying.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferMap,MemBufferMapView};
+//!
+//!let mut map = MemBufferMap::new();
+//!map.insert("lang",b"de");
+//!map.insert("country",b"AT");
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry(&map);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let view = reader.load_entry::<MemBufferMapView>(0).unwrap();
+//!assert_eq!(view.get("lang"),Some(&b"de"[..]));
+//!assert_eq!(view.get("region"),None);
+//!assert_eq!(view.keys().collect::<Vec<_>>(),vec!["country","lang"]);
+//!```
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,MemBufferSerialize,MemBufferDeserialize};
+use crate::utf8::is_valid_utf8;
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Builder of a map entry, inserting a key twice replaces its value
+#[derive(Debug, Clone, Default)]
+pub struct MemBufferMap {
+    entries: BTreeMap<String,Vec<u8>>,
+}
+
+impl MemBufferMap {
+    pub fn new() -> MemBufferMap {
+        MemBufferMap::default()
+    }
+
+    pub fn insert(&mut self, key: &str, val: &[u8]) {
+        self.entries.insert(key.to_string(),val.to_vec());
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferSerialize for &MemBufferMap {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        let mut writer = MemBufferWriter::new();
+        for key in self.entries.keys() {
+            writer.add_entry(&key[..]);
+        }
+        for val in self.entries.values() {
+            writer.add_entry(&val[..]);
+        }
+        Cow::Owned(writer.finalize())
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::Map as i32
+    }
+}
+
+///Map entry borrowed from a buffer, see the module documentation
+#[derive(Debug)]
+pub struct MemBufferMapView<'a> {
+    data: &'a [u8],
+    //Holds the sorted keys followed by the values in the same order
+    reader: MemBufferReader<'a>,
+}
+
+impl<'a> MemBufferMapView<'a> {
+    pub fn len(&self) -> usize {
+        self.reader.len()/2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Returns the raw key of the pair, the positions were checked when the map was loaded
+    fn key_bytes(&self, index: usize) -> &'a [u8] {
+        self.reader.entry_slice(self.reader.offsets.get(index)).unwrap_or_default()
+    }
+
+    fn value(&self, index: usize) -> &'a [u8] {
+        self.reader.entry_slice(self.reader.offsets.get(self.len()+index)).unwrap_or_default()
+    }
+
+    ///Returns the value stored under the key
+    pub fn get(&self, key: &str) -> Option<&'a [u8]> {
+        let (mut low,mut high) = (0,self.len());
+        while low < high {
+            let mid = low+(high-low)/2;
+            match self.key_bytes(mid).cmp(key.as_bytes()) {
+                core::cmp::Ordering::Less => low = mid+1,
+                core::cmp::Ordering::Greater => high = mid,
+                core::cmp::Ordering::Equal => return Some(self.value(mid)),
+            }
+        }
+        None
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    ///Returns the keys in sorted order
+    pub fn keys(&self) -> impl Iterator<Item=&'a str> + '_ {
+        //The keys were checked to be UTF-8 when the map was loaded
+        (0..self.len()).map(move |x| core::str::from_utf8(self.key_bytes(x)).unwrap_or_default())
+    }
+
+    ///Returns the pairs sorted by their keys
+    pub fn iter(&self) -> impl Iterator<Item=(&'a str,&'a [u8])> + '_ {
+        self.keys().enumerate().map(move |(index,key)| (key,self.value(index)))
+    }
+}
+
+impl MemBufferSerialize for MemBufferMapView<'_> {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Borrowed(self.data)
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::Map as i32
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,MemBufferMapView<'a>> for MemBufferMapView<'a> {
+    ///Checks the positions of all pairs, the types and the encoding of the stored keys and their
+    ///order once, so lookups can compare the raw keys. Keys have to be stored as plain text, maps
+    ///with interned, compressed or encrypted entries are rejected.
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<MemBufferMapView<'a>,MemBufferError> {
+        let reader = MemBufferReader::new_validated(mem)?;
+        let pairs = reader.len()/2;
+        if reader.len()%2 != 0 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let mut previous: Option<&[u8]> = None;
+        for index in 0..pairs {
+            let key = reader.offsets.get(index);
+            if key.variable_type != MemBufferTypes::Text as i32 || reader.offsets.get(pairs+index).variable_type != MemBufferTypes::VectorU8 as i32 {
+                return Err(MemBufferError::WrongFormat);
+            }
+            if reader.is_compressed(index) || reader.is_compressed(pairs+index) || reader.is_encrypted(index) || reader.is_encrypted(pairs+index) {
+                return Err(MemBufferError::WrongFormat);
+            }
+            let key = reader.entry_slice(key)?;
+            if !is_valid_utf8(key) {
+                return Err(MemBufferError::InvalidUtf8);
+            }
+            if previous.is_some_and(|x| x >= key) {
+                return Err(MemBufferError::WrongFormat);
+            }
+            previous = Some(key);
+        }
+        Ok(MemBufferMapView {
+            data: mem,
+            reader,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferMap,MemBufferMapView,MemBufferError,Value};
+
+    #[test]
+    fn check_map() {
+        let mut map = MemBufferMap::new();
+        for x in (0..100).rev() {
+            map.insert(&format!("key{:03}",x),format!("value{}",x).as_bytes());
+        }
+        map.insert("key050",b"replaced");
+        assert_eq!(map.len(),100);
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry(&map);
+        writer.add_entry(&MemBufferMap::new());
+        writer.add_entry("no map");
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        let view = reader.load_entry::<MemBufferMapView>(0).unwrap();
+        assert_eq!(view.len(),100);
+        assert_eq!(view.get("key000"),Some(&b"value0"[..]));
+        assert_eq!(view.get("key099"),Some(&b"value99"[..]));
+        assert_eq!(view.get("key050"),Some(&b"replaced"[..]));
+        assert!(!view.contains_key("key100"));
+        assert!(view.iter().all(|(key,val)| view.get(key) == Some(val)));
+        assert!(reader.load_entry::<MemBufferMapView>(1).unwrap().is_empty());
+        assert!(matches!(reader.load_entry::<MemBufferMapView>(2),Err(MemBufferError::FieldTypeError(_,_))));
+
+        //Maps are values of their own and are copied unchanged
+        let mut copy = MemBufferWriter::new();
+        for value in reader.values() {
+            let value = value.unwrap();
+            copy.add_value(&value).unwrap();
+        }
+        let copied = copy.finalize();
+        assert!(matches!(MemBufferReader::new(&copied).unwrap().load_value(0).unwrap(),Value::Map(x) if x.get("key001") == Some(&b"value1"[..])));
+
+        //Nested buffers which are no maps are rejected
+        let mut nested = MemBufferWriter::new();
+        nested.add_entry("key");
+        nested.add_entry(7);
+        let mut writer = MemBufferWriter::new();
+        writer.add_raw_entry(crate::MemBufferTypes::Map as i32,&nested.finalize());
+        let data = writer.finalize();
+        assert!(matches!(MemBufferReader::new(&data).unwrap().load_entry::<MemBufferMapView>(0),Err(MemBufferError::WrongFormat)));
+
+        //Keys are compared as stored, interned and unsorted keys are rejected
+        let mut interned = MemBufferWriter::new();
+        let mut unsorted = MemBufferWriter::new();
+        for x in 0..200 {
+            interned.add_interned_entry(&format!("k{:03}",x));
+            unsorted.add_entry(&format!("k{:03}",199-x)[..]);
+        }
+        for _ in 0..200 {
+            interned.add_entry(&b"value"[..]);
+            unsorted.add_entry(&b"value"[..]);
+        }
+        let mut writer = MemBufferWriter::new();
+        writer.add_raw_entry(crate::MemBufferTypes::Map as i32,&interned.finalize());
+        writer.add_raw_entry(crate::MemBufferTypes::Map as i32,&unsorted.finalize());
+        let data = writer.finalize();
+        let reader = MemBufferReader::new_validated(&data).unwrap();
+        assert!(matches!(reader.load_entry::<MemBufferMapView>(0),Err(MemBufferError::WrongFormat)));
+        assert!(matches!(reader.load_entry::<MemBufferMapView>(1),Err(MemBufferError::WrongFormat)));
+    }
+}
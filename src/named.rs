@@ -0,0 +1,200 @@
+//!Entries addressed by name instead of position. The key table is stored as metadata entry behind
+//!the user entries holding the sorted names followed by the entry index of every name, the reader
+//!looks names up with a binary search directly over the borrowed memory.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry_named("title","Frankenstein");
+//!writer.add_entry_named("year",1818);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+//!assert_eq!(reader.load_entry_by_name::<i32>("year").unwrap(),1818);
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize,MemBufferTypes,SYSTEM_TYPE_NAMES};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+use crate::endian::read_u32;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds an entry which can be loaded by its name as well as by its position, adding a name
+    ///which already exists replaces the value of the existing entry
+    pub fn add_entry_named<T: MemBufferSerialize>(&mut self, name: &str, val: T) {
+        match self.names.get(name) {
+            Some(index) => {
+                let index = *index;
+                self.replace_entry(val,index);
+            },
+            None => {
+                self.names.insert(name.to_string(),self.types.len());
+                self.add_entry(val);
+            }
+        }
+    }
+
+    ///Returns the position of the entry with the given name
+    pub fn entry_index(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    pub fn load_entry_by_name<'a, T: MemBufferDeserialize<'a,T>+MemBufferSerialize>(&'a self, name: &str) -> Result<T,MemBufferError> {
+        let index = self.entry_index(name).ok_or_else(|| MemBufferError::KeyNotFound(name.to_string()))?;
+        self.load_entry(index)
+    }
+
+    ///Serializes the key table as nested buffer with the sorted names and the entry indices
+    pub(crate) fn name_table(&self) -> Option<Vec<u8>> {
+        if self.names.is_empty() {
+            return None;
+        }
+        let mut table = MemBufferWriter::new();
+        let mut indices: Vec<u32> = Vec::with_capacity(self.names.len());
+        for (name,index) in self.names.iter() {
+            table.add_entry(name);
+            indices.push(*index as u32);
+        }
+        table.add_entry(&indices[..]);
+        Some(table.finalize())
+    }
+
+    ///Takes over the key table of the reader
+    pub(crate) fn load_name_table(&mut self, reader: &MemBufferReader) -> Result<(),MemBufferError> {
+        if let Some((names,indices)) = reader.name_table()? {
+            for x in 0..indices.len() {
+                let name: &str = names.load_entry(x)?;
+                self.names.insert(name.to_string(),indices.get(x));
+            }
+        }
+        Ok(())
+    }
+}
+
+///Entry indices stored as u32 slice behind the keys of a metadata table
+#[derive(Debug)]
+pub(crate) struct NameIndices<'a> {
+    pub(crate) data: &'a [u8],
+    pub(crate) swapped: bool,
+}
+
+impl<'a> NameIndices<'a> {
+    pub(crate) fn len(&self) -> usize {
+        self.data.len()/4
+    }
+
+    pub(crate) fn get(&self, index: usize) -> usize {
+        read_u32(&self.data[4*index..],self.swapped) as usize
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the reader over the sorted names and the entry indices, the indices are read with
+    ///the byte order of the name table
+    fn name_table(&self) -> Result<Option<(MemBufferReader<'a>,NameIndices<'a>)>,MemBufferError> {
+        match self.system_entry(SYSTEM_TYPE_NAMES) {
+            Some(table) => {
+                let names = MemBufferReader::new(table)?;
+                if names.is_empty() {
+                    return Err(MemBufferError::WrongFormat);
+                }
+                let indices = names.offsets.get(names.len()-1);
+                if indices.variable_type != MemBufferTypes::VectorU32 as i32 || indices.len() != 4*(names.len()-1) {
+                    return Err(MemBufferError::WrongFormat);
+                }
+                let indices = NameIndices {
+                    data: names.entry_slice(indices)?,
+                    swapped: names.is_byte_swapped(),
+                };
+                Ok(Some((names,indices)))
+            },
+            None => Ok(None)
+        }
+    }
+
+    ///Returns the position of the entry with the given name
+    pub fn entry_index(&self, name: &str) -> Option<usize> {
+        let (names,indices) = self.name_table().ok()??;
+        let mut low = 0;
+        let mut high = indices.len();
+        while low < high {
+            let mid = (low+high)/2;
+            match names.entry_slice(names.offsets.get(mid)).ok()?.cmp(name.as_bytes()) {
+                core::cmp::Ordering::Less => low = mid+1,
+                core::cmp::Ordering::Greater => high = mid,
+                core::cmp::Ordering::Equal => return Some(indices.get(mid)),
+            }
+        }
+        None
+    }
+
+    ///Loads the entry with the given name like `load_entry`, returns `MemBufferError::KeyNotFound`
+    ///if the buffer has no entry with this name
+    pub fn load_entry_by_name<X: MemBufferDeserialize<'a,X> + MemBufferSerialize>(&self, name: &str) -> Result<X,MemBufferError> {
+        let index = self.entry_index(name).ok_or_else(|| MemBufferError::KeyNotFound(name.to_string()))?;
+        if index >= self.len() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        self.load_entry(index)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError};
+
+    #[test]
+    fn check_named_entries() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("positional");
+        writer.add_entry_named("title","Der moderne Prometheus");
+        writer.add_entry_named("author","Mary Shelley");
+        writer.add_entry_named::<&[u64]>("ids",&[1,2,3]);
+        assert_eq!(writer.load_entry_by_name::<&str>("author").unwrap(),"Mary Shelley");
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.len(),4);
+        assert_eq!(reader.entry_index("title"),Some(1));
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Der moderne Prometheus");
+        assert_eq!(reader.load_entry_by_name::<&str>("author").unwrap(),"Mary Shelley");
+        assert_eq!(reader.load_entry_by_name::<&[u64]>("ids").unwrap(),&[1,2,3]);
+        assert_eq!(reader.load_entry::<&str>(2).unwrap(),"Mary Shelley");
+        match reader.load_entry_by_name::<&str>("year") {
+            Err(MemBufferError::KeyNotFound(x)) => assert_eq!(x,"year"),
+            _ => panic!("Expected key not found error"),
+        }
+
+        //Positions within the name table are not covered by the validation of the buffer
+        let table = reader.system.iter().find(|x| x.variable_type == crate::SYSTEM_TYPE_NAMES).unwrap();
+        let start = result.len()-reader.data.len()+table.start;
+        let mut corrupted = result.clone();
+        corrupted[start+12..start+16].copy_from_slice(&u32::MAX.to_ne_bytes());
+        let reader = MemBufferReader::new_validated(&corrupted).unwrap();
+        assert!(reader.load_entry_by_name::<&str>("author").is_err());
+    }
+
+    #[test]
+    fn check_named_entries_replace_and_reload() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry_named("title","first");
+        writer.add_entry_named("title","second");
+        assert_eq!(writer.len(),1);
+        let result = writer.finalize();
+
+        let mut writer2 = MemBufferWriter::from(&result).unwrap();
+        writer2.add_entry_named("body","text");
+        let result2 = writer2.finalize();
+
+        let reader = MemBufferReader::new(&result2).unwrap();
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"second");
+        assert_eq!(reader.load_entry_by_name::<&str>("body").unwrap(),"text");
+
+        let empty = MemBufferWriter::new().finalize();
+        assert_eq!(MemBufferReader::new(&empty).unwrap().entry_index("title"),None);
+    }
+}
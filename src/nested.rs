@@ -0,0 +1,116 @@
+//!Nested buffers written without serializing them twice. `add_entry` with a writer finalizes the
+//!nested writer into its own vector which is copied again when the outer buffer is written.
+//!`begin_nested` adds an empty nested writer and returns it to be filled, `add_nested` takes over a
+//!filled one. Both are serialized straight into the output when the outer buffer is written, so the
+//!header and the payload of every nested buffer are written exactly once. Deeper levels nest the
+//!same way.
+//!
+//!Until the outer buffer is written the nested writers are kept as writers, loading the entry from
+//!the outer writer fails, `nested_writer` returns them instead.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("outer");
+//!let nested = writer.begin_nested();
+//!nested.add_entry("inner");
+//!nested.begin_nested().add_entry(42);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let nested = reader.load_recursive_reader(1).unwrap();
+//!assert_eq!(nested.load_entry::<&str>(0).unwrap(),"inner");
+//!assert_eq!(nested.load_recursive_reader(1).unwrap().load_entry::<i32>(0).unwrap(),42);
+//!```
+use std::borrow::Cow;
+use crate::{MemBufferWriter,MemBufferTypes,Change,Payload};
+
+impl MemBufferWriter {
+    ///Adds an empty nested writer as entry and returns it, it is written when the outer buffer is
+    ///written
+    pub fn begin_nested(&mut self) -> &mut MemBufferWriter {
+        let index = self.types.len();
+        self.add_nested(MemBufferWriter::new());
+        self.nested.get_mut(&index).unwrap()
+    }
+
+    ///Adds the writer as nested buffer entry without serializing it, it is written when the outer
+    ///buffer is written
+    pub fn add_nested(&mut self, writer: MemBufferWriter) {
+        let index = self.types.len();
+        self.check_schema_field(index,MemBufferTypes::MemBuffer as i32);
+        self.types.push(MemBufferTypes::MemBuffer as i32);
+        self.data.push(Vec::new().into());
+        self.nested.insert(index,writer);
+        self.log_payload(index,Change::Add);
+    }
+
+    ///Returns the nested writer added at the index with `begin_nested` or `add_nested`
+    pub fn nested_writer(&mut self, index: usize) -> Option<&mut MemBufferWriter> {
+        self.nested.get_mut(&index)
+    }
+
+    ///Returns the payloads of the entries to write, nested writers are serialized and spooled
+    ///payloads are read while they are written
+    pub(crate) fn payloads(&self) -> Vec<Payload<'_>> {
+        self.data.iter().enumerate().map(|(index,x)| match (self.nested.get(&index),self.spooled_payload(index)) {
+            (Some(nested),_) => Payload::Nested(nested),
+            (None,Some(spooled)) => spooled,
+            (None,None) => Payload::Bytes(x),
+        }).collect()
+    }
+
+    ///Returns the payload of the entry, nested writers are serialized and spooled payloads are
+    ///read for this
+    pub(crate) fn entry_payload(&self, index: usize) -> Cow<'_,[u8]> {
+        match (self.nested.get(&index),self.spooled_payload(index)) {
+            (Some(nested),_) => Cow::Owned(nested.finalize()),
+            //Reading back the spool file only fails if it was tampered with
+            (None,Some(spooled)) => Cow::Owned(spooled.into_vec().expect("Spooled payload cannot be read")),
+            (None,None) => Cow::Borrowed(&self.data[index]),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,Endianness};
+
+    #[test]
+    fn check_deferred_nesting() {
+        let mut inner = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        inner.add_entry("inner");
+        inner.add_entry::<&[u64]>(&[1,2,3]);
+        let mut eager = MemBufferWriter::new().with_field_checksums().with_manifest();
+        eager.add_entry("first");
+        eager.add_entry(&inner);
+        eager.add_entry(&inner);
+        eager.add_entry(7);
+
+        let mut deferred = MemBufferWriter::new().with_field_checksums().with_manifest();
+        deferred.add_entry("first");
+        deferred.add_nested(inner.clone());
+        let nested = deferred.begin_nested();
+        *nested = nested.clone().with_byte_order(Endianness::Big);
+        nested.add_entry("inner");
+        nested.add_entry::<&[u64]>(&[1,2,3]);
+        deferred.add_entry(7);
+        let data = deferred.finalize();
+        assert_eq!(data,eager.finalize());
+        assert_eq!(data.len(),deferred.serialized_len());
+        assert!(MemBufferReader::new_verified(&data).is_ok());
+
+        //Nested writers move with their entries and are dropped when they are replaced
+        deferred.swap_entries(0,2);
+        deferred.nested_writer(0).unwrap().add_entry("added");
+        deferred.remove_entry(1);
+        deferred.set_entry("replaced",2).unwrap();
+        assert!(deferred.nested_writer(2).is_none());
+        let data = deferred.finalize();
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        assert_eq!(reader.load_recursive_reader(0).unwrap().load_entry::<&str>(2).unwrap(),"added");
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"first");
+        assert_eq!(reader.load_entry::<&str>(2).unwrap(),"replaced");
+    }
+}
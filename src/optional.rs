@@ -0,0 +1,104 @@
+//!Optional entries. `MemBufferWriter::add_optional_entry` stores `None` as an entry of type
+//!`MemBufferTypes::Null` without payload, so slots of a schema can be reserved and left empty
+//!while readers still tell them apart from empty text or empty slices.
+//!`MemBufferReader::load_optional_entry` returns `None` for null entries and loads all other
+//!entries like `load_entry`.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_optional_entry(Some("Hello"));
+//!writer.add_optional_entry::<&str>(None);
+//!writer.add_entry("");
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_optional_entry::<&str>(0).unwrap(),Some("Hello"));
+//!assert_eq!(reader.load_optional_entry::<&str>(1).unwrap(),None);
+//!assert_eq!(reader.load_optional_entry::<&str>(2).unwrap(),Some(""));
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,MemBufferSerialize,MemBufferDeserialize};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,Change};
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds the value or a null entry for `None`
+    pub fn add_optional_entry<T: MemBufferSerialize>(&mut self, val: Option<T>) {
+        match val {
+            Some(val) => self.add_entry(val),
+            None => self.add_null_entry(),
+        }
+    }
+
+    ///Adds an entry of type `MemBufferTypes::Null` without payload
+    pub fn add_null_entry(&mut self) {
+        self.check_schema_field(self.types.len(),MemBufferTypes::Null as i32);
+        self.types.push(MemBufferTypes::Null as i32);
+        self.data.push(Vec::new().into());
+        self.log_payload(self.types.len()-1,Change::Add);
+    }
+
+    ///Returns whether the entry is a null entry
+    pub fn is_null(&self, index: usize) -> bool {
+        self.types.get(index) == Some(&(MemBufferTypes::Null as i32))
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns whether the entry is a null entry
+    pub fn is_null(&self, index: usize) -> bool {
+        index < self.offsets.len() && self.offsets.get(index).variable_type == MemBufferTypes::Null as i32
+    }
+
+    ///Loads the entry like `load_entry`, null entries are returned as `None`
+    pub fn load_optional_entry<X: MemBufferDeserialize<'a,X> + MemBufferSerialize>(&self, key: usize) -> Result<Option<X>,MemBufferError> {
+        if self.is_null(key) {
+            if let Some(hook) = self.access_hook {
+                hook(key,MemBufferTypes::Null as i32,0);
+            }
+            return Ok(None);
+        }
+        self.load_entry(key).map(Some)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,Schema,Value};
+
+    #[test]
+    fn check_optional_entries() {
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_optional_entry(Some(7u64));
+        writer.add_optional_entry::<u64>(None);
+        writer.add_optional_entry::<&[u32]>(Some(&[]));
+        writer.add_null_entry();
+        assert!(writer.is_null(1) && !writer.is_null(2) && !writer.is_null(4));
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.len(),4);
+        assert_eq!(reader.load_optional_entry::<u64>(0).unwrap(),Some(7));
+        assert_eq!(reader.load_optional_entry::<u64>(1).unwrap(),None);
+        assert_eq!(reader.load_optional_entry::<&[u32]>(2).unwrap(),Some(&[][..]));
+        assert!(matches!(reader.load_optional_entry::<&str>(0),Err(MemBufferError::FieldTypeError(_,_))));
+        assert!(matches!(reader.load_optional_entry::<u64>(4),Err(MemBufferError::IndexOutOfBounds(4,4))));
+        //Only null entries are absent, loading them as a value fails
+        assert!(matches!(reader.load_entry::<u64>(1),Err(MemBufferError::FieldTypeError(_,_))));
+        //Null entries fill any slot of a schema
+        let schema = Schema::new().field::<u64>("id").field::<u64>("parent").field::<&[u32]>("children").field::<&str>("note").build();
+        assert!(schema.check(&reader).is_ok());
+
+        //Null entries survive dynamic copies
+        let mut copy = MemBufferWriter::new();
+        for value in reader.values() {
+            copy.add_value(&value.unwrap()).unwrap();
+        }
+        let copied = copy.finalize();
+        let reader = MemBufferReader::new(&copied).unwrap();
+        assert!(matches!(reader.load_value(3).unwrap(),Value::Null));
+        assert!(reader.is_null(1) && !reader.is_null(0));
+    }
+}
@@ -0,0 +1,97 @@
+//!Readers owning their buffer. `MemBufferOwnedReader` takes any `Storage` like a `Vec<u8>`, an
+//!`Arc<[u8]>` or a mmap by value and checks the header once, so it can be stored in structs and
+//!caches or moved to other threads together with its buffer. Loads borrow from the reader.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferOwnedReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello");
+//!let reader = MemBufferOwnedReader::new(writer.finalize()).unwrap();
+//!
+//!let handle = std::thread::spawn(move || reader.load_entry::<&str>(0).unwrap().to_string());
+//!assert_eq!(handle.join().unwrap(),"Hello");
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize,Storage};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Reader holding the storage of its buffer
+pub struct MemBufferOwnedReader<S: Storage = Vec<u8>> {
+    storage: S,
+}
+
+impl<S: Storage> MemBufferOwnedReader<S> {
+    ///Takes the storage and checks that it holds a buffer whose entries lie within the payload
+    pub fn new(storage: S) -> Result<MemBufferOwnedReader<S>,MemBufferError> {
+        MemBufferReader::new_validated(storage.slice(0..storage.len()))?;
+        Ok(MemBufferOwnedReader {
+            storage,
+        })
+    }
+
+    ///Returns a reader borrowing the buffer, creating it only parses the header
+    pub fn reader(&self) -> MemBufferReader<'_> {
+        //The buffer was checked when the reader was created
+        MemBufferReader::from_storage(&self.storage).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reader().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reader().is_empty()
+    }
+
+    pub fn load_entry<'a, X: MemBufferDeserialize<'a,X>+MemBufferSerialize>(&'a self, key: usize) -> Result<X,MemBufferError> {
+        self.reader().load_entry(key)
+    }
+
+    pub fn load_entry_by_name<'a, X: MemBufferDeserialize<'a,X>+MemBufferSerialize>(&'a self, name: &str) -> Result<X,MemBufferError> {
+        self.reader().load_entry_by_name(name)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.storage.slice(0..self.storage.len())
+    }
+
+    ///Returns the storage of the buffer
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl MemBufferOwnedReader<alloc::sync::Arc<[u8]>> {
+    ///Returns the shared buffer
+    pub(crate) fn shared(&self) -> &alloc::sync::Arc<[u8]> {
+        &self.storage
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferOwnedReader};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn check_owned_reader() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry_named("title","Frankenstein");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        let data = writer.finalize();
+
+        let mut cache: HashMap<&str,MemBufferOwnedReader<Arc<[u8]>>> = HashMap::new();
+        cache.insert("book",MemBufferOwnedReader::new(data.clone().into()).unwrap());
+        let reader = &cache["book"];
+        assert_eq!(reader.len(),2);
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+        assert_eq!(reader.reader().load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+        assert_eq!(reader.as_bytes(),&data[..]);
+
+        let owned = MemBufferOwnedReader::new(data.clone()).unwrap();
+        assert_eq!(owned.into_inner(),data);
+        assert!(MemBufferOwnedReader::new(data[..20].to_vec()).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//!Parallel writing and loading with rayon, available with the `rayon` feature. Copying the
+//!payloads of buffers with many large entries is bound by memory bandwidth which a single thread
+//!does not saturate. `MemBufferWriter::finalize_parallel` calculates the positions of all entries
+//!first and copies the payloads into their regions of the buffer concurrently, the result is the
+//!same buffer `finalize` writes. `MemBufferReader::load_all_parallel` loads every entry of a
+//!buffer holding one type concurrently.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new().with_field_checksums();
+//!for x in 0..16u64 {
+//!    writer.add_entry::<&[u64]>(&vec![x;1024]);
+//!}
+//!let data = writer.finalize_parallel();
+//!assert_eq!(data,writer.finalize());
+//!
+//!let reader = MemBufferReader::new_verified(&data).unwrap();
+//!let slices = reader.load_all_parallel::<&[u64]>().unwrap();
+//!assert_eq!(slices[3],&[3;1024][..]);
+//!```
+use std::borrow::Cow;
+use rayon::prelude::*;
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize,Payload};
+use crate::endian::{swap_integers,swap_width};
+
+impl MemBufferWriter {
+    ///Finalizes the buffer like `finalize` and copies the payloads of the entries concurrently,
+    ///compressed and encrypted entries are encoded up front
+    pub fn finalize_parallel(&self) -> Vec<u8> {
+        let system = self.system_entries();
+        let types: Vec<i32> = self.types.iter().copied().chain(system.iter().map(|x| x.0)).collect();
+        let mut payloads = self.payloads();
+        payloads.extend(system.iter().map(|x| Payload::Bytes(&x.1)));
+        //Payloads in memory are encoded without any IO
+        self.compress_payloads(&types,&mut payloads).unwrap();
+        #[cfg(feature = "encryption")]
+        self.encrypt_payloads(&types,&mut payloads).unwrap();
+        let mut payloads: Vec<Cow<[u8]>> = payloads.into_iter().map(|x| match x {
+            Payload::Bytes(x) => Cow::Borrowed(x),
+            x => Cow::Owned(x.into_vec().unwrap()),
+        }).collect();
+        let widths: Vec<usize> = (0..payloads.len()).map(|x| if self.byte_order.is_swapped() && !self.is_encoded(x) { swap_width(types[x]) } else { 1 }).collect();
+
+        let (types,lens) = self.with_trailing_entries(&types,payloads.iter().map(|x| x.len()).collect());
+        let order = self.placement_order(types.len());
+        let shared = self.shared_payloads(&types,&payloads.iter().map(|x| Payload::Bytes(x)).collect::<Vec<Payload>>(),&order);
+        let (header,starts) = self.serialize_header(&types,&lens,&order,&shared);
+        #[cfg(feature = "encryption")]
+        if let Some((index,sealed)) = self.seal_key_check(&types,&lens,&starts,payloads.iter().map(|x| &x[..])) {
+            payloads[index] = Cow::Owned(sealed);
+        }
+        let last = types.len()-1;
+        let mut var = vec![0u8;header.len()+starts[last]+lens[last]];
+        var[..header.len()].copy_from_slice(&header);
+
+        //The manifest and the checksum entry are calculated from the written buffer
+        let mut regions = Vec::with_capacity(payloads.len());
+        let mut rest = &mut var[header.len()..];
+        let mut position = 0;
+        for x in order.iter().copied().filter(|x| *x < payloads.len() && shared[*x].is_none()) {
+            let (region,tail) = std::mem::take(&mut rest)[starts[x]-position..].split_at_mut(lens[x]);
+            regions.push((x,region));
+            rest = tail;
+            position = starts[x]+lens[x];
+        }
+        regions.into_par_iter().for_each(|(x,region)| {
+            region.copy_from_slice(&payloads[x]);
+            if widths[x] > 1 {
+                swap_integers(region,widths[x]);
+            }
+        });
+        if self.manifest {
+            self.write_manifest(&mut var);
+        }
+        self.write_checksums(&mut var);
+        self.counters.add_written(var.len());
+        var
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Loads every entry as the given type concurrently, fails with the error of the first entry
+    ///which cannot be loaded. The access hook is called from the threads loading the entries.
+    pub fn load_all_parallel<T>(&self) -> Result<Vec<T>,MemBufferError>
+        where T: MemBufferDeserialize<'a,T>+MemBufferSerialize+Send {
+        (0..self.len()).into_par_iter().map(|x| self.load_entry::<T>(x)).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,Compression,Endianness};
+    use std::borrow::Cow;
+
+    #[test]
+    fn check_parallel_finalize() {
+        for byte_order in [Endianness::Native,Endianness::Big].iter() {
+            let mut writer = MemBufferWriter::new().with_byte_order(*byte_order).with_field_checksums().with_manifest();
+            for x in 0..64u32 {
+                writer.add_entry_named(&format!("entry{}",x),&vec![x;x as usize*100][..]);
+            }
+            writer.add_entry_compressed("compressed",Compression::None);
+            writer.add_interned_entry("interned");
+            writer.set_placement_priorities(&[0,0,5,0,9]);
+            let data = writer.finalize_parallel();
+            assert_eq!(data,writer.finalize());
+
+            let reader = MemBufferReader::new_verified(&data).unwrap();
+            reader.verify_manifest().unwrap();
+            assert!(matches!(reader.load_all_parallel::<Cow<[u32]>>(),Err(MemBufferError::FieldTypeError(_,_))));
+            let mut entries = MemBufferWriter::from(&data).unwrap();
+            entries.truncate(64);
+            let data = entries.finalize_parallel();
+            let slices = MemBufferReader::new(&data).unwrap().load_all_parallel::<Cow<[u32]>>().unwrap();
+            assert!(slices.iter().enumerate().all(|(x,slice)| slice.len() == x*100 && slice.iter().all(|y| *y as usize == x)));
+        }
+    }
+}
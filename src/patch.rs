@@ -0,0 +1,249 @@
+//!Incremental updates of buffers. `diff` compares two versions of a buffer entry by entry and
+//!returns a `MemBufferPatch` holding the entries which were changed or added with their payloads
+//!and the new number of entries, so removed trailing entries are dropped. `apply_patch` applies it
+//!to a copy of the old version and returns the new one, so syncing a document only ships the
+//!modified fields. The patch is itself encoded as buffer with `MemBufferPatch::to_bytes`.
+//!
+//!Entries are compared by index and by their decoded payload, so the byte order and compression
+//!of the versions do not matter. Removing an entry from the middle changes all entries behind it.
+//!Names and other metadata of the entries are taken from the old version.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferPatch};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("unchanged");
+//!writer.add_entry(1);
+//!let old = writer.finalize();
+//!writer.set_entry(2,1).unwrap();
+//!writer.add_entry("added");
+//!let new = writer.finalize();
+//!
+//!let patch = membuffer::diff(&MemBufferReader::new(&old).unwrap(),&MemBufferReader::new(&new).unwrap()).unwrap();
+//!assert_eq!(patch.changes().len(),2);
+//!let shipped = patch.to_bytes();
+//!
+//!let patch = MemBufferPatch::from_bytes(&shipped).unwrap();
+//!let updated = membuffer::apply_patch(&old,&patch).unwrap();
+//!let reader = MemBufferReader::new(&updated).unwrap();
+//!assert_eq!(reader.load_entry::<i32>(1).unwrap(),2);
+//!assert_eq!(reader.load_entry::<&str>(2).unwrap(),"added");
+//!```
+use std::borrow::Cow;
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferTypes,Change,is_system_type};
+use crate::endian::{swap_integers,swap_width};
+
+///Entry which differs in the new version of a buffer, interned text holds the text itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchEntry {
+    pub index: usize,
+    pub variable_type: i32,
+    ///Payload in native byte order
+    pub data: Vec<u8>,
+}
+
+///Changes turning one version of a buffer into another, see the module documentation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemBufferPatch {
+    base_len: usize,
+    len: usize,
+    changes: Vec<PatchEntry>,
+}
+
+impl MemBufferPatch {
+    ///Returns the changed and added entries sorted by their index
+    pub fn changes(&self) -> &[PatchEntry] {
+        &self.changes
+    }
+
+    ///Returns the number of entries of the old version
+    pub fn base_len(&self) -> usize {
+        self.base_len
+    }
+
+    ///Returns the number of entries of the new version
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    ///Returns whether both versions hold the same entries
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.base_len == self.len
+    }
+
+    ///Serializes the patch as buffer holding the numbers of entries, the indices and types of the
+    ///changes followed by their payloads
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let indices: Vec<u64> = self.changes.iter().map(|x| x.index as u64).collect();
+        let types: Vec<u32> = self.changes.iter().map(|x| x.variable_type as u32).collect();
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry::<&[u64]>(&[self.base_len as u64,self.len as u64]);
+        writer.add_entry(&indices[..]);
+        writer.add_entry(&types[..]);
+        for change in self.changes.iter() {
+            writer.add_entry(&change.data[..]);
+        }
+        writer.finalize()
+    }
+
+    ///Loads a patch serialized with `to_bytes`
+    pub fn from_bytes(data: &[u8]) -> Result<MemBufferPatch,MemBufferError> {
+        let reader = MemBufferReader::new(data)?;
+        if reader.len() < 3 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let lens = reader.load_entry::<Cow<[u64]>>(0)?;
+        let indices = reader.load_entry::<Cow<[u64]>>(1)?;
+        let types = reader.load_entry::<Cow<[u32]>>(2)?;
+        if lens.len() != 2 || indices.len() != types.len() || reader.len() != 3+indices.len() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let mut changes = Vec::with_capacity(indices.len());
+        for x in 0..indices.len() {
+            changes.push(PatchEntry {
+                index: indices[x] as usize,
+                variable_type: types[x] as i32,
+                data: reader.load_entry::<Cow<[u8]>>(3+x)?.into_owned(),
+            });
+        }
+        Ok(MemBufferPatch {
+            base_len: lens[0] as usize,
+            len: lens[1] as usize,
+            changes,
+        })
+    }
+}
+
+///Returns the payload of the entry decoded and in native byte order, interned text is resolved
+fn native_payload<'a>(reader: &MemBufferReader<'a>, index: usize) -> Result<Cow<'a,[u8]>,MemBufferError> {
+    let entry = reader.offsets.get(index);
+    let slice = reader.entry_slice(entry)?;
+    if entry.variable_type == MemBufferTypes::InternedText as i32 {
+        return Ok(Cow::Borrowed(reader.resolve_interned(slice)?));
+    }
+    if let Some(data) = reader.decompress_entry(index,slice)? {
+        return Ok(Cow::Owned(data));
+    }
+    if reader.is_byte_swapped() {
+        let mut data = slice.to_vec();
+        swap_integers(&mut data,swap_width(entry.variable_type));
+        return Ok(Cow::Owned(data));
+    }
+    Ok(Cow::Borrowed(slice))
+}
+
+///Returns the patch turning the buffer of `old` into the buffer of `new`, fails if an entry cannot
+///be decoded
+pub fn diff(old: &MemBufferReader, new: &MemBufferReader) -> Result<MemBufferPatch,MemBufferError> {
+    let mut changes = Vec::new();
+    for index in 0..new.len() {
+        let variable_type = new.offsets.get(index).variable_type;
+        let data = native_payload(new,index)?;
+        if index < old.len() && old.offsets.get(index).variable_type == variable_type && native_payload(old,index)? == data {
+            continue;
+        }
+        changes.push(PatchEntry {
+            index,
+            variable_type,
+            data: data.into_owned(),
+        });
+    }
+    Ok(MemBufferPatch {
+        base_len: old.len(),
+        len: new.len(),
+        changes,
+    })
+}
+
+///Applies the patch to the old version of the buffer and returns the new one. Fails with
+///`MemBufferError::WrongFormat` if the buffer has another number of entries than the one the
+///patch was created from or the patch is inconsistent.
+pub fn apply_patch(base: &[u8], patch: &MemBufferPatch) -> Result<Vec<u8>,MemBufferError> {
+    let mut writer = MemBufferWriter::from(base)?;
+    if writer.len() != patch.base_len {
+        return Err(MemBufferError::WrongFormat);
+    }
+    writer.truncate(patch.len);
+    for change in patch.changes.iter() {
+        writer.apply_patch_entry(change)?;
+    }
+    if writer.len() != patch.len {
+        return Err(MemBufferError::WrongFormat);
+    }
+    Ok(writer.finalize())
+}
+
+impl MemBufferWriter {
+    ///Replaces the entry at the index of the change or adds it if the index is the number of
+    ///entries
+    fn apply_patch_entry(&mut self, change: &PatchEntry) -> Result<(),MemBufferError> {
+        if change.index > self.types.len() || is_system_type(change.variable_type) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let data: Vec<u8> = if change.variable_type == MemBufferTypes::InternedText as i32 {
+            let text = core::str::from_utf8(&change.data).map_err(|_| MemBufferError::InvalidUtf8)?;
+            self.intern(text).to_ne_bytes().to_vec()
+        }
+        else {
+            change.data.clone()
+        };
+        if change.index == self.types.len() {
+            self.types.push(change.variable_type);
+            self.data.push(data.into());
+            self.log_payload(change.index,Change::Add);
+        }
+        else {
+            self.compression.remove(&change.index);
+            self.types[change.index] = change.variable_type;
+            self.data[change.index] = data.into();
+            self.log_payload(change.index,Change::Set);
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferPatch,Endianness,diff,apply_patch};
+
+    #[test]
+    fn check_diff_and_apply() {
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry_named("title","Frankenstein");
+        writer.add_interned_entry("draft");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry("removed");
+        let old = writer.finalize();
+
+        //The new version is written in the other byte order, only real changes end up in the patch
+        let mut update = MemBufferWriter::from(&old).unwrap().with_byte_order(Endianness::Little);
+        update.truncate(3);
+        update.add_interned_entry("final");
+        update.swap_entries(1,3);
+        update.set_entry::<&[u64]>(&[1,2,4],2).unwrap();
+        let new = update.finalize();
+
+        let patch = diff(&MemBufferReader::new(&old).unwrap(),&MemBufferReader::new(&new).unwrap()).unwrap();
+        assert_eq!(patch.changes().iter().map(|x| x.index).collect::<Vec<usize>>(),vec![1,2,3]);
+        assert_eq!((patch.base_len(),patch.len()),(4,4));
+        let patch = MemBufferPatch::from_bytes(&patch.to_bytes()).unwrap();
+        let updated = apply_patch(&old,&patch).unwrap();
+        let reader = MemBufferReader::new(&updated).unwrap();
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"final");
+        assert_eq!(&reader.load_entry::<std::borrow::Cow<[u64]>>(2).unwrap()[..],&[1,2,4]);
+        assert_eq!(reader.load_entry::<&str>(3).unwrap(),"draft");
+        assert!(diff(&MemBufferReader::new(&new).unwrap(),&reader).unwrap().is_empty());
+
+        //Removed entries and patches for other buffers
+        let mut shorter = MemBufferWriter::from(&old).unwrap();
+        shorter.truncate(1);
+        let patch = diff(&MemBufferReader::new(&old).unwrap(),&MemBufferReader::new(&shorter.finalize()).unwrap()).unwrap();
+        assert!(patch.changes().is_empty());
+        assert_eq!(MemBufferReader::new(&apply_patch(&old,&patch).unwrap()).unwrap().len(),1);
+        assert!(apply_patch(&updated[..0],&patch).is_err());
+        let mut other = MemBufferWriter::new();
+        other.add_entry("other");
+        assert!(matches!(apply_patch(&other.finalize(),&patch),Err(MemBufferError::WrongFormat)));
+    }
+}
@@ -0,0 +1,127 @@
+//!Crash safe persistence of buffers. Writing a buffer straight into its file leaves a torn file
+//!behind if the process or the machine stops while it is written. `persist_atomic` writes the
+//!buffer into a temporary file next to the target, flushes it to the disk and renames it over the
+//!target, so the target holds either the old or the new buffer. On Unix the directory is flushed as
+//!well to persist the rename.
+//!
+//!Files written otherwise can be checked with `MemBufferReader::declared_len`, which reads the
+//!length of the buffer from its header and entry table. A buffer shorter than that fails with
+//!`MemBufferError::Truncated` holding the declared and the actual length. With the `mmap` feature
+//!`MemBufferFile::open_checked` does this and verifies the checksums when the file is opened.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferError};
+//!
+//!let path = std::env::temp_dir().join("membuffer_persist_doc.mem");
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("durable");
+//!let written = writer.persist_atomic(&path).unwrap();
+//!
+//!let data = std::fs::read(&path).unwrap();
+//!assert_eq!(MemBufferReader::declared_len(&data).unwrap(),written as usize);
+//!assert!(matches!(MemBufferReader::declared_len(&data[..data.len()-1]),Err(MemBufferError::Truncated(x,y)) if x == data.len() && y == data.len()-1));
+//!# std::fs::remove_file(&path).unwrap();
+//!```
+use std::fs::{File,OpenOptions};
+use std::io::{BufWriter,Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,table,stream};
+
+///Distinguishes the temporary files of concurrent writes within one process
+static PERSISTED_FILES: AtomicUsize = AtomicUsize::new(0);
+
+impl MemBufferWriter {
+    ///Writes the buffer into the file without ever leaving a partially written file behind, see
+    ///the module documentation. Returns the number of bytes written, the temporary file is removed
+    ///if writing fails.
+    pub fn persist_atomic<P: AsRef<Path>>(&self, path: P) -> std::io::Result<u64> {
+        let path = path.as_ref();
+        let name = path.file_name().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput,"Memory buffer error: The path does not name a file"))?;
+        let temporary = path.with_file_name(format!(".{}.{}.{}.tmp",name.to_string_lossy(),std::process::id(),PERSISTED_FILES.fetch_add(1,Ordering::Relaxed)));
+        let result = self.write_synced(&temporary).and_then(|written| {
+            std::fs::rename(&temporary,path)?;
+            Ok(written)
+        });
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temporary);
+        }
+        let written = result?;
+        #[cfg(unix)]
+        {
+            let directory = path.parent().filter(|x| !x.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+            File::open(directory)?.sync_all()?;
+        }
+        Ok(written)
+    }
+
+    ///Writes the buffer into a new file and flushes it to the disk
+    fn write_synced(&self, path: &Path) -> std::io::Result<u64> {
+        let mut file = BufWriter::new(OpenOptions::new().write(true).create_new(true).open(path)?);
+        let written = self.finalize_into(&mut file)?;
+        file.flush()?;
+        file.get_ref().sync_all()?;
+        Ok(written)
+    }
+}
+
+impl MemBufferReader<'_> {
+    ///Returns the length of the buffer the header and the entry table declare. Fails with
+    ///`MemBufferError::Truncated` if the given bytes end before, so torn files are told apart from
+    ///other corruption.
+    pub fn declared_len(val: &[u8]) -> Result<usize,MemBufferError> {
+        if val.len() < 8 {
+            return Err(MemBufferError::Truncated(8,val.len()));
+        }
+        let count = MemBufferReader::deserialize_i32_from(val);
+        if !stream::is_stream_magic(count) {
+            let layout = table::header_layout(count,MemBufferReader::deserialize_i32_from(&val[4..]))?;
+            let header_len = layout.header_len().ok_or(MemBufferError::WrongFormat)?;
+            if val.len() < header_len {
+                return Err(MemBufferError::Truncated(header_len,val.len()));
+            }
+        }
+        let reader = MemBufferReader::new(val)?;
+        let payload_start = reader.data.as_ptr() as usize-val.as_ptr() as usize;
+        let payload_end = reader.offsets.iter().chain(reader.system.iter()).map(|x| x.end).max().unwrap_or(0);
+        //Interleaved buffers end with their entry table
+        let declared = if stream::is_stream_magic(count) { val.len() } else { payload_start.checked_add(payload_end).ok_or(MemBufferError::WrongFormat)? };
+        if val.len() < declared {
+            return Err(MemBufferError::Truncated(declared,val.len()));
+        }
+        Ok(declared)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError};
+
+    #[test]
+    fn check_persist_atomic() {
+        let directory = std::env::temp_dir().join(format!("membuffer_check_persist_atomic_{}",std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("buffer.mem");
+        let mut writer = MemBufferWriter::new().with_large_offsets();
+        writer.add_entry("first");
+        writer.persist_atomic(&path).unwrap();
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        let written = writer.persist_atomic(&path).unwrap();
+
+        //The old file was replaced and no temporary file is left behind
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len() as u64,written);
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(),1);
+        assert_eq!(MemBufferReader::new(&data).unwrap().len(),2);
+        assert!(writer.persist_atomic(directory.join("missing").join("buffer.mem")).is_err());
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(),1);
+
+        //Truncated buffers report the declared length, also within the header
+        assert_eq!(MemBufferReader::declared_len(&data).unwrap(),data.len());
+        assert!(matches!(MemBufferReader::declared_len(&data[..20]),Err(MemBufferError::Truncated(_,20))));
+        assert!(matches!(MemBufferReader::declared_len(&data[..5]),Err(MemBufferError::Truncated(8,5))));
+        let interleaved = writer.finalize_interleaved();
+        assert_eq!(MemBufferReader::declared_len(&interleaved).unwrap(),interleaved.len());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
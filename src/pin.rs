@@ -0,0 +1,143 @@
+//!Pinned entries for C and GPU libraries. `pin_entry` hands out the raw pointer and length of an
+//!entry as stored in the buffer. The guard borrows the buffer, so the memory stays valid and
+//!unchanged as long as the guard lives. Owned readers over an `Arc<[u8]>` hand out guards which
+//!keep a reference to the buffer instead, they can outlive the reader and be moved to the thread
+//!which feeds the foreign library.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferOwnedReader};
+//!use std::sync::Arc;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry::<&[u64]>(&[1,2,3]);
+//!let shared: Arc<[u8]> = writer.finalize().into();
+//!let reader = MemBufferOwnedReader::new(shared).unwrap();
+//!
+//!let pinned = reader.pin_entry_shared(0).unwrap();
+//!drop(reader);
+//!//The pointer is passed on to a C function, here it is read back
+//!let values = unsafe{ std::slice::from_raw_parts(pinned.as_ptr().cast::<u64>(),pinned.len()/8) };
+//!assert_eq!(values,&[1,2,3]);
+//!```
+use core::marker::PhantomData;
+use alloc::sync::Arc;
+use crate::{MemBufferReader,MemBufferError,MemBufferOwnedReader,Storage};
+
+///Pointer and length of an entry which stay valid while the guard lives
+pub struct PinnedEntry<'a> {
+    ptr: *const u8,
+    len: usize,
+    variable_type: i32,
+    //Shared buffer the entry lies in for guards which do not borrow it
+    _owner: Option<Arc<[u8]>>,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+//The guard only hands out shared access to immutable bytes like a `&[u8]`
+unsafe impl<'a> Send for PinnedEntry<'a> {}
+unsafe impl<'a> Sync for PinnedEntry<'a> {}
+
+impl<'a> PinnedEntry<'a> {
+    ///Returns the pointer to the first byte of the payload
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    ///Returns the length of the payload in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn variable_type(&self) -> i32 {
+        self.variable_type
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        //The memory is borrowed or kept alive by the guard
+        unsafe{ core::slice::from_raw_parts(self.ptr,self.len) }
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Pins the payload of the entry as stored in the buffer, integers keep the byte order of the
+    ///buffer. Compressed entries cannot be pinned and fail with `MemBufferError::CompressedEntry`.
+    pub fn pin_entry(&self, index: usize) -> Result<PinnedEntry<'a>,MemBufferError> {
+        if index >= self.offsets.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,self.offsets.len()));
+        }
+        let entry = self.offsets.get(index);
+        if let Some(hook) = self.access_hook {
+            hook(index,entry.variable_type,entry.len());
+        }
+        if self.compression_of(index)?.is_some() || self.is_encrypted(index) {
+            return Err(MemBufferError::CompressedEntry);
+        }
+        self.ensure_validated(index)?;
+        let slice = self.entry_slice(entry)?;
+        Ok(PinnedEntry {
+            ptr: slice.as_ptr(),
+            len: slice.len(),
+            variable_type: entry.variable_type,
+            _owner: None,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<S: Storage> MemBufferOwnedReader<S> {
+    ///Pins the payload of the entry like `MemBufferReader::pin_entry`
+    pub fn pin_entry(&self, index: usize) -> Result<PinnedEntry<'_>,MemBufferError> {
+        self.reader().pin_entry(index)
+    }
+}
+
+impl MemBufferOwnedReader<Arc<[u8]>> {
+    ///Pins the payload of the entry with a guard holding a reference to the buffer, so it can
+    ///outlive the reader
+    pub fn pin_entry_shared(&self, index: usize) -> Result<PinnedEntry<'static>,MemBufferError> {
+        let pinned = self.pin_entry(index)?;
+        Ok(PinnedEntry {
+            ptr: pinned.ptr,
+            len: pinned.len,
+            variable_type: pinned.variable_type,
+            _owner: Some(self.shared().clone()),
+            _marker: PhantomData,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferOwnedReader,MemBufferError,MemBufferTypes};
+    use std::sync::Arc;
+
+    #[test]
+    fn check_pinned_entries() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u32]>(&[1,2,3]);
+        writer.add_entry("");
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        let pinned = reader.pin_entry(1).unwrap();
+        assert_eq!(pinned.len(),12);
+        assert_eq!(pinned.variable_type(),MemBufferTypes::VectorU32 as i32);
+        assert_eq!(pinned.as_ptr(),reader.load_entry::<&[u32]>(1).unwrap().as_ptr().cast::<u8>());
+        assert!(reader.pin_entry(2).unwrap().is_empty());
+        assert!(matches!(reader.pin_entry(3),Err(MemBufferError::IndexOutOfBounds(3,3))));
+
+        let shared: Arc<[u8]> = data.into();
+        let owned = MemBufferOwnedReader::new(shared.clone()).unwrap();
+        let pinned = owned.pin_entry_shared(0).unwrap();
+        drop(owned);
+        assert_eq!(Arc::strong_count(&shared),2);
+        let handle = std::thread::spawn(move || pinned.as_bytes().to_vec());
+        assert_eq!(handle.join().unwrap(),b"Hello");
+        assert_eq!(Arc::strong_count(&shared),1);
+    }
+}
@@ -0,0 +1,110 @@
+//!Placement of the entry payloads. By default payloads are stored in the order of the entries,
+//!with priorities the hot entries are moved to the front of the payload so that a mapped reader
+//!touching only those entries stays within a few pages. The entry indices do not change. Access
+//!counts recorded with an `AccessRecorder` can be used as priorities directly.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,AccessRecorder};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("cold ".repeat(1000).as_str());
+//!writer.add_entry("hot");
+//!let data = writer.finalize();
+//!
+//!let recorder = AccessRecorder::new();
+//!let hook = |index: usize, variable_type: i32, len: usize| recorder.record(index,variable_type,len);
+//!let reader = MemBufferReader::new(&data).unwrap().with_access_hook(&hook);
+//!reader.load_entry::<&str>(1).unwrap();
+//!
+//!let mut writer = MemBufferWriter::from(&data).unwrap();
+//!writer.set_placement_priorities(&recorder.counts());
+//!let optimized = writer.finalize();
+//!let reader = MemBufferReader::new(&optimized).unwrap();
+//!assert_eq!(reader.load_entry::<&str>(1).unwrap(),"hot");
+//!```
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+
+///Counts how often every entry of a reader is loaded, pass `record` to the access hook of the reader.
+///The recorder can be shared by readers on several threads.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct AccessRecorder {
+    counts: Mutex<Vec<u64>>,
+}
+
+#[cfg(feature = "std")]
+impl AccessRecorder {
+    pub fn new() -> AccessRecorder {
+        AccessRecorder::default()
+    }
+
+    ///Counts one access of the entry, the signature matches the access hook of the reader
+    pub fn record(&self, index: usize, _variable_type: i32, _len: usize) {
+        let mut counts = self.counts.lock().unwrap();
+        if counts.len() <= index {
+            counts.resize(index+1,0);
+        }
+        counts[index] += 1;
+    }
+
+    ///Returns the number of accesses per entry index
+    pub fn counts(&self) -> Vec<u64> {
+        self.counts.lock().unwrap().clone()
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Places the payloads of entries with higher priority first, the priority of entry `i` is
+    ///`priorities[i]` and 0 for entries without a priority. Entries of the same priority keep
+    ///their order.
+    pub fn set_placement_priorities(&mut self, priorities: &[u64]) {
+        self.placement = priorities.to_vec();
+    }
+
+    ///Returns the order the payloads of the given number of entries are written in, metadata
+    ///entries always follow the user entries
+    pub(crate) fn placement_order(&self, count: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..count).collect();
+        if !self.placement.is_empty() && !self.uses_compact_header() {
+            order[..self.types.len()].sort_by_key(|x| core::cmp::Reverse(self.placement.get(*x).copied().unwrap_or(0)));
+        }
+        order
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,AccessRecorder};
+
+    #[test]
+    fn check_placement_priorities() {
+        let mut writer = MemBufferWriter::new().with_field_checksums();
+        writer.add_entry("first");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry("third");
+        writer.add_entry_named("answer",42);
+        writer.set_placement_priorities(&[0,1,5]);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new_verified(&result).unwrap();
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"first");
+        assert_eq!(reader.load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+        assert_eq!(reader.load_entry::<&str>(2).unwrap(),"third");
+        assert_eq!(reader.load_entry_by_name::<i32>("answer").unwrap(),42);
+        let starts: Vec<usize> = reader.offsets.iter().map(|x| x.start).collect();
+        assert!(starts[2] < starts[1] && starts[1] < starts[0] && starts[0] < starts[3]);
+    }
+
+    #[test]
+    fn check_access_recorder() {
+        let recorder = AccessRecorder::new();
+        recorder.record(2,0,0);
+        recorder.record(2,0,0);
+        recorder.record(0,0,0);
+        assert_eq!(recorder.counts(),vec![1,0,2]);
+    }
+}
@@ -0,0 +1,134 @@
+//!Slices of plain old data structs, available with the `bytemuck` feature. Structs implementing
+//!`bytemuck::Pod` and `PodEntry` are stored as raw bytes with the type id `pod_type_id(TAG)` and
+//!loaded back as slice cast in place. The entries are aligned to 8 bytes, structs with a larger
+//!alignment fail with `MemBufferError::UnalignedEntry`. The layout depends on the byte order, so
+//!buffers written with the other byte order fail with `MemBufferError::ByteOrderMismatch`.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,PodEntry,PodSlice};
+//!
+//!#[repr(C)]
+//!#[derive(Clone, Copy, Debug, PartialEq)]
+//!struct Vertex {
+//!    position: [f32;3],
+//!    color: u32,
+//!}
+//!
+//!unsafe impl bytemuck::Zeroable for Vertex {}
+//!unsafe impl bytemuck::Pod for Vertex {}
+//!
+//!impl PodEntry for Vertex {
+//!    const TAG: u16 = 1;
+//!}
+//!
+//!let vertices = vec![Vertex { position: [0.0,1.0,2.0], color: 0xFF00FF }; 100];
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry(PodSlice(&vertices[..]));
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let loaded = reader.load_entry::<PodSlice<Vertex>>(0).unwrap();
+//!assert_eq!(&loaded[..],&vertices[..]);
+//!```
+use alloc::borrow::Cow;
+use crate::{MemBufferError,MemBufferSerialize,MemBufferDeserialize,POD_TYPE_BASE};
+
+///Plain old data struct which can be stored as slice, the tag has to be unique among the structs
+///stored in the same buffers
+pub trait PodEntry: bytemuck::Pod {
+    const TAG: u16;
+}
+
+///Returns the type id of slices of the struct with the given tag
+pub const fn pod_type_id(tag: u16) -> i32 {
+    POD_TYPE_BASE+tag as i32
+}
+
+///Slice of plain old data structs stored as one entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PodSlice<'a,T>(pub &'a [T]);
+
+impl<'a,T> core::ops::Deref for PodSlice<'a,T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.0
+    }
+}
+
+impl<T: PodEntry> MemBufferSerialize for PodSlice<'_,T> {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Borrowed(bytemuck::cast_slice(self.0))
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        pod_type_id(T::TAG)
+    }
+}
+
+impl<'a,T: PodEntry> MemBufferDeserialize<'a,PodSlice<'a,T>> for PodSlice<'a,T> {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<PodSlice<'a,T>,MemBufferError> {
+        //Empty entries are valid but their pointer does not need to be aligned
+        if mem.is_empty() {
+            return Ok(PodSlice(&[]));
+        }
+        match bytemuck::try_cast_slice(mem) {
+            Ok(x) => Ok(PodSlice(x)),
+            Err(bytemuck::PodCastError::TargetAlignmentGreaterAndInputNotAligned) => Err(MemBufferError::UnalignedEntry),
+            Err(_) => Err(MemBufferError::WrongFormat),
+        }
+    }
+
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<PodSlice<'a,T>,MemBufferError> {
+        if mem.is_empty() {
+            return Ok(PodSlice(&[]));
+        }
+        Err(MemBufferError::ByteOrderMismatch)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,PodEntry,PodSlice,pod_type_id};
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Sample {
+        time: u64,
+        value: f64,
+    }
+
+    unsafe impl bytemuck::Zeroable for Sample {}
+    unsafe impl bytemuck::Pod for Sample {}
+
+    impl PodEntry for Sample {
+        const TAG: u16 = 7;
+    }
+
+    #[test]
+    fn check_pod_slices() {
+        let samples: Vec<Sample> = (0..10).map(|x| Sample { time: x, value: x as f64/2.0 }).collect();
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("padding");
+        writer.add_entry(PodSlice(&samples[..]));
+        writer.add_entry(PodSlice::<Sample>(&[]));
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        let loaded = reader.load_entry::<PodSlice<Sample>>(1).unwrap();
+        assert_eq!(loaded.0,&samples[..]);
+        //The slice is cast in place
+        assert!(data.as_ptr_range().contains(&loaded.as_ptr().cast::<u8>()));
+        assert!(matches!(reader.load_value(1).unwrap(),crate::Value::Unknown(id,_) if id == pod_type_id(7)));
+        assert!(reader.load_entry::<PodSlice<Sample>>(2).unwrap().is_empty());
+        assert!(matches!(reader.load_entry::<PodSlice<Sample>>(0),Err(MemBufferError::FieldTypeError(_,_))));
+
+        let mut writer = MemBufferWriter::new().with_byte_order(crate::Endianness::Big);
+        writer.add_entry(PodSlice(&samples[..]));
+        let data = writer.finalize();
+        let result = MemBufferReader::new(&data).unwrap().load_entry::<PodSlice<Sample>>(0);
+        if cfg!(target_endian = "little") {
+            assert!(matches!(result,Err(MemBufferError::ByteOrderMismatch)));
+        }
+    }
+}
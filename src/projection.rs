@@ -0,0 +1,169 @@
+//!Loading single fields of serde entries. Bincode does not describe the layout of its values, so
+//!`load_serde_field` deserializes the struct with its own field types up to the requested field,
+//!takes that field and stops. The fields behind it are never read and the struct is never built,
+//!which saves the work for the trailing fields of large structs.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!use serde::{Serialize,Deserialize};
+//!
+//!#[derive(Serialize,Deserialize)]
+//!struct Page {
+//!    title: String,
+//!    words: Vec<u32>,
+//!}
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_serde_entry(&Page { title: "Preface".to_string(), words: vec![7;10_000] }).unwrap();
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let title: &str = reader.load_serde_field::<Page,_>(0,"title").unwrap();
+//!assert_eq!(title,"Preface");
+//!```
+use serde::de::{self,Deserialize,DeserializeSeed,Deserializer,SeqAccess,Visitor};
+use bincode::Options;
+use crate::{MemBufferReader,MemBufferError};
+
+///Message of the error aborting the deserialization once the field was taken
+const PROJECTED: &str = "membuffer: field projected";
+
+///Deserializer handing the struct to bincode with a visitor taking the requested field
+struct Projection<'s,D,T> {
+    inner: D,
+    field: &'s str,
+    slot: &'s mut Option<T>,
+}
+
+impl<'de,'s,D: Deserializer<'de>,T: Deserialize<'de>> Deserializer<'de> for Projection<'s,D,T> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value,D::Error> {
+        Err(de::Error::custom("only fields of structs can be projected"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value,D::Error> {
+        let target = fields.iter().position(|x| *x == self.field).ok_or_else(|| de::Error::custom(format!("unknown field {}",self.field)))?;
+        self.inner.deserialize_struct(name,fields,ProjectVisitor {
+            inner: visitor,
+            target,
+            slot: self.slot,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+struct ProjectVisitor<'s,V,T> {
+    inner: V,
+    target: usize,
+    slot: &'s mut Option<T>,
+}
+
+impl<'de,'s,V: Visitor<'de>,T: Deserialize<'de>> Visitor<'de> for ProjectVisitor<'s,V,T> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<V::Value,A::Error> {
+        self.inner.visit_seq(ProjectSeq {
+            inner: seq,
+            index: 0,
+            target: self.target,
+            slot: self.slot,
+        })
+    }
+}
+
+///Passes the fields in front of the requested one to the struct and takes the requested one
+struct ProjectSeq<'s,A,T> {
+    inner: A,
+    index: usize,
+    target: usize,
+    slot: &'s mut Option<T>,
+}
+
+impl<'de,'s,A: SeqAccess<'de>,T: Deserialize<'de>> SeqAccess<'de> for ProjectSeq<'s,A,T> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>,A::Error> {
+        if self.index == self.target {
+            *self.slot = self.inner.next_element::<T>()?;
+            return Err(de::Error::custom(PROJECTED));
+        }
+        self.index += 1;
+        self.inner.next_element_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Loads the field with the given name of a struct `S` stored with `add_serde_entry`, the
+    ///fields behind it are skipped. Fails with `MemBufferError::SerdeFailure` if `S` is no struct
+    ///or has no field with this name.
+    pub fn load_serde_field<S: Deserialize<'a>,T: Deserialize<'a>>(&self, key: usize, field: &str) -> Result<T,MemBufferError> {
+        let data: &'a [u8] = self.load_entry(key)?;
+        //The options of bincode::serialize which is used to write the entries
+        let mut deserializer = bincode::Deserializer::from_slice(data,bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes());
+        let mut slot = None;
+        let result = S::deserialize(Projection {
+            inner: &mut deserializer,
+            field,
+            slot: &mut slot,
+        });
+        match (slot,result) {
+            (Some(value),_) => Ok(value),
+            (None,Err(err)) => Err(MemBufferError::SerdeFailure(err.to_string())),
+            (None,Ok(_)) => Err(MemBufferError::SerdeFailure(format!("missing field {}",field))),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError};
+    use serde::{Serialize,Deserialize};
+
+    #[derive(Serialize,Deserialize,Debug,PartialEq)]
+    struct Inner {
+        id: u64,
+        tags: Vec<String>,
+    }
+
+    #[derive(Serialize,Deserialize)]
+    struct Record {
+        name: String,
+        inner: Inner,
+        payload: Vec<u8>,
+        score: f64,
+    }
+
+    #[test]
+    fn check_serde_field() {
+        let record = Record {
+            name: "first".to_string(),
+            inner: Inner { id: 7, tags: vec!["a".to_string(),"b".to_string()] },
+            payload: vec![1;1000],
+            score: 0.5,
+        };
+        let mut writer = MemBufferWriter::new();
+        writer.add_serde_entry(&record).unwrap();
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.load_serde_field::<Record,&str>(0,"name").unwrap(),"first");
+        assert_eq!(reader.load_serde_field::<Record,Inner>(0,"inner").unwrap(),record.inner);
+        assert_eq!(reader.load_serde_field::<Record,&[u8]>(0,"payload").unwrap(),&record.payload[..]);
+        assert_eq!(reader.load_serde_field::<Record,f64>(0,"score").unwrap(),0.5);
+        assert!(matches!(reader.load_serde_field::<Record,u64>(0,"missing"),Err(MemBufferError::SerdeFailure(_))));
+        assert!(matches!(reader.load_serde_field::<u64,u64>(0,"name"),Err(MemBufferError::SerdeFailure(_))));
+    }
+}
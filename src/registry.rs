@@ -0,0 +1,221 @@
+//!Names of user defined types. Libraries pick their type ids freely, so a `TypeRegistry` maps
+//!every id to the name of the type using it and rejects ids registered twice or colliding with the
+//!built in types. A writer created `with_type_registry` stores the names of the types it holds in
+//!a metadata entry, `MemBufferReader::check_types` compares them against the registry of the
+//!reading side and reports unknown or conflicting types instead of misinterpreting their bytes.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferSerialize,MemBufferTypes,TypeRegistry,MemBufferError};
+//!use std::borrow::Cow;
+//!
+//!struct Point(u8,u8);
+//!
+//!impl MemBufferSerialize for Point {
+//!    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+//!        Cow::Owned(vec![self.0,self.1])
+//!    }
+//!
+//!    fn get_mem_buffer_type() -> i32 {
+//!        MemBufferTypes::LastPreDefienedValue as i32
+//!    }
+//!}
+//!
+//!let mut registry = TypeRegistry::new();
+//!registry.register::<Point>("geometry::Point").unwrap();
+//!assert!(registry.register_id("color::Rgb",MemBufferTypes::LastPreDefienedValue as i32).is_err());
+//!
+//!let mut writer = MemBufferWriter::new().with_type_registry(&registry);
+//!writer.add_entry(Point(1,2));
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert!(reader.check_types(&registry).is_ok());
+//!assert!(matches!(reader.check_types(&TypeRegistry::new()),Err(MemBufferError::UnknownType(_))));
+//!```
+use alloc::collections::BTreeMap;
+use crate::{MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferTypes,Value,SYSTEM_TYPE_REGISTRY,LAST_RESERVED_TYPE,is_system_type};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Returns whether the id belongs to a type defined by this crate
+fn is_builtin_type(id: i32) -> bool {
+    (0..MemBufferTypes::LastPreDefienedValue as i32).contains(&id) || id == MemBufferTypes::InternedText as i32
+        || (MemBufferTypes::Integer64 as i32..=LAST_RESERVED_TYPE).contains(&id) || is_system_type(id)
+}
+
+///Maps the ids of user defined types to their names
+#[derive(Debug, Clone, Default)]
+pub struct TypeRegistry {
+    names: BTreeMap<i32,String>,
+}
+
+impl TypeRegistry {
+    pub fn new() -> TypeRegistry {
+        TypeRegistry::default()
+    }
+
+    ///Registers the type under its id, the serialization of the type is given by its trait
+    ///implementations
+    pub fn register<T: MemBufferSerialize>(&mut self, name: &str) -> Result<(),MemBufferError> {
+        self.register_id(name,T::get_mem_buffer_type())
+    }
+
+    ///Registers the id for the type with the given name, fails with `MemBufferError::TypeConflict`
+    ///if the id is built in or already registered with another name
+    pub fn register_id(&mut self, name: &str, id: i32) -> Result<(),MemBufferError> {
+        if is_builtin_type(id) {
+            return Err(MemBufferError::TypeConflict(id,"membuffer".to_string()));
+        }
+        match self.names.get(&id) {
+            Some(existing) if existing != name => Err(MemBufferError::TypeConflict(id,existing.clone())),
+            Some(_) => Ok(()),
+            None => {
+                self.names.insert(id,name.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    ///Returns the name registered for the id
+    pub fn name_of(&self, id: i32) -> Option<&str> {
+        self.names.get(&id).map(|x| &x[..])
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Stores the names of the registered types held by the buffer when it is written
+    pub fn with_type_registry(mut self, registry: &TypeRegistry) -> MemBufferWriter {
+        self.type_names.extend(registry.names.iter().map(|(id,name)| (*id,name.clone())));
+        self
+    }
+
+    ///Serializes the id and the name of every registered type used by an entry as nested buffer
+    pub(crate) fn type_table(&self) -> Option<Vec<u8>> {
+        let used: Vec<(&i32,&String)> = self.type_names.iter().filter(|(id,_)| self.types.contains(id)).collect();
+        if used.is_empty() {
+            return None;
+        }
+        let mut table = MemBufferWriter::new();
+        for (id,name) in used {
+            table.add_entry(*id);
+            table.add_entry(name);
+        }
+        Some(table.finalize())
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the ids and names of the registered types stored by the writer
+    pub fn type_names(&self) -> Result<Vec<(i32,&'a str)>,MemBufferError> {
+        let table = match self.system_entry(SYSTEM_TYPE_REGISTRY) {
+            Some(table) => MemBufferReader::new(table)?,
+            None => return Ok(Vec::new()),
+        };
+        if !table.len().is_multiple_of(2) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        (0..table.len()/2).map(|x| match table.load_value(2*x+1)? {
+            Value::Text(name) => Ok((table.load_entry::<i32>(2*x)?,name)),
+            _ => Err(MemBufferError::WrongFormat),
+        }).collect()
+    }
+
+    ///Checks every user defined type of the entries against the registry, fails with
+    ///`MemBufferError::UnknownType` for ids the registry does not know and with
+    ///`MemBufferError::TypeConflict` if the writer stored another name for the id
+    pub fn check_types(&self, registry: &TypeRegistry) -> Result<(),MemBufferError> {
+        let stored = self.type_names()?;
+        for entry in self.offsets.iter().filter(|x| !is_builtin_type(x.variable_type)) {
+            let name = registry.name_of(entry.variable_type).ok_or(MemBufferError::UnknownType(entry.variable_type))?;
+            if let Some((_,stored)) = stored.iter().find(|x| x.0 == entry.variable_type) {
+                if *stored != name {
+                    return Err(MemBufferError::TypeConflict(entry.variable_type,stored.to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    ///Creates the reader like `new_validated` and fails with `MemBufferError::UnknownType` if an
+    ///entry uses a user defined type which is not allowed, so untrusted buffers cannot carry
+    ///opaque payloads of unexpected types. Built in types are always allowed.
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferReader,MemBufferError};
+    ///
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("Hello");
+    ///let data = writer.finalize();
+    ///assert!(MemBufferReader::new_strict(&data,&[]).is_ok());
+    ///```
+    pub fn new_strict(val: &'a [u8], allowed: &[i32]) -> Result<MemBufferReader<'a>,MemBufferError> {
+        let reader = MemBufferReader::new_validated(val)?;
+        reader.check_allowed_types(allowed)?;
+        Ok(reader)
+    }
+
+    ///Fails with `MemBufferError::UnknownType` for the first entry whose user defined type is not
+    ///in the allowed ids
+    pub fn check_allowed_types(&self, allowed: &[i32]) -> Result<(),MemBufferError> {
+        match self.offsets.iter().find(|x| !is_builtin_type(x.variable_type) && !allowed.contains(&x.variable_type)) {
+            Some(entry) => Err(MemBufferError::UnknownType(entry.variable_type)),
+            None => Ok(()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,TypeRegistry};
+
+    #[test]
+    fn check_type_registry() {
+        let mut registry = TypeRegistry::new();
+        registry.register_id("first::Type",100).unwrap();
+        registry.register_id("first::Type",100).unwrap();
+        registry.register_id("second::Unused",101).unwrap();
+        assert!(matches!(registry.register_id("second::Type",100),Err(MemBufferError::TypeConflict(100,x)) if x == "first::Type"));
+        assert!(registry.register::<u64>("second::Integer").is_err());
+        assert!(registry.register_id("second::Text",0).is_err());
+
+        let mut nested = MemBufferWriter::new();
+        nested.add_entry(1);
+        let mut writer = MemBufferWriter::new().with_type_registry(&registry).with_byte_order(crate::Endianness::Big);
+        writer.add_entry("Hello");
+        writer.add_entry(nested);
+        writer.types.push(100);
+        writer.data.push(vec![1,2,3].into());
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.type_names().unwrap(),vec![(100,"first::Type")]);
+        reader.check_types(&registry).unwrap();
+        let mut other = TypeRegistry::new();
+        other.register_id("other::Type",100).unwrap();
+        assert!(matches!(reader.check_types(&other),Err(MemBufferError::TypeConflict(100,x)) if x == "first::Type"));
+        assert!(matches!(reader.check_types(&TypeRegistry::new()),Err(MemBufferError::UnknownType(100))));
+
+        //Writers loaded from the buffer keep the names
+        let copy = MemBufferWriter::from(&result).unwrap().finalize();
+        assert_eq!(MemBufferReader::new(&copy).unwrap().type_names().unwrap(),vec![(100,"first::Type")]);
+    }
+
+    #[test]
+    fn check_allowed_types() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_null_entry();
+        writer.types.push(200);
+        writer.data.push(vec![1].into());
+        let result = writer.finalize();
+
+        assert!(matches!(MemBufferReader::new_strict(&result,&[100]),Err(MemBufferError::UnknownType(200))));
+        let reader = MemBufferReader::new_strict(&result,&[100,200]).unwrap();
+        assert_eq!(reader.len(),3);
+        writer.types.truncate(2);
+        writer.data.truncate(2);
+        assert!(MemBufferReader::new_strict(&writer.finalize(),&[]).is_ok());
+    }
+}
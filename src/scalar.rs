@@ -0,0 +1,161 @@
+//!Scalars of the remaining integer widths, `bool` and `char`. Every scalar has its own type id, so
+//!a `u16` cannot be loaded as `i16` and integers are swapped by their width when the buffer is read
+//!in the other byte order. `bool` is stored as one byte holding 0 or 1 and `char` as its code
+//!point in 4 bytes, other payloads fail to load with `MemBufferError::WrongFormat`.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferError};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry(200u8);
+//!writer.add_entry(-3i64);
+//!writer.add_entry(true);
+//!writer.add_entry('ß');
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry::<u8>(0).unwrap(),200);
+//!assert_eq!(reader.load_entry::<i64>(1).unwrap(),-3);
+//!assert!(reader.load_entry::<bool>(2).unwrap());
+//!assert_eq!(reader.load_entry::<char>(3).unwrap(),'ß');
+//!assert!(matches!(reader.load_entry::<u64>(1),Err(MemBufferError::FieldTypeError(_,_))));
+//!```
+use alloc::borrow::Cow;
+use crate::{MemBufferError,MemBufferTypes,MemBufferSerialize,MemBufferDeserialize};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Implements storing the integer type as scalar with the given type id
+macro_rules! integer_scalar {
+    ($integer:ty,$variable_type:ident) => {
+        impl MemBufferSerialize for $integer {
+            fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+                Cow::Owned(self.to_ne_bytes().to_vec())
+            }
+
+            fn get_mem_buffer_type() -> i32 {
+                MemBufferTypes::$variable_type.into()
+            }
+        }
+
+        impl<'a> MemBufferDeserialize<'a,$integer> for $integer {
+            fn from_mem_buffer(mem: &'a [u8]) -> Result<$integer,MemBufferError> {
+                let mut bytes = [0u8;core::mem::size_of::<$integer>()];
+                if mem.len() != bytes.len() {
+                    return Err(MemBufferError::WrongFormat);
+                }
+                bytes.copy_from_slice(mem);
+                Ok(<$integer>::from_ne_bytes(bytes))
+            }
+
+            fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<$integer,MemBufferError> {
+                Ok(<$integer>::from_mem_buffer(mem)?.swap_bytes())
+            }
+
+            fn from_decompressed(mem: Vec<u8>) -> Result<$integer,MemBufferError> {
+                <$integer>::from_mem_buffer(&mem)
+            }
+        }
+    };
+}
+
+integer_scalar!(u8,Unsigned8);
+integer_scalar!(u16,Unsigned16);
+integer_scalar!(u32,Unsigned32);
+integer_scalar!(i8,Signed8);
+integer_scalar!(i16,Signed16);
+integer_scalar!(i64,Signed64);
+
+impl MemBufferSerialize for bool {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Owned(vec![*self as u8])
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::Bool.into()
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,bool> for bool {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<bool,MemBufferError> {
+        match mem {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(MemBufferError::WrongFormat),
+        }
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<bool,MemBufferError> {
+        bool::from_mem_buffer(&mem)
+    }
+}
+
+impl MemBufferSerialize for char {
+    fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+        Cow::Owned((*self as u32).to_ne_bytes().to_vec())
+    }
+
+    fn get_mem_buffer_type() -> i32 {
+        MemBufferTypes::Char.into()
+    }
+}
+
+impl<'a> MemBufferDeserialize<'a,char> for char {
+    fn from_mem_buffer(mem: &'a [u8]) -> Result<char,MemBufferError> {
+        char::from_u32(u32::from_mem_buffer(mem)?).ok_or(MemBufferError::WrongFormat)
+    }
+
+    fn from_swapped_mem_buffer(mem: &'a [u8]) -> Result<char,MemBufferError> {
+        char::from_u32(u32::from_swapped_mem_buffer(mem)?).ok_or(MemBufferError::WrongFormat)
+    }
+
+    fn from_decompressed(mem: Vec<u8>) -> Result<char,MemBufferError> {
+        char::from_mem_buffer(&mem)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferTypes,Endianness,Value};
+
+    #[test]
+    fn check_scalars() {
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big);
+        writer.add_entry(0xABu8);
+        writer.add_entry(0xABCDu16);
+        writer.add_entry(0xABCD_EF01u32);
+        writer.add_entry(-8i8);
+        writer.add_entry(-1600i16);
+        writer.add_entry(i64::MIN+7);
+        writer.add_entry(false);
+        writer.add_entry('😀');
+        writer.add_raw_entry(MemBufferTypes::Bool as i32,&[2]);
+        writer.add_raw_entry(MemBufferTypes::Char as i32,&0xD800u32.to_ne_bytes());
+        let data = writer.finalize();
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.load_entry::<u8>(0).unwrap(),0xAB);
+        assert_eq!(reader.load_entry::<u16>(1).unwrap(),0xABCD);
+        assert_eq!(reader.load_entry::<u32>(2).unwrap(),0xABCD_EF01);
+        assert_eq!(reader.load_entry::<i8>(3).unwrap(),-8);
+        assert_eq!(reader.load_entry::<i16>(4).unwrap(),-1600);
+        assert_eq!(reader.load_entry::<i64>(5).unwrap(),i64::MIN+7);
+        assert!(!reader.load_entry::<bool>(6).unwrap());
+        assert_eq!(reader.load_entry::<char>(7).unwrap(),'😀');
+        assert!(matches!(reader.load_entry::<bool>(8),Err(MemBufferError::WrongFormat)));
+        assert!(matches!(reader.load_entry::<char>(9),Err(MemBufferError::WrongFormat)));
+        assert!(matches!(reader.load_entry::<i16>(1),Err(MemBufferError::FieldTypeError(_,_))));
+        assert!(matches!(reader.load_value(4).unwrap(),Value::I16(-1600)));
+        assert!(matches!(reader.load_value(7).unwrap(),Value::Char('😀')));
+
+        //Values keep their types when copied
+        let mut copy = MemBufferWriter::new();
+        for value in reader.values().take(8) {
+            copy.add_value(&value.unwrap()).unwrap();
+        }
+        let copied = copy.finalize();
+        let copied = MemBufferReader::new(&copied).unwrap();
+        assert!(copied.values().zip(reader.values()).all(|(x,y)| x.unwrap().variable_type() == y.unwrap().variable_type()));
+        assert_eq!(copied.load_entry::<u32>(2).unwrap(),0xABCD_EF01);
+    }
+}
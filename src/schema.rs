@@ -0,0 +1,300 @@
+//!Schemas naming the entries of buffers with a stable layout. `Schema::new().field::<T>(name)`
+//!assigns the next index to every field and `Schema::check` verifies that a reader holds the
+//!fields with their types. The `schema!` macro generates a typed writer and reader with one
+//!setter and one getter per field whose indices are fixed at compile time, so entries cannot be
+//!mixed up by position.
+//!
+//!A writer created `with_schema` checks every entry when it is added, set or inserted and panics
+//!if its type does not match the field at its index, so producer bugs surface where the entry is
+//!written instead of as `MemBufferError::FieldTypeError` in some consumer. Interned text is
+//!accepted for text fields and null entries for every field like in `Schema::check`.
+//!```rust
+//!use membuffer::{Schema,MemBufferWriter,MemBufferReader};
+//!
+//!membuffer::schema! {
+//!    ///Books of the catalogue
+//!    pub struct BookWriter, BookReader {
+//!        title, set_title: &str,
+//!        ids, set_ids: &[u64],
+//!        year, set_year: i32,
+//!    }
+//!}
+//!
+//!let mut writer = BookWriter::new();
+//!writer.set_year(1818);
+//!writer.set_title("Frankenstein");
+//!let data = writer.finalize();
+//!
+//!let reader = BookReader::new(&data).unwrap();
+//!assert_eq!(reader.title(),"Frankenstein");
+//!assert!(reader.ids().is_empty());
+//!assert_eq!(reader.year(),1818);
+//!
+//!let schema = Schema::new().field::<&str>("title").field::<&[u64]>("ids").field::<i32>("year").build();
+//!assert_eq!(schema,BookReader::schema());
+//!assert_eq!(schema.index_of("year"),Some(2));
+//!assert!(schema.check(&MemBufferReader::new(&data).unwrap()).is_ok());
+//!
+//!let mut writer = MemBufferWriter::new().with_schema(schema);
+//!writer.add_entry("Dracula");
+//!writer.add_entry::<&[u64]>(&[7]);
+//!writer.add_entry(1897);
+//!assert!(BookReader::new(&writer.finalize()).is_ok());
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferTypes};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Names and types of the entries of a buffer in the order of their indices
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schema {
+    fields: Vec<(String,i32)>,
+}
+
+///Builder assigning the indices of the fields of a schema
+#[derive(Debug, Clone, Default)]
+pub struct SchemaBuilder {
+    fields: Vec<(String,i32)>,
+}
+
+impl SchemaBuilder {
+    ///Appends a field with the type of `T`, it gets the next index
+    pub fn field<T: MemBufferSerialize>(mut self, name: &str) -> SchemaBuilder {
+        self.fields.push((name.to_string(),T::get_mem_buffer_type()));
+        self
+    }
+
+    pub fn build(self) -> Schema {
+        Schema {
+            fields: self.fields,
+        }
+    }
+}
+
+impl Schema {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> SchemaBuilder {
+        SchemaBuilder::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    ///Returns the index of the field with the given name
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|x| x.0 == name)
+    }
+
+    ///Returns the name and the type id of the field at the given index
+    pub fn field(&self, index: usize) -> Option<(&str,i32)> {
+        self.fields.get(index).map(|x| (&x.0[..],x.1))
+    }
+
+    ///Checks that the reader holds exactly the fields of the schema, fails with
+    ///`MemBufferError::FieldTypeError` for the first entry with another type. Interned text is
+    ///accepted for text fields and null entries for every field, they mark fields left empty.
+    pub fn check(&self, reader: &MemBufferReader) -> Result<(),MemBufferError> {
+        if reader.len() != self.fields.len() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        for (index,entry) in reader.offsets.iter().enumerate() {
+            if self.accepts(index,entry.variable_type) != Some(true) {
+                return Err(MemBufferError::FieldTypeError(entry.variable_type,self.fields[index].1));
+            }
+        }
+        Ok(())
+    }
+
+    ///Returns whether an entry of the type can be stored in the field at the given index
+    fn accepts(&self, index: usize, variable_type: i32) -> Option<bool> {
+        let expected = self.fields.get(index)?.1;
+        let interned = variable_type == MemBufferTypes::InternedText as i32 && expected == MemBufferTypes::Text as i32;
+        Some(variable_type == expected || interned || variable_type == MemBufferTypes::Null as i32)
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Checks every entry added, set or inserted against the schema, see the module documentation
+    pub fn with_schema(mut self, schema: Schema) -> MemBufferWriter {
+        self.schema = Some(schema);
+        self
+    }
+
+    ///Returns the schema the entries are checked against
+    pub fn schema(&self) -> Option<&Schema> {
+        self.schema.as_ref()
+    }
+
+    ///Panics if the writer has a schema and the entry at the index cannot have the type
+    pub(crate) fn check_schema_field(&self, index: usize, variable_type: i32) {
+        if let Some(schema) = self.schema.as_ref() {
+            match schema.accepts(index,variable_type) {
+                Some(accepted) => assert!(accepted,"Entry {} has the type {} but the schema declares {}",index,variable_type,schema.fields[index].1),
+                None => panic!("Entry {} is not a field of the schema",index),
+            }
+        }
+    }
+}
+
+///Generates a writer and a reader for a schema, see the module documentation of `Schema`. Every
+///field is written as `name, setter: type` and its type has to implement `Default`, which is the
+///value of fields that are never set. The getters panic for entries which cannot be borrowed like integer
+///slices of buffers written with the other byte order.
+#[macro_export]
+macro_rules! schema {
+    ($(#[$meta:meta])* $vis:vis struct $writer:ident, $reader:ident { $($field:ident, $setter:ident: $ty:ty),* $(,)? }) => {
+        $(#[$meta])*
+        $vis struct $writer {
+            writer: $crate::MemBufferWriter,
+        }
+
+        #[allow(dead_code)]
+        impl $writer {
+            pub fn new() -> $writer {
+                let mut writer = $crate::MemBufferWriter::new();
+                $(writer.add_entry(<$ty as ::std::default::Default>::default());)*
+                $writer {
+                    writer,
+                }
+            }
+
+            pub fn finalize(&self) -> ::std::vec::Vec<u8> {
+                self.writer.finalize()
+            }
+
+            pub fn into_writer(self) -> $crate::MemBufferWriter {
+                self.writer
+            }
+        }
+
+        impl ::std::default::Default for $writer {
+            fn default() -> $writer {
+                $writer::new()
+            }
+        }
+
+        $(#[$meta])*
+        $vis struct $reader<'a> {
+            reader: $crate::MemBufferReader<'a>,
+        }
+
+        #[allow(dead_code)]
+        impl<'a> $reader<'a> {
+            ///Parses the buffer and checks it against the schema
+            pub fn new(data: &'a [u8]) -> ::std::result::Result<$reader<'a>,$crate::MemBufferError> {
+                let reader = $crate::MemBufferReader::new_validated(data)?;
+                $reader::schema().check(&reader)?;
+                Ok($reader {
+                    reader,
+                })
+            }
+
+            pub fn schema() -> $crate::Schema {
+                $crate::Schema::new()$(.field::<$ty>(stringify!($field)))*.build()
+            }
+
+            pub fn reader(&self) -> &$crate::MemBufferReader<'a> {
+                &self.reader
+            }
+        }
+
+        $crate::schema!(@fields $writer, $reader; 0; $($field, $setter: $ty),*);
+    };
+    //Generates the accessors of one field at a time, the index counts up with every field
+    (@fields $writer:ident, $reader:ident; $index:expr; $field:ident, $setter:ident: $ty:ty $(, $($rest:tt)*)?) => {
+        #[allow(dead_code)]
+        impl $writer {
+            pub fn $setter(&mut self, val: $ty) {
+                self.writer.set_entry(val,$index).expect("The writer holds every field of the schema");
+            }
+        }
+
+        #[allow(dead_code)]
+        impl<'a> $reader<'a> {
+            pub fn $field(&self) -> $ty {
+                //The type was checked against the schema when the reader was created
+                self.reader.load_entry::<$ty>($index).unwrap()
+            }
+        }
+
+        $crate::schema!(@fields $writer, $reader; $index+1; $($($rest)*)?);
+    };
+    (@fields $writer:ident, $reader:ident; $index:expr;) => {};
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferError,Schema};
+
+    crate::schema! {
+        struct PageWriter, PageReader {
+            text, set_text: &str,
+            links, set_links: &[u32],
+        }
+    }
+
+    #[test]
+    fn check_schema() {
+        let mut writer = PageWriter::default();
+        writer.set_links(&[3,4]);
+        let data = writer.finalize();
+        let reader = PageReader::new(&data).unwrap();
+        assert_eq!(reader.text(),"");
+        assert_eq!(reader.links(),&[3,4]);
+
+        let mut other = MemBufferWriter::new();
+        other.add_entry(&[3u32,4][..]);
+        other.add_entry("swapped");
+        assert!(matches!(PageReader::new(&other.finalize()),Err(MemBufferError::FieldTypeError(_,_))));
+        let mut short = MemBufferWriter::new();
+        short.add_entry("text");
+        assert!(matches!(PageReader::new(&short.finalize()),Err(MemBufferError::WrongFormat)));
+
+        let schema = Schema::new().field::<&str>("text").field::<&[u32]>("links").build();
+        assert_eq!(schema.len(),2);
+        assert_eq!(schema.field(1),Some(("links",PageReader::schema().field(1).unwrap().1)));
+        assert_eq!(schema.index_of("missing"),None);
+        let mut interned = MemBufferWriter::new();
+        interned.add_interned_entry("text");
+        interned.add_entry(&[1u32][..]);
+        let data = interned.finalize();
+        assert_eq!(PageReader::new(&data).unwrap().text(),"text");
+    }
+
+    #[test]
+    fn check_writer_schema() {
+        let mut writer = MemBufferWriter::new().with_schema(PageReader::schema());
+        writer.add_interned_entry("text");
+        writer.add_null_entry();
+        writer.set_entry(&[1u32][..],1).unwrap();
+        writer.set_entry("plain",0).unwrap();
+        assert_eq!(writer.schema(),Some(&PageReader::schema()));
+        assert_eq!(PageReader::new(&writer.finalize()).unwrap().links(),&[1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Entry 1 has the type")]
+    fn check_writer_schema_mismatch() {
+        let mut writer = MemBufferWriter::new().with_schema(PageReader::schema());
+        writer.add_entry("text");
+        writer.add_entry("swapped");
+    }
+
+    #[test]
+    #[should_panic(expected = "Entry 2 is not a field of the schema")]
+    fn check_writer_schema_extra_field() {
+        let mut writer = MemBufferWriter::new().with_schema(PageReader::schema());
+        writer.add_entry("text");
+        writer.add_entry(&[1u32][..]);
+        writer.add_entry(3);
+    }
+}
@@ -0,0 +1,782 @@
+//!Membuffer as serde data format. `to_vec` stores every field of a struct as entry of the buffer so
+//!readers can load single fields lazily, `from_slice` borrows text and byte fields directly from
+//!the buffer. Scalars are stored as `i32` or `u64` entries, text and bytes as text and byte
+//!entries, all other values as nested buffers: sequences, tuples, structs and the content of
+//!`Some` hold one entry per element, maps the keys and values alternating and enum variants with
+//!data the variant name followed by the data. Unit variants are stored as text. The top level
+//!struct additionally stores its field names so the fields can be loaded by name.
+//!```rust
+//!use membuffer::{MemBufferReader,to_vec,from_slice};
+//!use serde::{Serialize,Deserialize};
+//!
+//!#[derive(Serialize,Deserialize,PartialEq,Debug)]
+//!struct Book<'a> {
+//!    title: &'a str,
+//!    year: i32,
+//!    tags: Vec<String>,
+//!}
+//!
+//!let book = Book { title: "Frankenstein", year: 1818, tags: vec!["gothic".to_string()] };
+//!let data = to_vec(&book).unwrap();
+//!assert_eq!(from_slice::<Book>(&data).unwrap(),book);
+//!
+//!//Every field is an entry of its own
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+//!```
+use serde::{de,Deserialize};
+#[cfg(feature = "std")]
+use serde::{ser,Serialize};
+use serde::de::{Visitor,IntoDeserializer};
+use serde::de::value::{SeqDeserializer,MapDeserializer,BorrowedStrDeserializer,BorrowedBytesDeserializer};
+use crate::{MemBufferReader,MemBufferError,Value};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,MemBufferTypes,MemBufferSerialize};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Serializes the value into a buffer, see the module documentation for the mapping of the values
+#[cfg(feature = "std")]
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>,MemBufferError> {
+    let mut holder = MemBufferWriter::new();
+    value.serialize(EntrySerializer { writer: &mut holder, top: true })?;
+    //A compound value already is a buffer on its own, scalars are wrapped into one
+    if holder.types == [MemBufferTypes::MemBuffer as i32] {
+        return Ok(holder.data.pop().unwrap().to_vec());
+    }
+    Ok(holder.finalize())
+}
+
+///Deserializes the value from a buffer written by `to_vec`, text and bytes are borrowed from the
+///buffer
+pub fn from_slice<'a, T: Deserialize<'a>>(data: &'a [u8]) -> Result<T,MemBufferError> {
+    T::deserialize(BufferDeserializer { reader: MemBufferReader::new(data)? })
+}
+
+#[cfg(feature = "std")]
+impl ser::Error for MemBufferError {
+    fn custom<T: core::fmt::Display>(msg: T) -> Self {
+        MemBufferError::SerdeFailure(msg.to_string())
+    }
+}
+
+//Without std serde requires its own error trait which std::error::Error replaces otherwise
+#[cfg(not(feature = "std"))]
+impl de::StdError for MemBufferError {}
+
+impl de::Error for MemBufferError {
+    fn custom<T: core::fmt::Display>(msg: T) -> Self {
+        MemBufferError::SerdeFailure(msg.to_string())
+    }
+}
+
+///Serializes one value as entry of the writer
+#[cfg(feature = "std")]
+struct EntrySerializer<'w> {
+    writer: &'w mut MemBufferWriter,
+    //Whether the value is the top level value whose struct fields get names
+    top: bool,
+}
+
+#[cfg(feature = "std")]
+impl<'w> EntrySerializer<'w> {
+    fn add<T: MemBufferSerialize>(self, val: T) -> Result<(),MemBufferError> {
+        self.writer.add_entry(val);
+        Ok(())
+    }
+
+    fn compound(self, variant: Option<&'static str>, named: bool) -> Compound<'w> {
+        Compound {
+            parent: self.writer,
+            nested: MemBufferWriter::new(),
+            variant,
+            named: named && self.top,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w> ser::Serializer for EntrySerializer<'w> {
+    type Ok = ();
+    type Error = MemBufferError;
+    type SerializeSeq = Compound<'w>;
+    type SerializeTuple = Compound<'w>;
+    type SerializeTupleStruct = Compound<'w>;
+    type SerializeTupleVariant = Compound<'w>;
+    type SerializeMap = Compound<'w>;
+    type SerializeStruct = Compound<'w>;
+    type SerializeStructVariant = Compound<'w>;
+
+    fn serialize_bool(self, v: bool) -> Result<(),MemBufferError> {
+        self.add(v as i32)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(),MemBufferError> {
+        self.add(v as i32)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(),MemBufferError> {
+        self.add(v as i32)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(),MemBufferError> {
+        self.add(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(),MemBufferError> {
+        self.add(v as u64)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(),MemBufferError> {
+        self.add(v as i32)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(),MemBufferError> {
+        self.add(v as i32)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(),MemBufferError> {
+        self.add(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(),MemBufferError> {
+        self.add(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(),MemBufferError> {
+        self.add(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(),MemBufferError> {
+        self.add(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(),MemBufferError> {
+        let mut buffer = [0u8;4];
+        let text: &str = v.encode_utf8(&mut buffer);
+        self.add(text)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(),MemBufferError> {
+        self.add(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(),MemBufferError> {
+        self.add(v)
+    }
+
+    fn serialize_none(self) -> Result<(),MemBufferError> {
+        self.add(MemBufferWriter::new())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(),MemBufferError> {
+        let mut nested = MemBufferWriter::new();
+        value.serialize(EntrySerializer { writer: &mut nested, top: false })?;
+        self.add(nested)
+    }
+
+    fn serialize_unit(self) -> Result<(),MemBufferError> {
+        self.add(MemBufferWriter::new())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(),MemBufferError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(),MemBufferError> {
+        self.add(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<(),MemBufferError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<(),MemBufferError> {
+        let mut nested = MemBufferWriter::new();
+        nested.add_entry(variant);
+        value.serialize(EntrySerializer { writer: &mut nested, top: false })?;
+        self.add(nested)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'w>,MemBufferError> {
+        Ok(self.compound(None,false))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Compound<'w>,MemBufferError> {
+        Ok(self.compound(None,false))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Compound<'w>,MemBufferError> {
+        Ok(self.compound(None,false))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Compound<'w>,MemBufferError> {
+        Ok(self.compound(Some(variant),false))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'w>,MemBufferError> {
+        Ok(self.compound(None,false))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Compound<'w>,MemBufferError> {
+        Ok(self.compound(None,true))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Compound<'w>,MemBufferError> {
+        Ok(self.compound(Some(variant),false))
+    }
+}
+
+///Collects the elements of a compound value into a nested buffer which is added to the parent
+///when the value ends
+#[cfg(feature = "std")]
+struct Compound<'w> {
+    parent: &'w mut MemBufferWriter,
+    nested: MemBufferWriter,
+    variant: Option<&'static str>,
+    named: bool,
+}
+
+#[cfg(feature = "std")]
+impl<'w> Compound<'w> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(),MemBufferError> {
+        value.serialize(EntrySerializer { writer: &mut self.nested, top: false })
+    }
+
+    fn finish(self) -> Result<(),MemBufferError> {
+        match self.variant {
+            Some(variant) => {
+                let mut outer = MemBufferWriter::new();
+                outer.add_entry(variant);
+                outer.add_entry(self.nested);
+                self.parent.add_entry(outer);
+            },
+            None => self.parent.add_entry(self.nested),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w> ser::SerializeSeq for Compound<'w> {
+    type Ok = ();
+    type Error = MemBufferError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(),MemBufferError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(),MemBufferError> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w> ser::SerializeTuple for Compound<'w> {
+    type Ok = ();
+    type Error = MemBufferError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(),MemBufferError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(),MemBufferError> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w> ser::SerializeTupleStruct for Compound<'w> {
+    type Ok = ();
+    type Error = MemBufferError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(),MemBufferError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(),MemBufferError> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w> ser::SerializeTupleVariant for Compound<'w> {
+    type Ok = ();
+    type Error = MemBufferError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(),MemBufferError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(),MemBufferError> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w> ser::SerializeMap for Compound<'w> {
+    type Ok = ();
+    type Error = MemBufferError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(),MemBufferError> {
+        self.element(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(),MemBufferError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(),MemBufferError> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w> ser::SerializeStruct for Compound<'w> {
+    type Ok = ();
+    type Error = MemBufferError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(),MemBufferError> {
+        if self.named {
+            self.nested.names.insert(key.to_string(),self.nested.len());
+        }
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(),MemBufferError> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'w> ser::SerializeStructVariant for Compound<'w> {
+    type Ok = ();
+    type Error = MemBufferError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<(),MemBufferError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(),MemBufferError> {
+        self.finish()
+    }
+}
+
+///Deserializes the value stored in one entry of the reader
+struct EntryDeserializer<'r,'de> {
+    reader: &'r MemBufferReader<'de>,
+    index: usize,
+}
+
+impl<'r,'de> EntryDeserializer<'r,'de> {
+    fn nested(&self) -> Result<BufferAccess<'de>,MemBufferError> {
+        Ok(BufferAccess { reader: self.reader.load_recursive_reader(self.index)?, index: 0 })
+    }
+}
+
+impl<'r,'de> de::Deserializer<'de> for EntryDeserializer<'r,'de> {
+    type Error = MemBufferError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        match self.reader.load_value(self.index)? {
+            Value::Text(x) => visitor.visit_borrowed_str(x),
+            Value::I32(x) => visitor.visit_i32(x),
+            Value::U64(x) => visitor.visit_u64(x),
+            Value::F32(x) => visitor.visit_f32(x),
+            Value::F64(x) => visitor.visit_f64(x),
+            Value::Bytes(x) | Value::Unknown(_,x) => visitor.visit_borrowed_bytes(x),
+            Value::U32Slice(x) => visitor.visit_seq(SeqDeserializer::<_,MemBufferError>::new(x.iter().copied())),
+            Value::U64Slice(x) => visitor.visit_seq(SeqDeserializer::<_,MemBufferError>::new(x.iter().copied())),
+            Value::F32Slice(x) => visitor.visit_seq(SeqDeserializer::<_,MemBufferError>::new(x.iter().copied())),
+            Value::F64Slice(x) => visitor.visit_seq(SeqDeserializer::<_,MemBufferError>::new(x.iter().copied())),
+            Value::Nested(reader) => visitor.visit_seq(BufferAccess { reader, index: 0 }),
+            Value::Map(map) => visitor.visit_map(MapDeserializer::<_,MemBufferError>::new(map.iter().map(|(key,val)| (BorrowedStrDeserializer::new(key),BorrowedBytesDeserializer::new(val))))),
+            Value::Null => visitor.visit_none(),
+            Value::StrList(list) => visitor.visit_seq(SeqDeserializer::<_,MemBufferError>::new(list.iter().map(BorrowedStrDeserializer::new))),
+            Value::BytesList(list) => visitor.visit_seq(SeqDeserializer::<_,MemBufferError>::new(list.iter().map(BorrowedBytesDeserializer::new))),
+            Value::U8(x) => visitor.visit_u8(x),
+            Value::U16(x) => visitor.visit_u16(x),
+            Value::U32(x) => visitor.visit_u32(x),
+            Value::I8(x) => visitor.visit_i8(x),
+            Value::I16(x) => visitor.visit_i16(x),
+            Value::I64(x) => visitor.visit_i64(x),
+            Value::Bool(x) => visitor.visit_bool(x),
+            Value::Char(x) => visitor.visit_char(x),
+            Value::Chunked(x) => visitor.visit_byte_buf(x.to_vec()),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_bool(self.reader.load_entry::<i32>(self.index)? != 0)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_i32(visitor)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_i32(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_i32(self.reader.load_entry(self.index)?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_i64(self.reader.load_entry::<u64>(self.index)? as i64)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_i32(visitor)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_i32(visitor)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_u64(visitor)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_u64(self.reader.load_entry(self.index)?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_f32(self.reader.load_entry(self.index)?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_f64(self.reader.load_entry(self.index)?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_borrowed_str(self.reader.load_entry(self.index)?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_borrowed_bytes(self.reader.load_entry(self.index)?)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        if self.reader.is_null(self.index) {
+            return visitor.visit_none();
+        }
+        let nested = self.reader.load_recursive_reader(self.index)?;
+        if nested.is_empty() {
+            return visitor.visit_none();
+        }
+        visitor.visit_some(EntryDeserializer { reader: &nested, index: 0 })
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_seq(self.nested()?)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_map(self.nested()?)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value,MemBufferError> {
+        match self.reader.load_entry::<&str>(self.index) {
+            //Unit variants are stored as text
+            Ok(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Err(MemBufferError::FieldTypeError(_,_)) => visitor.visit_enum(self.nested()?),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        //Skipped entries are never loaded
+        visitor.visit_unit()
+    }
+}
+
+///Walks the entries of a buffer as sequence, map or enum variant
+struct BufferAccess<'de> {
+    reader: MemBufferReader<'de>,
+    index: usize,
+}
+
+impl<'de> BufferAccess<'de> {
+    fn next<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value,MemBufferError> {
+        self.index += 1;
+        seed.deserialize(EntryDeserializer { reader: &self.reader, index: self.index-1 })
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for BufferAccess<'de> {
+    type Error = MemBufferError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>,MemBufferError> {
+        if self.index >= self.reader.len() {
+            return Ok(None);
+        }
+        self.next(seed).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.reader.len().saturating_sub(self.index))
+    }
+}
+
+impl<'de> de::MapAccess<'de> for BufferAccess<'de> {
+    type Error = MemBufferError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>,MemBufferError> {
+        if self.index >= self.reader.len() {
+            return Ok(None);
+        }
+        self.next(seed).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value,MemBufferError> {
+        self.next(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.reader.len().saturating_sub(self.index)/2)
+    }
+}
+
+impl<'de> de::EnumAccess<'de> for BufferAccess<'de> {
+    type Error = MemBufferError;
+    type Variant = BufferAccess<'de>;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(mut self, seed: V) -> Result<(V::Value,BufferAccess<'de>),MemBufferError> {
+        let variant = self.next(seed)?;
+        Ok((variant,self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for BufferAccess<'de> {
+    type Error = MemBufferError;
+
+    fn unit_variant(self) -> Result<(),MemBufferError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(mut self, seed: T) -> Result<T::Value,MemBufferError> {
+        self.next(seed)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value,MemBufferError> {
+        de::Deserializer::deserialize_seq(EntryDeserializer { reader: &self.reader, index: self.index },visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value,MemBufferError> {
+        de::Deserializer::deserialize_seq(EntryDeserializer { reader: &self.reader, index: self.index },visitor)
+    }
+}
+
+///Deserializes the value stored as whole buffer, scalars are read from the only entry
+struct BufferDeserializer<'de> {
+    reader: MemBufferReader<'de>,
+}
+
+macro_rules! forward_to_first_entry {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+                de::Deserializer::$method(EntryDeserializer { reader: &self.reader, index: 0 },visitor)
+            }
+        )*
+    }
+}
+
+impl<'de> de::Deserializer<'de> for BufferDeserializer<'de> {
+    type Error = MemBufferError;
+
+    forward_to_first_entry! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_u8
+        deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        if self.reader.is_empty() {
+            return visitor.visit_none();
+        }
+        visitor.visit_some(EntryDeserializer { reader: &self.reader, index: 0 })
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_seq(BufferAccess { reader: self.reader, index: 0 })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_map(BufferAccess { reader: self.reader, index: 0 })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value,MemBufferError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value,MemBufferError> {
+        //A unit variant is wrapped like any other scalar
+        if self.reader.len() == 1 {
+            return de::Deserializer::deserialize_enum(EntryDeserializer { reader: &self.reader, index: 0 },name,variants,visitor);
+        }
+        visitor.visit_enum(BufferAccess { reader: self.reader, index: 0 })
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value,MemBufferError> {
+        visitor.visit_unit()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferReader,MemBufferError,to_vec,from_slice};
+    use serde::{Serialize,Deserialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize,Deserialize,PartialEq,Debug)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Point(i32,i32),
+        Rect { width: u32, height: u32 },
+    }
+
+    #[derive(Serialize,Deserialize,PartialEq,Debug)]
+    struct Document<'a> {
+        title: &'a str,
+        #[serde(with = "serde_bytes_slice")]
+        raw: &'a [u8],
+        owned: String,
+        flag: bool,
+        small: i8,
+        large: i64,
+        unsigned: u64,
+        ratio: f32,
+        letter: char,
+        missing: Option<u16>,
+        present: Option<Vec<u32>>,
+        shapes: Vec<Shape>,
+        lookup: BTreeMap<String,(u8,bool)>,
+        unit: (),
+    }
+
+    mod serde_bytes_slice {
+        pub fn serialize<S: serde::Serializer>(val: &[u8], serializer: S) -> Result<S::Ok,S::Error> {
+            serializer.serialize_bytes(val)
+        }
+
+        pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<&'de [u8],D::Error> {
+            serde::Deserialize::deserialize(deserializer)
+        }
+    }
+
+    #[test]
+    fn check_serde_format() {
+        let raw = vec![1u8,2,3];
+        let mut lookup = BTreeMap::new();
+        lookup.insert("a".to_string(),(1,true));
+        lookup.insert("b".to_string(),(2,false));
+        let document = Document {
+            title: "Frankenstein",
+            raw: &raw,
+            owned: "owned".to_string(),
+            flag: true,
+            small: -5,
+            large: -1_000_000_000_000,
+            unsigned: u64::MAX,
+            ratio: 0.5,
+            letter: 'ß',
+            missing: None,
+            present: Some(vec![1,2,3]),
+            shapes: vec![Shape::Empty,Shape::Circle(1.5),Shape::Point(-1,2),Shape::Rect { width: 3, height: 4 }],
+            lookup,
+            unit: (),
+        };
+        let data = to_vec(&document).unwrap();
+        let loaded: Document = from_slice(&data).unwrap();
+        assert_eq!(loaded,document);
+        //Text and bytes are borrowed from the buffer
+        assert!(data.as_ptr_range().contains(&loaded.title.as_ptr()));
+        assert!(data.as_ptr_range().contains(&loaded.raw.as_ptr()));
+
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert_eq!(reader.len(),14);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Frankenstein");
+        assert_eq!(reader.load_entry_by_name::<u64>("unsigned").unwrap(),u64::MAX);
+    }
+
+    #[test]
+    fn check_serde_format_values() {
+        assert_eq!(from_slice::<i32>(&to_vec(&7).unwrap()).unwrap(),7);
+        assert_eq!(from_slice::<Option<i32>>(&to_vec(&Some(7)).unwrap()).unwrap(),Some(7));
+        assert_eq!(from_slice::<Option<i32>>(&to_vec(&None::<i32>).unwrap()).unwrap(),None);
+        assert_eq!(from_slice::<Vec<&str>>(&to_vec(&vec!["a","b"]).unwrap()).unwrap(),vec!["a","b"]);
+        assert_eq!(from_slice::<Shape>(&to_vec(&Shape::Empty).unwrap()).unwrap(),Shape::Empty);
+        assert_eq!(from_slice::<Shape>(&to_vec(&Shape::Point(1,2)).unwrap()).unwrap(),Shape::Point(1,2));
+        assert!(matches!(from_slice::<Vec<i32>>(&to_vec(&vec!["a"]).unwrap()),Err(MemBufferError::FieldTypeError(_,_))));
+        assert!(matches!(from_slice::<u8>(&to_vec(&1000).unwrap()),Err(MemBufferError::SerdeFailure(_))));
+    }
+}
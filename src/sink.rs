@@ -0,0 +1,183 @@
+//!Writer serializing into any `io::Write` with entries whose payload is pulled from an `io::Read`
+//!while the buffer is written. Only the header and the small entries are kept in memory, so large
+//!fields can be copied from a file into the output without holding them in memory as a whole.
+//!Entries added with `add_entry_borrowed` are referenced until the buffer is written instead of
+//!being copied into the writer.
+//!```rust
+//!use membuffer::{MemBufferStreamWriter,MemBufferReader,MemBufferTypes};
+//!
+//!let large = vec![7u8;100_000];
+//!let mut writer = MemBufferStreamWriter::new();
+//!writer.add_entry("attachment.bin");
+//!writer.add_entry_from_reader(MemBufferTypes::VectorU8 as i32,large.len(),&large[..]);
+//!
+//!let mut out = Vec::new();
+//!writer.finalize_into(&mut out).unwrap();
+//!
+//!let reader = MemBufferReader::new_verified(&out).unwrap();
+//!assert_eq!(reader.load_entry::<&str>(0).unwrap(),"attachment.bin");
+//!assert_eq!(reader.load_entry::<&[u8]>(1).unwrap(),&large[..]);
+//!```
+use std::io::{Read,Write};
+use std::borrow::Cow;
+use crate::{MemBufferWriter,MemBufferSerialize,Payload};
+
+///Writer whose entries can be streamed from readers or borrowed until they are written, see the
+///module documentation
+pub struct MemBufferStreamWriter<'r> {
+    writer: MemBufferWriter,
+    //Entry index and payload of every streamed or borrowed entry in ascending order
+    streams: Vec<(usize,Payload<'r>)>,
+}
+
+impl<'r> MemBufferStreamWriter<'r> {
+    pub fn new() -> MemBufferStreamWriter<'r> {
+        MemBufferStreamWriter::from_writer(MemBufferWriter::new())
+    }
+
+    ///Continues with the entries and settings of the given writer
+    pub fn from_writer(writer: MemBufferWriter) -> MemBufferStreamWriter<'r> {
+        MemBufferStreamWriter {
+            writer,
+            streams: Vec::new(),
+        }
+    }
+
+    pub fn add_entry<T: MemBufferSerialize>(&mut self, val: T) {
+        self.writer.add_entry(val);
+    }
+
+    ///Adds an entry without copying its payload, the value is borrowed until the buffer is
+    ///written. Values which are converted when serialized like integers are stored as usual.
+    ///```rust
+    ///use membuffer::{MemBufferStreamWriter,MemBufferReader};
+    ///
+    ///let huge = "a".repeat(1_000_000);
+    ///let text = huge.as_str();
+    ///let mut writer = MemBufferStreamWriter::new();
+    ///writer.add_entry_borrowed(&text);
+    ///writer.add_entry_borrowed(&7);
+    ///let data = writer.finalize();
+    ///
+    ///let reader = MemBufferReader::new(&data).unwrap();
+    ///assert_eq!(reader.load_entry::<&str>(0).unwrap(),huge);
+    ///assert_eq!(reader.load_entry::<i32>(1).unwrap(),7);
+    ///```
+    pub fn add_entry_borrowed<T: MemBufferSerialize>(&mut self, val: &'r T) {
+        match val.to_mem_buffer() {
+            Cow::Borrowed(slice) => self.add_payload(T::get_mem_buffer_type(),Payload::Bytes(slice)),
+            Cow::Owned(data) => {
+                self.writer.types.push(T::get_mem_buffer_type());
+                self.writer.data.push(data.into());
+            }
+        }
+    }
+
+    ///Adds an entry of the given type whose payload is read from the reader when the buffer is
+    ///written, the reader has to provide at least `len` bytes
+    pub fn add_entry_from_reader<R: Read + 'r>(&mut self, variable_type: i32, len: usize, reader: R) {
+        self.add_payload(variable_type,Payload::Reader(Box::new(reader),len));
+    }
+
+    fn add_payload(&mut self, variable_type: i32, payload: Payload<'r>) {
+        self.streams.push((self.writer.len(),payload));
+        self.writer.types.push(variable_type);
+        self.writer.data.push(Vec::new().into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.writer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writer.is_empty()
+    }
+
+    ///Writes the buffer into the given writer and returns the number of bytes written. Fails with
+    ///`io::ErrorKind::UnexpectedEof` if a reader ends before its announced length.
+    pub fn finalize_into<W: Write>(self, w: W) -> std::io::Result<u64> {
+        let writer = self.writer;
+        let system = writer.system_entries();
+        let types: Vec<i32> = writer.types.iter().copied().chain(system.iter().map(|x| x.0)).collect();
+        let mut streams = self.streams.into_iter().peekable();
+        let mut payloads: Vec<Payload> = Vec::with_capacity(types.len());
+        for (x,data) in writer.payloads().into_iter().enumerate() {
+            match streams.next_if(|stream| stream.0 == x) {
+                Some((_,payload)) => payloads.push(payload),
+                None => payloads.push(data),
+            }
+        }
+        payloads.extend(system.iter().map(|x| Payload::Bytes(&x.1)));
+        writer.write_entries(&types,payloads,w)
+    }
+
+    ///Writes the buffer into memory, the borrowed entries are copied exactly once
+    pub fn finalize(self) -> Vec<u8> {
+        let mut var = Vec::new();
+        //Writing into a vector only fails if a reader fails
+        self.finalize_into(&mut var).unwrap();
+        var
+    }
+}
+
+impl<'r> Default for MemBufferStreamWriter<'r> {
+    fn default() -> Self {
+        MemBufferStreamWriter::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferStreamWriter,MemBufferWriter,MemBufferReader,MemBufferTypes};
+
+    #[test]
+    fn check_stream_writer() {
+        let numbers: Vec<u8> = (0..200_000u32).flat_map(|x| x.to_ne_bytes().to_vec()).collect();
+        let mut writer = MemBufferStreamWriter::from_writer(MemBufferWriter::new().with_field_checksums());
+        writer.add_entry("Hello");
+        writer.add_entry_from_reader(MemBufferTypes::VectorU32 as i32,numbers.len(),&numbers[..]);
+        writer.add_entry_from_reader(MemBufferTypes::Text as i32,5,&b"World and more"[..]);
+        writer.add_entry(42);
+        let mut out = Vec::new();
+        let written = writer.finalize_into(&mut out).unwrap();
+        assert_eq!(written as usize,out.len());
+
+        let reader = MemBufferReader::new_verified(&out).unwrap();
+        assert_eq!(reader.len(),4);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+        let loaded: &[u32] = reader.load_entry(1).unwrap();
+        assert_eq!(loaded.len(),200_000);
+        assert_eq!(loaded[199_999],199_999);
+        assert_eq!(reader.load_entry::<&str>(2).unwrap(),"World");
+        assert_eq!(reader.load_entry::<i32>(3).unwrap(),42);
+    }
+
+    #[test]
+    fn check_borrowed_entries() {
+        let huge = "Frankenstein".repeat(10_000);
+        let text = huge.as_str();
+        let ids: &[u64] = &[1,2,3];
+        let mut writer = MemBufferStreamWriter::new();
+        writer.add_entry_borrowed(&text);
+        writer.add_entry("copied");
+        writer.add_entry_borrowed(&ids);
+        writer.add_entry_borrowed(&-5);
+        let result = writer.finalize();
+
+        let mut copied = MemBufferWriter::new();
+        copied.add_entry(text);
+        copied.add_entry("copied");
+        copied.add_entry(ids);
+        copied.add_entry(-5);
+        assert_eq!(result,copied.finalize());
+    }
+
+    #[test]
+    fn check_stream_writer_short_reader() {
+        let mut writer = MemBufferStreamWriter::new();
+        writer.add_entry_from_reader(MemBufferTypes::Text as i32,10,&b"short"[..]);
+        let err = writer.finalize_into(Vec::new()).unwrap_err();
+        assert_eq!(err.kind(),std::io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,102 @@
+//!Frozen snapshots of a writer. The payloads of the entries are reference counted blocks, so
+//!`MemBufferWriter::snapshot` only copies the entry list while the writer continues to add and
+//!replace entries. A snapshot can be shared between threads, it loads entries directly and
+//!serializes the buffer the first time its bytes are requested.
+//!```rust
+//!use membuffer::MemBufferWriter;
+//!use std::sync::Arc;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("first draft");
+//!let snapshot = Arc::new(writer.snapshot());
+//!writer.set_entry("second draft",0).unwrap();
+//!
+//!let shared = snapshot.clone();
+//!std::thread::spawn(move || {
+//!    assert_eq!(shared.reader().load_entry::<&str>(0).unwrap(),"first draft");
+//!}).join().unwrap();
+//!assert_eq!(writer.load_entry::<&str>(0).unwrap(),"second draft");
+//!```
+use std::sync::OnceLock;
+use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferDeserialize};
+
+///Immutable state of a writer at the time the snapshot was taken
+pub struct FrozenBuffer {
+    writer: MemBufferWriter,
+    bytes: OnceLock<Vec<u8>>,
+}
+
+impl FrozenBuffer {
+    pub fn len(&self) -> usize {
+        self.writer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writer.is_empty()
+    }
+
+    ///Loads an entry without serializing the buffer
+    pub fn load_entry<'a, T: MemBufferDeserialize<'a,T>+MemBufferSerialize>(&'a self, index: usize) -> Result<T,MemBufferError> {
+        self.writer.load_entry(index)
+    }
+
+    ///Returns the serialized buffer, it is written once on the first call
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.get_or_init(|| self.writer.finalize())
+    }
+
+    ///Returns a reader over the serialized buffer
+    pub fn reader(&self) -> MemBufferReader<'_> {
+        //The buffer was just written by the writer
+        MemBufferReader::new(self.as_bytes()).unwrap()
+    }
+}
+
+impl MemBufferWriter {
+    ///Returns a snapshot of the current entries, the payloads are shared with the writer instead
+    ///of being copied
+    pub fn snapshot(&self) -> FrozenBuffer {
+        FrozenBuffer {
+            writer: self.clone(),
+            bytes: OnceLock::new(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::MemBufferWriter;
+    use std::sync::Arc;
+
+    #[test]
+    fn check_snapshot() {
+        let large = vec![7u8;100_000];
+        let mut writer = MemBufferWriter::new().with_field_checksums();
+        writer.add_entry(&large[..]);
+        writer.add_entry_named("state","draft");
+        let snapshot = Arc::new(writer.snapshot());
+        //The payload is shared and not copied
+        assert_eq!(snapshot.load_entry::<&[u8]>(0).unwrap().as_ptr(),writer.load_entry::<&[u8]>(0).unwrap().as_ptr());
+
+        writer.add_entry_named("state","final");
+        writer.add_entry("appended");
+
+        let threads: Vec<_> = (0..4).map(|_| {
+            let snapshot = snapshot.clone();
+            std::thread::spawn(move || {
+                let reader = snapshot.reader();
+                assert_eq!(reader.len(),2);
+                assert_eq!(reader.load_entry_by_name::<&str>("state").unwrap(),"draft");
+                assert_eq!(reader.load_entry::<&[u8]>(0).unwrap().len(),100_000);
+            })
+        }).collect();
+        threads.into_iter().for_each(|x| x.join().unwrap());
+        assert_eq!(snapshot.as_bytes().as_ptr(),snapshot.as_bytes().as_ptr());
+
+        let current = writer.finalize();
+        let reader = crate::MemBufferReader::new_verified(&current).unwrap();
+        assert_eq!(reader.load_entry_by_name::<&str>("state").unwrap(),"final");
+        assert_eq!(reader.len(),3);
+    }
+}
@@ -0,0 +1,201 @@
+//!Entries read from streams of unknown length. `add_entry_from_reader` reads the payload of an
+//!entry from an `io::Read` when it is added. Payloads up to the spool threshold are kept in memory,
+//!larger ones are spooled into a temporary file in the spool directory and copied from it when the
+//!buffer is written, so the memory of the writer stays bounded regardless of the size of the
+//!entries. The temporary files are removed when the last writer referring to them is dropped.
+//!
+//!The spool directory defaults to the temporary directory of the system and the threshold to
+//!1 MiB, both can be changed with `with_spool_directory` and `with_spool_threshold`. Compressing
+//!or encrypting a spooled entry and `finalize_parallel` read its payload into memory.
+//!```rust
+//!use std::io::Read;
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferTypes};
+//!
+//!let source = std::io::repeat(7).take(100_000);
+//!let mut writer = MemBufferWriter::new().with_spool_threshold(4096);
+//!writer.add_entry("attachment.bin");
+//!writer.add_entry_from_reader(MemBufferTypes::VectorU8 as i32,None,source).unwrap();
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry::<&[u8]>(1).unwrap(),&[7u8;100_000][..]);
+//!```
+use std::io::{Read,Seek,SeekFrom,Write};
+use std::fs::{File,OpenOptions};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use crate::{MemBufferWriter,Change,Payload,is_system_type};
+
+///Payloads up to this size are kept in memory unless the writer sets another threshold
+const DEFAULT_SPOOL_THRESHOLD: usize = 1 << 20;
+
+///Distinguishes the spool files of one process
+static SPOOL_FILES: AtomicUsize = AtomicUsize::new(0);
+
+///Where and from which size payloads read from streams are spooled
+#[derive(Debug, Clone)]
+pub(crate) struct SpoolSettings {
+    directory: Option<PathBuf>,
+    threshold: usize,
+}
+
+impl Default for SpoolSettings {
+    fn default() -> SpoolSettings {
+        SpoolSettings {
+            directory: None,
+            threshold: DEFAULT_SPOOL_THRESHOLD,
+        }
+    }
+}
+
+///Payload spooled into a temporary file which is removed when it is dropped
+#[derive(Debug)]
+pub(crate) struct SpooledPayload {
+    file: Mutex<File>,
+    path: PathBuf,
+    len: usize,
+}
+
+impl SpooledPayload {
+    ///Creates a new temporary file in the directory
+    fn create(settings: &SpoolSettings) -> std::io::Result<SpooledPayload> {
+        let directory = settings.directory.clone().unwrap_or_else(std::env::temp_dir);
+        let path = directory.join(format!("membuffer_spool_{}_{}.tmp",std::process::id(),SPOOL_FILES.fetch_add(1,Ordering::Relaxed)));
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        Ok(SpooledPayload {
+            file: Mutex::new(file),
+            path,
+            len: 0,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    ///Returns a reader over the payload, readers of one payload do not interfere
+    pub(crate) fn reader(&self) -> SpoolReader<'_> {
+        SpoolReader {
+            spooled: self,
+            position: 0,
+        }
+    }
+}
+
+impl Drop for SpooledPayload {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+///Reads a spooled payload from its own position in the shared file
+pub(crate) struct SpoolReader<'s> {
+    spooled: &'s SpooledPayload,
+    position: u64,
+}
+
+impl Read for SpoolReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.spooled.len as u64-self.position;
+        let len = core::cmp::min(buf.len() as u64,remaining) as usize;
+        let mut file = self.spooled.file.lock().unwrap_or_else(|x| x.into_inner());
+        file.seek(SeekFrom::Start(self.position))?;
+        let read = file.read(&mut buf[..len])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl MemBufferWriter {
+    ///Spools payloads read with `add_entry_from_reader` into the directory instead of the
+    ///temporary directory of the system
+    pub fn with_spool_directory<P: Into<PathBuf>>(mut self, directory: P) -> MemBufferWriter {
+        self.spool.directory = Some(directory.into());
+        self
+    }
+
+    ///Keeps payloads read with `add_entry_from_reader` of up to the given number of bytes in
+    ///memory, larger ones are spooled
+    pub fn with_spool_threshold(mut self, threshold: usize) -> MemBufferWriter {
+        self.spool.threshold = threshold;
+        self
+    }
+
+    ///Adds an entry of the given type whose payload in native byte order is read from the reader
+    ///until it ends, see the module documentation. The length hint only sizes the buffer for
+    ///small payloads and spools larger ones right away. Fails if the reader or the spool file
+    ///fails, the writer is unchanged then.
+    pub fn add_entry_from_reader<R: Read>(&mut self, variable_type: i32, len_hint: Option<usize>, mut reader: R) -> std::io::Result<()> {
+        assert!(!is_system_type(variable_type),"The type id {} is reserved for metadata entries",variable_type);
+        let index = self.types.len();
+        self.check_schema_field(index,variable_type);
+        let threshold = self.spool.threshold;
+        let mut data = Vec::new();
+        if len_hint.is_none_or(|x| x <= threshold) {
+            data.reserve(len_hint.unwrap_or(0));
+            (&mut reader).take(threshold as u64+1).read_to_end(&mut data)?;
+            if data.len() <= threshold {
+                self.add_raw_entry(variable_type,&data);
+                return Ok(());
+            }
+        }
+        let mut spooled = SpooledPayload::create(&self.spool)?;
+        {
+            let file = spooled.file.get_mut().unwrap();
+            file.write_all(&data)?;
+            let copied = std::io::copy(&mut reader,file)?;
+            file.flush()?;
+            spooled.len = data.len()+copied as usize;
+        }
+        self.types.push(variable_type);
+        self.data.push(Vec::new().into());
+        self.spooled.insert(index,std::sync::Arc::new(spooled));
+        self.log_payload(index,Change::Add);
+        Ok(())
+    }
+
+    ///Returns the payload of a spooled entry streamed from its file
+    pub(crate) fn spooled_payload(&self, index: usize) -> Option<Payload<'_>> {
+        self.spooled.get(&index).map(|x| Payload::Reader(Box::new(x.reader()),x.len()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferTypes,Endianness};
+
+    #[test]
+    fn check_spooled_entries() {
+        let directory = std::env::temp_dir().join(format!("membuffer_check_spooled_entries_{}",std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let numbers: Vec<u64> = (0..10_000).collect();
+        let bytes: Vec<u8> = numbers.iter().flat_map(|x| x.to_ne_bytes()).collect();
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big).with_field_checksums()
+            .with_spool_directory(&directory).with_spool_threshold(1000);
+        writer.add_entry_from_reader(MemBufferTypes::Text as i32,None,&b"small"[..]).unwrap();
+        writer.add_entry_from_reader(MemBufferTypes::VectorU64 as i32,Some(bytes.len()),&bytes[..]).unwrap();
+        writer.add_entry_from_reader(MemBufferTypes::VectorU8 as i32,Some(10),std::io::repeat(1).take(5000)).unwrap();
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(),2);
+        assert_eq!(writer.load_entry::<&str>(0).unwrap(),"small");
+
+        //Copies of the writer share the spool files
+        let copy = writer.clone();
+        let data = writer.finalize();
+        assert_eq!(data.len(),writer.serialized_len());
+        assert_eq!(copy.finalize(),data);
+        let reader = MemBufferReader::new_verified(&data).unwrap();
+        assert_eq!(&reader.load_entry::<std::borrow::Cow<[u64]>>(1).unwrap()[..],&numbers[..]);
+        assert_eq!(reader.load_entry::<&[u8]>(2).unwrap(),&[1u8;5000][..]);
+
+        //Replaced entries and dropped writers remove their files
+        writer.set_entry("replaced",2).unwrap();
+        drop(copy);
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(),1);
+        drop(writer);
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(),0);
+        std::fs::remove_dir(&directory).unwrap();
+    }
+}
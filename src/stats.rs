@@ -0,0 +1,103 @@
+//!Counters for the bytes a writer copies out of existing buffers, leaves in place and writes when
+//!finalizing, so the cost of update patterns on large documents can be quantified.
+//!```rust
+//!use membuffer::MemBufferWriter;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello");
+//!let data = writer.finalize();
+//!
+//!let mut update = MemBufferWriter::from(&data).unwrap();
+//!update.add_entry("World");
+//!update.finalize();
+//!let stats = update.write_stats();
+//!assert_eq!(stats.copied_bytes,5);
+//!assert!(stats.written_bytes > 10);
+//!```
+use std::sync::atomic::{AtomicU64,Ordering};
+use crate::MemBufferWriter;
+
+///Snapshot of the write amplification counters of a writer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteStats {
+    ///Bytes copied out of existing buffers when loading them into the writer
+    pub copied_bytes: u64,
+    ///Bytes of existing buffers which were left in place instead of being copied, only append
+    ///style workflows can reuse bytes
+    pub reused_bytes: u64,
+    ///Bytes produced by all finalize calls
+    pub written_bytes: u64,
+}
+
+impl std::fmt::Display for WriteStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f,"copied {} bytes, reused {} bytes, written {} bytes",self.copied_bytes,self.reused_bytes,self.written_bytes)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct WriteCounters {
+    copied: AtomicU64,
+    reused: AtomicU64,
+    written: AtomicU64,
+}
+
+impl WriteCounters {
+    pub(crate) fn add_copied(&self, bytes: usize) {
+        self.copied.fetch_add(bytes as u64,Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_reused(&self, bytes: usize) {
+        self.reused.fetch_add(bytes as u64,Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_written(&self, bytes: usize) {
+        self.written.fetch_add(bytes as u64,Ordering::Relaxed);
+    }
+}
+
+impl MemBufferWriter {
+    ///Returns how many bytes the writer copied, reused and wrote since it was created or the
+    ///counters were reset
+    pub fn write_stats(&self) -> WriteStats {
+        WriteStats {
+            copied_bytes: self.counters.copied.load(Ordering::Relaxed),
+            reused_bytes: self.counters.reused.load(Ordering::Relaxed),
+            written_bytes: self.counters.written.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset_write_stats(&mut self) {
+        self.counters = WriteCounters::default();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,WriteStats};
+
+    #[test]
+    fn check_write_stats() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello World");
+        writer.add_entry(&[1u8,2,3][..]);
+        assert_eq!(writer.write_stats(),WriteStats::default());
+        let result = writer.finalize();
+        assert_eq!(writer.write_stats().written_bytes,result.len() as u64);
+
+        let mut writer2 = MemBufferWriter::from(&result).unwrap();
+        let stats = writer2.write_stats();
+        assert_eq!(stats.copied_bytes,14);
+        assert_eq!(stats.reused_bytes,0);
+        assert_eq!(stats.written_bytes,0);
+
+        let result2 = writer2.finalize();
+        writer2.finalize();
+        assert_eq!(writer2.write_stats().written_bytes,2*result2.len() as u64);
+        assert_eq!(format!("{}",writer2.write_stats()),format!("copied 14 bytes, reused 0 bytes, written {} bytes",2*result2.len()));
+
+        writer2.reset_write_stats();
+        assert_eq!(writer2.write_stats(),WriteStats::default());
+    }
+}
@@ -0,0 +1,167 @@
+//!Backing storage of readers. Everything that can hand out its bytes as one continuous slice
+//!implements `Storage`, the reader is created over that slice so plain slices, vectors, shared
+//!buffers, mmaps and `bytes::Bytes` all go through the same code. Storage which can only fetch
+//!byte ranges on request like remote objects implements the `ReadAt` extension trait instead,
+//!which every `Storage` provides as well. `LazyMemBufferReader` reads buffers from such storage.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!use std::sync::Arc;
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("shared");
+//!let shared: Arc<[u8]> = writer.finalize().into();
+//!
+//!let reader = MemBufferReader::from_storage(&shared).unwrap();
+//!assert_eq!(reader.load_entry::<&str>(0).unwrap(),"shared");
+//!```
+#[cfg(feature = "std")]
+use alloc::borrow::Cow;
+use core::ops::Range;
+use crate::{MemBufferReader,MemBufferError};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Storage holding a buffer in memory
+pub trait Storage {
+    ///Returns the bytes in the given range, panics if the range is out of bounds
+    fn slice(&self, range: Range<usize>) -> &[u8];
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Storage for [u8] {
+    fn slice(&self, range: Range<usize>) -> &[u8] {
+        &self[range]
+    }
+
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+}
+
+impl Storage for Vec<u8> {
+    fn slice(&self, range: Range<usize>) -> &[u8] {
+        &self[range]
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+impl Storage for Box<[u8]> {
+    fn slice(&self, range: Range<usize>) -> &[u8] {
+        &self[range]
+    }
+
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+}
+
+impl Storage for alloc::sync::Arc<[u8]> {
+    fn slice(&self, range: Range<usize>) -> &[u8] {
+        &self[range]
+    }
+
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Storage for memmap2::Mmap {
+    fn slice(&self, range: Range<usize>) -> &[u8] {
+        &self[range]
+    }
+
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Storage for bytes::Bytes {
+    fn slice(&self, range: Range<usize>) -> &[u8] {
+        &self[range]
+    }
+
+    fn len(&self) -> usize {
+        bytes::Bytes::len(self)
+    }
+}
+
+///Storage which fetches byte ranges on request, for example from a file or an object store
+#[cfg(feature = "std")]
+pub trait ReadAt {
+    ///Reads the bytes in the given range, storage in memory returns them without copying
+    fn read_at(&self, range: Range<u64>) -> std::io::Result<Cow<'_,[u8]>>;
+
+    ///Returns the size of the stored buffer in bytes
+    fn size(&self) -> u64;
+}
+
+#[cfg(feature = "std")]
+impl<S: Storage + ?Sized> ReadAt for S {
+    fn read_at(&self, range: Range<u64>) -> std::io::Result<Cow<'_,[u8]>> {
+        if range.start > range.end || range.end > Storage::len(self) as u64 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+        }
+        Ok(Cow::Borrowed(self.slice(range.start as usize..range.end as usize)))
+    }
+
+    fn size(&self) -> u64 {
+        Storage::len(self) as u64
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Creates the reader over the bytes of the storage
+    pub fn from_storage<S: Storage + ?Sized>(storage: &'a S) -> Result<MemBufferReader<'a>,MemBufferError> {
+        MemBufferReader::new(storage.slice(0..storage.len()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,ReadAt};
+
+    #[test]
+    fn check_storages() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        let data = writer.finalize();
+
+        let boxed: Box<[u8]> = data.clone().into_boxed_slice();
+        let shared: std::sync::Arc<[u8]> = data.clone().into();
+        let readers = vec![
+            MemBufferReader::from_storage(&data).unwrap(),
+            MemBufferReader::from_storage(&data[..]).unwrap(),
+            MemBufferReader::from_storage(&boxed).unwrap(),
+            MemBufferReader::from_storage(&shared).unwrap(),
+        ];
+        for reader in readers {
+            assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+            assert_eq!(reader.load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+        }
+
+        assert_eq!(data.size(),data.len() as u64);
+        assert_eq!(&data.read_at(0..4).unwrap()[..],&data[..4]);
+        assert!(data.read_at(0..data.len() as u64+1).is_err());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn check_bytes_storage() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        let data = bytes::Bytes::from(writer.finalize());
+        assert_eq!(MemBufferReader::from_storage(&data).unwrap().load_entry::<&str>(0).unwrap(),"Hello");
+    }
+}
@@ -0,0 +1,249 @@
+//!Proptest strategies for downstream tests, available with the `proptest` feature which is meant
+//!to be enabled in the dev-dependencies of dependent crates. `buffers` generates valid buffers
+//!with arbitrary entries, names, byte orders and layouts, `corrupted_buffers` applies arbitrary
+//!`Mutation`s to them, so code handling buffers can be tested against realistic valid and invalid
+//!input. `arbitrary_buffer` keeps the generated entries next to the buffer to compare loads
+//!against them. Failing cases shrink to fewer and shorter entries and fewer mutations.
+//!```rust
+//!use membuffer::MemBufferReader;
+//!use membuffer::strategies::{buffers,corrupted_buffers};
+//!use proptest::prelude::*;
+//!
+//!proptest! {
+//!    #![proptest_config(ProptestConfig::with_cases(64))]
+//!
+//!    fn reads_valid_buffers(data in buffers()) {
+//!        prop_assert!(MemBufferReader::new_validated(&data).is_ok());
+//!    }
+//!
+//!    fn rejects_or_reads_corrupted_buffers(data in corrupted_buffers()) {
+//!        if let Ok(reader) = MemBufferReader::new_validated(&data) {
+//!            reader.values().for_each(drop);
+//!        }
+//!    }
+//!}
+//!# reads_valid_buffers();
+//!# rejects_or_reads_corrupted_buffers();
+//!```
+use proptest::prelude::*;
+use proptest::collection::vec;
+use proptest::sample::Index;
+use crate::{MemBufferWriter,Endianness};
+
+///Largest number of elements of generated text, byte and slice entries
+const MAX_ELEMENTS: usize = 64;
+
+///Owned value of a generated entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArbitraryEntry {
+    Text(String),
+    InternedText(String),
+    I32(i32),
+    U64(u64),
+    F64(f64),
+    Bytes(Vec<u8>),
+    U32Slice(Vec<u32>),
+    U64Slice(Vec<u64>),
+    F64Slice(Vec<f64>),
+    Null,
+}
+
+///Entries of a generated buffer and the options of its writer
+#[derive(Debug, Clone)]
+pub struct ArbitraryBuffer {
+    ///The entries with their optional names
+    pub entries: Vec<(Option<String>,ArbitraryEntry)>,
+    pub byte_order: Endianness,
+    pub large_offsets: bool,
+    pub field_checksums: bool,
+    pub interleaved: bool,
+}
+
+impl ArbitraryBuffer {
+    ///Returns a writer holding the entries, a name used twice refers to the later entry
+    pub fn writer(&self) -> MemBufferWriter {
+        let mut writer = MemBufferWriter::new().with_byte_order(self.byte_order);
+        if self.large_offsets {
+            writer = writer.with_large_offsets();
+        }
+        if self.field_checksums {
+            writer = writer.with_field_checksums();
+        }
+        for (name,entry) in self.entries.iter() {
+            match entry {
+                ArbitraryEntry::Text(x) => writer.add_entry(&x[..]),
+                ArbitraryEntry::InternedText(x) => writer.add_interned_entry(x),
+                ArbitraryEntry::I32(x) => writer.add_entry(*x),
+                ArbitraryEntry::U64(x) => writer.add_entry(*x),
+                ArbitraryEntry::F64(x) => writer.add_entry(*x),
+                ArbitraryEntry::Bytes(x) => writer.add_entry(&x[..]),
+                ArbitraryEntry::U32Slice(x) => writer.add_entry(&x[..]),
+                ArbitraryEntry::U64Slice(x) => writer.add_entry(&x[..]),
+                ArbitraryEntry::F64Slice(x) => writer.add_entry(&x[..]),
+                ArbitraryEntry::Null => writer.add_null_entry(),
+            }
+            if let Some(name) = name {
+                writer.names.insert(name.clone(),writer.len()-1);
+            }
+        }
+        writer
+    }
+
+    ///Serializes the buffer with the generated layout
+    pub fn finalize(&self) -> Vec<u8> {
+        let writer = self.writer();
+        if self.interleaved { writer.finalize_interleaved() } else { writer.finalize() }
+    }
+}
+
+///Generates entries of every built in type
+pub fn arbitrary_entry() -> impl Strategy<Value=ArbitraryEntry> {
+    prop_oneof![
+        ".{0,64}".prop_map(ArbitraryEntry::Text),
+        "[a-z]{1,8}".prop_map(ArbitraryEntry::InternedText),
+        any::<i32>().prop_map(ArbitraryEntry::I32),
+        any::<u64>().prop_map(ArbitraryEntry::U64),
+        any::<f64>().prop_map(ArbitraryEntry::F64),
+        vec(any::<u8>(),0..MAX_ELEMENTS).prop_map(ArbitraryEntry::Bytes),
+        vec(any::<u32>(),0..MAX_ELEMENTS).prop_map(ArbitraryEntry::U32Slice),
+        vec(any::<u64>(),0..MAX_ELEMENTS).prop_map(ArbitraryEntry::U64Slice),
+        vec(any::<f64>(),0..MAX_ELEMENTS).prop_map(ArbitraryEntry::F64Slice),
+        Just(ArbitraryEntry::Null),
+    ]
+}
+
+///Generates up to 32 entries, some of them named, with arbitrary writer options
+pub fn arbitrary_buffer() -> impl Strategy<Value=ArbitraryBuffer> {
+    let byte_order = prop_oneof![Just(Endianness::Native),Just(Endianness::Little),Just(Endianness::Big)];
+    (vec((proptest::option::weighted(0.2,"[a-z]{1,8}"),arbitrary_entry()),0..32),byte_order,any::<bool>(),any::<bool>(),any::<bool>())
+        .prop_map(|(entries,byte_order,large_offsets,field_checksums,interleaved)| ArbitraryBuffer {
+            entries,
+            byte_order,
+            large_offsets,
+            field_checksums,
+            interleaved,
+        })
+}
+
+///Generates valid buffers, see `arbitrary_buffer`
+pub fn buffers() -> impl Strategy<Value=Vec<u8>> {
+    arbitrary_buffer().prop_map(|x| x.finalize())
+}
+
+///Change applied to a buffer, the positions are scaled to the length of the buffer
+#[derive(Debug, Clone)]
+pub enum Mutation {
+    ///Flips the bit of the byte at the position
+    FlipBit(Index,u8),
+    ///Overwrites the byte at the position
+    SetByte(Index,u8),
+    ///Overwrites one of the first 64 bytes, where the header and the first entries are stored
+    SetHeaderByte(Index,u8),
+    ///Inserts a byte in front of the position, moving the following bytes
+    InsertByte(Index,u8),
+    ///Removes up to the given number of bytes starting at the position
+    Remove(Index,usize),
+    ///Cuts the buffer off at the position
+    Truncate(Index),
+}
+
+impl Mutation {
+    pub fn apply(&self, data: &mut Vec<u8>) {
+        if data.is_empty() {
+            return;
+        }
+        match self {
+            Mutation::FlipBit(at,bit) => {
+                let at = at.index(data.len());
+                data[at] ^= 1 << (bit%8);
+            },
+            Mutation::SetByte(at,val) => {
+                let at = at.index(data.len());
+                data[at] = *val;
+            },
+            Mutation::SetHeaderByte(at,val) => {
+                let at = at.index(data.len().min(64));
+                data[at] = *val;
+            },
+            Mutation::InsertByte(at,val) => {
+                let at = at.index(data.len());
+                data.insert(at,*val);
+            },
+            Mutation::Remove(at,len) => {
+                let at = at.index(data.len());
+                let end = data.len().min(at+len);
+                data.drain(at..end);
+            },
+            Mutation::Truncate(at) => {
+                let at = at.index(data.len());
+                data.truncate(at);
+            },
+        }
+    }
+}
+
+///Generates mutations of every kind
+pub fn mutation() -> impl Strategy<Value=Mutation> {
+    prop_oneof![
+        (any::<Index>(),any::<u8>()).prop_map(|(at,bit)| Mutation::FlipBit(at,bit)),
+        (any::<Index>(),any::<u8>()).prop_map(|(at,val)| Mutation::SetByte(at,val)),
+        (any::<Index>(),any::<u8>()).prop_map(|(at,val)| Mutation::SetHeaderByte(at,val)),
+        (any::<Index>(),any::<u8>()).prop_map(|(at,val)| Mutation::InsertByte(at,val)),
+        (any::<Index>(),1..16usize).prop_map(|(at,len)| Mutation::Remove(at,len)),
+        any::<Index>().prop_map(Mutation::Truncate),
+    ]
+}
+
+///Generates valid buffers changed by one to three mutations, most of them are invalid
+pub fn corrupted_buffers() -> impl Strategy<Value=Vec<u8>> {
+    (buffers(),vec(mutation(),1..4)).prop_map(|(mut data,mutations)| {
+        for mutation in mutations.iter() {
+            mutation.apply(&mut data);
+        }
+        data
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferReader,MemBufferError,Value};
+    use super::{ArbitraryEntry,arbitrary_buffer,corrupted_buffers};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn check_arbitrary_buffers(buffer in arbitrary_buffer()) {
+            let data = buffer.finalize();
+            let reader = MemBufferReader::new_validated(&data).unwrap();
+            prop_assert_eq!(reader.len(),buffer.entries.len());
+            for (index,value) in reader.values().enumerate() {
+                let matches = match (&buffer.entries[index].1,value) {
+                    (ArbitraryEntry::Text(x),Ok(Value::Text(y))) | (ArbitraryEntry::InternedText(x),Ok(Value::Text(y))) => x == y,
+                    (ArbitraryEntry::I32(x),Ok(Value::I32(y))) => *x == y,
+                    (ArbitraryEntry::U64(x),Ok(Value::U64(y))) => *x == y,
+                    (ArbitraryEntry::F64(x),Ok(Value::F64(y))) => x.to_bits() == y.to_bits(),
+                    (ArbitraryEntry::Bytes(x),Ok(Value::Bytes(y))) => x == y,
+                    (ArbitraryEntry::U32Slice(x),Ok(Value::U32Slice(y))) => x == y,
+                    (ArbitraryEntry::U64Slice(x),Ok(Value::U64Slice(y))) => x == y,
+                    (ArbitraryEntry::F64Slice(x),Ok(Value::F64Slice(y))) => x.iter().map(|x| x.to_bits()).eq(y.iter().map(|x| x.to_bits())),
+                    (ArbitraryEntry::Null,Ok(Value::Null)) => true,
+                    //Slices of the other byte order cannot be borrowed
+                    (_,Err(MemBufferError::ByteOrderMismatch)) => buffer.byte_order.is_swapped(),
+                    _ => false,
+                };
+                prop_assert!(matches,"Entry {} does not match",index);
+            }
+        }
+
+        #[test]
+        fn check_corrupted_buffers(data in corrupted_buffers()) {
+            if let Ok(reader) = MemBufferReader::new_validated(&data) {
+                reader.values().for_each(drop);
+                for index in 0..reader.len() {
+                    let _ = reader.load_entry::<&str>(index);
+                }
+            }
+        }
+    }
+}
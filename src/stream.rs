@@ -0,0 +1,417 @@
+//!Interleaved layout for streaming consumers. Instead of one header in front of the payload every
+//!entry is written as `(type, len, bytes)` followed by a small index at the end of the buffer. A
+//!consumer reading from a pipe can therefore process the entries as they arrive, while
+//!`MemBufferReader::new` still finds every entry through the index once the whole buffer is there.
+//!Like the header of other buffers the magic word marks the byte order the buffer was written in,
+//!the records and the index of a buffer written with the other byte order are read swapped. The
+//!compression table is written in front of the entries, so stream consumers know which entries
+//!are compressed before they arrive.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferStreamReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("first");
+//!writer.add_entry(100);
+//!let data = writer.finalize_interleaved();
+//!
+//!//Entries can be consumed one after another from any io::Read
+//!let mut stream = MemBufferStreamReader::new(&data[..]).unwrap();
+//!let first = stream.next_entry().unwrap().unwrap();
+//!assert_eq!(first.load_entry::<&str>().unwrap(),"first");
+//!
+//!//Or the complete buffer is loaded as usual
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry::<i32>(1).unwrap(),100);
+//!```
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use byteorder::ReadBytesExt;
+use byteorder::{NativeEndian,ByteOrder};
+use crate::endian::read_u32;
+use crate::{MemBufferReader,MemBufferError,MemBufferDeserialize,MemBufferSerialize,InternPosition,FORMAT_VERSION,LEGACY_FORMAT_VERSION};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,is_system_type,SYSTEM_TYPE_COMPRESSION};
+#[cfg(feature = "std")]
+use crate::compress::find_record;
+#[cfg(feature = "std")]
+use crate::table::EntryPosition;
+#[cfg(feature = "std")]
+use crate::endian::{swap_integers,swap_width};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///First word of every interleaved buffer
+pub(crate) const STREAM_MAGIC: i32 = 0x7AFE57EA;
+///Type of the record terminating the entries, its length field holds the number of entries
+const STREAM_INDEX_MARKER: i32 = -0x57EA;
+///Every record starts at a multiple of this, which keeps the payloads aligned for slice casts
+#[cfg(feature = "std")]
+const RECORD_ALIGNMENT: usize = 8;
+
+///Length of the type and the length in front of every record
+#[cfg(feature = "std")]
+pub(crate) const RECORD_HEADER_LEN: usize = 8;
+
+#[cfg(feature = "std")]
+fn padding_for(len: usize) -> usize {
+    (RECORD_ALIGNMENT - len%RECORD_ALIGNMENT)%RECORD_ALIGNMENT
+}
+
+///Returns whether the first word of a buffer marks the interleaved layout in either byte order
+pub(crate) fn is_stream_magic(word: i32) -> bool {
+    word == STREAM_MAGIC || word == STREAM_MAGIC.swap_bytes()
+}
+
+///Appends the word in the byte order of the buffer
+#[cfg(feature = "std")]
+fn write_word(var: &mut Vec<u8>, val: i32, swapped: bool) {
+    MemBufferWriter::serialize_i32_to(if swapped { val.swap_bytes() } else { val }, var);
+}
+
+///Second word of an interleaved buffer, legacy buffers store zero instead of the version
+#[cfg(feature = "std")]
+fn stream_version_word(version: u32) -> i32 {
+    if version == LEGACY_FORMAT_VERSION { 0 } else { version as i32 }
+}
+
+///Returns the format version stored in the second word of an interleaved buffer
+fn stream_version(word: i32) -> Result<u32,MemBufferError> {
+    match word as u32 {
+        0 => Ok(LEGACY_FORMAT_VERSION),
+        version if version <= FORMAT_VERSION => Ok(version),
+        version => Err(MemBufferError::UnsupportedVersion(version)),
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Finalize the schema using the interleaved layout, every entry is preceded by its type and
+    ///length and the index is stored at the end of the buffer. The layout uses 32 bit positions and
+    ///is limited to buffers of 2 GB. The buffer is written in the byte order of the writer.
+    pub fn finalize_interleaved(&self) -> Vec<u8> {
+        let swapped = self.byte_order.is_swapped();
+        self.with_all_entries(|types,data| {
+            let mut var: Vec<u8> = Vec::new();
+            write_word(&mut var,STREAM_MAGIC,swapped);
+            write_word(&mut var,stream_version_word(self.format_version),swapped);
+            //The compression table is placed first, the index keeps the order of the entries
+            let table = types.iter().position(|x| *x == SYSTEM_TYPE_COMPRESSION);
+            let order: Vec<usize> = table.into_iter().chain((0..types.len()).filter(|x| Some(*x) != table)).collect();
+            let placed = write_records(&mut var,0,&order.iter().map(|x| types[*x]).collect::<Vec<i32>>(),&order.iter().map(|x| data[*x]).collect::<Vec<&[u8]>>(),swapped);
+            let mut index = placed.clone();
+            for (x,entry) in order.iter().zip(placed) {
+                index[*x] = entry;
+            }
+            write_index(&mut var,&index,swapped);
+            if self.manifest {
+                self.write_manifest(&mut var);
+            }
+            self.write_checksums(&mut var);
+            self.counters.add_written(var.len());
+            var
+        })
+    }
+}
+
+///Appends a record for every entry, the records are placed as if `var` started `base` bytes into
+///the buffer. The payloads have to be in the byte order of the buffer already. Returns the positions
+///of the entries for the index.
+#[cfg(feature = "std")]
+pub(crate) fn write_records(var: &mut Vec<u8>, base: usize, types: &[i32], data: &[&[u8]], swapped: bool) -> Vec<EntryPosition> {
+    let mut positions = Vec::with_capacity(types.len());
+    for (variable_type,data) in types.iter().zip(data.iter()) {
+        write_word(var,*variable_type,swapped);
+        write_word(var,data.len() as i32,swapped);
+        let start = base+var.len();
+        positions.push(EntryPosition {
+            start,
+            end: start+data.len(),
+            variable_type: *variable_type,
+        });
+        var.extend_from_slice(data);
+        var.resize(var.len()+padding_for(data.len()),0);
+    }
+    positions
+}
+
+///Appends the index terminating the records, the trailer holds the number of entries
+#[cfg(feature = "std")]
+pub(crate) fn write_index(var: &mut Vec<u8>, index: &[EntryPosition], swapped: bool) {
+    write_word(var,STREAM_INDEX_MARKER,swapped);
+    write_word(var,index.len() as i32,swapped);
+    for entry in index.iter() {
+        write_word(var,entry.start as i32,swapped);
+        write_word(var,entry.end as i32,swapped);
+        write_word(var,entry.variable_type,swapped);
+    }
+    write_word(var,index.len() as i32,swapped);
+    write_word(var,(core::num::Wrapping(index.len() as i32)-core::num::Wrapping(STREAM_MAGIC)).0,swapped);
+}
+
+///Returns the length of the index including its marker and the trailer
+#[cfg(feature = "std")]
+pub(crate) fn index_len(entries: usize) -> usize {
+    entries*core::mem::size_of::<InternPosition>()+16
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Loads a complete buffer written with the interleaved layout by reading the index at the end
+    pub(crate) fn new_interleaved(val: &'a [u8]) -> Result<MemBufferReader<'a>,MemBufferError> {
+        if val.len() < 16 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let swapped = NativeEndian::read_i32(val) != STREAM_MAGIC;
+        let word = |data: &[u8]| read_u32(data,swapped) as i32;
+        let trailer = &val[val.len()-8..];
+        let vec_len = word(trailer) as usize;
+        let checksum = word(&trailer[4..]) as usize;
+        if core::num::Wrapping(checksum)+core::num::Wrapping(STREAM_MAGIC as usize) != core::num::Wrapping(vec_len) {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let index_len = vec_len.checked_mul(core::mem::size_of::<InternPosition>()).ok_or(MemBufferError::WrongFormat)?;
+        if val.len() < index_len+24 {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let index_start = val.len()-8-index_len;
+        if word(&val[index_start-8..]) != STREAM_INDEX_MARKER {
+            return Err(MemBufferError::WrongFormat);
+        }
+        let version = stream_version(word(&val[4..]))?;
+
+        let index = &val[index_start..val.len()-8];
+        if swapped {
+            return Ok(MemBufferReader::from_parts(crate::EntryTable::Swapped(index,false),val,version));
+        }
+        if index.as_ptr().align_offset(core::mem::align_of::<InternPosition>()) != 0 {
+            return Ok(MemBufferReader::from_parts(crate::EntryTable::Unaligned(index,false),val,version));
+        }
+        //The index holds `vec_len` aligned entries which consist of integers only
+        unsafe {
+        Ok(MemBufferReader::from_parts(crate::EntryTable::Small(core::slice::from_raw_parts(index.as_ptr().cast::<InternPosition>(),vec_len)),val,version))
+        }
+    }
+}
+
+///One entry read from an interleaved stream
+#[derive(Debug)]
+pub struct StreamEntry {
+    pub variable_type: i32,
+    pub data: Vec<u8>,
+    ///Whether `data` holds the compressed payload, entries of buffers whose compression table
+    ///follows the entries are never marked
+    pub compressed: bool,
+}
+
+impl StreamEntry {
+    ///Loads the entry as the given type, fails if the types do not match and with
+    ///`MemBufferError::CompressedEntry` for compressed entries, which can only be loaded through a
+    ///reader over the whole buffer
+    pub fn load_entry<'a, T: MemBufferDeserialize<'a,T>+MemBufferSerialize>(&'a self) -> Result<T,MemBufferError> {
+        if T::get_mem_buffer_type() != self.variable_type {
+            return Err(MemBufferError::FieldTypeError(self.variable_type,T::get_mem_buffer_type()));
+        }
+        if self.compressed {
+            return Err(MemBufferError::CompressedEntry);
+        }
+        T::from_mem_buffer(&self.data)
+    }
+}
+
+///Reads the entries of an interleaved buffer one after another from any `io::Read` without
+///waiting for the whole buffer to arrive
+#[cfg(feature = "std")]
+pub struct MemBufferStreamReader<R: Read> {
+    reader: R,
+    finished: bool,
+    swapped: bool,
+    //Index of the next entry and the compression table once it was read
+    index: usize,
+    compression: Option<Vec<u8>>,
+}
+
+#[cfg(feature = "std")]
+fn invalid_data(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> MemBufferStreamReader<R> {
+    ///Reads and checks the preamble of the interleaved buffer
+    pub fn new(mut reader: R) -> std::io::Result<MemBufferStreamReader<R>> {
+        let magic = reader.read_i32::<NativeEndian>()?;
+        if !is_stream_magic(magic) {
+            return Err(invalid_data("Memory buffer error: Stream does not start with an interleaved buffer"));
+        }
+        let mut stream = MemBufferStreamReader {
+            reader,
+            finished: false,
+            swapped: magic != STREAM_MAGIC,
+            index: 0,
+            compression: None,
+        };
+        stream_version(stream.read_word()?).map_err(|_| invalid_data("Memory buffer error: Stream was written with an unsupported format version"))?;
+        Ok(stream)
+    }
+
+    fn read_word(&mut self) -> std::io::Result<i32> {
+        let word = self.reader.read_i32::<NativeEndian>()?;
+        Ok(if self.swapped { word.swap_bytes() } else { word })
+    }
+
+    ///Returns the next entry or None once the index at the end of the buffer is reached, the index
+    ///itself is not consumed from the underlying reader. Metadata entries are skipped, entries of
+    ///buffers with the other byte order are converted to the native one. Compressed entries keep
+    ///their compressed payload and are marked as compressed.
+    pub fn next_entry(&mut self) -> std::io::Result<Option<StreamEntry>> {
+        loop {
+            match self.next_record()? {
+                Some(entry) if entry.variable_type == SYSTEM_TYPE_COMPRESSION => self.compression = Some(entry.data),
+                Some(entry) if is_system_type(entry.variable_type) => continue,
+                Some(mut entry) => {
+                    if let Some(table) = self.compression.as_ref() {
+                        entry.compressed = find_record(table,self.index,self.swapped).map_err(|_| invalid_data("Memory buffer error: Invalid compression table in stream"))?.is_some();
+                    }
+                    //Compressed payloads are stored in the byte order of the buffer
+                    if self.swapped && !entry.compressed {
+                        swap_integers(&mut entry.data,swap_width(entry.variable_type));
+                    }
+                    self.index += 1;
+                    return Ok(Some(entry));
+                },
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn next_record(&mut self) -> std::io::Result<Option<StreamEntry>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let variable_type = self.read_word()?;
+        let len = self.read_word()?;
+        if variable_type == STREAM_INDEX_MARKER {
+            self.finished = true;
+            return Ok(None);
+        }
+        if len < 0 {
+            return Err(invalid_data("Memory buffer error: Entry with negative length in stream"));
+        }
+        let mut data = Vec::new();
+        (&mut self.reader).take(len as u64).read_to_end(&mut data)?;
+        if data.len() != len as usize {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+        }
+        let mut padding = [0u8;RECORD_ALIGNMENT];
+        self.reader.read_exact(&mut padding[..padding_for(data.len())])?;
+        Ok(Some(StreamEntry {
+            variable_type,
+            data,
+            compressed: false,
+        }))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Iterator for MemBufferStreamReader<R> {
+    type Item = std::io::Result<StreamEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferStreamReader,MemBufferTypes,Endianness};
+    #[cfg(any(feature = "lz4",feature = "zstd"))]
+    use crate::Compression;
+    use std::borrow::Cow;
+
+    #[test]
+    fn check_interleaved_reader() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry(42);
+        let result = writer.finalize_interleaved();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(reader.len(),3);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+        assert_eq!(reader.load_entry::<&[u64]>(1).unwrap(),vec![1,2,3]);
+        assert_eq!(reader.load_entry::<i32>(2).unwrap(),42);
+
+        let copy = MemBufferWriter::from(&result).unwrap();
+        assert_eq!(copy.finalize(),writer.finalize());
+    }
+
+    #[test]
+    fn check_interleaved_byte_order() {
+        let text = "It was on a dreary night of November ".repeat(100);
+        for byte_order in [Endianness::Big,Endianness::Little].iter() {
+            let mut writer = MemBufferWriter::new().with_byte_order(*byte_order).with_field_checksums();
+            writer.add_entry("Hello");
+            writer.add_entry::<&[u64]>(&[1,2,3]);
+            writer.add_entry_named("answer",42);
+            writer.add_interned_entry("label");
+            #[cfg(feature = "lz4")]
+            writer.add_entry_compressed(&text,Compression::Lz4);
+            #[cfg(not(feature = "lz4"))]
+            writer.add_entry(&text);
+            #[cfg(feature = "zstd")]
+            writer.add_entry_compressed::<&[u64]>(&[7;500],Compression::Zstd(3));
+            let result = writer.finalize_interleaved();
+
+            let reader = MemBufferReader::new_verified(&result).unwrap();
+            assert_eq!(reader.is_byte_swapped(),byte_order.is_swapped());
+            assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+            assert_eq!(&reader.load_entry::<Cow<[u64]>>(1).unwrap()[..],&[1,2,3]);
+            assert_eq!(reader.load_entry_by_name::<i32>("answer").unwrap(),42);
+            assert_eq!(reader.load_entry::<&str>(3).unwrap(),"label");
+            assert_eq!(reader.load_entry::<String>(4).unwrap(),text);
+            #[cfg(feature = "zstd")]
+            assert_eq!(&reader.load_entry::<Cow<[u64]>>(reader.len()-1).unwrap()[..],&[7;500][..]);
+            assert_eq!(MemBufferWriter::from(&result).unwrap().with_byte_order(*byte_order).finalize(),writer.finalize());
+
+            let entries: Vec<_> = MemBufferStreamReader::new(&result[..]).unwrap().map(|x| x.unwrap()).collect();
+            assert_eq!(entries[1].load_entry::<&[u64]>().unwrap(),&[1,2,3]);
+            assert_eq!(entries[2].load_entry::<i32>().unwrap(),42);
+            assert!(!entries[1].compressed);
+            #[cfg(feature = "lz4")]
+            assert!(entries[4].compressed && matches!(entries[4].load_entry::<&str>(),Err(crate::MemBufferError::CompressedEntry)));
+            #[cfg(feature = "zstd")]
+            assert!(entries[entries.len()-1].compressed);
+        }
+    }
+
+    #[test]
+    fn check_stream_reader() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u32]>(&[1,2,3]);
+        let result = writer.finalize_interleaved();
+
+        let entries: Vec<_> = MemBufferStreamReader::new(&result[..]).unwrap().map(|x| x.unwrap()).collect();
+        assert_eq!(entries.len(),2);
+        assert_eq!(entries[0].load_entry::<&str>().unwrap(),"Hello");
+        assert_eq!(entries[1].variable_type,MemBufferTypes::VectorU32 as i32);
+        assert_eq!(entries[1].load_entry::<&[u32]>().unwrap(),vec![1,2,3]);
+        assert!(entries[1].load_entry::<&str>().is_err());
+    }
+
+    #[test]
+    fn check_stream_truncated() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry("World");
+        let result = writer.finalize_interleaved();
+
+        let mut stream = MemBufferStreamReader::new(&result[..30]).unwrap();
+        assert_eq!(stream.next_entry().unwrap().unwrap().load_entry::<&str>().unwrap(),"Hello");
+        assert!(stream.next_entry().is_err());
+        assert!(MemBufferReader::new(&result[..result.len()-1]).is_err());
+        assert!(MemBufferStreamReader::new(&writer.finalize()[..]).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+//!The entry table in front of the payload. Buffers up to 2 GB store the positions as 32 bit
+//!integers, larger buffers and writers created with `with_large_offsets` switch to a second header
+//!layout with 64 bit positions. The layout and the format version are marked by the second word of
+//!the header, readers handle both layouts and every version up to `FORMAT_VERSION`. A table
+//!written with the other byte order is read in place as well, every access swaps the positions.
+//!Tables of buffers which do not start at an aligned address are decoded byte by byte on every
+//!access instead of being cast in place.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new().with_large_offsets();
+//!writer.add_entry("Hello");
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+//!```
+use crate::{MemBufferError,InternPosition,FORMAT_VERSION,LEGACY_FORMAT_VERSION};
+use crate::compact::CompactTable;
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,type_alignment};
+use crate::endian::{read_u32,read_u64};
+#[cfg(feature = "std")]
+use crate::endian::swap_integers;
+
+///Second header word of legacy buffers with 32 bit positions, stored as difference to the entry
+///count
+pub(crate) const HEADER_MAGIC: i32 = 0x7AFECAFE;
+///Second header word of legacy buffers with 64 bit positions, stored as difference to the entry
+///count
+pub(crate) const HEADER_MAGIC_LARGE: i32 = 0x7AFE64CA;
+///Second header word of versioned buffers, the format version is stored in the second byte, the
+///lowest bit marks 64 bit positions and the second bit compact headers
+pub(crate) const HEADER_MAGIC_VERSIONED: i32 = 0x7AFE0000;
+///Bit of the versioned magic word marking a compact header
+const HEADER_COMPACT_BIT: i32 = 2;
+
+///Layout of the entry table announced by the first two words of a header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HeaderLayout {
+    ///Number of entries, compact headers store the length of the header instead
+    pub(crate) count: usize,
+    pub(crate) large: bool,
+    pub(crate) compact: bool,
+    pub(crate) version: u32,
+    pub(crate) swapped: bool,
+}
+
+impl HeaderLayout {
+    pub(crate) fn entry_size(&self) -> usize {
+        if self.large { core::mem::size_of::<InternPosition64>() } else { core::mem::size_of::<InternPosition>() }
+    }
+
+    ///Returns the length of the header including the two words announcing the layout, None if it
+    ///overflows
+    pub(crate) fn header_len(&self) -> Option<usize> {
+        if self.compact {
+            Some(self.count).filter(|x| *x >= 8)
+        }
+        else {
+            self.count.checked_mul(self.entry_size()).and_then(|x| x.checked_add(8))
+        }
+    }
+}
+
+///Returns whether the magic word stands for 64 bit positions, whether it stands for a compact
+///header and the format version or None if it is no magic word
+fn magic_layout(magic: i32) -> Option<(bool,bool,u32)> {
+    let version = (magic >> 8) as u32 & 0xFF;
+    if magic == HEADER_MAGIC {
+        Some((false,false,LEGACY_FORMAT_VERSION))
+    }
+    else if magic == HEADER_MAGIC_LARGE {
+        Some((true,false,LEGACY_FORMAT_VERSION))
+    }
+    else if magic & !(0xFF01 | HEADER_COMPACT_BIT) == HEADER_MAGIC_VERSIONED && version > LEGACY_FORMAT_VERSION {
+        Some((magic & 1 != 0,magic & HEADER_COMPACT_BIT != 0,version))
+    }
+    else {
+        None
+    }
+}
+
+///Returns the magic word of a header with the given version and layout, legacy headers cannot be
+///compact
+#[cfg(feature = "std")]
+pub(crate) fn header_magic(version: u32, large: bool, compact: bool) -> i32 {
+    match (version,large) {
+        (LEGACY_FORMAT_VERSION,false) => HEADER_MAGIC,
+        (LEGACY_FORMAT_VERSION,true) => HEADER_MAGIC_LARGE,
+        _ => HEADER_MAGIC_VERSIONED | (version as i32) << 8 | if compact { HEADER_COMPACT_BIT } else { large as i32 },
+    }
+}
+
+///Reads the layout of a header starting with the given words in either byte order, fails with
+///`MemBufferError::UnsupportedVersion` for headers of newer format versions
+pub(crate) fn header_layout(count: i32, check: i32) -> Result<HeaderLayout,MemBufferError> {
+    let layout = |count: i32, check: i32| magic_layout((core::num::Wrapping(count)-core::num::Wrapping(check)).0);
+    let (count,(large,compact,version),swapped) = match (layout(count,check),layout(count.swap_bytes(),check.swap_bytes())) {
+        (Some(layout),_) => (count,layout,false),
+        (None,Some(layout)) => (count.swap_bytes(),layout,true),
+        (None,None) => return Err(MemBufferError::WrongFormat),
+    };
+    if version > FORMAT_VERSION {
+        return Err(MemBufferError::UnsupportedVersion(version));
+    }
+    Ok(HeaderLayout {
+        count: count as u32 as usize,
+        large,
+        compact,
+        version,
+        swapped,
+    })
+}
+
+///Returns the length of the header whose first 8 bytes are given, fails for interleaved buffers
+///which do not have a header in front of the payload
+#[cfg(feature = "std")]
+pub(crate) fn header_len(words: &[u8]) -> Result<u64,MemBufferError> {
+    let layout = header_layout(read_u32(words,false) as i32,read_u32(&words[4..],false) as i32)?;
+    if layout.compact {
+        return Ok(layout.count as u64);
+    }
+    Ok(layout.count as u64*layout.entry_size() as u64+8)
+}
+
+///Entry of the table with 64 bit positions, the last word keeps the entries 8 byte aligned
+#[repr(C)]
+pub(crate) struct InternPosition64 {
+    start: u64,
+    end: u64,
+    variable_type: i32,
+    reserved: i32,
+}
+
+///Position and type of one entry independent of the header layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EntryPosition {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) variable_type: i32,
+}
+
+impl EntryPosition {
+    pub(crate) fn len(&self) -> usize {
+        self.end.wrapping_sub(self.start)
+    }
+}
+
+///Entry table borrowed from the header of a buffer
+#[derive(Clone)]
+pub(crate) enum EntryTable<'a> {
+    Small(&'a [InternPosition]),
+    Large(&'a [InternPosition64]),
+    ///Raw table written with the other byte order and whether it holds 64 bit positions
+    Swapped(&'a [u8],bool),
+    ///Raw table in native byte order which is not aligned and whether it holds 64 bit positions
+    Unaligned(&'a [u8],bool),
+    ///Compact table decoded when the buffer was opened
+    Compact(CompactTable<'a>),
+}
+
+impl<'a> EntryTable<'a> {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            EntryTable::Small(x) => x.len(),
+            EntryTable::Large(x) => x.len(),
+            EntryTable::Swapped(x,_) | EntryTable::Unaligned(x,_) => x.len()/self.entry_size(),
+            EntryTable::Compact(x) => x.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Returns the entry at the given index, panics if the index is out of bounds
+    pub(crate) fn get(&self, index: usize) -> EntryPosition {
+        match self {
+            EntryTable::Small(x) => EntryPosition {
+                start: x[index].pos.start as usize,
+                end: x[index].pos.end as usize,
+                variable_type: x[index].variable_type,
+            },
+            EntryTable::Large(x) => EntryPosition {
+                start: x[index].start as usize,
+                end: x[index].end as usize,
+                variable_type: x[index].variable_type,
+            },
+            EntryTable::Swapped(x,large) | EntryTable::Unaligned(x,large) => {
+                let entry = &x[index*self.entry_size()..(index+1)*self.entry_size()];
+                let swapped = self.is_swapped();
+                if *large {
+                    EntryPosition {
+                        start: read_u64(entry,swapped) as usize,
+                        end: read_u64(&entry[8..],swapped) as usize,
+                        variable_type: read_u32(&entry[16..],swapped) as i32,
+                    }
+                }
+                else {
+                    EntryPosition {
+                        start: read_u32(entry,swapped) as i32 as usize,
+                        end: read_u32(&entry[4..],swapped) as i32 as usize,
+                        variable_type: read_u32(&entry[8..],swapped) as i32,
+                    }
+                }
+            },
+            EntryTable::Compact(x) => x.get(index),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item=EntryPosition> + 'a {
+        let table = self.clone();
+        (0..table.len()).map(move |x| table.get(x))
+    }
+
+    pub(crate) fn split_at(&self, mid: usize) -> (EntryTable<'a>,EntryTable<'a>) {
+        match self {
+            EntryTable::Small(x) => (EntryTable::Small(&x[..mid]),EntryTable::Small(&x[mid..])),
+            EntryTable::Large(x) => (EntryTable::Large(&x[..mid]),EntryTable::Large(&x[mid..])),
+            EntryTable::Swapped(x,large) => {
+                let (first,second) = x.split_at(mid*self.entry_size());
+                (EntryTable::Swapped(first,*large),EntryTable::Swapped(second,*large))
+            },
+            EntryTable::Unaligned(x,large) => {
+                let (first,second) = x.split_at(mid*self.entry_size());
+                (EntryTable::Unaligned(first,*large),EntryTable::Unaligned(second,*large))
+            },
+            EntryTable::Compact(x) => {
+                let (first,second) = x.split_at(mid);
+                (EntryTable::Compact(first),EntryTable::Compact(second))
+            },
+        }
+    }
+
+    ///Returns the size of one entry in the header, entries of compact tables have no fixed size
+    pub(crate) fn entry_size(&self) -> usize {
+        match self {
+            EntryTable::Small(_) | EntryTable::Swapped(_,false) | EntryTable::Unaligned(_,false) => core::mem::size_of::<InternPosition>(),
+            EntryTable::Large(_) | EntryTable::Swapped(_,true) | EntryTable::Unaligned(_,true) => core::mem::size_of::<InternPosition64>(),
+            EntryTable::Compact(_) => 0,
+        }
+    }
+
+    pub(crate) fn is_swapped(&self) -> bool {
+        match self {
+            EntryTable::Swapped(_,_) => true,
+            EntryTable::Compact(x) => x.is_swapped(),
+            _ => false,
+        }
+    }
+
+    ///Returns whether the table was stored as compact header
+    #[cfg(feature = "std")]
+    pub(crate) fn is_compact(&self) -> bool {
+        matches!(self,EntryTable::Compact(_))
+    }
+
+    ///Returns the stored table, which holds this table followed by the given number of entries
+    pub(crate) fn stored(&self, following: usize) -> &'a [u8] {
+        let len = (self.len()+following)*self.entry_size();
+        match self {
+            EntryTable::Small(x) => unsafe{ core::slice::from_raw_parts(x.as_ptr().cast::<u8>(),len) },
+            EntryTable::Large(x) => unsafe{ core::slice::from_raw_parts(x.as_ptr().cast::<u8>(),len) },
+            EntryTable::Swapped(x,_) | EntryTable::Unaligned(x,_) => unsafe{ core::slice::from_raw_parts(x.as_ptr(),len) },
+            EntryTable::Compact(x) => x.stored(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Always writes 64 bit positions, without this the writer only switches to them for buffers
+    ///which do not fit into 2 GB
+    pub fn with_large_offsets(mut self) -> MemBufferWriter {
+        self.large_offsets = true;
+        self
+    }
+
+    ///Serializes the header for entries with the given types and lengths whose payloads are placed
+    ///in the given order, entries sharing the payload of another entry get its range. Returns the
+    ///header and the start of every entry relative to the end of the header.
+    pub(crate) fn serialize_header(&self, types: &[i32], lens: &[usize], order: &[usize], shared: &[Option<usize>]) -> (Vec<u8>,Vec<usize>) {
+        if self.uses_compact_header() {
+            return self.serialize_compact_header(types,lens);
+        }
+        //Upper bound of the payload including the padding of every entry
+        let payload_bound = lens.iter().sum::<usize>()+types.len()*core::mem::align_of::<u64>();
+        let large = self.large_offsets || payload_bound > i32::MAX as usize;
+        let entry_size = if large { core::mem::size_of::<InternPosition64>() } else { core::mem::size_of::<InternPosition>() };
+        let magic = header_magic(self.format_version,large,false);
+
+        let header_len = 8+types.len()*entry_size;
+        let mut header: Vec<u8> = Vec::with_capacity(header_len);
+        MemBufferWriter::serialize_i32_to(types.len() as i32,&mut header);
+        MemBufferWriter::serialize_i32_to((core::num::Wrapping(types.len() as i32)-core::num::Wrapping(magic)).0,&mut header);
+        let mut starts = vec![0;types.len()];
+        let mut offset = 0;
+        for x in order.iter().copied() {
+            if let Some(first) = shared[x] {
+                starts[x] = starts[first];
+                continue;
+            }
+            //Pad the entry so that slices can be cast in place when the buffer itself is aligned
+            let align = type_alignment(types[x]);
+            offset += (align - (header_len+offset)%align)%align;
+            starts[x] = offset;
+            offset += lens[x];
+        }
+        for ((variable_type,len),start) in types.iter().zip(lens.iter()).zip(starts.iter()) {
+            if large {
+                header.extend_from_slice(&(*start as u64).to_ne_bytes());
+                header.extend_from_slice(&((start+len) as u64).to_ne_bytes());
+                MemBufferWriter::serialize_i32_to(*variable_type, &mut header);
+                MemBufferWriter::serialize_i32_to(0, &mut header);
+            }
+            else {
+                MemBufferWriter::serialize_i32_to(*start as i32, &mut header);
+                MemBufferWriter::serialize_i32_to((start+len) as i32, &mut header);
+                MemBufferWriter::serialize_i32_to(*variable_type, &mut header);
+            }
+        }
+        if self.byte_order.is_swapped() {
+            swap_integers(&mut header[..8],4);
+            for entry in header[8..].chunks_exact_mut(entry_size) {
+                if large {
+                    swap_integers(&mut entry[..16],8);
+                    swap_integers(&mut entry[16..],4);
+                }
+                else {
+                    swap_integers(entry,4);
+                }
+            }
+        }
+        (header,starts)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader};
+
+    #[test]
+    fn check_large_offsets() {
+        let mut writer = MemBufferWriter::new().with_large_offsets().with_field_checksums();
+        writer.add_entry("Hello");
+        writer.add_entry::<&[u64]>(&[1,2,3]);
+        writer.add_entry_named("answer",42);
+        let result = writer.finalize();
+
+        let mut small = MemBufferWriter::new().with_field_checksums();
+        small.add_entry("Hello");
+        small.add_entry::<&[u64]>(&[1,2,3]);
+        small.add_entry_named("answer",42);
+        assert!(result.len() > small.finalize().len());
+
+        let reader = MemBufferReader::new_verified(&result).unwrap();
+        assert_eq!(reader.len(),3);
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+        assert_eq!(reader.load_entry::<&[u64]>(1).unwrap(),&[1,2,3]);
+        assert_eq!(reader.load_entry_by_name::<i32>("answer").unwrap(),42);
+        assert_eq!(reader.payload_len(),5+3+24+4);
+
+        //A writer loaded from the buffer keeps the entries but picks the layout by size again
+        let copy = MemBufferWriter::from(&result).unwrap().finalize();
+        assert_eq!(MemBufferReader::new(&copy).unwrap().load_entry_by_name::<i32>("answer").unwrap(),42);
+
+        //Truncated table
+        assert!(MemBufferReader::new(&result[..40]).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//!Transformations applied to the payloads of entries while they are loaded. Producers sometimes
+//!encode entries of their own types, for example obfuscated, prefixed or compressed with a codec
+//!this crate does not know. A `TransformRegistry` maps type ids to functions undoing the encoding,
+//!a reader created `with_transforms` applies them in `load_entry` before the payload is
+//!deserialized, so consumers load the entries like any other. Encryption, compression and the
+//!byte order of the buffer are undone before the transformation runs.
+//!
+//!Transformations returning borrowed data keep loads zero copy, owned results can only be loaded
+//!into owned types and have to be in native byte order. `MemBufferReader::load_transformed` returns
+//!the transformed payload itself.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferSerialize,MemBufferTypes,TransformRegistry};
+//!use std::borrow::Cow;
+//!
+//!//Payload is the text with every byte inverted
+//!struct Masked<'a>(&'a str);
+//!
+//!impl<'a> MemBufferSerialize for Masked<'a> {
+//!    fn to_mem_buffer<'b>(&'b self) -> Cow<'b,[u8]> {
+//!        Cow::Owned(self.0.bytes().map(|x| !x).collect())
+//!    }
+//!
+//!    fn get_mem_buffer_type() -> i32 {
+//!        MemBufferTypes::LastPreDefienedValue as i32
+//!    }
+//!}
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry(Masked("hidden"));
+//!let data = writer.finalize();
+//!
+//!let mut transforms = TransformRegistry::new();
+//!transforms.register_id(MemBufferTypes::LastPreDefienedValue as i32,|data: &[u8]| Ok(Cow::Owned(data.iter().map(|x| !x).collect())));
+//!let reader = MemBufferReader::new(&data).unwrap().with_transforms(&transforms);
+//!assert_eq!(&reader.load_transformed(0).unwrap()[..],b"hidden");
+//!```
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use crate::{MemBufferReader,MemBufferError,MemBufferSerialize};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+///Function undoing the encoding of the payloads of one type
+pub type Transform = dyn for<'x> Fn(&'x [u8]) -> Result<Cow<'x,[u8]>,MemBufferError> + Send + Sync;
+
+///Maps type ids to the transformations applied to their payloads, see the module documentation
+#[derive(Default)]
+pub struct TransformRegistry {
+    transforms: BTreeMap<i32,Box<Transform>>,
+}
+
+impl TransformRegistry {
+    pub fn new() -> TransformRegistry {
+        TransformRegistry::default()
+    }
+
+    ///Registers the transformation for the entries of the type, replacing an earlier one
+    pub fn register<T: MemBufferSerialize, F>(&mut self, transform: F)
+        where F: for<'x> Fn(&'x [u8]) -> Result<Cow<'x,[u8]>,MemBufferError> + Send + Sync + 'static {
+        self.register_id(T::get_mem_buffer_type(),transform);
+    }
+
+    ///Registers the transformation for the entries with the type id, replacing an earlier one
+    pub fn register_id<F>(&mut self, id: i32, transform: F)
+        where F: for<'x> Fn(&'x [u8]) -> Result<Cow<'x,[u8]>,MemBufferError> + Send + Sync + 'static {
+        self.transforms.insert(id,Box::new(transform));
+    }
+
+    ///Returns the transformation of the type
+    pub fn get(&self, id: i32) -> Option<&Transform> {
+        self.transforms.get(&id).map(|x| &**x)
+    }
+}
+
+impl core::fmt::Debug for TransformRegistry {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_set().entries(self.transforms.keys()).finish()
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Applies the transformations of the registry when entries are loaded
+    pub fn with_transforms(mut self, transforms: &'a TransformRegistry) -> MemBufferReader<'a> {
+        self.transforms = Some(transforms);
+        self
+    }
+
+    ///Returns the payload of the entry decrypted, decompressed and transformed. Integers are in
+    ///the byte order of the buffer if the payload is borrowed and in native byte order otherwise.
+    pub fn load_transformed(&self, index: usize) -> Result<Cow<'a,[u8]>,MemBufferError> {
+        if index >= self.offsets.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,self.offsets.len()));
+        }
+        let entry = self.offsets.get(index);
+        if let Some(hook) = self.access_hook {
+            hook(index,entry.variable_type,entry.len());
+        }
+        if entry.len() > self.max_entry_size {
+            return Err(MemBufferError::EntryTooLarge(entry.len(),self.max_entry_size));
+        }
+        self.ensure_validated(index)?;
+        let slice = self.entry_slice(entry)?;
+        self.transform_entry(index,slice)
+    }
+
+    ///Decodes the payload of the entry and applies the transformation of its type
+    pub(crate) fn transform_entry(&self, index: usize, slice: &'a [u8]) -> Result<Cow<'a,[u8]>,MemBufferError> {
+        let transform = self.transforms.and_then(|x| x.get(self.offsets.get(index).variable_type));
+        match (self.decompress_entry(index,slice)?,transform) {
+            (Some(data),Some(transform)) => Ok(Cow::Owned(transform(&data)?.into_owned())),
+            (Some(data),None) => Ok(Cow::Owned(data)),
+            (None,Some(transform)) => transform(slice),
+            (None,None) => Ok(Cow::Borrowed(slice)),
+        }
+    }
+
+    ///Returns whether loads of the entry pass through a transformation
+    pub(crate) fn has_transform(&self, variable_type: i32) -> bool {
+        self.transforms.is_some_and(|x| x.get(variable_type).is_some())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferTypes,TransformRegistry};
+    use std::borrow::Cow;
+
+    #[test]
+    fn check_transforms() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry(&[4u8,b'a',b'b',b'c'][..]);
+        writer.add_entry("untouched");
+        writer.add_entry("x");
+        let data = writer.finalize();
+
+        let mut transforms = TransformRegistry::new();
+        //Byte slices carry a length prefix which is stripped without copying
+        transforms.register::<&[u8],_>(|data: &[u8]| data.get(1..).map(Cow::Borrowed).ok_or(MemBufferError::WrongFormat));
+        let reader = MemBufferReader::new(&data).unwrap().with_transforms(&transforms);
+        assert_eq!(reader.load_entry::<&[u8]>(0).unwrap(),b"abc");
+        assert!(matches!(reader.load_transformed(0).unwrap(),Cow::Borrowed(b"abc")));
+        assert_eq!(reader.load_entry::<&str>(1).unwrap(),"untouched");
+
+        //Owned results can only be loaded into owned types
+        transforms.register_id(MemBufferTypes::Text as i32,|data: &[u8]| Ok(Cow::Owned(data.repeat(2))));
+        let reader = MemBufferReader::new(&data).unwrap().with_transforms(&transforms);
+        assert_eq!(reader.load_entry::<String>(2).unwrap(),"xx");
+        assert!(matches!(reader.load_entry::<&str>(2),Err(MemBufferError::CompressedEntry)));
+        assert_eq!(MemBufferReader::new(&data).unwrap().load_entry::<&str>(2).unwrap(),"x");
+    }
+}
@@ -0,0 +1,112 @@
+//!UTF-8 validation of text entries. With the default `simd` feature the validation runs on
+//!simdutf8 which checks several gigabytes per second, without it the standard library is used.
+//!`MemBufferReader::new_verified` validates every text entry together with the checksums.
+//!
+//!Loading text as `&str` or `String` always validates the entry and fails with
+//!`MemBufferError::InvalidUtf8`. `MemBufferReader::load_entry_unchecked` skips the validation for
+//!trusted buffers and `MemBufferReader::load_text_lossy` recovers damaged text by replacing the
+//!invalid sequences.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferError};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Grüße");
+//!let mut data = writer.finalize();
+//!assert!(MemBufferReader::new(&data).unwrap().verify_text().is_ok());
+//!
+//!//Turn the umlaut into an invalid byte sequence
+//!let pos = data.windows(2).position(|x| x == "ü".as_bytes()).unwrap();
+//!data[pos+1] = 0xFF;
+//!assert!(MemBufferReader::new(&data).unwrap().verify_text().is_err());
+//!assert!(matches!(MemBufferReader::new(&data).unwrap().load_entry::<&str>(0),Err(MemBufferError::InvalidUtf8)));
+//!assert_eq!(MemBufferReader::new(&data).unwrap().load_text_lossy(0).unwrap(),"Gr\u{FFFD}\u{FFFD}ße");
+//!```
+use alloc::borrow::Cow;
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+#[cfg(feature = "simd")]
+pub(crate) fn is_valid_utf8(val: &[u8]) -> bool {
+    simdutf8::basic::from_utf8(val).is_ok()
+}
+
+#[cfg(not(feature = "simd"))]
+pub(crate) fn is_valid_utf8(val: &[u8]) -> bool {
+    core::str::from_utf8(val).is_ok()
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Checks that every text entry of the buffer is valid UTF-8, readers created with
+    ///`new_lazily_validated` validate the text entries like a load does
+    pub fn verify_text(&self) -> Result<(),MemBufferError> {
+        //Compressed and encrypted text is checked when it is decoded
+        for (index,entry) in self.offsets.iter().enumerate().filter(|x| x.1.variable_type == MemBufferTypes::Text as i32) {
+            if self.validated.is_some() {
+                self.ensure_validated(index)?;
+            }
+            else if !self.is_compressed(index) && !self.is_encrypted(index) && !is_valid_utf8(self.entry_slice(entry)?) {
+                return Err(MemBufferError::InvalidUtf8);
+            }
+        }
+        Ok(())
+    }
+
+    ///Loads the text entry replacing invalid UTF-8 sequences with U+FFFD, valid text is borrowed
+    ///without copying. Meant for best effort recovery of damaged buffers.
+    pub fn load_text_lossy(&self, key: usize) -> Result<Cow<'a,str>,MemBufferError> {
+        match self.intern_load_entry::<Cow<'a,[u8]>>(key,MemBufferTypes::Text as i32,true)? {
+            Cow::Borrowed(data) => Ok(String::from_utf8_lossy(data)),
+            Cow::Owned(data) => Ok(Cow::Owned(String::from_utf8_lossy(&data).into_owned())),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError};
+    use std::borrow::Cow;
+
+    #[test]
+    fn check_verify_text() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry(&[0xFFu8,0xFE][..]);
+        writer.add_entry("Wörld");
+        let result = writer.finalize();
+        //Invalid bytes in binary entries are fine
+        assert!(MemBufferReader::new_verified(&result).is_ok());
+
+        let mut corrupted = MemBufferWriter::new();
+        corrupted.add_entry("Hello");
+        corrupted.add_entry("Wörld");
+        let mut corrupted = corrupted.finalize();
+        let pos = corrupted.windows(2).position(|x| x == "ö".as_bytes()).unwrap();
+        corrupted[pos] = 0xFF;
+        let reader = MemBufferReader::new(&corrupted).unwrap();
+        assert!(matches!(reader.verify_text(),Err(MemBufferError::InvalidUtf8)));
+        assert!(MemBufferReader::new_verified(&corrupted).is_err());
+    }
+
+    #[test]
+    fn check_checked_text_loads() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry("Wörld");
+        let mut data = writer.finalize();
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert!(matches!(reader.load_text_lossy(0).unwrap(),Cow::Borrowed("Hello")));
+        assert_eq!(unsafe{ reader.load_entry_unchecked::<&str>(1) }.unwrap(),"Wörld");
+
+        let pos = data.windows(2).position(|x| x == "ö".as_bytes()).unwrap();
+        data[pos] = 0xFF;
+        let reader = MemBufferReader::new(&data).unwrap();
+        assert!(matches!(reader.load_entry::<&str>(1),Err(MemBufferError::InvalidUtf8)));
+        assert!(matches!(reader.load_entry::<String>(1),Err(MemBufferError::InvalidUtf8)));
+        assert!(matches!(reader.load_value(1),Err(MemBufferError::InvalidUtf8)));
+        assert_eq!(reader.load_text_lossy(1).unwrap(),"W\u{FFFD}\u{FFFD}rld");
+        assert!(matches!(reader.load_text_lossy(5),Err(MemBufferError::IndexOutOfBounds(5,2))));
+        assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+    }
+}
@@ -0,0 +1,186 @@
+//!Lazy validation of untrusted buffers. `MemBufferReader::new_verified` hashes and checks the whole
+//!buffer before the first load, which dominates the open latency of buffers with thousands of
+//!entries when only a few of them are read. `MemBufferReader::new_lazily_validated` only checks
+//!the entry table and the metadata entries including the tables stored as nested buffers like the
+//!names when the buffer is opened and validates every entry the first time it is loaded. The
+//!result is remembered in a bitset with one bit per entry, so every entry is checked once before
+//!it is used and later loads pay nothing.
+//!
+//!An entry is valid if it lies within the payload, matches its field checksum if the writer stored
+//!field checksums and holds valid UTF-8 if it is text. The checksum of the whole payload can only
+//!be verified eagerly with `verify_checksums`.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,MemBufferError};
+//!
+//!let mut writer = MemBufferWriter::new().with_field_checksums();
+//!writer.add_entry("intact");
+//!writer.add_entry("damaged");
+//!let mut data = writer.finalize();
+//!let pos = data.windows(7).position(|x| x == b"damaged").unwrap();
+//!data[pos] ^= 1;
+//!
+//!let reader = MemBufferReader::new_lazily_validated(&data).unwrap();
+//!assert_eq!(reader.load_entry::<&str>(0).unwrap(),"intact");
+//!assert!(matches!(reader.load_entry::<&str>(1),Err(MemBufferError::ChecksumMismatch)));
+//!```
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize,Ordering};
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes};
+use crate::{SYSTEM_TYPE_INTERN_TABLE,SYSTEM_TYPE_NAMES,SYSTEM_TYPE_REGISTRY,SYSTEM_TYPE_CONTENT_TYPES,SYSTEM_TYPE_LABELS,SYSTEM_TYPE_FLAGS};
+use crate::utf8::is_valid_utf8;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
+const WORD_BITS: usize = usize::BITS as usize;
+
+///Metadata entries which hold a nested buffer
+const NESTED_TABLES: [i32;6] = [SYSTEM_TYPE_INTERN_TABLE,SYSTEM_TYPE_NAMES,SYSTEM_TYPE_REGISTRY,SYSTEM_TYPE_CONTENT_TYPES,SYSTEM_TYPE_LABELS,SYSTEM_TYPE_FLAGS];
+
+///One bit per entry which is set once the entry passed validation, shared by the readers loading
+///the entries of one buffer in parallel
+#[derive(Debug)]
+pub(crate) struct ValidatedEntries {
+    bits: Vec<AtomicUsize>,
+}
+
+impl ValidatedEntries {
+    fn new(entries: usize) -> ValidatedEntries {
+        ValidatedEntries {
+            bits: (0..entries.div_ceil(WORD_BITS)).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        self.bits[index/WORD_BITS].load(Ordering::Acquire) & 1 << (index%WORD_BITS) != 0
+    }
+
+    fn insert(&self, index: usize) {
+        self.bits[index/WORD_BITS].fetch_or(1 << (index%WORD_BITS),Ordering::Release);
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Creates the reader like `MemBufferReader::new`, checks the entry table against its checksum
+    ///and the metadata entries and validates every other entry on its first load, see the module
+    ///documentation
+    pub fn new_lazily_validated(val: &'a [u8]) -> Result<MemBufferReader<'a>,MemBufferError> {
+        let mut reader = MemBufferReader::new(val)?;
+        for entry in reader.system.iter() {
+            if entry.start > entry.end || entry.end > reader.data.len() {
+                return Err(MemBufferError::WrongFormat);
+            }
+        }
+        reader.verify_header_checksum()?;
+        reader.check_metadata_tables()?;
+        reader.validated = Some(Arc::new(ValidatedEntries::new(reader.len())));
+        Ok(reader)
+    }
+
+    ///Checks that every entry of the metadata tables stored as nested buffers lies within its table
+    fn check_metadata_tables(&self) -> Result<(),MemBufferError> {
+        for entry in self.system.iter().filter(|x| NESTED_TABLES.contains(&x.variable_type)) {
+            MemBufferReader::new_validated(self.entry_slice(entry)?)?;
+        }
+        Ok(())
+    }
+
+    ///Checks that the entry lies within the payload, matches its field checksum if the buffer has
+    ///field checksums, holds valid UTF-8 if it is text and records a possible uncompressed length
+    ///if it is compressed. Compressed and encrypted text is checked when it is decoded.
+    pub fn validate_entry(&self, index: usize) -> Result<(),MemBufferError> {
+        if index >= self.offsets.len() {
+            return Err(MemBufferError::IndexOutOfBounds(index,self.offsets.len()));
+        }
+        let entry = self.offsets.get(index);
+        if entry.start > entry.end || entry.end > self.data.len() {
+            return Err(MemBufferError::WrongFormat);
+        }
+        self.verify_field_checksum(index)?;
+        self.check_compressed_entry(index)?;
+        if entry.variable_type == MemBufferTypes::Text as i32 && !self.is_compressed(index) && !self.is_encrypted(index) && !is_valid_utf8(self.entry_slice(entry)?) {
+            return Err(MemBufferError::InvalidUtf8);
+        }
+        Ok(())
+    }
+
+    ///Validates the entry before its first load if the reader was created with
+    ///`new_lazily_validated`, entries which fail are checked again on the next load
+    pub(crate) fn ensure_validated(&self, index: usize) -> Result<(),MemBufferError> {
+        match self.validated.as_ref() {
+            Some(validated) if !validated.contains(index) => {
+                self.validate_entry(index)?;
+                validated.insert(index);
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,Endianness};
+
+    #[test]
+    fn check_lazy_validation() {
+        let mut writer = MemBufferWriter::new().with_byte_order(Endianness::Big).with_field_checksums();
+        for x in 0..100u64 {
+            writer.add_entry::<&[u64]>(&[x;3]);
+        }
+        writer.add_entry("Grüße");
+        let data = writer.finalize();
+        let reader = MemBufferReader::new_lazily_validated(&data).unwrap();
+        assert!(!reader.validated.as_ref().unwrap().contains(70));
+        assert_eq!(reader.load_entry::<std::borrow::Cow<[u64]>>(70).unwrap()[..],[70;3]);
+        assert!(reader.validated.as_ref().unwrap().contains(70));
+        assert!(!reader.validated.as_ref().unwrap().contains(69));
+        assert_eq!(reader.load_entry::<&str>(100).unwrap(),"Grüße");
+
+        //Corrupted entries fail on every load while the other entries stay readable
+        let mut corrupted = data.clone();
+        let pos = corrupted.windows(2).position(|x| x == "ü".as_bytes()).unwrap();
+        corrupted[pos] = 0xFF;
+        let reader = MemBufferReader::new_lazily_validated(&corrupted).unwrap();
+        for _ in 0..2 {
+            assert!(matches!(reader.load_entry::<&str>(100),Err(MemBufferError::ChecksumMismatch)));
+            assert!(matches!(reader.load_value(100),Err(MemBufferError::ChecksumMismatch)));
+        }
+        assert!(reader.load_entry::<std::borrow::Cow<[u64]>>(3).is_ok());
+
+        //The entry table is checked when the buffer is opened
+        let mut corrupted = data.clone();
+        corrupted[8+5*12+8] ^= 1;
+        assert!(matches!(MemBufferReader::new_lazily_validated(&corrupted),Err(MemBufferError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn check_lazy_metadata() {
+        let mut writer = MemBufferWriter::new().with_field_checksums();
+        writer.add_entry_named("title","Frankenstein");
+        writer.add_entry_labeled("Grüße","greetings");
+        writer.add_entry_labeled("Hello","greetings");
+        let data = writer.finalize();
+        let reader = MemBufferReader::new_lazily_validated(&data).unwrap();
+        assert!(reader.verify_text().is_ok());
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+        assert_eq!(reader.stats_by_label()[&Some("greetings")].entries,2);
+
+        //Damaged text is found by the same check a load runs and left out of the statistics
+        let mut corrupted = data.clone();
+        let pos = corrupted.windows(2).position(|x| x == "ü".as_bytes()).unwrap();
+        corrupted[pos] = 0xFF;
+        let reader = MemBufferReader::new_lazily_validated(&corrupted).unwrap();
+        assert!(matches!(reader.verify_text(),Err(MemBufferError::ChecksumMismatch)));
+        assert_eq!(reader.stats_by_label()[&Some("greetings")].entries,1);
+        assert_eq!(reader.load_entry_by_name::<&str>("title").unwrap(),"Frankenstein");
+
+        //The name table is checked when the buffer is opened
+        let names = MemBufferReader::new(&data).unwrap().system.iter().find(|x| x.variable_type == crate::SYSTEM_TYPE_NAMES).unwrap();
+        let mut corrupted = data.clone();
+        let table = data.len()-MemBufferReader::new(&data).unwrap().data.len()+names.start;
+        corrupted[table+8+4..table+8+8].copy_from_slice(&u32::MAX.to_ne_bytes());
+        assert!(MemBufferReader::new(&corrupted).is_ok());
+        assert!(matches!(MemBufferReader::new_lazily_validated(&corrupted),Err(MemBufferError::WrongFormat)));
+    }
+}
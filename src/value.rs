@@ -0,0 +1,492 @@
+//!Dynamically typed access to the entries of a buffer. `MemBufferReader::values` walks all entries
+//!without knowing their types up front and checks every entry before handing it out: the position
+//!has to lie within the payload, text has to be valid UTF-8, integer slices have to be aligned and
+//!nested buffers need a valid header. A broken entry yields its error and the scan continues with
+//!the next one, so services ingesting untrusted buffers can salvage the intact entries.
+//!`MemBufferReader::entries` yields the same values for trusted buffers and skips the validation
+//!of text and nested headers, `MemBufferReader::iter` yields the raw payloads with their type ids.
+//!Entries of type ids this version does not know are yielded as `Value::Unknown` with their raw
+//!payload and `MemBufferWriter::add_value` stores them unchanged, so copies keep them.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,Value};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello");
+//!writer.add_entry(42);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!let values: Vec<Value> = reader.values().filter_map(Result::ok).collect();
+//!assert!(matches!(values[0],Value::Text("Hello")));
+//!assert!(matches!(values[1],Value::I32(42)));
+//!```
+use crate::{MemBufferReader,MemBufferError,MemBufferTypes,MemBufferDeserialize,MemBufferSerialize,MemBufferMapView,StrListView,BytesListView,ChunkedView};
+#[cfg(feature = "std")]
+use crate::{MemBufferWriter,is_system_type};
+
+///Entry of a buffer with its type resolved at runtime, entries of types not built into the crate
+///are returned as `Unknown` with their type id and raw payload
+#[derive(Debug)]
+pub enum Value<'a> {
+    Text(&'a str),
+    I32(i32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Bytes(&'a [u8]),
+    U32Slice(&'a [u32]),
+    U64Slice(&'a [u64]),
+    F32Slice(&'a [f32]),
+    F64Slice(&'a [f64]),
+    Nested(MemBufferReader<'a>),
+    Map(MemBufferMapView<'a>),
+    Null,
+    StrList(StrListView<'a>),
+    BytesList(BytesListView<'a>),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    I8(i8),
+    I16(i16),
+    I64(i64),
+    Bool(bool),
+    Char(char),
+    Chunked(ChunkedView<'a>),
+    Unknown(i32,&'a [u8]),
+}
+
+///Name of `Value` used by the entry iterators
+pub type MemBufferValue<'a> = Value<'a>;
+
+///Iterator over the values of all entries of a reader, see the module documentation
+pub struct Values<'r,'a> {
+    reader: &'r MemBufferReader<'a>,
+    index: usize,
+    checked: bool,
+}
+
+impl<'r,'a> Iterator for Values<'r,'a> {
+    type Item = Result<Value<'a>,MemBufferError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.reader.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.reader.dynamic_value(self.index-1,self.checked))
+    }
+
+    fn size_hint(&self) -> (usize,Option<usize>) {
+        let remaining = self.reader.len()-self.index;
+        (remaining,Some(remaining))
+    }
+}
+
+impl<'r,'a> ExactSizeIterator for Values<'r,'a> {}
+
+///Iterator over the index, the type id and the raw payload of all entries of a reader
+pub struct EntryIter<'r,'a> {
+    reader: &'r MemBufferReader<'a>,
+    index: usize,
+}
+
+impl<'r,'a> Iterator for EntryIter<'r,'a> {
+    type Item = (usize,i32,&'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.reader.len() {
+            return None;
+        }
+        let entry = self.reader.offsets.get(self.index);
+        self.index += 1;
+        //Entries outside of the payload only occur in corrupted buffers
+        Some((self.index-1,entry.variable_type,self.reader.entry_slice(entry).unwrap_or_default()))
+    }
+
+    fn size_hint(&self) -> (usize,Option<usize>) {
+        let remaining = self.reader.len()-self.index;
+        (remaining,Some(remaining))
+    }
+}
+
+impl<'r,'a> ExactSizeIterator for EntryIter<'r,'a> {}
+
+///Checks that the payload can be cast to a slice of integers with the given size
+fn check_slice(data: &[u8], size: usize, swapped: bool) -> Result<(),MemBufferError> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    if swapped {
+        return Err(MemBufferError::ByteOrderMismatch);
+    }
+    if !data.len().is_multiple_of(size) {
+        return Err(MemBufferError::WrongFormat);
+    }
+    if data.as_ptr().align_offset(size) != 0 {
+        return Err(MemBufferError::UnalignedEntry);
+    }
+    Ok(())
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns an iterator yielding the checked value of every entry or the error of the entry
+    pub fn values(&self) -> Values<'_,'a> {
+        Values {
+            reader: self,
+            index: 0,
+            checked: true,
+        }
+    }
+
+    ///Returns an iterator yielding the value of every entry without validating nested headers and
+    ///interned text ids, use `values` for untrusted buffers
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferReader,MemBufferValue};
+    ///
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("Hello");
+    ///writer.add_entry::<&[u64]>(&[1,2]);
+    ///let data = writer.finalize();
+    ///
+    ///let reader = MemBufferReader::new(&data).unwrap();
+    ///for value in reader.entries() {
+    ///    match value.unwrap() {
+    ///        MemBufferValue::Text(x) => assert_eq!(x,"Hello"),
+    ///        MemBufferValue::U64Slice(x) => assert_eq!(x,&[1,2]),
+    ///        _ => unreachable!(),
+    ///    }
+    ///}
+    ///assert_eq!(reader.iter().map(|(index,_,payload)| (index,payload.len())).collect::<Vec<_>>(),vec![(0,5),(1,16)]);
+    ///```
+    pub fn entries(&self) -> Values<'_,'a> {
+        Values {
+            reader: self,
+            index: 0,
+            checked: false,
+        }
+    }
+
+    ///Returns an iterator yielding the index, the type id and the raw payload of every entry
+    pub fn iter(&self) -> EntryIter<'_,'a> {
+        EntryIter {
+            reader: self,
+            index: 0,
+        }
+    }
+
+    ///Loads the entry with the given index as dynamically typed value, validating its payload.
+    ///Compressed entries fail with `MemBufferError::CompressedEntry`.
+    pub fn load_value(&self, key: usize) -> Result<Value<'a>,MemBufferError> {
+        self.dynamic_value(key,true)
+    }
+
+    ///Loads the entry as dynamically typed value, text and nested headers are only validated if
+    ///requested
+    fn dynamic_value(&self, key: usize, checked: bool) -> Result<Value<'a>,MemBufferError> {
+        if key >= self.offsets.len() {
+            return Err(MemBufferError::IndexOutOfBounds(key,self.offsets.len()));
+        }
+        let entry = self.offsets.get(key);
+        if entry.start > entry.end {
+            return Err(MemBufferError::WrongFormat);
+        }
+        if entry.len() > self.max_entry_size {
+            return Err(MemBufferError::EntryTooLarge(entry.len(),self.max_entry_size));
+        }
+        self.ensure_validated(key)?;
+        let data = self.entry_slice(entry)?;
+        if self.compression_of(key)?.is_some() || self.is_encrypted(key) {
+            return Err(MemBufferError::CompressedEntry);
+        }
+        let swapped = self.is_byte_swapped();
+        let variable_type = entry.variable_type;
+
+        if variable_type == MemBufferTypes::Text as i32 {
+            Ok(Value::Text(<&str>::from_mem_buffer(data)?))
+        }
+        else if variable_type == MemBufferTypes::InternedText as i32 {
+            Ok(Value::Text(<&str>::from_mem_buffer(self.resolve_interned(data)?)?))
+        }
+        else if variable_type == MemBufferTypes::Integer32 as i32 {
+            let val = if swapped { i32::from_swapped_mem_buffer(data)? } else { i32::from_mem_buffer(data)? };
+            Ok(Value::I32(val))
+        }
+        else if variable_type == <u64 as MemBufferSerialize>::get_mem_buffer_type() {
+            let val = if swapped { u64::from_swapped_mem_buffer(data)? } else { u64::from_mem_buffer(data)? };
+            Ok(Value::U64(val))
+        }
+        else if variable_type == MemBufferTypes::Float32 as i32 {
+            let val = if swapped { f32::from_swapped_mem_buffer(data)? } else { f32::from_mem_buffer(data)? };
+            Ok(Value::F32(val))
+        }
+        else if variable_type == MemBufferTypes::Float64 as i32 {
+            let val = if swapped { f64::from_swapped_mem_buffer(data)? } else { f64::from_mem_buffer(data)? };
+            Ok(Value::F64(val))
+        }
+        else if variable_type == MemBufferTypes::VectorU8 as i32 {
+            Ok(Value::Bytes(data))
+        }
+        else if variable_type == MemBufferTypes::VectorU32 as i32 {
+            check_slice(data,core::mem::size_of::<u32>(),swapped)?;
+            Ok(Value::U32Slice(<&[u32]>::from_mem_buffer(data)?))
+        }
+        else if variable_type == MemBufferTypes::VectorU64 as i32 {
+            check_slice(data,core::mem::size_of::<u64>(),swapped)?;
+            Ok(Value::U64Slice(<&[u64]>::from_mem_buffer(data)?))
+        }
+        else if variable_type == MemBufferTypes::VectorF32 as i32 {
+            check_slice(data,core::mem::size_of::<f32>(),swapped)?;
+            Ok(Value::F32Slice(<&[f32]>::from_mem_buffer(data)?))
+        }
+        else if variable_type == MemBufferTypes::VectorF64 as i32 {
+            check_slice(data,core::mem::size_of::<f64>(),swapped)?;
+            Ok(Value::F64Slice(<&[f64]>::from_mem_buffer(data)?))
+        }
+        else if variable_type == MemBufferTypes::MemBuffer as i32 {
+            let nested = if checked { MemBufferReader::new_validated(data)? } else { MemBufferReader::new(data)? };
+            Ok(Value::Nested(nested.with_max_entry_size(self.max_entry_size)))
+        }
+        else if variable_type == MemBufferTypes::Map as i32 {
+            Ok(Value::Map(MemBufferMapView::from_mem_buffer(data)?))
+        }
+        else if variable_type == MemBufferTypes::Null as i32 {
+            if !data.is_empty() {
+                return Err(MemBufferError::WrongFormat);
+            }
+            Ok(Value::Null)
+        }
+        else if variable_type == MemBufferTypes::StrList as i32 {
+            Ok(Value::StrList(StrListView::from_mem_buffer(data)?))
+        }
+        else if variable_type == MemBufferTypes::BytesList as i32 {
+            Ok(Value::BytesList(BytesListView::from_mem_buffer(data)?))
+        }
+        else if variable_type == MemBufferTypes::Chunked as i32 {
+            Ok(Value::Chunked(ChunkedView::from_mem_buffer(data)?))
+        }
+        else if let Some(val) = self.scalar_value(variable_type,data,swapped) {
+            val
+        }
+        else {
+            Ok(Value::Unknown(variable_type,data))
+        }
+    }
+
+    ///Loads the scalars of the remaining integer widths, `bool` and `char`, returns None for
+    ///other types
+    fn scalar_value(&self, variable_type: i32, data: &[u8], swapped: bool) -> Option<Result<Value<'a>,MemBufferError>> {
+        fn load<'x,T: MemBufferDeserialize<'x,T>>(data: &'x [u8], swapped: bool) -> Result<T,MemBufferError> {
+            if swapped { T::from_swapped_mem_buffer(data) } else { T::from_mem_buffer(data) }
+        }
+        Some(match variable_type {
+            x if x == MemBufferTypes::Unsigned8 as i32 => load(data,swapped).map(Value::U8),
+            x if x == MemBufferTypes::Unsigned16 as i32 => load(data,swapped).map(Value::U16),
+            x if x == MemBufferTypes::Unsigned32 as i32 => load(data,swapped).map(Value::U32),
+            x if x == MemBufferTypes::Signed8 as i32 => load(data,swapped).map(Value::I8),
+            x if x == MemBufferTypes::Signed16 as i32 => load(data,swapped).map(Value::I16),
+            x if x == MemBufferTypes::Signed64 as i32 => load(data,swapped).map(Value::I64),
+            x if x == MemBufferTypes::Bool as i32 => load(data,swapped).map(Value::Bool),
+            x if x == MemBufferTypes::Char as i32 => load(data,swapped).map(Value::Char),
+            _ => return None,
+        })
+    }
+}
+
+impl<'a> Value<'a> {
+    ///Returns the type id the value is stored with, interned text is reported as text
+    pub fn variable_type(&self) -> i32 {
+        match self {
+            Value::Text(_) => MemBufferTypes::Text as i32,
+            Value::I32(_) => MemBufferTypes::Integer32 as i32,
+            Value::U64(_) => MemBufferTypes::Integer64 as i32,
+            Value::F32(_) => MemBufferTypes::Float32 as i32,
+            Value::F64(_) => MemBufferTypes::Float64 as i32,
+            Value::Bytes(_) => MemBufferTypes::VectorU8 as i32,
+            Value::U32Slice(_) => MemBufferTypes::VectorU32 as i32,
+            Value::U64Slice(_) => MemBufferTypes::VectorU64 as i32,
+            Value::F32Slice(_) => MemBufferTypes::VectorF32 as i32,
+            Value::F64Slice(_) => MemBufferTypes::VectorF64 as i32,
+            Value::Nested(_) => MemBufferTypes::MemBuffer as i32,
+            Value::Map(_) => MemBufferTypes::Map as i32,
+            Value::Null => MemBufferTypes::Null as i32,
+            Value::StrList(_) => MemBufferTypes::StrList as i32,
+            Value::BytesList(_) => MemBufferTypes::BytesList as i32,
+            Value::U8(_) => MemBufferTypes::Unsigned8 as i32,
+            Value::U16(_) => MemBufferTypes::Unsigned16 as i32,
+            Value::U32(_) => MemBufferTypes::Unsigned32 as i32,
+            Value::I8(_) => MemBufferTypes::Signed8 as i32,
+            Value::I16(_) => MemBufferTypes::Signed16 as i32,
+            Value::I64(_) => MemBufferTypes::Signed64 as i32,
+            Value::Bool(_) => MemBufferTypes::Bool as i32,
+            Value::Char(_) => MemBufferTypes::Char as i32,
+            Value::Chunked(_) => MemBufferTypes::Chunked as i32,
+            Value::Unknown(variable_type,_) => *variable_type,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Adds an entry with the given type id and payload without interpreting it, the payload has to
+    ///be in the byte order of the machine. Panics for the type ids reserved for metadata entries.
+    pub fn add_raw_entry(&mut self, variable_type: i32, data: &[u8]) {
+        assert!(!is_system_type(variable_type),"The type id {} is reserved for metadata entries",variable_type);
+        self.check_schema_field(self.types.len(),variable_type);
+        self.types.push(variable_type);
+        self.data.push(data.into());
+        self.log_payload(self.types.len()-1,crate::Change::Add);
+    }
+
+    ///Adds a value loaded with `MemBufferReader::values` or `entries`. `Value::Unknown` is stored
+    ///with its type id and payload unchanged, so intermediaries copying buffers keep the entries
+    ///of types added by newer versions. Fails if a nested buffer cannot be loaded.
+    ///```rust
+    ///use membuffer::{MemBufferWriter,MemBufferReader};
+    ///
+    ///let mut writer = MemBufferWriter::new();
+    ///writer.add_entry("known");
+    ///writer.add_raw_entry(5000,&[1,2,3]);
+    ///let data = writer.finalize();
+    ///
+    ///let reader = MemBufferReader::new(&data).unwrap();
+    ///let mut copy = MemBufferWriter::new();
+    ///for value in reader.values() {
+    ///    copy.add_value(&value.unwrap()).unwrap();
+    ///}
+    ///let copied = copy.finalize();
+    ///assert!(MemBufferReader::new(&copied).unwrap().iter().eq(reader.iter()));
+    ///```
+    pub fn add_value(&mut self, val: &Value) -> Result<(),MemBufferError> {
+        match val {
+            Value::Text(x) => self.add_entry(*x),
+            Value::I32(x) => self.add_entry(*x),
+            Value::U64(x) => self.add_entry(*x),
+            Value::F32(x) => self.add_entry(*x),
+            Value::F64(x) => self.add_entry(*x),
+            Value::Bytes(x) => self.add_entry(*x),
+            Value::U32Slice(x) => self.add_entry(*x),
+            Value::U64Slice(x) => self.add_entry(*x),
+            Value::F32Slice(x) => self.add_entry(*x),
+            Value::F64Slice(x) => self.add_entry(*x),
+            Value::Nested(x) => self.add_entry(MemBufferWriter::from_reader(x)?),
+            Value::Map(x) => self.add_entry_ref(x),
+            Value::Null => self.add_null_entry(),
+            Value::StrList(x) => self.add_entry_ref(x),
+            Value::BytesList(x) => self.add_entry_ref(x),
+            Value::U8(x) => self.add_entry(*x),
+            Value::U16(x) => self.add_entry(*x),
+            Value::U32(x) => self.add_entry(*x),
+            Value::I8(x) => self.add_entry(*x),
+            Value::I16(x) => self.add_entry(*x),
+            Value::I64(x) => self.add_entry(*x),
+            Value::Bool(x) => self.add_entry(*x),
+            Value::Char(x) => self.add_entry(*x),
+            Value::Chunked(x) => self.add_entry_ref(x),
+            Value::Unknown(variable_type,x) => self.add_raw_entry(*variable_type,x),
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferSerialize,MemBufferTypes,MemBufferValue,Value};
+    use std::borrow::Cow;
+
+    struct Custom;
+
+    impl MemBufferSerialize for Custom {
+        fn to_mem_buffer<'a>(&'a self) -> Cow<'a,[u8]> {
+            Cow::Borrowed(&[9])
+        }
+
+        fn get_mem_buffer_type() -> i32 {
+            777
+        }
+    }
+
+    #[test]
+    fn check_values() {
+        let mut nested = MemBufferWriter::new();
+        nested.add_entry("inner");
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry(-3);
+        writer.add_entry(7u64);
+        writer.add_entry(&[1u8,2][..]);
+        writer.add_entry::<&[u32]>(&[3,4]);
+        writer.add_entry::<&[u64]>(&[5]);
+        writer.add_entry(nested);
+        writer.add_interned_entry("label");
+        writer.add_entry(Custom);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        let values: Vec<Value> = reader.values().map(Result::unwrap).collect();
+        assert_eq!(values.len(),9);
+        assert!(matches!(values[0],Value::Text("Hello")));
+        assert!(matches!(values[1],Value::I32(-3)));
+        assert!(matches!(values[2],Value::U64(7)));
+        assert!(matches!(values[3],Value::Bytes(&[1,2])));
+        assert!(matches!(values[4],Value::U32Slice(&[3,4])));
+        assert!(matches!(values[5],Value::U64Slice(&[5])));
+        match &values[6] {
+            Value::Nested(inner) => assert_eq!(inner.load_entry::<&str>(0).unwrap(),"inner"),
+            _ => panic!("Expected nested buffer"),
+        }
+        assert!(matches!(values[7],Value::Text("label")));
+        assert!(matches!(values[8],Value::Unknown(777,&[9])));
+
+        //Copies keep the entries of unknown types
+        let mut copy = MemBufferWriter::new();
+        values.iter().for_each(|x| copy.add_value(x).unwrap());
+        let copied = copy.finalize();
+        let reader = MemBufferReader::new(&copied).unwrap();
+        assert_eq!(reader.iter().nth(6).unwrap().2,MemBufferReader::new(&result).unwrap().iter().nth(6).unwrap().2);
+        let copied: Vec<Value> = reader.values().map(Result::unwrap).collect();
+        assert!(copied.iter().zip(values.iter()).all(|(x,y)| x.variable_type() == y.variable_type()));
+        assert!(matches!(copied[8],Value::Unknown(777,&[9])));
+        let copied = MemBufferWriter::from(&result).unwrap().finalize();
+        assert!(matches!(MemBufferReader::new(&copied).unwrap().load_value(8).unwrap(),Value::Unknown(777,&[9])));
+    }
+
+    #[test]
+    fn check_entries() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_interned_entry("label");
+        writer.add_entry(Custom);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        let raw: Vec<(usize,i32,&[u8])> = reader.iter().collect();
+        assert_eq!(raw,vec![(0,MemBufferTypes::Text as i32,&b"Hello"[..]),(1,MemBufferTypes::InternedText as i32,&0i32.to_ne_bytes()[..]),(2,777,&[9][..])]);
+        let entries: Vec<MemBufferValue> = reader.entries().map(Result::unwrap).collect();
+        assert!(matches!(entries[0],MemBufferValue::Text("Hello")));
+        assert!(matches!(entries[1],MemBufferValue::Text("label")));
+        assert!(matches!(entries[2],MemBufferValue::Unknown(777,&[9])));
+        assert_eq!(reader.entries().len(),3);
+    }
+
+    #[test]
+    fn check_values_salvage() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry("Wörld");
+        writer.add_entry(&[0u8;4][..]);
+        writer.add_entry("intact");
+        let mut result = writer.finalize();
+        let pos = result.windows(2).position(|x| x == "ö".as_bytes()).unwrap();
+        result[pos] = 0xFF;
+        //Let the end of the third entry point far behind the payload
+        result[8+2*12+4..8+2*12+8].copy_from_slice(&1_000_000i32.to_ne_bytes());
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        let values: Vec<Result<Value,MemBufferError>> = reader.values().collect();
+        assert!(matches!(values[0],Ok(Value::Text("Hello"))));
+        assert!(matches!(values[1],Err(MemBufferError::InvalidUtf8)));
+        assert!(matches!(values[2],Err(MemBufferError::WrongFormat)));
+        assert!(matches!(values[3],Ok(Value::Text("intact"))));
+    }
+}
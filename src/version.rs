@@ -0,0 +1,95 @@
+//!Format versions. Every buffer carries the version of its layout, the default layout stores it in
+//!the magic word of the header and the interleaved layout in the word behind its magic. Buffers of
+//!earlier releases have no version and are read as `LEGACY_FORMAT_VERSION`. Readers fail with
+//!`MemBufferError::UnsupportedVersion` for buffers of newer versions instead of reporting them as
+//!corrupted, and writers can target an older version with `with_format_version` so consumers
+//!which were not updated yet can still read the buffers. Writers loaded from a buffer keep its
+//!version.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader,FORMAT_VERSION,LEGACY_FORMAT_VERSION};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello");
+//!let data = writer.finalize();
+//!assert_eq!(MemBufferReader::new(&data).unwrap().format_version(),FORMAT_VERSION);
+//!
+//!let legacy = writer.with_format_version(LEGACY_FORMAT_VERSION).finalize();
+//!let reader = MemBufferReader::new(&legacy).unwrap();
+//!assert_eq!(reader.format_version(),LEGACY_FORMAT_VERSION);
+//!assert_eq!(reader.load_entry::<&str>(0).unwrap(),"Hello");
+//!```
+use crate::{MemBufferReader};
+#[cfg(feature = "std")]
+use crate::MemBufferWriter;
+
+///Version of the layout written by default
+pub const FORMAT_VERSION: u32 = 2;
+///Version of buffers written before the layout carried a version
+pub const LEGACY_FORMAT_VERSION: u32 = 1;
+
+#[cfg(feature = "std")]
+impl MemBufferWriter {
+    ///Writes the layout of the given version for readers which do not support the current one.
+    ///Panics if the version is not between `LEGACY_FORMAT_VERSION` and `FORMAT_VERSION`.
+    pub fn with_format_version(mut self, version: u32) -> MemBufferWriter {
+        assert!((LEGACY_FORMAT_VERSION..=FORMAT_VERSION).contains(&version),"Format version {} is not supported",version);
+        self.format_version = version;
+        self
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns the format version the buffer was written with
+    pub fn format_version(&self) -> u32 {
+        self.version
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader,MemBufferError,MemBufferStreamReader,Endianness,FORMAT_VERSION,LEGACY_FORMAT_VERSION};
+
+    #[test]
+    fn check_format_versions() {
+        for byte_order in [Endianness::Native,Endianness::Big,Endianness::Little].iter() {
+            for large in [false,true].iter() {
+                for version in LEGACY_FORMAT_VERSION..=FORMAT_VERSION {
+                    let mut writer = MemBufferWriter::new().with_byte_order(*byte_order).with_format_version(version);
+                    if *large {
+                        writer = writer.with_large_offsets();
+                    }
+                    writer.add_entry("Hello");
+                    writer.add_entry(42);
+                    let data = writer.finalize();
+                    let reader = MemBufferReader::new(&data).unwrap();
+                    assert_eq!(reader.format_version(),version);
+                    assert_eq!(reader.load_entry::<i32>(1).unwrap(),42);
+                    assert_eq!(MemBufferReader::new(&MemBufferWriter::from(&data).unwrap().finalize()).unwrap().format_version(),version);
+                }
+            }
+        }
+
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        let interleaved = writer.finalize_interleaved();
+        assert_eq!(MemBufferReader::new(&interleaved).unwrap().format_version(),FORMAT_VERSION);
+        let legacy = writer.clone().with_format_version(LEGACY_FORMAT_VERSION).finalize_interleaved();
+        assert_eq!(&legacy[4..8],&[0;4]);
+        assert_eq!(MemBufferReader::new(&legacy).unwrap().format_version(),LEGACY_FORMAT_VERSION);
+
+        //Buffers of newer versions are told apart from corrupted ones
+        let mut newer = writer.finalize();
+        let count = i32::from_ne_bytes([newer[0],newer[1],newer[2],newer[3]]);
+        let magic = crate::table::header_magic(FORMAT_VERSION+1,false,false);
+        newer[4..8].copy_from_slice(&(std::num::Wrapping(count)-std::num::Wrapping(magic)).0.to_ne_bytes());
+        assert!(matches!(MemBufferReader::new(&newer),Err(MemBufferError::UnsupportedVersion(x)) if x == FORMAT_VERSION+1));
+        let mut newer = interleaved.clone();
+        newer[4..8].copy_from_slice(&(FORMAT_VERSION+1).to_ne_bytes());
+        assert!(matches!(MemBufferReader::new(&newer),Err(MemBufferError::UnsupportedVersion(_))));
+        assert!(MemBufferStreamReader::new(&newer[..]).is_err());
+        let mut corrupted = writer.finalize();
+        corrupted[4] ^= 0x80;
+        assert!(matches!(MemBufferReader::new(&corrupted),Err(MemBufferError::WrongFormat)));
+    }
+}
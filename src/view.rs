@@ -0,0 +1,115 @@
+//!Typed views over the entries of a reader, implementing `Index<usize>` for quick scripts and
+//!tests. Indexing panics with the error of the checked load, use `get` to handle errors.
+//!```rust
+//!use membuffer::{MemBufferWriter,MemBufferReader};
+//!
+//!let mut writer = MemBufferWriter::new();
+//!writer.add_entry("Hello");
+//!writer.add_entry(&[1u8,2,3][..]);
+//!let data = writer.finalize();
+//!
+//!let reader = MemBufferReader::new(&data).unwrap();
+//!assert_eq!(&reader.strings()[0],"Hello");
+//!assert_eq!(&reader.blobs()[1],&[1,2,3]);
+//!```
+use core::marker::PhantomData;
+use crate::{MemBufferReader,MemBufferError,MemBufferDeserialize,MemBufferSerialize};
+
+///View of a reader which loads every entry as `&T`
+pub struct MemBufferView<'r,'a,T: ?Sized> {
+    reader: &'r MemBufferReader<'a>,
+    phantom: PhantomData<&'a T>,
+}
+
+impl<'r,'a,T: ?Sized> MemBufferView<'r,'a,T> where &'a T: MemBufferDeserialize<'a,&'a T> + MemBufferSerialize {
+    ///Loads the entry with the checked API of the reader
+    pub fn get(&self, index: usize) -> Result<&'a T,MemBufferError> {
+        self.reader.load_entry::<&'a T>(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.reader.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reader.is_empty()
+    }
+}
+
+impl<'r,'a,T: ?Sized> core::ops::Index<usize> for MemBufferView<'r,'a,T> where &'a T: MemBufferDeserialize<'a,&'a T> + MemBufferSerialize {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        if index >= self.len() {
+            panic!("Entry {} is out of bounds, the buffer has {} entries",index,self.len());
+        }
+        match self.get(index) {
+            Ok(val) => val,
+            Err(err) => panic!("Could not load entry {} of {} entries as {}: {}",index,self.len(),core::any::type_name::<T>(),err),
+        }
+    }
+}
+
+impl<'a> MemBufferReader<'a> {
+    ///Returns a view loading every entry as `&T`
+    pub fn view<T: ?Sized>(&self) -> MemBufferView<'_,'a,T> where &'a T: MemBufferDeserialize<'a,&'a T> + MemBufferSerialize {
+        MemBufferView {
+            reader: self,
+            phantom: PhantomData,
+        }
+    }
+
+    ///Returns a view loading every entry as text
+    pub fn strings(&self) -> MemBufferView<'_,'a,str> {
+        self.view()
+    }
+
+    ///Returns a view loading every entry as byte slice
+    pub fn blobs(&self) -> MemBufferView<'_,'a,[u8]> {
+        self.view()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemBufferWriter,MemBufferReader};
+
+    #[test]
+    fn check_views() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry(&[1u8,2,3][..]);
+        writer.add_entry::<&[u64]>(&[4,5]);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        assert_eq!(&reader.strings()[0],"Hello");
+        assert_eq!(&reader.blobs()[1],&[1,2,3]);
+        assert_eq!(&reader.view::<[u64]>()[2],&[4,5]);
+        assert!(reader.strings().get(1).is_err());
+        assert_eq!(reader.blobs().len(),3);
+    }
+
+    #[should_panic(expected = "Could not load entry 1")]
+    #[test]
+    fn check_view_wrong_type() {
+        let mut writer = MemBufferWriter::new();
+        writer.add_entry("Hello");
+        writer.add_entry(&[1u8,2,3][..]);
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        let _ = &reader.strings()[1];
+    }
+
+    #[should_panic(expected = "out of bounds")]
+    #[test]
+    fn check_view_out_of_bounds() {
+        let writer = MemBufferWriter::new();
+        let result = writer.finalize();
+
+        let reader = MemBufferReader::new(&result).unwrap();
+        let _ = &reader.blobs()[0];
+    }
+}